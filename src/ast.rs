@@ -0,0 +1,1368 @@
+// the tree of a file, built from its tokens by a recursive descent parser. nothing is looked up
+// here, names stay names until ExprWeights lowers the tree into the program the generator wants
+
+use crate::{compiler::{Cfg, OSES}, diagnostic::{Diagnostic, Diagnostics}, tokeniser::{Number, Span, SpannedToken, Token}};
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ItemKind {
+    Import(String),
+    CEmbed(String),
+    Link { os: Option<String>, libs: Vec<String> }, // @link(os) "name";
+    CFlags { os: Option<String>, flags: Vec<String> }, // @cflags(os) "-flag";
+    Func(Func),
+    Extern(Extern),
+    Struct(Struct),
+    Enum(Enum),
+    Var(VarDecl), // globals, struct fields and typeid masks
+    EnumField { name: String, value: Option<Expr> }, // only in the body of an enum
+}
+
+#[derive(Debug, Clone)]
+pub struct Func {
+    pub ret: Type,
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Block,
+    pub is_inline: bool,
+}
+
+// a c function called directly, it only has a signature
+#[derive(Debug, Clone)]
+pub struct Extern {
+    pub ret: Type,
+    pub name: String,
+    pub params: Vec<Param>,
+    pub link_name: Option<String>, // @extern("name"), otherwise the part of name after the last `.`
+    pub variadic: bool, // a `...` after the params
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub typ: Type,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Struct {
+    pub name: String,
+    pub generics: Vec<String>,
+    pub is_shared: bool,
+    pub body: Vec<Item>, // fields, @c and functions
+}
+
+#[derive(Debug, Clone)]
+pub struct Enum {
+    pub name: String,
+    pub body: Vec<Item>, // fields and functions
+}
+
+#[derive(Debug, Clone)]
+pub struct VarDecl {
+    pub typ: Type,
+    pub name: String,
+    pub constant: bool,
+    pub value: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Type {
+    Name { name: String, generics: Option<Vec<Type>> }, // int, vec2, dyn[T]
+    Pointer(Box<Type>),
+    Array { len: Option<Box<Expr>>, elem: Box<Type> },
+    Generic(String), // $T
+    Void,
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub close: Span, // the `}`
+}
+
+#[derive(Debug, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
+    Var(VarDecl),
+    Assign { target: Expr, value: Expr },
+    Expr(Expr),
+    If(Vec<Branch>), // the if, then any orifs and an else
+    Switch { cond: Expr, cases: Vec<Case>, close: Span },
+    Loop { cond: Option<Expr>, step: Option<LoopStep>, body: Block },
+    For { iter: Expr, elem: String, elem_ptr: bool, index: Option<String>, body: Block },
+    Defer(Box<Stmt>),
+    Return(Option<Expr>),
+    Break,
+    Continue,
+    Mut(String),
+    CEmbed(String),
+    Block(Block),
+}
+
+#[derive(Debug, Clone)]
+pub struct Branch {
+    pub cond: Option<Expr>, // None for the else
+    pub capture: Option<String>,
+    pub body: Block,
+    pub span: Span, // just the header
+}
+
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub cond: Option<Expr>,
+    pub fall: bool,
+    pub body: Block,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoopStep {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    Number(Number),
+    Str(String),
+    Char(String),
+    Bool(bool),
+    Name(String),
+    Call { name: String, args: Vec<Expr> },
+    Index { base: Box<Expr>, index: Box<Expr> },
+    Deref(Box<Expr>),
+    Address(Box<Expr>),
+    Type(Type), // like the ^char in mem.alloc(^char 10)
+    Square(Box<Expr>), // maths inside []
+    Paren(Box<Expr>), // grouped in ( )
+    Binary { op: BinOp, lhs: Box<Expr>, rhs: Box<Expr> },
+    Unary { op: UnOp, expr: Box<Expr> },
+    ArrayLit(Vec<Expr>),
+    Cast { typ: Type, expr: Box<Expr> }, // cast(u8 x)
+    CEmbed(String),
+    Default,
+    Garbage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnOp {
+    Neg,
+    Not,
+    BitNot,
+}
+
+type PResult<T> = Result<T, Diagnostic>;
+
+// code under @windows, @linux, @macos and @cfg(name) is only kept when `cfg` says so
+pub fn parse(tokens: Vec<SpannedToken>, filename: &str, cfg: &Cfg) -> Result<Module, Diagnostics> {
+    let mut parser = Parser::new(tokens, filename, None, cfg);
+    let mut items = Vec::new();
+
+    while parser.peek().is_some() {
+        let start = parser.pos;
+        if let Some(Token::Rcurl) = parser.peek() {
+            let e = parser.err(parser.span(), "unexpected `}` without a `{` before it");
+            parser.diagnostics.push(e);
+            parser.bump();
+            continue;
+        }
+
+        if let Err(e) = parser.part(&mut items, &mut |parser| parser.item(false)) {
+            parser.recover(e, start);
+        }
+    }
+
+    if parser.diagnostics.has_errors() {
+        return Err(parser.diagnostics);
+    }
+
+    Ok(Module { items })
+}
+
+struct Parser<'a> {
+    tokens: Vec<SpannedToken>,
+    pos: usize,
+    filename: &'a str,
+    end: Span, // where errors about running out of tokens point
+    bitwise: bool, // inside a [], where `and`, `or` and `!` are bitwise
+    cfg: &'a Cfg,
+    diagnostics: Diagnostics,
+}
+
+impl<'a> Parser<'a> {
+    // newlines don't matter to the grammar and the quotes around strings and chars are folded into them
+    fn new(tokens: Vec<SpannedToken>, filename: &'a str, end: Option<Span>, cfg: &'a Cfg) -> Parser<'a> {
+        let mut kept: Vec<SpannedToken> = Vec::new();
+        let mut open_quote: Option<Span> = None;
+
+        for spanned in tokens {
+            match spanned.token {
+                Token::Newline => (),
+                Token::Quote | Token::SingleQuote => match open_quote.take() {
+                    None => open_quote = Some(spanned.span),
+                    Some(open) => {
+                        if let Some(last) = kept.last_mut() {
+                            last.span = open.to(spanned.span);
+                        }
+                    },
+                },
+                _ => kept.push(spanned),
+            }
+        }
+
+        let end = end.or(kept.last().map(|last| Span { start: last.span.end, ..last.span })).unwrap_or(Span { line: 1, col: 1, ..Span::default() });
+        Parser { tokens: kept, pos: 0, filename, end, bitwise: false, cfg, diagnostics: Diagnostics::new() }
+    }
+
+    // a parser for the tokens inside a [], whose `]` is at `end`
+    fn square(&self, tokens: &[SpannedToken], end: Span) -> Parser<'a> {
+        Parser { bitwise: true, ..Parser::new(tokens.to_vec(), self.filename, Some(end), self.cfg) }
+    }
+
+    fn err(&self, span: Span, message: &str) -> Diagnostic {
+        Diagnostic::error(self.filename, span.line, message).with_span(span)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|spanned| &spanned.token)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n).map(|spanned| &spanned.token)
+    }
+
+    fn peek_ident(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Ident(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn span(&self) -> Span {
+        self.tokens.get(self.pos).map_or(self.end, |spanned| spanned.span)
+    }
+
+    fn prev_span(&self) -> Span {
+        match self.pos {
+            0 => self.span(),
+            pos => self.tokens[pos - 1].span,
+        }
+    }
+
+    // from `start` up to the last token taken, leaving out a closing `;`
+    fn since(&self, start: Span) -> Span {
+        match self.pos.checked_sub(2).map(|i| &self.tokens[i..self.pos]) {
+            Some([before, SpannedToken { token: Token::SemiColon, .. }]) if before.span.start >= start.start => start.to(before.span),
+            _ => start.to(self.prev_span()),
+        }
+    }
+
+    // the token we're on starts right where the previous one ended, no whitespace between
+    fn touches_prev(&self) -> bool {
+        self.pos > 0 && self.pos < self.tokens.len() && self.tokens[self.pos - 1].span.end == self.tokens[self.pos].span.start
+    }
+
+    // the token we're on runs straight into the next one
+    fn touches_next(&self) -> bool {
+        self.pos + 1 < self.tokens.len() && self.tokens[self.pos].span.end == self.tokens[self.pos + 1].span.start
+    }
+
+    // a `,` between list elements is optional
+    fn skip_comma(&mut self) {
+        if let Some(Token::Comma) = self.peek() {
+            self.bump();
+        }
+    }
+
+    // if the list we're at the start of uses `,` then whitespace doesn't split it, `close` is
+    // what ends the list
+    fn list_has_commas(&self, close: fn(&Token) -> bool) -> bool {
+        let mut depth = 0;
+        let mut in_array = false;
+        for spanned in &self.tokens[self.pos..] {
+            match &spanned.token {
+                token if depth == 0 && !in_array && close(token) => return false,
+                Token::Comma if depth == 0 && !in_array => return true,
+                Token::Lbrack => depth += 1,
+                Token::Rbrack if depth == 0 => return false,
+                Token::Rbrack => depth -= 1,
+                Token::Pipe => in_array = !in_array,
+                Token::SemiColon | Token::Lcurl => return false,
+                _ => (),
+            }
+        }
+
+        false
+    }
+
+    // `x-1` comes out of the tokeniser as `x` and `-1`, when it's really a subtraction
+    fn split_negative(&mut self) {
+        let spanned = &self.tokens[self.pos];
+        let Token::Number(number) = &spanned.token else {
+            return;
+        };
+
+        let minus = Span { end: spanned.span.start + 1, ..spanned.span };
+        let positive = Number { digits: number.digits[1..].to_string(), ..number.clone() };
+        let rest = Span { start: spanned.span.start + 1, col: spanned.span.col + 1, ..spanned.span };
+        self.tokens[self.pos] = SpannedToken { token: Token::Number(positive), span: rest };
+        self.tokens.insert(self.pos, SpannedToken { token: Token::Minus, span: minus });
+    }
+
+    fn bump(&mut self) -> SpannedToken {
+        let spanned = self.tokens[self.pos].clone();
+        self.pos += 1;
+        spanned
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("`{token}`"),
+            None => String::from("nothing"),
+        }
+    }
+
+    fn expect(&mut self, what: &str, matches: fn(&Token) -> bool) -> PResult<SpannedToken> {
+        match self.peek() {
+            Some(token) if matches(token) => Ok(self.bump()),
+            _ => Err(self.err(self.span(), &format!("expected {what}, found {}", self.found()))),
+        }
+    }
+
+    fn expect_semicolon(&mut self) -> PResult<()> {
+        match self.peek() {
+            Some(Token::SemiColon) => {
+                self.bump();
+                Ok(())
+            },
+            _ => {
+                let prev = self.prev_span();
+                let after = Span { start: prev.end, end: prev.end, line: prev.line, col: prev.col + (prev.end - prev.start) as u32 };
+                Err(self.err(after, &format!("expected `;`, found {}", self.found())))
+            },
+        }
+    }
+
+    fn ident(&mut self, what: &str) -> PResult<String> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.bump();
+                Ok(name)
+            },
+            _ => Err(self.err(self.span(), &format!("expected {what}, found {}", self.found()))),
+        }
+    }
+
+    fn at_double_colon(&self) -> bool {
+        matches!((self.peek(), self.peek_at(1)), (Some(Token::Colon), Some(Token::Colon)))
+    }
+
+    // reports the error and skips the rest of the broken statement, or the block it opened
+    fn recover(&mut self, diagnostic: Diagnostic, start: usize) {
+        self.diagnostics.push(diagnostic);
+
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::SemiColon if depth == 0 => {
+                    self.bump();
+                    return;
+                },
+                Token::Lcurl => depth += 1,
+                Token::Rcurl if depth == 0 => break,
+                Token::Rcurl => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.bump();
+                        return;
+                    }
+                },
+                _ => (),
+            }
+            self.bump();
+        }
+
+        // always make progress, or a stray token would be reported forever
+        if self.pos == start && self.peek().is_some() && !matches!(self.peek(), Some(Token::Rcurl)) {
+            self.bump();
+        }
+    }
+
+    // things that can go at the top of a file, or `in_body` of a struct or enum
+    fn item(&mut self, in_body: bool) -> PResult<Item> {
+        let start = self.span();
+
+        let kind = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Macro), Some(Token::Ident(mac))) => match mac.as_str() {
+                "import" if !in_body => {
+                    self.bump();
+                    self.bump();
+                    let path = match self.peek() {
+                        Some(Token::Str(path)) => path.clone(),
+                        _ => return Err(self.err(self.span(), &format!("expected \"path_to_file\", found {}", self.found()))),
+                    };
+                    self.bump();
+                    self.expect_semicolon()?;
+                    ItemKind::Import(path)
+                },
+                "c" => {
+                    let code = self.c_embed()?;
+                    self.expect_semicolon()?;
+                    ItemKind::CEmbed(code)
+                },
+                "inline" => {
+                    self.bump();
+                    self.bump();
+                    let typ = self.typ()?;
+                    let name = self.ident("a function name")?;
+                    if !self.at_double_colon() || !matches!(self.peek_at(2), Some(Token::Lbrack)) {
+                        return Err(self.err(self.span(), &format!("expected a function after @inline, like `@inline {name} :: () {{`")));
+                    }
+                    ItemKind::Func(self.func(typ, name, true)?)
+                },
+                "link" if !in_body => {
+                    let (os, libs) = self.build_strings()?;
+                    ItemKind::Link { os, libs }
+                },
+                "cflags" if !in_body => {
+                    let (os, flags) = self.build_strings()?;
+                    ItemKind::CFlags { os, flags }
+                },
+                "extern" if !in_body => {
+                    self.bump();
+                    self.bump();
+                    ItemKind::Extern(self.extern_func()?)
+                },
+                "shared" if !in_body => {
+                    self.bump();
+                    self.bump();
+                    if self.peek_ident() != Some("struct") {
+                        return Err(self.err(self.span(), &format!("expected `struct` after @shared, found {}", self.found())));
+                    }
+                    ItemKind::Struct(self.struct_def(true)?)
+                },
+                other => return Err(self.err(start, &format!("macro @{other} can't be used here"))),
+            },
+            (Some(Token::Ident(kw)), _) if (kw == "struct" || kw == "enum") && in_body => {
+                return Err(self.err(start, &format!("can't create a {kw} inside an enum or struct")));
+            },
+            (Some(Token::Ident(kw)), _) if kw == "struct" => ItemKind::Struct(self.struct_def(false)?),
+            (Some(Token::Ident(kw)), _) if kw == "enum" => ItemKind::Enum(self.enum_def()?),
+            _ => {
+                let typ = self.typ()?;
+                let name = self.ident("a name")?;
+                if self.at_double_colon() && matches!(self.peek_at(2), Some(Token::Lbrack)) {
+                    ItemKind::Func(self.func(typ, name, false)?)
+                } else {
+                    ItemKind::Var(self.var_rest(typ, name)?)
+                }
+            },
+        };
+
+        Ok(Item { kind, span: self.since(start) })
+    }
+
+    // the `(os) "a" "b";` after @link and @cflags, the os is optional
+    fn build_strings(&mut self) -> PResult<(Option<String>, Vec<String>)> {
+        self.bump();
+        self.bump();
+
+        let mut os = None;
+        if let Some(Token::Lbrack) = self.peek() {
+            self.bump();
+            os = Some(self.ident("an os, windows, linux or macos")?);
+            self.expect("`)`", |t| matches!(t, Token::Rbrack))?;
+        }
+
+        let mut strings = Vec::new();
+        while let Some(Token::Str(string)) = self.peek() {
+            strings.push(string.clone());
+            self.bump();
+            self.skip_comma();
+        }
+
+        if strings.is_empty() {
+            return Err(self.err(self.span(), &format!("expected \"...\", found {}", self.found())));
+        }
+        self.expect_semicolon()?;
+        Ok((os, strings))
+    }
+
+    // @c [...], without the `;`
+    fn c_embed(&mut self) -> PResult<String> {
+        self.bump();
+        self.bump();
+        self.expect("`[` after @c", |t| matches!(t, Token::Lsquare))?;
+        let code = match self.peek() {
+            Some(Token::CCode(code)) => code.clone(),
+            _ => return Err(self.err(self.span(), &format!("expected [_code_], found {}", self.found()))),
+        };
+        self.bump();
+        self.expect("`]`", |t| matches!(t, Token::Rsquare))?;
+        Ok(code)
+    }
+
+    // the `:: (params) { body }` after a function's type and name
+    fn func(&mut self, ret: Type, name: String, is_inline: bool) -> PResult<Func> {
+        self.bump();
+        self.bump();
+        let dots = self.span();
+        let (params, variadic) = self.params()?;
+        if variadic {
+            return Err(self.err(dots, &format!("only @extern functions can take `...`, {name} has a body")));
+        }
+
+        let body = self.block()?;
+        Ok(Func { ret, name, params, body, is_inline })
+    }
+
+    // `("link_name") type name :: (params);` after @extern
+    fn extern_func(&mut self) -> PResult<Extern> {
+        let mut link_name = None;
+        if let Some(Token::Lbrack) = self.peek() {
+            self.bump();
+            match self.peek() {
+                Some(Token::Str(name)) => link_name = Some(name.clone()),
+                _ => return Err(self.err(self.span(), &format!("expected the c function's name, like @extern(\"printf\"), found {}", self.found()))),
+            }
+            self.bump();
+            self.expect("`)`", |t| matches!(t, Token::Rbrack))?;
+        }
+
+        let ret = self.typ()?;
+        let name = self.ident("a function name")?;
+        if !self.at_double_colon() || !matches!(self.peek_at(2), Some(Token::Lbrack)) {
+            return Err(self.err(self.span(), &format!("expected a function after @extern, like `@extern {name} :: ();`")));
+        }
+        self.bump();
+        self.bump();
+
+        let (params, variadic) = self.params()?;
+        if let Some(Token::Lcurl) = self.peek() {
+            return Err(self.err(self.span(), &format!("@extern function {name} can't have a body, it's the c function")));
+        }
+        self.expect_semicolon()?;
+        Ok(Extern { ret, name, params, link_name, variadic })
+    }
+
+    // `(type name ...)`, and whether it ends in `...`
+    fn params(&mut self) -> PResult<(Vec<Param>, bool)> {
+        self.expect("`(`", |t| matches!(t, Token::Lbrack))?;
+
+        let mut params = Vec::new();
+        let mut variadic = false;
+        loop {
+            match self.peek() {
+                Some(Token::Rbrack) => {
+                    self.bump();
+                    break;
+                },
+                None => return Err(self.err(self.span(), "expected `)` to close the parameters")),
+                _ if variadic => return Err(self.err(self.span(), &format!("`...` has to be the last parameter, found {}", self.found()))),
+                Some(Token::Ident(dots)) if dots == "..." => {
+                    self.bump();
+                    variadic = true;
+                },
+                _ => {
+                    let typ = self.typ()?;
+                    let name = self.ident("a parameter name")?;
+                    params.push(Param { typ, name });
+                    self.skip_comma();
+                },
+            }
+        }
+
+        Ok((params, variadic))
+    }
+
+    fn struct_def(&mut self, is_shared: bool) -> PResult<Struct> {
+        self.bump();
+
+        let mut generics = Vec::new();
+        if let Some(Token::Lsquare) = self.peek() {
+            let (inner, end) = self.square_tokens()?;
+            let mut sub = self.square(&inner, end);
+            while sub.peek().is_some() {
+                generics.push(sub.ident("a generic name")?);
+            }
+        }
+
+        let name = self.ident("a struct name")?;
+        if !self.at_double_colon() {
+            return Err(self.err(self.span(), &format!("expected assigment operator `:`. did you mean `struct {name} :: {{`?")));
+        }
+        self.bump();
+        self.bump();
+
+        let body = self.body(|parser| parser.item(true))?;
+        Ok(Struct { name, generics, is_shared, body })
+    }
+
+    fn enum_def(&mut self) -> PResult<Enum> {
+        self.bump();
+        let name = self.ident("an enum name")?;
+        if !self.at_double_colon() {
+            return Err(self.err(self.span(), &format!("expected assigment operator `:`. did you mean `enum {name} :: {{`?")));
+        }
+        self.bump();
+        self.bump();
+
+        let body = self.body(|parser| {
+            // `X;`, `X :: value;` or the mistaken `X : value;`, anything else is a function
+            let is_field = match (parser.peek(), parser.peek_at(1), parser.peek_at(2), parser.peek_at(3)) {
+                (Some(Token::Ident(_)), Some(Token::SemiColon), ..) => true,
+                (Some(Token::Ident(_)), Some(Token::Colon), Some(Token::Colon), next) => !matches!(next, Some(Token::Lbrack)),
+                (Some(Token::Ident(_)), Some(Token::Colon), ..) => true,
+                _ => false,
+            };
+            if !is_field {
+                return parser.item(true);
+            }
+
+            let start = parser.span();
+            let name = parser.ident("an enum field")?;
+            let value = match parser.peek() {
+                Some(Token::SemiColon) => None,
+                _ if parser.at_double_colon() => {
+                    parser.bump();
+                    parser.bump();
+                    Some(parser.value()?)
+                },
+                _ => return Err(parser.err(start, &format!("enum field {name} must be constant if given value. did you mean `{name} :: <value>`?"))),
+            };
+            parser.expect_semicolon()?;
+            Ok(Item { kind: ItemKind::EnumField { name, value }, span: parser.since(start) })
+        })?;
+
+        Ok(Enum { name, body })
+    }
+
+    // an item or statement from `part`, or the ones under a conditional
+    fn part<T>(&mut self, parts: &mut Vec<T>, part: &mut impl FnMut(&mut Parser<'a>) -> PResult<T>) -> PResult<()> {
+        if !self.conditional(parts, part)? {
+            parts.push(part(self)?);
+        }
+        Ok(())
+    }
+
+    // @windows, @linux, @macos or @cfg(name) in front of one part, or a { } of them. the ones for
+    // another build are still parsed so they have to be valid, they just aren't kept
+    fn conditional<T>(&mut self, parts: &mut Vec<T>, part: &mut impl FnMut(&mut Parser<'a>) -> PResult<T>) -> PResult<bool> {
+        let mac = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Macro), Some(Token::Ident(mac))) if OSES.contains(&mac.as_str()) || mac == "cfg" => mac.clone(),
+            _ => return Ok(false),
+        };
+        self.bump();
+        self.bump();
+
+        let mut name = None;
+        if mac == "cfg" {
+            self.expect("`(` after @cfg", |t| matches!(t, Token::Lbrack))?;
+            name = Some(self.ident("a name given with --cfg")?);
+            self.expect("`)`", |t| matches!(t, Token::Rbrack))?;
+        }
+
+        let mut kept = Vec::new();
+        if let Some(Token::Lcurl) = self.peek() {
+            let open = self.bump().span;
+            loop {
+                match self.peek() {
+                    Some(Token::Rcurl) => {
+                        self.bump();
+                        break;
+                    },
+                    None => return Err(self.err(open, "missing a `}` to close this")),
+                    _ => self.part(&mut kept, part)?,
+                }
+            }
+        } else {
+            self.part(&mut kept, part)?;
+        }
+
+        if self.cfg.holds(&mac, name.as_deref()) {
+            parts.append(&mut kept);
+        }
+        Ok(true)
+    }
+
+    // the items between the { } of a struct or enum
+    fn body(&mut self, mut item: impl FnMut(&mut Parser<'a>) -> PResult<Item>) -> PResult<Vec<Item>> {
+        let open = self.expect("`{`", |t| matches!(t, Token::Lcurl))?.span;
+
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Rcurl) => {
+                    self.bump();
+                    return Ok(items);
+                },
+                None => return Err(self.err(open, "missing a `}` to close this")),
+                _ => {
+                    let start = self.pos;
+                    if let Err(e) = self.part(&mut items, &mut item) {
+                        self.recover(e, start);
+                    }
+                },
+            }
+        }
+    }
+
+    // `;`, `: value;` or `:: value;` after the type and name of a variable
+    fn var_rest(&mut self, typ: Type, name: String) -> PResult<VarDecl> {
+        let (constant, value) = if let Some(Token::SemiColon) = self.peek() {
+            (false, None)
+        } else if self.at_double_colon() {
+            self.bump();
+            self.bump();
+            (true, Some(self.value()?))
+        } else if let Some(Token::Colon) = self.peek() {
+            self.bump();
+            (false, Some(self.value()?))
+        } else {
+            return Err(self.err(self.span(), &format!("expected `:`, `::` or `;` after {name}, found {}", self.found())));
+        };
+
+        self.expect_semicolon()?;
+        Ok(VarDecl { typ, name, constant, value })
+    }
+
+    // the tokens of a [ ] and where its `]` is
+    fn square_tokens(&mut self) -> PResult<(Vec<SpannedToken>, Span)> {
+        self.expect("`[`", |t| matches!(t, Token::Lsquare))?;
+        let inner = match self.peek() {
+            Some(Token::Square(inner)) => inner.clone(),
+            _ => return Err(self.err(self.span(), &format!("expected the inside of a [], found {}", self.found()))),
+        };
+        self.bump();
+        let end = self.expect("`]`", |t| matches!(t, Token::Rsquare))?.span;
+        Ok((inner, end))
+    }
+
+    fn typ(&mut self) -> PResult<Type> {
+        match self.peek() {
+            Some(Token::Caret) => {
+                self.bump();
+                Ok(Type::Pointer(Box::new(self.typ()?)))
+            },
+            Some(Token::Dollar) => {
+                self.bump();
+                Ok(Type::Generic(self.ident("a typeid after `$`")?))
+            },
+            Some(Token::Underscore) => {
+                self.bump();
+                Ok(Type::Void)
+            },
+            Some(Token::Lsquare) => {
+                let (inner, end) = self.square_tokens()?;
+                let len = if inner.is_empty() {
+                    None
+                } else {
+                    Some(Box::new(self.square(&inner, end).maths_only()?))
+                };
+                Ok(Type::Array { len, elem: Box::new(self.typ()?) })
+            },
+            Some(Token::Ident(_)) => {
+                let name = self.ident("a type")?;
+                let generics = if let Some(Token::Lsquare) = self.peek() {
+                    let (inner, end) = self.square_tokens()?;
+                    let mut sub = self.square(&inner, end);
+                    let mut generics = Vec::new();
+                    while sub.peek().is_some() {
+                        generics.push(sub.typ()?);
+                    }
+                    Some(generics)
+                } else {
+                    None
+                };
+                Ok(Type::Name { name, generics })
+            },
+            _ => Err(self.err(self.span(), &format!("expected a type, found {}", self.found()))),
+        }
+    }
+
+    fn block(&mut self) -> PResult<Block> {
+        let open = self.expect("`{`", |t| matches!(t, Token::Lcurl))?.span;
+
+        let mut stmts = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Rcurl) => {
+                    let close = self.bump().span;
+                    return Ok(Block { stmts, close });
+                },
+                None => return Err(self.err(open, "missing a `}` to close this")),
+                _ => {
+                    let start = self.pos;
+                    if let Err(e) = self.part(&mut stmts, &mut |parser| parser.stmt()) {
+                        self.recover(e, start);
+                    }
+                },
+            }
+        }
+    }
+
+    fn stmt(&mut self) -> PResult<Stmt> {
+        let start = self.span();
+
+        let kind = match self.peek() {
+            Some(Token::Lcurl) => StmtKind::Block(self.block()?),
+            Some(Token::Macro) => {
+                let mac = match self.peek_at(1) {
+                    Some(Token::Ident(mac)) => mac.clone(),
+                    _ => return Err(self.err(start, "expected a macro name after `@`")),
+                };
+                match mac.as_str() {
+                    "c" => {
+                        let code = self.c_embed()?;
+                        self.expect_semicolon()?;
+                        StmtKind::CEmbed(code)
+                    },
+                    "mut" => {
+                        self.bump();
+                        self.bump();
+                        let name = self.ident("an identifier")?;
+                        self.expect_semicolon()?;
+                        StmtKind::Mut(name)
+                    },
+                    _ => return Err(self.err(start, &format!("macro @{mac} can't be used inside a function"))),
+                }
+            },
+            Some(Token::Ident(kw)) => match kw.as_str() {
+                "if" => StmtKind::If(self.if_chain()?),
+                "orif" | "else" => return Err(self.err(start, &format!("`{kw}` without an `if` before it"))),
+                "switch" => self.switch()?,
+                "case" | "fall" => return Err(self.err(start, &format!("`{kw}` can only be used inside a switch"))),
+                "loop" => {
+                    self.bump();
+                    // `loop {` runs forever, same as `loop () {`
+                    let cond = match self.peek() {
+                        Some(Token::Lcurl) => None,
+                        _ => self.cond()?,
+                    };
+                    let step = if let Some(Token::Lsquare) = self.peek() {
+                        let (inner, _) = self.square_tokens()?;
+                        match &inner[..] {
+                            [] => None,
+                            [SpannedToken { token: Token::Plus, .. }] => Some(LoopStep::Up),
+                            [SpannedToken { token: Token::Minus, .. }] => Some(LoopStep::Down),
+                            [SpannedToken { token: Token::Underscore, .. }] => None,
+                            other => {
+                                let span = other[0].span.to(other[other.len() - 1].span);
+                                return Err(self.err(span, &format!("unexpected loop modifier: {}", crate::tokeniser::tokens_text(other))));
+                            },
+                        }
+                    } else {
+                        None
+                    };
+                    StmtKind::Loop { cond, step, body: self.block()? }
+                },
+                "for" => self.for_loop()?,
+                "defer" => {
+                    self.bump();
+                    StmtKind::Defer(Box::new(self.stmt()?))
+                },
+                "return" => {
+                    self.bump();
+                    let value = match self.peek() {
+                        Some(Token::SemiColon) => None,
+                        _ => Some(self.value()?),
+                    };
+                    self.expect_semicolon()?;
+                    StmtKind::Return(value)
+                },
+                "break" | "continue" => {
+                    let is_break = kw == "break";
+                    self.bump();
+                    self.expect_semicolon()?;
+                    if is_break { StmtKind::Break } else { StmtKind::Continue }
+                },
+                "struct" | "enum" => return Err(self.err(start, &format!("cannot make a {kw} inside a function"))),
+                _ => self.simple_stmt()?,
+            },
+            _ => self.simple_stmt()?,
+        };
+
+        Ok(Stmt { kind, span: self.since(start) })
+    }
+
+    // a declaration starts with a type, `int x`, `^char x`, `dyn[int] x`, `[5]int x` or `$T x`
+    fn at_declaration(&self) -> bool {
+        match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Caret | Token::Dollar | Token::Lsquare | Token::Underscore), _) => true,
+            (Some(Token::Ident(_)), Some(Token::Ident(_))) => true,
+            (Some(Token::Ident(_)), Some(Token::Lsquare)) => {
+                matches!((self.peek_at(2), self.peek_at(3), self.peek_at(4)), (Some(Token::Square(_)), Some(Token::Rsquare), Some(Token::Ident(_) | Token::Lsquare)))
+            },
+            _ => false,
+        }
+    }
+
+    fn simple_stmt(&mut self) -> PResult<StmtKind> {
+        if self.at_declaration() {
+            let typ = self.typ()?;
+            let name = self.ident("a variable name")?;
+            if self.at_double_colon() && matches!(self.peek_at(2), Some(Token::Lbrack)) {
+                return Err(self.err(self.prev_span(), &format!("can't declare function {name} inside another function")));
+            }
+            return Ok(StmtKind::Var(self.var_rest(typ, name)?));
+        }
+
+        let target = self.operand(false)?;
+        if self.at_double_colon() {
+            return Err(self.err(self.span(), "`::` declares a constant, it needs a type before the name like `int x :: 5;`"));
+        }
+
+        if let Some(Token::Colon) = self.peek() {
+            self.bump();
+            let value = self.value()?;
+            self.expect_semicolon()?;
+            return Ok(StmtKind::Assign { target, value });
+        }
+
+        self.expect_semicolon()?;
+        Ok(StmtKind::Expr(target))
+    }
+
+    fn if_chain(&mut self) -> PResult<Vec<Branch>> {
+        let mut branches = Vec::new();
+
+        loop {
+            let start = self.span();
+            let kw = self.bump();
+            let is_else = matches!(&kw.token, Token::Ident(kw) if kw == "else");
+
+            let (cond, capture) = if is_else {
+                (None, None)
+            } else {
+                let cond = match self.cond()? {
+                    Some(cond) => cond,
+                    None => return Err(self.err(self.since(start), "expected a condition inside the ( )")),
+                };
+                let capture = if let Some(Token::Lsquare) = self.peek() {
+                    let (inner, end) = self.square_tokens()?;
+                    match &inner[..] {
+                        [SpannedToken { token: Token::Ident(name), .. }] => Some(name.clone()),
+                        [SpannedToken { token: Token::Underscore, .. }] => Some(String::from("_")),
+                        _ => return Err(self.err(end, "expected one name to capture into, like `[value]`")),
+                    }
+                } else {
+                    None
+                };
+                (Some(cond), capture)
+            };
+
+            let span = self.since(start);
+            let body = self.block()?;
+            branches.push(Branch { cond, capture, body, span });
+
+            match self.peek_ident() {
+                Some("orif") if !is_else => continue,
+                Some("else") if !is_else => continue,
+                _ => return Ok(branches),
+            }
+        }
+    }
+
+    fn switch(&mut self) -> PResult<StmtKind> {
+        self.bump();
+        let cond = match self.cond()? {
+            Some(cond) => cond,
+            None => return Err(self.err(self.prev_span(), "expected a value to switch on inside the ( )")),
+        };
+
+        let open = self.expect("`{`", |t| matches!(t, Token::Lcurl))?.span;
+        let mut cases = Vec::new();
+        loop {
+            let start = self.span();
+            match self.peek_ident() {
+                Some("case" | "fall") => {
+                    let fall = self.peek_ident() == Some("fall");
+                    self.bump();
+                    let cond = if let Some(Token::Lbrack) = self.peek() { self.cond()? } else { None };
+                    let span = self.since(start);
+                    let body = self.block()?;
+                    cases.push(Case { cond, fall, body, span });
+                },
+                _ => match self.peek() {
+                    Some(Token::Rcurl) => {
+                        self.bump();
+                        break;
+                    },
+                    None => return Err(self.err(open, "missing a `}` to close this")),
+                    _ => return Err(self.err(start, &format!("expected `case` or `fall` inside a switch, found {}", self.found()))),
+                },
+            }
+        }
+
+        Ok(StmtKind::Switch { cond, cases, close: self.prev_span() })
+    }
+
+    fn for_loop(&mut self) -> PResult<StmtKind> {
+        let start = self.span();
+        self.bump();
+        self.expect("`(`", |t| matches!(t, Token::Lbrack))?;
+        let iter = self.expr(false)?;
+        if !matches!(self.peek(), Some(Token::Rbrack)) {
+            return Err(self.err(self.span(), "expected only one expression to loop through"));
+        }
+        self.bump();
+
+        if !matches!(self.peek(), Some(Token::Lsquare)) {
+            return Err(self.err(self.since(start), "expected new variable names e.g. `[elem]` or `[elem i]`"));
+        }
+        let (inner, end) = self.square_tokens()?;
+        let (elem_ptr, names) = match &inner[..] {
+            [SpannedToken { token: Token::Caret, .. }, rest @ ..] => (true, rest),
+            rest => (false, rest),
+        };
+        let (elem, index) = match names {
+            [SpannedToken { token: Token::Ident(elem), .. }] => (elem.clone(), None),
+            [SpannedToken { token: Token::Ident(elem), .. }, SpannedToken { token: Token::Ident(index), .. }] => (elem.clone(), Some(index.clone())),
+            [] => return Err(self.err(end, "expected new variable names e.g. `[elem]` or `[elem i]`")),
+            _ if names.len() > 2 => return Err(self.err(end, &format!("expected at most two variables to extract in for loop, got {}", names.len()))),
+            _ => return Err(self.err(end, "expected new variable names e.g. `[elem]` or `[elem i]`")),
+        };
+
+        Ok(StmtKind::For { iter, elem, elem_ptr, index, body: self.block()? })
+    }
+
+    // a value on the right of a `:` or after return
+    fn value(&mut self) -> PResult<Expr> {
+        self.expr(false)
+    }
+
+    // one whole expression. `in_list` when it's in a whitespace separated list like call arguments,
+    // where `x [0]` is two arguments and `x[0]` is indexing, `a -b` is `a` then `-b`, and `x^ int`
+    // is a deref then `int`. spacing that doesn't settle it, like `x ^ int` or `f (x)`, is an error
+    fn expr(&mut self, in_list: bool) -> PResult<Expr> {
+        self.binary(0, in_list)
+    }
+
+    // pratt parsing, loosest first:
+    //   or
+    //   and
+    //   = != < > <= >=
+    //   or      inside []
+    //   xor
+    //   and     inside []
+    //   lshift rshift
+    //   + -
+    //   * / %
+    //   ! - &   prefix, `!` is `~` inside []
+    //   ^ () [] after a name, deref, call and index
+    // `and`, `or` and `!` are logical unless the closest brackets around them are [], and
+    // comparisons can't go in [] at all
+    fn binary(&mut self, min_prec: u8, in_list: bool) -> PResult<Expr> {
+        let mut lhs = self.prefix(in_list)?;
+        loop {
+            // in a list `a -b` is `a` then `-b`
+            match self.peek() {
+                Some(Token::Minus) if in_list && !self.touches_prev() && self.touches_next() => return Ok(lhs),
+                Some(Token::Number(number)) if number.digits.starts_with('-') => {
+                    if in_list && !self.touches_prev() {
+                        return Ok(lhs);
+                    }
+                    self.split_negative();
+                },
+                _ => (),
+            }
+
+            let followed_by_eq = matches!(self.peek_at(1), Some(Token::Equal));
+            let (op, prec, len) = match self.peek() {
+                Some(Token::Or) if self.bitwise => (BinOp::BitOr, 4, 1),
+                Some(Token::Or) => (BinOp::Or, 1, 1),
+                Some(Token::And) if self.bitwise => (BinOp::BitAnd, 6, 1),
+                Some(Token::And) => (BinOp::And, 2, 1),
+                Some(Token::Equal) if !self.bitwise => (BinOp::Eq, 3, 1),
+                Some(Token::Exclaim) if !self.bitwise && followed_by_eq => (BinOp::Ne, 3, 2),
+                Some(Token::SmallerThan) if !self.bitwise && followed_by_eq => (BinOp::Le, 3, 2),
+                Some(Token::SmallerThan) if !self.bitwise => (BinOp::Lt, 3, 1),
+                Some(Token::BiggerThan) if !self.bitwise && followed_by_eq => (BinOp::Ge, 3, 2),
+                Some(Token::BiggerThan) if !self.bitwise => (BinOp::Gt, 3, 1),
+                Some(Token::Xor) => (BinOp::BitXor, 5, 1),
+                Some(Token::Lshift) => (BinOp::Shl, 7, 1),
+                Some(Token::Rshift) => (BinOp::Shr, 7, 1),
+                Some(Token::Plus) => (BinOp::Add, 8, 1),
+                Some(Token::Minus) => (BinOp::Sub, 8, 1),
+                Some(Token::Multiply) => (BinOp::Mul, 9, 1),
+                Some(Token::Divide) => (BinOp::Div, 9, 1),
+                Some(Token::Mod) => (BinOp::Mod, 9, 1),
+                _ => return Ok(lhs),
+            };
+            if prec < min_prec {
+                return Ok(lhs);
+            }
+
+            for _ in 0..len {
+                self.bump();
+            }
+            let rhs = self.binary(prec + 1, in_list)?;
+            lhs = binary(op, lhs, rhs);
+        }
+    }
+
+    fn prefix(&mut self, in_list: bool) -> PResult<Expr> {
+        let start = self.span();
+        let op = match self.peek() {
+            Some(Token::Minus) => UnOp::Neg,
+            Some(Token::Exclaim) if self.bitwise => UnOp::BitNot,
+            Some(Token::Exclaim) => UnOp::Not,
+            Some(Token::Ampersand) => {
+                self.bump();
+                let expr = self.prefix(in_list)?;
+                return Ok(Expr { kind: ExprKind::Address(Box::new(expr)), span: self.since(start) });
+            },
+            Some(Token::Rbrack) | None if self.pos > 0 => {
+                return Err(self.err(self.prev_span(), &format!("expected an expression after `{}`", self.tokens[self.pos - 1].token)));
+            },
+            _ => return self.operand(in_list),
+        };
+
+        self.bump();
+        let expr = self.prefix(in_list)?;
+        Ok(Expr { kind: ExprKind::Unary { op, expr: Box::new(expr) }, span: self.since(start) })
+    }
+
+    // a single value and whatever is stuck on the end of it
+    fn operand(&mut self, in_list: bool) -> PResult<Expr> {
+        let start = self.span();
+
+        let kind = match self.peek() {
+            Some(Token::Number(number)) => {
+                let number = number.clone();
+                self.bump();
+                ExprKind::Number(number)
+            },
+            Some(Token::Str(text)) => {
+                let text = text.clone();
+                self.bump();
+                ExprKind::Str(text)
+            },
+            Some(Token::Char(text)) => {
+                let text = text.clone();
+                self.bump();
+                ExprKind::Char(text)
+            },
+            Some(Token::True) => {
+                self.bump();
+                ExprKind::Bool(true)
+            },
+            Some(Token::False) => {
+                self.bump();
+                ExprKind::Bool(false)
+            },
+            Some(Token::Lsquare) => {
+                let (inner, end) = self.square_tokens()?;
+                if inner.is_empty() {
+                    return Err(self.err(self.since(start), "expected an expression inside []"));
+                }
+                let maths = self.square(&inner, end).maths_only()?;
+                ExprKind::Square(Box::new(maths))
+            },
+            Some(Token::Lbrack) => {
+                let open = self.bump().span;
+                ExprKind::Paren(Box::new(self.group(open)?))
+            },
+            Some(Token::Caret | Token::Dollar) => ExprKind::Type(self.typ()?),
+            Some(Token::Pipe) => {
+                self.bump();
+                let in_list = !self.list_has_commas(|t| matches!(t, Token::Pipe));
+                let mut elems = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(Token::Pipe) => {
+                            self.bump();
+                            break;
+                        },
+                        None => return Err(self.err(start, "missing a `|` to close this array")),
+                        _ => {
+                            elems.push(self.expr(in_list)?);
+                            self.skip_comma();
+                        },
+                    }
+                }
+                ExprKind::ArrayLit(elems)
+            },
+            Some(Token::Macro) => match self.peek_at(1) {
+                Some(Token::Ident(mac)) if mac == "c" => ExprKind::CEmbed(self.c_embed()?),
+                Some(Token::Ident(mac)) if mac == "default" || mac == "garbage" => {
+                    let is_default = mac == "default";
+                    self.bump();
+                    self.bump();
+                    if is_default { ExprKind::Default } else { ExprKind::Garbage }
+                },
+                Some(Token::Ident(mac)) => return Err(self.err(start, &format!("macro @{mac} can't be used as a value"))),
+                _ => return Err(self.err(start, "expected a macro name after `@`")),
+            },
+            Some(Token::Ident(name)) if name == "cast" && matches!(self.peek_at(1), Some(Token::Lbrack)) => self.cast()?,
+            Some(Token::Ident(_)) => {
+                let name = self.ident("an identifier")?;
+                if in_list && !self.touches_prev() && matches!(self.peek(), Some(Token::Lbrack)) {
+                    return Err(self.err(self.span(), &format!("ambiguous `(`, it could call {name} or start a new value. use `,` between values or remove the space before the `(`")));
+                }
+                if let Some(Token::Lbrack) = self.peek() {
+                    ExprKind::Call { name, args: self.args()? }
+                } else {
+                    ExprKind::Name(name)
+                }
+            },
+            _ => return Err(self.err(start, &format!("expected an expression, found {}", self.found()))),
+        };
+
+        let mut expr = Expr { kind, span: self.since(start) };
+        if !matches!(expr.kind, ExprKind::Name(_) | ExprKind::Call { .. }) {
+            return Ok(expr);
+        }
+
+        loop {
+            match self.peek() {
+                Some(Token::Lsquare) if !in_list || self.touches_prev() => {
+                    let (inner, end) = self.square_tokens()?;
+                    if inner.is_empty() {
+                        return Err(self.err(end, "expected an index inside []"));
+                    }
+                    let index = self.square(&inner, end).maths_only()?;
+                    expr = Expr { kind: ExprKind::Index { base: Box::new(expr), index: Box::new(index) }, span: self.since(start) };
+                },
+                Some(Token::Caret) if in_list && self.touches_prev() == self.touches_next() && !matches!(self.peek_at(1), Some(Token::Comma | Token::Rbrack | Token::Pipe) | None) => {
+                    return Err(self.err(self.span(), "ambiguous `^`, it could dereference the value before it or start a pointer type. use `,` between values or put the `^` right against the side it belongs to"));
+                },
+                Some(Token::Caret) if !in_list || self.touches_prev() => {
+                    self.bump();
+                    expr = Expr { kind: ExprKind::Deref(Box::new(expr)), span: self.since(start) };
+                },
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    // the ( ) after a function name
+    fn args(&mut self) -> PResult<Vec<Expr>> {
+        let open = self.bump().span;
+        let in_list = !self.list_has_commas(|t| matches!(t, Token::Rbrack));
+        let mut args = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Rbrack) => {
+                    self.bump();
+                    return Ok(args);
+                },
+                None => return Err(self.err(open, "missing a `)` to close this")),
+                _ => {
+                    args.push(self.expr(in_list)?);
+                    self.skip_comma();
+                },
+            }
+        }
+    }
+
+    // cast(type value), the type comes first like the typeid of a macro function
+    fn cast(&mut self) -> PResult<ExprKind> {
+        self.bump();
+        let open = self.bump().span;
+        // `cast(uint [x / 2])` is a uint and some maths, not a uint with generics
+        let typ = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Ident(_)), Some(Token::Lsquare)) if !self.touches_next() => Type::Name { name: self.ident("a type")?, generics: None },
+            _ => self.typ()?,
+        };
+        self.skip_comma();
+        if let Some(Token::Rbrack) | None = self.peek() {
+            return Err(self.err(self.since(open), "expected a value to cast after the type"));
+        }
+
+        // there's only ever one value, so spaces can't split it
+        let expr = self.expr(false)?;
+        self.expect("`)` after the value to cast", |t| matches!(t, Token::Rbrack))?;
+        Ok(ExprKind::Cast { typ, expr: Box::new(expr) })
+    }
+
+    // a condition in ( ), None when they're empty
+    fn cond(&mut self) -> PResult<Option<Expr>> {
+        let open = self.expect("`(`", |t| matches!(t, Token::Lbrack))?.span;
+        if let Some(Token::Rbrack) = self.peek() {
+            self.bump();
+            return Ok(None);
+        }
+
+        Ok(Some(self.group(open)?))
+    }
+
+    // the inside of a ( ) whose `(` was at `open`, where `and` and `or` are logical again
+    fn group(&mut self, open: Span) -> PResult<Expr> {
+        let bitwise = std::mem::replace(&mut self.bitwise, false);
+        let inner = self.expr(false);
+        self.bitwise = bitwise;
+        let inner = inner?;
+
+        match self.peek() {
+            Some(Token::Rbrack) => {
+                self.bump();
+                Ok(inner)
+            },
+            None => Err(self.err(open, "missing a `)` to close this")),
+            _ => Err(self.err(self.span(), &format!("expected `)`, found {}", self.found()))),
+        }
+    }
+
+    // the whole of a [], nothing can be left over
+    fn maths_only(mut self) -> PResult<Expr> {
+        let expr = self.expr(false)?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(Token::Equal | Token::SmallerThan | Token::BiggerThan) => {
+                Err(self.err(self.span(), "comparisons can't go inside [], put them in ( ) instead"))
+            },
+            _ => Err(self.err(self.span(), &format!("expected an operator, found {}", self.found()))),
+        }
+    }
+}
+
+fn binary(op: BinOp, lhs: Expr, rhs: Expr) -> Expr {
+    let span = lhs.span.to(rhs.span);
+    Expr { kind: ExprKind::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) }, span }
+}
@@ -0,0 +1,1643 @@
+// `impulse bindgen`, reads a c header and writes the .imp module that binds it
+// the header is read as written without running the preprocessor. structs, unions, enums, typedefs,
+// #define constants and function prototypes are bound, anything that can't be is left as a `# skipped` comment
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::diagnostic::Diagnostic;
+
+// fields and parameters with these names get a `_` after them
+const KEYWORDS: [&str; 37] = [
+    "u8", "i8", "char", "u16", "i16", "u32", "i32", "uint", "int", "u64", "i64", "usize", "f32", "f64", "bool",
+    "typeid", "any", "let", "if", "orif", "else", "switch", "case", "fall", "loop", "for", "return", "break",
+    "continue", "struct", "enum", "defer", "true", "false", "and", "or", "cast",
+];
+
+const PRIM_WORDS: [&str; 11] = ["void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "_Bool", "bool"];
+
+// skipped wherever they are, they don't change what the type is to impulse
+const QUALIFIERS: [&str; 16] = [
+    "const", "volatile", "restrict", "__restrict", "__restrict__", "register", "extern", "static",
+    "inline", "__inline", "__inline__", "__forceinline", "__extension__", "_Noreturn", "__cdecl", "__stdcall",
+];
+const ATTRIBUTES: [&str; 6] = ["__attribute__", "__attribute", "__declspec", "__asm__", "__asm", "asm"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Num(String),
+    Str(String),
+    Char(String),
+    Punct(String),
+    Define(String, Vec<CToken>), // an object-like #define and its body
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CToken {
+    tok: Tok,
+    line: u32,
+}
+
+impl CToken {
+    fn text(&self) -> &str {
+        match &self.tok {
+            Tok::Ident(text) | Tok::Num(text) | Tok::Str(text) | Tok::Char(text) | Tok::Punct(text) => text,
+            Tok::Define(..) => "",
+        }
+    }
+
+    fn is_word(&self) -> bool {
+        matches!(self.tok, Tok::Ident(_) | Tok::Num(_))
+    }
+}
+
+// the tokens as c, spaced only where two words would run together
+fn c_join<'a>(tokens: impl Iterator<Item = &'a CToken>) -> String {
+    let mut text = String::new();
+    let mut last_word = false;
+    for token in tokens {
+        if last_word && token.is_word() {
+            text.push(' ');
+        }
+        text.push_str(token.text());
+        last_word = token.is_word();
+    }
+
+    text
+}
+
+struct Comment {
+    text: String,
+    own_line: bool, // false when it's after code on the same line
+}
+
+struct Lexer<'a> {
+    file: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+    line: u32,
+    line_start: bool,
+    code_line: u32,
+    conditions: Vec<(bool, bool)>, // for every #if: is it on __cplusplus, are its lines skipped
+    tokens: Vec<CToken>,
+    comments: HashMap<u32, Comment>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(file: &'a str, source: &str) -> Lexer<'a> {
+        Lexer {
+            file,
+            chars: source.chars().collect(),
+            pos: 0,
+            line: 1,
+            line_start: true,
+            code_line: 0,
+            conditions: Vec::new(),
+            tokens: Vec::new(),
+            comments: HashMap::new(),
+        }
+    }
+
+    fn peek_at(&self, n: usize) -> char {
+        self.chars.get(self.pos + n).copied().unwrap_or('\0')
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn text(&self, start: usize) -> String {
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn skipping(&self) -> bool {
+        self.conditions.iter().any(|(_, skip)| *skip)
+    }
+
+    fn lex(mut self) -> Result<(Vec<CToken>, HashMap<u32, Comment>), Diagnostic> {
+        loop {
+            self.skip_space(false)?;
+            if self.at_end() {
+                break;
+            }
+
+            if self.peek_at(0) == '#' && self.line_start {
+                self.directive()?;
+                continue;
+            }
+
+            self.line_start = false;
+            let token = self.token()?;
+            if !self.skipping() {
+                self.tokens.push(token);
+            }
+        }
+
+        Ok((self.tokens, self.comments))
+    }
+
+    // whitespace and comments, inside a directive it stops at the end of the line
+    fn skip_space(&mut self, in_directive: bool) -> Result<(), Diagnostic> {
+        loop {
+            match (self.peek_at(0), self.peek_at(1)) {
+                (' ' | '\t' | '\r' | '\x0c', _) => self.pos += 1,
+                ('\\', '\n') => {
+                    self.pos += 2;
+                    self.line += 1;
+                },
+                ('\n', _) => {
+                    if in_directive {
+                        return Ok(());
+                    }
+                    self.pos += 1;
+                    self.line += 1;
+                    self.line_start = true;
+                },
+                ('/', '/') => {
+                    let start = self.pos;
+                    while !self.at_end() && self.peek_at(0) != '\n' {
+                        self.pos += 1;
+                    }
+                    let text = self.text(start);
+                    self.comment(&text, self.line);
+                },
+                ('/', '*') => {
+                    let (start, line) = (self.pos, self.line);
+                    self.pos += 2;
+                    while !(self.peek_at(0) == '*' && self.peek_at(1) == '/') {
+                        if self.at_end() {
+                            return Err(Diagnostic::error(self.file, line, "unterminated comment"));
+                        }
+                        if self.peek_at(0) == '\n' {
+                            self.line += 1;
+                        }
+                        self.pos += 1;
+                    }
+                    self.pos += 2;
+
+                    // comments over more than one line are usually licences and banners
+                    if line == self.line {
+                        let text = self.text(start);
+                        self.comment(&text, line);
+                    }
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn comment(&mut self, text: &str, line: u32) {
+        let text = text.trim_matches(|ch: char| ch == '/' || ch == '*' || ch.is_whitespace());
+        if self.skipping() || !text.chars().any(|ch| ch.is_alphanumeric()) {
+            return;
+        }
+
+        let own_line = self.code_line != line;
+        self.comments.entry(line).or_insert(Comment { text: text.to_string(), own_line });
+    }
+
+    fn token(&mut self) -> Result<CToken, Diagnostic> {
+        let line = self.line;
+        self.code_line = line;
+
+        let start = self.pos;
+        let ch = self.peek_at(0);
+        let tok = if ch.is_ascii_alphabetic() || ch == '_' {
+            while self.peek_at(0).is_ascii_alphanumeric() || self.peek_at(0) == '_' {
+                self.pos += 1;
+            }
+            Tok::Ident(self.text(start))
+        } else if ch.is_ascii_digit() || (ch == '.' && self.peek_at(1).is_ascii_digit()) {
+            let hex = ch == '0' && matches!(self.peek_at(1), 'x' | 'X');
+            loop {
+                let ch = self.peek_at(0);
+                let prev = if self.pos > start { self.chars[self.pos - 1] } else { ' ' };
+                let exponent = matches!(prev, 'p' | 'P') || (!hex && matches!(prev, 'e' | 'E'));
+                if ch.is_ascii_alphanumeric() || ch == '.' || ch == '_' || ((ch == '+' || ch == '-') && exponent) {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            Tok::Num(self.text(start))
+        } else if ch == '"' || ch == '\'' {
+            self.pos += 1;
+            while self.peek_at(0) != ch {
+                if self.at_end() || self.peek_at(0) == '\n' {
+                    return Err(Diagnostic::error(self.file, line, "unterminated string"));
+                }
+                if self.peek_at(0) == '\\' {
+                    self.pos += 1;
+                }
+                self.pos += 1;
+            }
+            self.pos += 1;
+
+            if ch == '"' {
+                Tok::Str(self.text(start))
+            } else {
+                Tok::Char(self.text(start))
+            }
+        } else {
+            let punct = ["...", "<<", ">>", "->", "==", "!=", "<=", ">=", "&&", "||", "##"].into_iter()
+                .find(|punct| punct.chars().enumerate().all(|(i, ch)| self.peek_at(i) == ch))
+                .map(String::from)
+                .unwrap_or(ch.to_string());
+            self.pos += punct.chars().count();
+            Tok::Punct(punct)
+        };
+
+        Ok(CToken { tok, line })
+    }
+
+    // what's left of a directive's line, with its continuations
+    fn rest_of_line(&mut self) -> String {
+        let start = self.pos;
+        while !self.at_end() && self.peek_at(0) != '\n' {
+            if self.peek_at(0) == '\\' && self.peek_at(1) == '\n' {
+                self.pos += 1;
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+
+        self.text(start)
+    }
+
+    fn directive(&mut self) -> Result<(), Diagnostic> {
+        let line = self.line;
+        self.code_line = line;
+        self.pos += 1;
+        self.skip_space(true)?;
+
+        let start = self.pos;
+        while self.peek_at(0).is_ascii_alphabetic() {
+            self.pos += 1;
+        }
+
+        let name = self.text(start);
+        match name.as_str() {
+            "define" if !self.skipping() => self.define(line)?,
+            "if" | "ifdef" | "ifndef" => {
+                // the extern "C" { } around a header is only there for c++
+                let condition = self.rest_of_line();
+                let on_cpp = condition.contains("__cplusplus");
+                let negated = name == "ifndef" || condition.trim_start().starts_with('!');
+                self.conditions.push((on_cpp, on_cpp && !negated));
+            },
+            "elif" | "else" => {
+                self.rest_of_line();
+                if let Some((true, skip)) = self.conditions.last_mut() {
+                    *skip = !*skip;
+                }
+            },
+            "endif" => {
+                self.rest_of_line();
+                self.conditions.pop();
+            },
+            _ => {
+                self.rest_of_line();
+            },
+        }
+
+        Ok(())
+    }
+
+    fn define(&mut self, line: u32) -> Result<(), Diagnostic> {
+        self.skip_space(true)?;
+        let start = self.pos;
+        while self.peek_at(0).is_ascii_alphanumeric() || self.peek_at(0) == '_' {
+            self.pos += 1;
+        }
+
+        // function-like macros can't be bound
+        let name = self.text(start);
+        if name.is_empty() || self.peek_at(0) == '(' {
+            self.rest_of_line();
+            return Ok(());
+        }
+
+        let mut body = Vec::new();
+        loop {
+            self.skip_space(true)?;
+            if self.at_end() || self.peek_at(0) == '\n' {
+                break;
+            }
+            body.push(self.token()?);
+        }
+
+        self.tokens.push(CToken { tok: Tok::Define(name, body), line });
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum CType {
+    Void,
+    Prim(&'static str, String), // the impulse type and how c spelt it
+    Named(String), // a typedef, or `struct X`, `union X` and `enum X`. `#n` is the anonymous one at items[n]
+    Ptr(Box<CType>),
+    FnPtr, // bound as ^_
+}
+
+// how c spells it, for casting the impulse value to it
+fn c_text(typ: &CType) -> String {
+    match typ {
+        CType::Void => String::from("void"),
+        CType::Prim(_, c) | CType::Named(c) => c.to_owned(),
+        CType::Ptr(inner) => format!("{}*", c_text(inner)),
+        CType::FnPtr => String::from("void*"),
+    }
+}
+
+// typedefs from the c standard headers that have an impulse type
+fn std_type(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "size_t" => "usize",
+        "int8_t" => "i8",
+        "uint8_t" => "u8",
+        "int16_t" => "i16",
+        "uint16_t" => "u16",
+        "int32_t" => "i32",
+        "uint32_t" => "u32",
+        "int64_t" => "i64",
+        "uint64_t" => "u64",
+        _ => return None,
+    })
+}
+
+// long is taken as 64 bits like it is on linux and macos
+fn prim(words: &[String]) -> Result<CType, String> {
+    let has = |word: &str| words.iter().any(|w| w == word);
+    let longs = words.iter().filter(|word| *word == "long").count();
+    let unsigned = has("unsigned");
+
+    let imp = if has("void") {
+        return Ok(CType::Void);
+    } else if has("_Bool") || has("bool") {
+        "bool"
+    } else if has("float") {
+        "f32"
+    } else if has("double") {
+        if longs > 0 {
+            return Err(String::from("long double has no impulse type"));
+        }
+        "f64"
+    } else if has("char") {
+        if unsigned { "u8" } else if has("signed") { "i8" } else { "char" }
+    } else if has("short") {
+        if unsigned { "u16" } else { "i16" }
+    } else if longs > 0 {
+        if unsigned { "u64" } else { "i64" }
+    } else if unsigned {
+        "uint"
+    } else {
+        "int"
+    };
+
+    Ok(CType::Prim(imp, words.join(" ")))
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Int(i64),
+    Float(f64, bool), // whether it's an f32
+    Str(String),
+    Compound(String, Vec<Value>), // `(Color){ 255, 0, 0, 255 }`
+}
+
+struct Declarator {
+    name: Option<String>,
+    name_pos: Option<usize>,
+    typ: CType,
+    params: Option<(Vec<Param>, bool)>, // a function, and whether it's variadic
+    dims: Vec<Vec<CToken>>,
+    line: u32,
+}
+
+struct Param {
+    name: Option<String>,
+    typ: CType,
+    c: String, // the type as the header wrote it, for casting to
+}
+
+struct Field {
+    name: String,
+    typ: CType,
+    line: u32,
+}
+
+struct Record {
+    name: Option<String>,
+    tag: Option<String>,
+    union: bool,
+    fields: Option<Vec<Field>>, // none when it's only declared, which binds it as an empty struct
+    error: Option<String>,
+    line: u32,
+}
+
+impl Record {
+    fn name(&self) -> Option<&String> {
+        self.name.as_ref().or(self.tag.as_ref())
+    }
+
+    fn spellings(&self) -> Vec<String> {
+        let keyword = if self.union { "union" } else { "struct" };
+        self.tag.iter().map(|tag| format!("{keyword} {tag}")).chain(self.name.clone()).collect()
+    }
+}
+
+struct Enum {
+    name: Option<String>,
+    tag: Option<String>,
+    variants: Vec<(String, i64, u32)>,
+    error: Option<String>,
+    line: u32,
+}
+
+enum Item {
+    Record(Record),
+    Enum(Enum),
+    Alias { name: String, typ: CType, line: u32 },
+    Const { name: String, value: Value, line: u32 },
+    Func { name: String, ret: CType, params: Vec<Param>, variadic: bool, line: u32 },
+    Skipped(String),
+}
+
+struct Parser {
+    tokens: Vec<CToken>,
+    pos: usize,
+    items: Vec<Item>,
+    consts: HashMap<String, Value>,
+    decorators: HashSet<String>, // #defines like RLAPI that only mark up declarations
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|token| &token.tok)
+    }
+
+    fn is(&self, punct: &str) -> bool {
+        self.is_at(0, punct)
+    }
+
+    fn is_at(&self, n: usize, punct: &str) -> bool {
+        matches!(self.tokens.get(self.pos + n), Some(CToken { tok: Tok::Punct(p), .. }) if p == punct)
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident(w)) if w == word)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if self.is(punct) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        Err(format!("expected `{punct}`"))
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(Tok::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Some(name)
+            },
+            _ => None,
+        }
+    }
+
+    fn line(&self) -> u32 {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(0, |token| token.line)
+    }
+
+    fn parse(mut self) -> Vec<Item> {
+        while let Some(tok) = self.peek().cloned() {
+            match tok {
+                Tok::Define(name, body) => {
+                    let line = self.line();
+                    self.pos += 1;
+                    self.define(name, &body, line);
+                },
+                Tok::Punct(punct) if punct == ";" || punct == "}" => self.pos += 1,
+                Tok::Ident(word) if word == "extern" && matches!(self.tokens.get(self.pos + 1), Some(CToken { tok: Tok::Str(_), .. })) => {
+                    self.pos += 2;
+                    self.eat("{");
+                },
+                _ => {
+                    let start = self.pos;
+                    if let Err(reason) = self.declaration() {
+                        self.skip_declaration(start);
+                        self.items.push(Item::Skipped(reason));
+                    }
+                },
+            }
+        }
+
+        self.items
+    }
+
+    fn define(&mut self, name: String, body: &[CToken], line: u32) {
+        let decorates = match body.first().map(|token| token.text()) {
+            None => true,
+            Some(word) => QUALIFIERS.contains(&word) || ATTRIBUTES.contains(&word),
+        };
+        if decorates {
+            self.decorators.insert(name);
+            return;
+        }
+
+        // macros that aren't a value are skipped without a comment, most headers have lots of them
+        if let Some(value) = eval(body, &self.consts) {
+            self.consts.insert(name.clone(), value.clone());
+            self.items.push(Item::Const { name, value, line });
+        }
+    }
+
+    // a declaration that couldn't be bound, up to its `;` or the end of its function body
+    fn skip_declaration(&mut self, start: usize) {
+        self.pos = start;
+        let mut depth = 0;
+        let mut function_body = false;
+        while let Some(tok) = self.peek() {
+            let punct = match tok {
+                Tok::Punct(punct) => punct.as_str(),
+                _ => "",
+            };
+            match punct {
+                "(" | "[" => depth += 1,
+                ")" | "]" => depth -= 1,
+                "{" => {
+                    function_body = depth == 0 && self.pos > start && self.tokens[self.pos - 1].text() == ")";
+                    depth += 1;
+                },
+                "}" => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                    if depth == 0 && function_body {
+                        self.pos += 1;
+                        return;
+                    }
+                },
+                ";" if depth == 0 => {
+                    self.pos += 1;
+                    return;
+                },
+                _ => (),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn skip_balanced(&mut self, open: &str, close: &str) {
+        let mut depth = 0;
+        while self.peek().is_some() {
+            if self.is(open) {
+                depth += 1;
+            } else if self.is(close) {
+                depth -= 1;
+                if depth == 0 {
+                    self.pos += 1;
+                    return;
+                }
+            }
+            self.pos += 1;
+        }
+    }
+
+    // const, static, __attribute__((...)) and the header's own markers like RLAPI
+    fn specifiers(&mut self) {
+        while let Some(Tok::Ident(word)) = self.peek() {
+            if ATTRIBUTES.contains(&word.as_str()) {
+                self.pos += 1;
+                if self.is("(") {
+                    self.skip_balanced("(", ")");
+                }
+            } else if QUALIFIERS.contains(&word.as_str()) || self.decorators.contains(word) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> Result<(), String> {
+        let line = self.line();
+        let typedef = self.is_word("typedef");
+        if typedef {
+            self.pos += 1;
+        }
+
+        self.specifiers();
+        let mut base = self.base_type().map_err(|why| format!("line {line}, {why}"))?;
+        // `struct X {...};` and `enum {...};` have nothing after them
+        if self.eat(";") {
+            return Ok(());
+        }
+
+        loop {
+            let decl = self.declarator(base.clone()).map_err(|why| format!("line {line}, {why}"))?;
+            if typedef {
+                self.typedef(decl)?;
+
+                // `typedef struct {...} X, *PX;`, the rest call the struct by its new name
+                if let CType::Named(spelling) = &base {
+                    if let Some(index) = spelling.strip_prefix('#').and_then(|index| index.parse::<usize>().ok()) {
+                        let name = match &self.items[index] {
+                            Item::Record(record) => record.name.clone(),
+                            Item::Enum(enumeration) => enumeration.name.clone(),
+                            _ => None,
+                        };
+                        if let Some(name) = name {
+                            base = CType::Named(name);
+                        }
+                    }
+                }
+            } else if let Some((params, variadic)) = decl.params {
+                let name = decl.name.ok_or(format!("line {line}, a function without a name"))?;
+                self.items.push(Item::Func { name, ret: decl.typ, params, variadic, line: decl.line });
+
+                // static inline functions in the header still get wrapped
+                if self.is("{") {
+                    self.skip_balanced("{", "}");
+                    return Ok(());
+                }
+            } else if let Some(name) = decl.name {
+                self.items.push(Item::Skipped(format!("{name}, only #define constants are bound, not variables")));
+            }
+
+            if !self.eat(",") {
+                break;
+            }
+        }
+
+        self.expect(";").map_err(|why| format!("line {line}, {why}"))
+    }
+
+    fn base_type(&mut self) -> Result<CType, String> {
+        let mut words = Vec::new();
+        let mut named = None;
+        loop {
+            self.specifiers();
+            let Some(Tok::Ident(word)) = self.peek().cloned() else {
+                break;
+            };
+
+            if PRIM_WORDS.contains(&word.as_str()) && named.is_none() {
+                words.push(word);
+                self.pos += 1;
+            } else if named.is_none() && words.is_empty() {
+                self.pos += 1;
+                named = Some(match word.as_str() {
+                    "struct" | "union" => self.record(word == "union")?,
+                    "enum" => self.enumeration()?,
+                    _ => CType::Named(word),
+                });
+            } else {
+                break;
+            }
+        }
+
+        match named {
+            Some(typ) => Ok(typ),
+            None if words.is_empty() => Err(String::from("expected a type")),
+            None => prim(&words),
+        }
+    }
+
+    fn declarator(&mut self, mut typ: CType) -> Result<Declarator, String> {
+        while self.eat("*") {
+            typ = CType::Ptr(Box::new(typ));
+            self.specifiers();
+        }
+
+        let mut line = self.line();
+        if self.is("(") && self.is_at(1, "*") {
+            // a function pointer, `void (*name)(int)`
+            self.pos += 2;
+            self.specifiers();
+            let name_pos = Some(self.pos);
+            let name = self.ident();
+            self.expect(")")?;
+            self.params()?;
+            return Ok(Declarator { name_pos: name.as_ref().and(name_pos), name, typ: CType::FnPtr, params: None, dims: Vec::new(), line });
+        }
+
+        let mut name_pos = None;
+        let mut name = None;
+        if let Some(Tok::Ident(word)) = self.peek() {
+            name = Some(word.clone());
+            name_pos = Some(self.pos);
+            line = self.line();
+            self.pos += 1;
+        }
+
+        let mut params = None;
+        if self.is("(") {
+            params = Some(self.params()?);
+            self.specifiers();
+        }
+
+        let mut dims = Vec::new();
+        while self.eat("[") {
+            let start = self.pos;
+            while !self.is("]") {
+                if self.peek().is_none() {
+                    return Err(String::from("missing a `]`"));
+                }
+                self.pos += 1;
+            }
+            dims.push(self.tokens[start..self.pos].to_vec());
+            self.pos += 1;
+        }
+
+        Ok(Declarator { name, name_pos, typ, params, dims, line })
+    }
+
+    fn params(&mut self) -> Result<(Vec<Param>, bool), String> {
+        self.expect("(")?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+
+        // `(void)` takes nothing
+        if self.is_word("void") && self.is_at(1, ")") {
+            self.pos += 1;
+        }
+
+        while !self.eat(")") {
+            if self.peek().is_none() {
+                return Err(String::from("missing a `)`"));
+            }
+
+            if self.eat("...") {
+                variadic = true;
+            } else {
+                let start = self.pos;
+                self.specifiers();
+                let base = self.base_type()?;
+                let decl = self.declarator(base)?;
+
+                // arrays and functions as parameters are pointers
+                let (typ, c) = if decl.params.is_some() {
+                    (CType::FnPtr, String::from("void*"))
+                } else if !decl.dims.is_empty() {
+                    let typ = CType::Ptr(Box::new(decl.typ));
+                    let c = c_text(&typ);
+                    (typ, c)
+                } else {
+                    let c = c_join((start..self.pos).filter(|&i| Some(i) != decl.name_pos).map(|i| &self.tokens[i]));
+                    (decl.typ, c)
+                };
+                params.push(Param { name: decl.name, typ, c });
+            }
+
+            if !self.is(")") {
+                self.expect(",")?;
+            }
+        }
+
+        Ok((params, variadic))
+    }
+
+    fn record(&mut self, union: bool) -> Result<CType, String> {
+        let line = self.tokens[self.pos - 1].line;
+        let keyword = if union { "union" } else { "struct" };
+        let tag = self.ident();
+        if !self.is("{") {
+            return match tag {
+                Some(tag) => Ok(CType::Named(format!("{keyword} {tag}"))),
+                None => Err(format!("expected a {keyword} name")),
+            };
+        }
+        self.pos += 1;
+
+        let mut fields = Vec::new();
+        let mut error = None;
+        while !self.eat("}") {
+            if self.peek().is_none() {
+                return Err(format!("missing a `}}` to close the {keyword}"));
+            }
+
+            let start = self.pos;
+            if let Err(why) = self.field(&mut fields) {
+                error.get_or_insert(why);
+                self.skip_declaration(start);
+            }
+        }
+
+        // `typedef struct X X;` came first, it's defined here instead
+        let mut name = None;
+        if let Some(tag) = &tag {
+            let declared = self.items.iter().position(|item| {
+                matches!(item, Item::Record(record) if record.fields.is_none() && record.union == union && record.tag.as_ref() == Some(tag))
+            });
+            if let Some(Item::Record(record)) = declared.map(|index| self.items.remove(index)) {
+                name = record.name;
+            }
+        }
+
+        let spelling = match &tag {
+            Some(tag) => format!("{keyword} {tag}"),
+            None => format!("#{}", self.items.len()),
+        };
+        self.items.push(Item::Record(Record { name, tag, union, fields: Some(fields), error, line }));
+        Ok(CType::Named(spelling))
+    }
+
+    fn field(&mut self, fields: &mut Vec<Field>) -> Result<(), String> {
+        let base = self.base_type()?;
+        if matches!(&base, CType::Named(spelling) if spelling.starts_with('#')) {
+            return Err(String::from("nested anonymous structs and unions aren't supported"));
+        }
+
+        loop {
+            let decl = self.declarator(base.clone())?;
+            let name = decl.name.ok_or(String::from("a field without a name"))?;
+            if self.is(":") {
+                return Err(format!("{name} is a bitfield, they aren't supported"));
+            }
+            if decl.params.is_some() {
+                return Err(format!("{name} is a function"));
+            }
+
+            // impulse's arrays are an array_T, not c's layout
+            if !decl.dims.is_empty() {
+                return Err(format!("{name} is an array, they aren't supported"));
+            }
+            fields.push(Field { name, typ: decl.typ, line: decl.line });
+
+            if !self.eat(",") {
+                break;
+            }
+        }
+
+        self.expect(";")
+    }
+
+    fn enumeration(&mut self) -> Result<CType, String> {
+        let line = self.tokens[self.pos - 1].line;
+        let tag = self.ident();
+        if !self.is("{") {
+            return match tag {
+                Some(tag) => Ok(CType::Named(format!("enum {tag}"))),
+                None => Err(String::from("expected an enum name")),
+            };
+        }
+        self.pos += 1;
+
+        let mut variants = Vec::new();
+        let mut error = None;
+        let mut next = 0i64;
+        while !self.eat("}") {
+            let variant_line = self.line();
+            let Some(name) = self.ident() else {
+                return Err(String::from("expected an enum field"));
+            };
+
+            if self.eat("=") {
+                let start = self.pos;
+                let mut depth = 0;
+                while self.peek().is_some() && !(depth == 0 && (self.is(",") || self.is("}"))) {
+                    if self.is("(") {
+                        depth += 1;
+                    } else if self.is(")") {
+                        depth -= 1;
+                    }
+                    self.pos += 1;
+                }
+
+                match eval(&self.tokens[start..self.pos], &self.consts) {
+                    Some(Value::Int(value)) => next = value,
+                    _ => {
+                        error.get_or_insert(format!("can't work out the value of {name}"));
+                    },
+                }
+            }
+
+            self.consts.insert(name.clone(), Value::Int(next));
+            variants.push((name, next, variant_line));
+            next = next.wrapping_add(1);
+
+            if !self.is("}") {
+                self.expect(",")?;
+            }
+        }
+
+        let spelling = match &tag {
+            Some(tag) => format!("enum {tag}"),
+            None => format!("#{}", self.items.len()),
+        };
+        self.items.push(Item::Enum(Enum { name: None, tag, variants, error, line }));
+        Ok(CType::Named(spelling))
+    }
+
+    // the struct, union or enum a typedef names, when it doesn't have a name yet
+    fn unnamed(&self, spelling: &str) -> Option<usize> {
+        if let Some(index) = spelling.strip_prefix('#') {
+            return index.parse().ok();
+        }
+
+        self.items.iter().position(|item| match item {
+            Item::Record(record) => record.name.is_none() && record.spellings().iter().any(|s| s == spelling),
+            Item::Enum(enumeration) => enumeration.name.is_none() && enumeration.tag.as_ref().is_some_and(|tag| format!("enum {tag}") == spelling),
+            _ => false,
+        })
+    }
+
+    fn unnamed_at(&self, typ: &CType) -> Option<usize> {
+        match typ {
+            CType::Named(spelling) => self.unnamed(spelling),
+            _ => None,
+        }
+    }
+
+    fn declared(&self, tag: &str) -> bool {
+        self.items.iter().any(|item| matches!(item, Item::Record(record) if record.tag.as_deref() == Some(tag)))
+    }
+
+    fn typedef(&mut self, decl: Declarator) -> Result<(), String> {
+        let Some(name) = decl.name else {
+            return Err(format!("line {}, a typedef without a name", decl.line));
+        };
+        if decl.params.is_some() || !decl.dims.is_empty() {
+            return Err(format!("{name}, function and array typedefs aren't supported"));
+        }
+
+        // headers that define bool or the like themselves
+        if PRIM_WORDS.contains(&name.as_str()) || std_type(&name).is_some() {
+            if let Some(index) = self.unnamed_at(&decl.typ) {
+                self.items.remove(index);
+            }
+            return Ok(());
+        }
+
+        let named = match &decl.typ {
+            CType::Named(spelling) => Some(spelling),
+            CType::Ptr(inner) => match inner.as_ref() {
+                CType::Named(spelling) => Some(spelling),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(index) = self.unnamed_at(&decl.typ) {
+            match &mut self.items[index] {
+                Item::Record(record) => record.name = Some(name),
+                Item::Enum(enumeration) => enumeration.name = Some(name),
+                _ => (),
+            }
+            return Ok(());
+        }
+
+        // a struct that isn't defined yet, or ever, which leaves it an opaque handle
+        if let Some((keyword @ ("struct" | "union"), tag)) = named.and_then(|spelling| spelling.split_once(' ')) {
+            if !self.declared(tag) {
+                let union = keyword == "union";
+                let tag = Some(tag.to_string());
+                if let CType::Named(_) = decl.typ {
+                    self.items.push(Item::Record(Record { name: Some(name), tag, union, fields: None, error: None, line: decl.line }));
+                    return Ok(());
+                }
+                self.items.push(Item::Record(Record { name: None, tag, union, fields: None, error: None, line: decl.line }));
+            }
+        }
+
+        self.items.push(Item::Alias { name, typ: decl.typ, line: decl.line });
+        Ok(())
+    }
+}
+
+fn eval(tokens: &[CToken], consts: &HashMap<String, Value>) -> Option<Value> {
+    if let [CToken { tok: Tok::Str(text), .. }] = tokens {
+        return Some(Value::Str(text.clone()));
+    }
+
+    // a compound literal like `CLITERAL(Color){ 255, 0, 0, 255 }`, its type is the name before the {
+    if let Some(open) = tokens.iter().position(|token| token.text() == "{") {
+        if tokens.last()?.text() != "}" {
+            return None;
+        }
+
+        let mut before = open.checked_sub(1)?;
+        if tokens[before].text() == ")" {
+            before = before.checked_sub(1)?;
+        }
+        let Tok::Ident(typ) = &tokens[before].tok else {
+            return None;
+        };
+
+        let values = tokens[open + 1..tokens.len() - 1]
+            .split(|token| token.text() == ",")
+            .filter(|part| !part.is_empty())
+            .map(|part| match eval(part, consts)? {
+                value @ (Value::Int(_) | Value::Float(..)) => Some(value),
+                _ => None,
+            })
+            .collect::<Option<Vec<Value>>>()?;
+        return Some(Value::Compound(typ.clone(), values));
+    }
+
+    let mut eval = Eval { tokens, pos: 0, consts };
+    let value = eval.binary(1)?;
+    if eval.pos != tokens.len() {
+        return None;
+    }
+
+    Some(value)
+}
+
+fn number(text: &str) -> Option<Value> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        return u64::from_str_radix(hex.trim_end_matches(['u', 'l']), 16).ok().map(|n| Value::Int(n as i64));
+    }
+    if let Some(binary) = lower.strip_prefix("0b") {
+        return u64::from_str_radix(binary.trim_end_matches(['u', 'l']), 2).ok().map(|n| Value::Int(n as i64));
+    }
+
+    if lower.contains(['.', 'e']) || lower.ends_with('f') {
+        let is_f32 = lower.ends_with('f');
+        return lower.trim_end_matches(['f', 'l']).parse::<f64>().ok().map(|n| Value::Float(n, is_f32));
+    }
+
+    let digits = lower.trim_end_matches(['u', 'l']);
+    let radix = if digits.len() > 1 && digits.starts_with('0') { 8 } else { 10 };
+    u64::from_str_radix(digits, radix).ok().map(|n| Value::Int(n as i64))
+}
+
+fn precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "|" => 1,
+        "^" => 2,
+        "&" => 3,
+        "<<" | ">>" => 4,
+        "+" | "-" => 5,
+        "*" | "/" | "%" => 6,
+        _ => return None,
+    })
+}
+
+fn apply(op: &str, left: Value, right: Value) -> Option<Value> {
+    match (left, right) {
+        (Value::Int(a), Value::Int(b)) => Some(Value::Int(match op {
+            "|" => a | b,
+            "^" => a ^ b,
+            "&" => a & b,
+            "<<" => a.checked_shl(u32::try_from(b).ok()?)?,
+            ">>" => a.checked_shr(u32::try_from(b).ok()?)?,
+            "+" => a.checked_add(b)?,
+            "-" => a.checked_sub(b)?,
+            "*" => a.checked_mul(b)?,
+            "/" => a.checked_div(b)?,
+            "%" => a.checked_rem(b)?,
+            _ => return None,
+        })),
+        (left, right) => {
+            // an int doesn't make an f32 into an f64
+            let float = |value: Value| match value {
+                Value::Int(n) => Some((n as f64, true)),
+                Value::Float(n, is_f32) => Some((n, is_f32)),
+                _ => None,
+            };
+            let ((a, a_f32), (b, b_f32)) = (float(left)?, float(right)?);
+            Some(Value::Float(match op {
+                "+" => a + b,
+                "-" => a - b,
+                "*" => a * b,
+                "/" => a / b,
+                _ => return None,
+            }, a_f32 && b_f32))
+        },
+    }
+}
+
+// the constant expressions #defines, enum values and array lengths are written with
+struct Eval<'a> {
+    tokens: &'a [CToken],
+    pos: usize,
+    consts: &'a HashMap<String, Value>,
+}
+
+impl Eval<'_> {
+    fn peek(&self) -> &str {
+        self.tokens.get(self.pos).map_or("", |token| token.text())
+    }
+
+    fn binary(&mut self, min: u8) -> Option<Value> {
+        let mut left = self.unary()?;
+        while let Some(prec) = precedence(self.peek()) {
+            if prec < min {
+                break;
+            }
+            let op = self.peek().to_string();
+            self.pos += 1;
+            let right = self.binary(prec + 1)?;
+            left = apply(&op, left, right)?;
+        }
+
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<Value> {
+        let token = self.tokens.get(self.pos)?.tok.clone();
+        self.pos += 1;
+
+        match token {
+            Tok::Num(text) => number(&text),
+            Tok::Ident(name) => match self.consts.get(&name)? {
+                value @ (Value::Int(_) | Value::Float(..)) => Some(value.clone()),
+                _ => None,
+            },
+            Tok::Punct(punct) => match punct.as_str() {
+                "-" => match self.unary()? {
+                    Value::Int(n) => Some(Value::Int(n.checked_neg()?)),
+                    Value::Float(n, is_f32) => Some(Value::Float(-n, is_f32)),
+                    _ => None,
+                },
+                "+" => self.unary(),
+                "~" => match self.unary()? {
+                    Value::Int(n) => Some(Value::Int(!n)),
+                    _ => None,
+                },
+                "!" => match self.unary()? {
+                    Value::Int(n) => Some(Value::Int((n == 0) as i64)),
+                    _ => None,
+                },
+                "(" => {
+                    // a cast, `(float)` or `(unsigned int)`
+                    let start = self.pos;
+                    while PRIM_WORDS.contains(&self.peek()) {
+                        self.pos += 1;
+                    }
+                    if self.pos > start && self.peek() == ")" {
+                        let words: Vec<String> = self.tokens[start..self.pos].iter().map(|token| token.text().to_string()).collect();
+                        self.pos += 1;
+                        let value = self.unary()?;
+                        return match (prim(&words).ok()?, value) {
+                            (CType::Prim("f32", _), Value::Int(n)) => Some(Value::Float(n as f64, true)),
+                            (CType::Prim("f32", _), Value::Float(n, _)) => Some(Value::Float(n, true)),
+                            (CType::Prim("f64", _), Value::Int(n)) => Some(Value::Float(n as f64, false)),
+                            (CType::Prim("f64", _), Value::Float(n, _)) => Some(Value::Float(n, false)),
+                            (CType::Prim(..), Value::Float(n, _)) => Some(Value::Int(n as i64)),
+                            (CType::Prim(..), value) => Some(value),
+                            _ => None,
+                        };
+                    }
+
+                    self.pos = start;
+                    let value = self.binary(1)?;
+                    if self.peek() != ")" {
+                        return None;
+                    }
+                    self.pos += 1;
+                    Some(value)
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+enum Target {
+    Record(String),
+    Enum(String),
+    Alias(CType),
+}
+
+#[derive(PartialEq)]
+enum Kind {
+    Record,
+    Pointer,
+    Value,
+}
+
+// a name impulse can take
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        return format!("{name}_");
+    }
+    name.to_string()
+}
+
+// how the generator names an impulse type in c, `^rl.Image` is `rl__Image*`
+fn imp_c(typ: &str) -> String {
+    let base = typ.trim_start_matches('^');
+    let stars = "*".repeat(typ.len() - base.len());
+    match base {
+        "_" => format!("void{stars}"),
+        _ => format!("{}{stars}", base.replace('.', "__")),
+    }
+}
+
+fn float_text(n: f64) -> Option<String> {
+    if !n.is_finite() {
+        return None;
+    }
+
+    let text = n.to_string();
+    if text.contains('.') {
+        return Some(text);
+    }
+    Some(format!("{text}.0"))
+}
+
+// the part every field starts with, `KEY_A` and `KEY_B` are `A` and `B`, as long as what's left is still a name
+fn common_prefix(names: &[&str]) -> usize {
+    if names.len() < 2 {
+        return 0;
+    }
+
+    let first = names[0];
+    let mut len = first.len();
+    for name in &names[1..] {
+        len = len.min(first.bytes().zip(name.bytes()).take_while(|(a, b)| a == b).count());
+    }
+
+    let Some(len) = first[..len].rfind('_').map(|i| i + 1) else {
+        return 0;
+    };
+    let fits = names.iter().all(|name| {
+        let rest = &name[len..];
+        rest.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_') && !KEYWORDS.contains(&rest)
+    });
+
+    if fits { len } else { 0 }
+}
+
+struct Bindings<'a> {
+    prefix: &'a str,
+    types: HashMap<String, Target>,
+    broken: HashMap<String, String>, // structs that can't be bound and why
+    removed: HashSet<String>, // how c spells them
+    comments: &'a HashMap<u32, Comment>,
+}
+
+impl<'a> Bindings<'a> {
+    fn new(prefix: &'a str, items: &[Item], comments: &'a HashMap<u32, Comment>) -> Bindings<'a> {
+        let mut bindings = Bindings { prefix, types: HashMap::new(), broken: HashMap::new(), removed: HashSet::new(), comments };
+        for item in items {
+            match item {
+                Item::Record(record) => {
+                    let Some(name) = record.name() else { continue };
+                    for spelling in record.spellings() {
+                        bindings.types.insert(spelling, Target::Record(name.clone()));
+                    }
+                    if let Some(error) = &record.error {
+                        bindings.broken.insert(name.clone(), error.clone());
+                    }
+                },
+                Item::Enum(Enum { name, tag, error: None, .. }) => {
+                    let Some(name) = name.as_ref().or(tag.as_ref()) else { continue };
+                    if let Some(tag) = tag {
+                        bindings.types.insert(format!("enum {tag}"), Target::Enum(name.clone()));
+                    }
+                    bindings.types.insert(name.clone(), Target::Enum(name.clone()));
+                },
+                Item::Alias { name, typ, .. } => {
+                    bindings.types.insert(name.clone(), Target::Alias(typ.clone()));
+                },
+                _ => (),
+            }
+        }
+
+        // a struct with a field that can't be bound takes the structs that hold it by value with it
+        loop {
+            let mut changed = false;
+            for item in items {
+                let Item::Record(record) = item else { continue };
+                let Some(name) = record.name() else { continue };
+                if !bindings.broken.contains_key(name) {
+                    let fields = record.fields.iter().flatten();
+                    if let Some(error) = fields.map(|field| bindings.field_type(&field.typ)).find_map(|typ| typ.err()) {
+                        bindings.broken.insert(name.clone(), error);
+                    }
+                }
+
+                if bindings.broken.contains_key(name) && bindings.types.contains_key(name) {
+                    for spelling in record.spellings() {
+                        bindings.types.remove(&spelling);
+                        bindings.removed.insert(spelling);
+                    }
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        bindings
+    }
+
+    fn imp_type(&self, typ: &CType) -> Result<String, String> {
+        match typ {
+            CType::Void => Ok(String::from("_")),
+            CType::Prim(imp, _) => Ok(imp.to_string()),
+            CType::FnPtr => Ok(String::from("^_")),
+            CType::Ptr(inner) => Ok(format!("^{}", self.imp_type(inner)?)),
+            CType::Named(spelling) => match self.types.get(spelling) {
+                Some(Target::Record(name) | Target::Enum(name)) => Ok(format!("{}.{name}", self.prefix)),
+                // typedefs are bound as what they stand for so the type checker sees the same type either way
+                Some(Target::Alias(typ)) => self.imp_type(typ),
+                None if self.removed.contains(spelling) => Err(format!("{spelling} was skipped")),
+                None => std_type(spelling).map(String::from).ok_or(format!("unknown type {spelling}")),
+            },
+        }
+    }
+
+    fn field_type(&self, typ: &CType) -> Result<String, String> {
+        match self.imp_type(typ)? {
+            typ if typ == "_" => Err(String::from("void can't be a field")),
+            typ => Ok(typ),
+        }
+    }
+
+    fn kind(&self, typ: &CType) -> Kind {
+        match typ {
+            CType::Ptr(_) | CType::FnPtr => Kind::Pointer,
+            CType::Named(spelling) => match self.types.get(spelling) {
+                Some(Target::Record(_)) => Kind::Record,
+                Some(Target::Alias(typ)) => self.kind(typ),
+                _ => Kind::Value,
+            },
+            _ => Kind::Value,
+        }
+    }
+
+    // impulse gives c the same type, so it can be passed without a cast
+    fn same_in_c(&self, typ: &CType) -> bool {
+        match typ {
+            CType::Void | CType::Prim(..) => true,
+            CType::Ptr(to) => match to.as_ref() {
+                CType::Void | CType::Prim(..) => true,
+                CType::Ptr(_) => self.same_in_c(to),
+                _ => false,
+            },
+            CType::Named(spelling) => match self.types.get(spelling) {
+                Some(Target::Record(_)) => false,
+                Some(Target::Alias(typ)) => self.same_in_c(typ),
+                _ => true,
+            },
+            CType::FnPtr => false,
+        }
+    }
+
+    fn leading(&self, line: u32) -> String {
+        let mut lines = Vec::new();
+        let mut line = line;
+        while let Some(comment) = line.checked_sub(1).and_then(|above| self.comments.get(&above)) {
+            if !comment.own_line {
+                break;
+            }
+            lines.push(format!("# {}\n", comment.text));
+            line -= 1;
+        }
+
+        lines.reverse();
+        lines.concat()
+    }
+
+    fn trailing(&self, line: u32) -> String {
+        match self.comments.get(&line) {
+            Some(comment) if !comment.own_line => format!(" # {}", comment.text),
+            _ => String::new(),
+        }
+    }
+
+    fn write(&self, file: &str, items: &[Item]) -> String {
+        let mut out = format!("# generated from {file} by `impulse bindgen`, run it again instead of editing this\n\n");
+        out.push_str(&format!("@c [ #include \"{file}\" ];\n"));
+
+        let mut entries = Vec::new();
+        let mut functions = HashSet::new();
+        let mut written = HashSet::new();
+        let mut waiting: HashMap<String, Vec<(&str, u32, String)>> = HashMap::new();
+        for item in items {
+            let entry = match item {
+                Item::Record(record) => {
+                    let Some(text) = self.record(record) else { continue };
+
+                    // constants of it from before it was defined go after it
+                    let name = format!("{}.{}", self.prefix, record.name().unwrap());
+                    entries.push(("struct", record.line, text));
+                    entries.extend(waiting.remove(&name).unwrap_or_default());
+                    written.insert(name);
+                    continue;
+                },
+                Item::Enum(enumeration) => match self.enumeration(enumeration) {
+                    Ok(text) if enumeration.name.is_none() && enumeration.tag.is_none() => ("const", enumeration.line, text),
+                    Ok(text) => ("enum", enumeration.line, text),
+                    Err(reason) => ("skipped", 0, format!("# skipped {reason}\n")),
+                },
+                Item::Alias { name, typ, line } => {
+                    // only structs and enums get a typeid, other typedefs are written as what they stand for
+                    let Ok(target) = self.imp_type(typ) else { continue };
+                    if !target.contains('.') || target.starts_with('^') || target == format!("{}.{name}", self.prefix) {
+                        continue;
+                    }
+                    ("typeid", *line, format!("typeid {}.{name} :: {target};{}\n", self.prefix, self.trailing(*line)))
+                },
+                Item::Const { name, value, line } => {
+                    let Some(text) = self.constant(name, value) else { continue };
+                    let entry = ("const", *line, format!("{text}{}\n", self.trailing(*line)));
+                    if let Value::Compound(typ, _) = value {
+                        let typ = self.imp_type(&CType::Named(typ.clone())).unwrap_or_default();
+                        if !written.contains(&typ) {
+                            waiting.entry(typ).or_default().push(entry);
+                            continue;
+                        }
+                    }
+                    entry
+                },
+                Item::Func { name, ret, params, variadic, line } => {
+                    if !functions.insert(name) {
+                        continue;
+                    }
+                    match self.function(name, ret, params, *variadic) {
+                        Ok(text) => ("func", *line, format!("{text}{}\n", self.trailing(*line))),
+                        Err(reason) => ("skipped", 0, format!("# skipped {name}, {reason}\n")),
+                    }
+                },
+                Item::Skipped(reason) => ("skipped", 0, format!("# skipped {reason}\n")),
+            };
+            entries.push(entry);
+        }
+
+        let mut last = "";
+        for (kind, line, text) in entries {
+            // structs and enums get lines to themselves, runs of constants and functions stay together
+            let leading = if line > 0 { self.leading(line) } else { String::new() };
+            if kind != "skipped" {
+                if kind == "struct" || kind == "enum" || kind != last || !leading.is_empty() {
+                    out.push('\n');
+                }
+                last = kind;
+            }
+            out.push_str(&leading);
+            out.push_str(&text);
+        }
+
+        out
+    }
+
+    fn record(&self, record: &Record) -> Option<String> {
+        let name = record.name()?;
+        if let Some(reason) = self.broken.get(name) {
+            return Some(format!("# skipped {name}, {reason}\n"));
+        }
+
+        let shared = if record.union { "@shared " } else { "" };
+        let Some(fields) = &record.fields else {
+            return Some(format!("{shared}struct {}.{name} :: {{}}\n", self.prefix));
+        };
+
+        let mut text = format!("{shared}struct {}.{name} :: {{\n", self.prefix);
+        let mut last_line = 0;
+        for field in fields {
+            let typ = self.field_type(&field.typ).ok()?;
+            let name = ident(&field.name);
+
+            // `f32 x, y; // position` only comments the first
+            let trailing = if field.line == last_line { String::new() } else { self.trailing(field.line) };
+            last_line = field.line;
+            text.push_str(&format!("    {typ} {name};{trailing}\n"));
+        }
+        text.push_str("}\n");
+
+        Some(text)
+    }
+
+    fn enumeration(&self, enumeration: &Enum) -> Result<String, String> {
+        let name = enumeration.name.as_ref().or(enumeration.tag.as_ref());
+        if let Some(error) = &enumeration.error {
+            return Err(format!("{}, {error}", name.map_or("an enum", |name| name.as_str())));
+        }
+
+        // an enum without a name is a list of constants
+        let Some(name) = name else {
+            let mut text = String::new();
+            for (variant, value, line) in &enumeration.variants {
+                text.push_str(&format!("int {}.{variant} :: {value};{}\n", self.prefix, self.trailing(*line)));
+            }
+            return Ok(text);
+        };
+
+        let names: Vec<&str> = enumeration.variants.iter().map(|(variant, _, _)| variant.as_str()).collect();
+        let prefix = common_prefix(&names);
+
+        let mut text = format!("enum {}.{name} :: {{\n", self.prefix);
+        let mut next = 0;
+        for (variant, value, line) in &enumeration.variants {
+            let variant = ident(&variant[prefix..]);
+            let trailing = self.trailing(*line);
+            if *value == next {
+                text.push_str(&format!("    {variant};{trailing}\n"));
+            } else {
+                text.push_str(&format!("    {variant} :: {value};{trailing}\n"));
+            }
+            next = value.wrapping_add(1);
+        }
+        text.push_str("}\n");
+
+        Ok(text)
+    }
+
+    fn constant(&self, name: &str, value: &Value) -> Option<String> {
+        let prefix = self.prefix;
+        Some(match value {
+            Value::Int(n) if i32::try_from(*n).is_ok() => format!("int {prefix}.{name} :: {n};"),
+            Value::Int(n) => format!("i64 {prefix}.{name} :: {n};"),
+            Value::Float(n, true) => format!("f32 {prefix}.{name} :: {};", float_text(*n)?),
+            Value::Float(n, false) => format!("f64 {prefix}.{name} :: {};", float_text(*n)?),
+            Value::Str(text) => format!("^char {prefix}.{name} :: {text};"),
+            Value::Compound(typ, values) => {
+                let typ = CType::Named(typ.clone());
+                if self.kind(&typ) != Kind::Record {
+                    return None;
+                }
+
+                let values = values.iter().map(|value| match value {
+                    Value::Int(n) => Some(n.to_string()),
+                    Value::Float(n, _) => float_text(*n),
+                    _ => None,
+                }).collect::<Option<Vec<String>>>()?;
+                format!("{} {prefix}.{name} :: |{}|;", self.imp_type(&typ).ok()?, values.join(" "))
+            },
+        })
+    }
+
+    fn function(&self, name: &str, ret: &CType, params: &[Param], variadic: bool) -> Result<String, String> {
+        let mut decls = Vec::new();
+        let mut args = Vec::new();
+        for (i, param) in params.iter().enumerate() {
+            let typ = self.field_type(&param.typ)?;
+            let name = param.name.as_deref().map_or(format!("arg{i}"), ident);
+            decls.push(format!("{typ} {name}"));
+
+            // the c struct and the impulse one have the same layout but not the same name
+            args.push(match self.kind(&param.typ) {
+                Kind::Record => format!("*({}*)&{name}", param.c),
+                Kind::Pointer => format!("({}){name}", param.c),
+                Kind::Value => name,
+            });
+        }
+
+        // a wrapper can't pass `...` on, so c is called directly even when it returns a const pointer
+        let ret_typ = self.imp_type(ret)?;
+        if variadic {
+            if !params.iter().all(|param| self.same_in_c(&param.typ)) || !self.same_in_c(ret) {
+                return Err(String::from("variadic functions are called directly, so they can't take or return structs"));
+            }
+            decls.push(String::from("..."));
+            return Ok(format!("@extern {ret_typ} {}.{name} :: ({});", self.prefix, decls.join(" ")));
+        }
+
+        // pointers are returned through a wrapper too, c's might be const
+        if params.iter().all(|param| self.same_in_c(&param.typ)) && (ret_typ == "_" || self.kind(ret) == Kind::Value) {
+            return Ok(format!("@extern {ret_typ} {}.{name} :: ({});", self.prefix, decls.join(" ")));
+        }
+
+        let call = format!("{name}({})", args.join(", "));
+        let body = match self.kind(ret) {
+            _ if ret_typ == "_" => format!("{call};"),
+            Kind::Record => format!("{} ret__ = {call}; return *({}*)&ret__;", c_text(ret), imp_c(&ret_typ)),
+            Kind::Pointer => format!("return ({}){call};", imp_c(&ret_typ)),
+            Kind::Value => format!("return {call};"),
+        };
+
+        Ok(format!("@inline {ret_typ} {}.{name} :: ({}) {{@c [{body}];}}", self.prefix, decls.join(" ")))
+    }
+}
+
+// the header's file name as a namespace, `raylib.h` is `raylib`
+pub fn default_prefix(header: &str) -> String {
+    let stem = Path::new(header).file_stem().map_or(String::new(), |stem| stem.to_string_lossy().to_string());
+    let prefix: String = stem.chars().map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' }).collect();
+    match prefix.chars().next() {
+        Some(ch) if !ch.is_ascii_digit() => prefix,
+        _ => format!("_{prefix}"),
+    }
+}
+
+// `file` is the path the binding #includes, relative to where impulse builds like any other @c include
+pub fn generate(file: &str, source: &str, prefix: &str) -> Result<String, Diagnostic> {
+    let (tokens, comments) = Lexer::new(file, source).lex()?;
+    let parser = Parser { tokens, pos: 0, items: Vec::new(), consts: HashMap::new(), decorators: HashSet::new() };
+    let items = parser.parse();
+
+    let file = file.replace('\\', "/");
+    Ok(Bindings::new(prefix, &items, &comments).write(&file, &items))
+}
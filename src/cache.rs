@@ -0,0 +1,91 @@
+use std::{env, fs, hash::{DefaultHasher, Hash, Hasher}, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{parser::Expr, tokeniser::Span, vfs::FileSystem};
+
+// changes whenever impulse itself is rebuilt, so nothing made by an older compiler gets reused
+fn compiler_stamp() -> (u64, u128) {
+    let metadata = env::current_exe().and_then(|exe| exe.metadata());
+    match metadata {
+        Ok(metadata) => {
+            let modified = metadata.modified().ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_nanos());
+            (metadata.len(), modified)
+        },
+        Err(_) => (0, 0),
+    }
+}
+
+pub fn fingerprint<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    compiler_stamp().hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// everything importing a module added to the parser, saved so an unchanged import isn't parsed again
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ParsedModule {
+    pub sources: Vec<(String, u64)>, // every file that was read, with the fingerprint of its content
+    pub program: Vec<(Expr, String, Span)>,
+    pub imports: Vec<String>,
+    pub functions: Vec<Expr>,
+    pub structures: Vec<Expr>,
+    pub enums: Vec<Expr>,
+    pub enums_fields: Vec<Expr>,
+    pub global_vars: Vec<Expr>,
+}
+
+impl ParsedModule {
+    // a module that imports others is stale as soon as any of them changes
+    fn is_fresh(&self, files: &dyn FileSystem) -> bool {
+        self.sources.iter().all(|(path, hash)| {
+            files.read_to_string(Path::new(path)).is_ok_and(|content| fingerprint(&content) == *hash)
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(dir: PathBuf) -> ParseCache {
+        ParseCache { dir }
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{key:016x}.json"))
+    }
+
+    // the cache itself is always on disk, the sources it was made from are read from `files`
+    pub fn load(&self, key: u64, files: &dyn FileSystem) -> Option<ParsedModule> {
+        let content = fs::read_to_string(self.path(key)).ok()?;
+        let module: ParsedModule = serde_json::from_str(&content).ok()?;
+        module.is_fresh(files).then_some(module)
+    }
+
+    // the cache only saves time, failing to write it isn't worth stopping the build over
+    pub fn store(&self, key: u64, module: &ParsedModule) {
+        if fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+
+        if let Ok(content) = serde_json::to_string(module) {
+            let _ = fs::write(self.path(key), content);
+        }
+    }
+}
+
+// an object file is reused while the key written next to it still matches
+pub fn object_is_fresh(object: &Path, key: u64) -> bool {
+    object.exists() && fs::read_to_string(object.with_extension("key")).is_ok_and(|saved| saved.trim() == format!("{key:016x}"))
+}
+
+pub fn record_object(object: &Path, key: u64) {
+    let _ = fs::write(object.with_extension("key"), format!("{key:016x}\n"));
+}
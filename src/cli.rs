@@ -0,0 +1,370 @@
+use impulse::{declare_types::Lang, diagnostic::Diagnostic};
+
+const USAGE: &str = "\
+Usage:
+impulse <command> [arguments]
+
+-----------------------------------------------------
+
+COMMANDS:
+| init <directory>: initalise new project | impulse init . |
+| build [path]: build project | impulse build . |
+| run [path]: build and run project | impulse run . |
+| transpile [path]: generate c without compiling | impulse transpile . |
+| check [path]: report errors without generating c | impulse check . |
+| clean [path]: delete the build directory | impulse clean . |
+| bindgen <header>: generate an .imp binding for a c header | impulse bindgen raylib.h --prefix rl |
+
+run `impulse <command> --help` for the flags of each command
+
+-----------------------------------------------------
+";
+
+const INIT_HELP: &str = "\
+Usage:
+impulse init <directory>
+
+creates impulse.toml in <directory>, which build, run, transpile and check read for the
+entry file, binary name, c compiler, c flags per profile, libraries and vendored dependencies
+";
+
+const BUILD_HELP: &str = "\
+Usage:
+impulse build [path] [flags] [-- <c flags>]
+
+path defaults to the current directory, everything after `--` goes to the c compiler
+the generated c and the binary go in target/impulse/debug, or target/impulse/release with --release
+each .imp file is its own c file in <name>.units, only the ones that changed since the last build are recompiled
+
+FLAGS:
+| --out-dir <dir>: put the generated c and the binary here instead |
+| --file: name the binary after the entry file instead of the directory |
+| --out <name>: name of the binary |
+| --cc <compiler>: gcc, clang, tcc, cc or a path to one, defaults to $CC then gcc |
+| --target <triple>: cross compile, like x86_64-w64-mingw32 or aarch64-linux-gnu |
+| --release: build with optimisations instead of debug info |
+| --line-directives: map generated c back to .imp files for gcc and gdb |
+| --lib-path <dir>: look for base/ and vendor/ in <dir> first, can be given more than once |
+| --cfg <name>: keep the code under @cfg(name), can be given more than once |
+";
+
+const RUN_HELP: &str = "\
+Usage:
+impulse run [path] [flags] [-- <program arguments>]
+
+builds into target/impulse like build does then runs the binary
+everything after `--` is passed to the program, stdin is passed through and its exit code is impulse's
+
+FLAGS:
+| --out-dir <dir>: put the generated c and the binary here instead |
+| --file: name the binary after the entry file instead of the directory |
+| --out <name>: name of the binary |
+| --cc <compiler>: gcc, clang, tcc, cc or a path to one, defaults to $CC then gcc |
+| --release: build with optimisations instead of debug info |
+| --line-directives: map generated c back to .imp files for gcc and gdb |
+| --lib-path <dir>: look for base/ and vendor/ in <dir> first, can be given more than once |
+| --cfg <name>: keep the code under @cfg(name), can be given more than once |
+";
+
+const TRANSPILE_HELP: &str = "\
+Usage:
+impulse transpile [path] [flags]
+
+the generated c goes in target/impulse/debug
+
+FLAGS:
+| --lang <c|cpp>: language to generate, defaults to c |
+| --out <name>: name of the generated file without the extension, defaults to output |
+| --out-dir <dir>: put the generated c here instead |
+| --line-directives: map generated c back to .imp files for gcc and gdb |
+| --target <triple>: keep the @windows, @linux or @macos code of the os the triple is for |
+| --lib-path <dir>: look for base/ and vendor/ in <dir> first, can be given more than once |
+| --cfg <name>: keep the code under @cfg(name), can be given more than once |
+";
+
+const CHECK_HELP: &str = "\
+Usage:
+impulse check [path] [flags]
+
+tokenises and parses the project, reporting every error without generating c
+
+FLAGS:
+| --target <triple>: keep the @windows, @linux or @macos code of the os the triple is for |
+| --lib-path <dir>: look for base/ and vendor/ in <dir> first, can be given more than once |
+| --cfg <name>: keep the code under @cfg(name), can be given more than once |
+";
+
+const CLEAN_HELP: &str = "\
+Usage:
+impulse clean [path]
+
+deletes target/impulse in the project, which has every generated c file, binary and vendor copy
+";
+
+const BINDGEN_HELP: &str = "\
+Usage:
+impulse bindgen <header> [flags]
+
+reads the structs, unions, enums, typedefs, #define constants and function prototypes of a c header
+and prints an .imp module that binds them, anything it can't bind is left as a `# skipped` comment
+the module #includes the header by the path it was given, so run it from where you build
+
+FLAGS:
+| --prefix <name>: namespace of everything in the module, defaults to the header's name |
+| --out <file>: write the module here instead of printing it |
+";
+
+pub struct BindgenArgs {
+    pub header: String,
+    pub prefix: Option<String>,
+    pub out: Option<String>,
+}
+
+pub struct BuildArgs {
+    pub path: String,
+    pub out_dir: Option<String>,
+    pub as_file: bool,
+    pub out: Option<String>,
+    pub cc: Option<String>,
+    pub target: Option<String>,
+    pub release: bool,
+    pub line_directives: bool,
+    pub lang: Lang,
+    pub lib_paths: Vec<String>, // --lib-path can be given more than once
+    pub cfg: Vec<String>, // so can --cfg
+    pub extra_args: Vec<String>, // whatever came after `--`
+}
+
+impl BuildArgs {
+    fn new() -> BuildArgs {
+        BuildArgs {
+            path: String::from("."),
+            out_dir: None,
+            as_file: false,
+            out: None,
+            cc: None,
+            target: None,
+            release: false,
+            line_directives: false,
+            lang: Lang::C,
+            lib_paths: Vec::new(),
+            cfg: Vec::new(),
+            extra_args: Vec::new(),
+        }
+    }
+}
+
+pub enum Command {
+    Init(String),
+    Build(BuildArgs),
+    Run(BuildArgs),
+    Transpile(BuildArgs),
+    Check(BuildArgs),
+    Clean(BuildArgs),
+    Bindgen(BindgenArgs),
+    Help(&'static str),
+}
+
+fn flag_value(args: &[String], i: &mut usize, flag: &str, subcommand: &str) -> Result<String, Diagnostic> {
+    *i += 1;
+    match args.get(*i) {
+        Some(value) if !value.starts_with("--") => Ok(value.to_owned()),
+        _ => Err(Diagnostic::global(&format!("expected a value after {flag}"))
+            .with_note(&format!("run `impulse {subcommand} --help` to see how to use it"))),
+    }
+}
+
+// flags can come in any order, `allowed` is every flag the subcommand understands
+fn parse_build_args(subcommand: &str, args: &[String], allowed: &[&str]) -> Result<BuildArgs, Diagnostic> {
+    let mut build_args = BuildArgs::new();
+    let mut path: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+
+        // accept --flag=value as well as --flag value
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_owned())),
+            _ => (arg, None),
+        };
+
+        if flag.starts_with('-') && flag != "--" && !allowed.contains(&flag) {
+            return Err(Diagnostic::global(&format!("unknown flag {flag} for `impulse {subcommand}`"))
+                .with_note(&format!("run `impulse {subcommand} --help` to see the available flags")));
+        }
+
+        match flag {
+            "--" => {
+                build_args.extra_args = args[i+1..].to_vec();
+                break;
+            },
+            // the generated c is always kept in the build dir now, still accepted so old scripts work
+            "--keep" => (),
+            "--file" => build_args.as_file = true,
+            "--release" => build_args.release = true,
+            "--line-directives" => build_args.line_directives = true,
+            "--out" | "--out-dir" | "--cc" | "--target" | "--lang" | "--lib-path" | "--cfg" => {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => flag_value(args, &mut i, flag, subcommand)?,
+                };
+
+                match flag {
+                    "--out" => build_args.out = Some(value),
+                    "--out-dir" => build_args.out_dir = Some(value),
+                    "--cc" => build_args.cc = Some(value),
+                    "--target" => build_args.target = Some(value),
+                    "--lib-path" => build_args.lib_paths.push(value),
+                    "--cfg" => build_args.cfg.push(value),
+                    _ => {
+                        build_args.lang = match value.as_str() {
+                            "c" => Lang::C,
+                            "cpp" => Lang::Cpp,
+                            _ => return Err(Diagnostic::global(&format!("unknown language {value}, expected c or cpp"))),
+                        }
+                    },
+                }
+            },
+            _ => {
+                if path.is_some() {
+                    return Err(Diagnostic::global(&format!("unexpected argument {arg}, a path was already given"))
+                        .with_note("c flags go after `--`, like `impulse build . -- -lm`"));
+                }
+                path = Some(arg.to_owned());
+            },
+        }
+
+        i += 1;
+    }
+
+    if let Some(path) = path {
+        build_args.path = path;
+    }
+
+    Ok(build_args)
+}
+
+fn parse_bindgen_args(args: &[String]) -> Result<BindgenArgs, Diagnostic> {
+    let mut header = None;
+    let mut prefix = None;
+    let mut out = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_owned())),
+            _ => (arg, None),
+        };
+
+        match flag {
+            "--prefix" | "--out" => {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => flag_value(args, &mut i, flag, "bindgen")?,
+                };
+
+                if flag == "--prefix" {
+                    prefix = Some(value);
+                } else {
+                    out = Some(value);
+                }
+            },
+            _ if flag.starts_with('-') => {
+                return Err(Diagnostic::global(&format!("unknown flag {flag} for `impulse bindgen`"))
+                    .with_note("run `impulse bindgen --help` to see the available flags"));
+            },
+            _ => {
+                if header.is_some() {
+                    return Err(Diagnostic::global(&format!("unexpected argument {arg}, a header was already given")));
+                }
+                header = Some(arg.to_owned());
+            },
+        }
+
+        i += 1;
+    }
+
+    match header {
+        Some(header) => Ok(BindgenArgs { header, prefix, out }),
+        None => Err(Diagnostic::global("expected a header to generate bindings for")
+            .with_note("usage: impulse bindgen <header> [--prefix <name>] [--out <file>]")),
+    }
+}
+
+fn wants_help(args: &[String]) -> bool {
+    for arg in args {
+        if arg == "--" {
+            return false;
+        }
+
+        if arg == "--help" || arg == "-h" {
+            return true;
+        }
+    }
+
+    false
+}
+
+pub fn parse(args: &[String]) -> Result<Command, Diagnostic> {
+    let Some(subcommand) = args.first() else {
+        return Ok(Command::Help(USAGE));
+    };
+    let rest = &args[1..];
+
+    let help = match subcommand.as_str() {
+        "init" => INIT_HELP,
+        "build" => BUILD_HELP,
+        "run" => RUN_HELP,
+        "transpile" => TRANSPILE_HELP,
+        "check" => CHECK_HELP,
+        "clean" => CLEAN_HELP,
+        "bindgen" => BINDGEN_HELP,
+        "help" | "--help" | "-h" => {
+            // `impulse help build` is the same as `impulse build --help`
+            return match rest.first() {
+                Some(subcommand) => parse(&[subcommand.to_owned(), String::from("--help")]),
+                None => Ok(Command::Help(USAGE)),
+            };
+        },
+        unknown => {
+            return Err(Diagnostic::global(&format!("unknown command {unknown}"))
+                .with_note("run `impulse --help` to see the available commands"));
+        },
+    };
+
+    if wants_help(rest) {
+        return Ok(Command::Help(help));
+    }
+
+    let run_flags = ["--file", "--out", "--out-dir", "--cc", "--release", "--line-directives", "--lib-path", "--cfg"];
+    let build_flags = [&run_flags[..], &["--keep", "--target"]].concat();
+    match subcommand.as_str() {
+        "init" => match rest {
+            [dir] => Ok(Command::Init(dir.to_owned())),
+            _ => Err(Diagnostic::global("expected exactly one directory to initalise")
+                .with_note("usage: impulse init <directory>")),
+        },
+        "build" => Ok(Command::Build(parse_build_args("build", rest, &build_flags)?)),
+        "run" => Ok(Command::Run(parse_build_args("run", rest, &run_flags)?)),
+        "transpile" => {
+            // `impulse transpile c .` and `impulse transpile cpp .` from before --lang existed
+            let mut rest = rest.to_vec();
+            let mut lang = None;
+            if rest.len() > 1 && (rest[0] == "c" || rest[0] == "cpp") {
+                lang = Some(if rest[0] == "c" { Lang::C } else { Lang::Cpp });
+                rest.remove(0);
+            }
+
+            let mut build_args = parse_build_args("transpile", &rest, &["--lang", "--out", "--out-dir", "--target", "--line-directives", "--lib-path", "--cfg"])?;
+            if let Some(lang) = lang {
+                build_args.lang = lang;
+            }
+            Ok(Command::Transpile(build_args))
+        },
+        "check" => Ok(Command::Check(parse_build_args("check", rest, &["--target", "--lib-path", "--cfg"])?)),
+        "bindgen" => Ok(Command::Bindgen(parse_bindgen_args(rest)?)),
+        _ => Ok(Command::Clean(parse_build_args("clean", rest, &[])?)),
+    }
+}
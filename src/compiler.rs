@@ -0,0 +1,243 @@
+use std::{env, io::ErrorKind, path::Path, process::Command};
+
+use crate::diagnostic::Diagnostic;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilerKind {
+    Gcc,
+    Clang,
+    Tcc,
+    Cc, // the system compiler, or anything else that takes gcc style flags
+}
+
+impl CompilerKind {
+    // going by the executable name means full paths and prefixed cross compilers work too
+    fn detect(program: &str) -> CompilerKind {
+        let name = Path::new(program).file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if name.contains("clang") {
+            CompilerKind::Clang
+        } else if name.contains("tcc") {
+            CompilerKind::Tcc
+        } else if name.contains("gcc") {
+            CompilerKind::Gcc
+        } else {
+            CompilerKind::Cc
+        }
+    }
+}
+
+// what @link(os) and @cflags(os) can be for, named like std::env::consts::OS
+pub const OSES: [&str; 3] = ["windows", "linux", "macos"];
+
+// the os a target triple builds for, the one impulse runs on without a triple
+pub fn target_os(target: Option<&str>) -> &'static str {
+    match target {
+        Some(target) if target.contains("windows") || target.contains("mingw") => "windows",
+        Some(target) if target.contains("darwin") || target.contains("macos") => "macos",
+        Some(target) if target.contains("linux") => "linux",
+        Some(_) => "unknown",
+        None => env::consts::OS,
+    }
+}
+
+// what @windows, @linux, @macos and @cfg(name) are checked against while parsing
+#[derive(Debug, Clone, Hash)]
+pub struct Cfg {
+    pub target: Option<String>, // the triple the os came from
+    pub os: String,
+    pub names: Vec<String>, // from --cfg
+}
+
+impl Default for Cfg {
+    fn default() -> Cfg {
+        Cfg::new(None, &[])
+    }
+}
+
+impl Cfg {
+    pub fn new(target: Option<&str>, names: &[String]) -> Cfg {
+        Cfg { target: target.map(|target| target.to_string()), os: target_os(target).to_string(), names: names.to_vec() }
+    }
+
+    // `@windows` is `windows`, `@cfg(name)` is `cfg` and `name`
+    pub fn holds(&self, mac: &str, name: Option<&str>) -> bool {
+        match (mac, name) {
+            ("cfg", Some(name)) => self.names.iter().any(|given| given == name),
+            (os, _) => self.os == os,
+        }
+    }
+}
+
+// runs the c compiler directly with an argument list, no shell in between
+#[derive(Debug, Clone)]
+pub struct Compiler {
+    program: String,
+    kind: CompilerKind,
+    leading_args: Vec<String>, // CC="gcc -m32" puts -m32 here
+    target: Option<String>,
+    flags: Vec<String>,
+    pub(crate) line_directives: bool, // the c has #line in it, so errors already point at the .imp files
+}
+
+impl Default for Compiler {
+    fn default() -> Compiler {
+        Compiler {
+            program: String::from("gcc"),
+            kind: CompilerKind::Gcc,
+            leading_args: Vec::new(),
+            target: None,
+            flags: Vec::new(),
+            line_directives: false,
+        }
+    }
+}
+
+impl Compiler {
+    pub fn new(cc: &str, target: Option<&str>, flags: &[String]) -> Result<Compiler, Diagnostic> {
+        let mut words = cc.split_whitespace().map(|word| word.to_string());
+        let Some(mut program) = words.next() else {
+            return Err(Diagnostic::global("the c compiler can't be empty")
+                .with_note("use --cc, `cc` under [build] in impulse.toml or the CC environment variable"));
+        };
+        let kind = CompilerKind::detect(&program);
+
+        if let Some(target) = target {
+            let name = Path::new(&program).file_name().unwrap_or_default().to_string_lossy().to_string();
+
+            match kind {
+                // clang can target anything it was built with
+                CompilerKind::Clang => (),
+                // gcc needs a cross compiler, which is named after the triple
+                _ if name.starts_with(&format!("{target}-")) => (),
+                CompilerKind::Gcc | CompilerKind::Cc if program == "gcc" || program == "cc" => {
+                    program = format!("{target}-gcc");
+                },
+                CompilerKind::Tcc => {
+                    return Err(Diagnostic::global(&format!("tcc can't cross compile to {target}"))
+                        .with_note("use clang, or gcc with a cross compiler installed"));
+                },
+                _ => {
+                    return Err(Diagnostic::global(&format!("don't know how to cross compile to {target} with {program}"))
+                        .with_note(&format!("use clang, or point --cc at a cross compiler like {target}-gcc")));
+                },
+            }
+        }
+
+        Ok(Compiler {
+            program,
+            kind,
+            leading_args: words.collect(),
+            target: target.map(|target| target.to_string()),
+            flags: flags.to_vec(),
+            line_directives: false,
+        })
+    }
+
+    // the os the program is built for
+    pub fn target_os(&self) -> &str {
+        target_os(self.target.as_deref())
+    }
+
+    // flags the imported modules asked for, after the ones impulse was given
+    pub fn add_flags(&mut self, flags: &[String]) {
+        self.flags.extend(flags.iter().cloned());
+    }
+
+    fn start_args(&self) -> Vec<String> {
+        let mut args = self.leading_args.clone();
+
+        if let (CompilerKind::Clang, Some(target)) = (self.kind, &self.target) {
+            args.push(format!("--target={target}"));
+        }
+
+        args
+    }
+
+    pub fn args(&self, sources: &[String], out_file: &str) -> Vec<String> {
+        let mut args = self.start_args();
+        args.extend(sources.iter().cloned());
+        args.push(String::from("-o"));
+        args.push(out_file.to_string());
+        args.extend(self.flags.iter().cloned());
+        args
+    }
+
+    // -c for a single object, libraries and linker flags wait for the link
+    fn object_args(&self, source: &str, object: &str) -> Vec<String> {
+        let mut args = self.start_args();
+        args.extend([String::from("-c"), source.to_string(), String::from("-o"), object.to_string()]);
+        args.extend(self.flags.iter()
+            .filter(|flag| !flag.starts_with("-l") && !flag.starts_with("-L") && !flag.starts_with("-Wl,"))
+            .cloned());
+        args
+    }
+
+    // what would be typed into a shell, only for showing
+    fn format_command(&self, args: &[String]) -> String {
+        let mut line = quote(&self.program);
+        for arg in args {
+            line.push(' ');
+            line.push_str(&quote(arg));
+        }
+
+        line
+    }
+
+    pub fn command_line(&self, sources: &[String], out_file: &str) -> String {
+        self.format_command(&self.args(sources, out_file))
+    }
+
+    pub fn object_command_line(&self, source: &str, object: &str) -> String {
+        self.format_command(&self.object_args(source, object))
+    }
+
+    pub fn compile(&self, sources: &[String], out_file: &str) -> Result<(), Diagnostic> {
+        self.run(self.args(sources, out_file))
+    }
+
+    pub fn compile_object(&self, source: &str, object: &str) -> Result<(), Diagnostic> {
+        self.run(self.object_args(source, object))
+    }
+
+    // the command goes to stderr so it isn't mixed into what `impulse run` prints
+    fn run(&self, args: Vec<String>) -> Result<(), Diagnostic> {
+        eprintln!("{}", self.format_command(&args));
+
+        let status = match Command::new(&self.program).args(&args).status() {
+            Ok(status) => status,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(Diagnostic::global(&format!("unable to find c compiler {}", self.program))
+                    .with_note("install it or pick another with --cc, `cc` under [build] in impulse.toml or the CC environment variable"));
+            },
+            Err(e) => {
+                return Err(Diagnostic::global(&format!("failed to run {} with error: {e}", self.program)));
+            },
+        };
+
+        if status.success() {
+            return Ok(());
+        }
+
+        let how = match status.code() {
+            Some(code) => format!("exit code {code}"),
+            None => String::from("no exit code, it was killed"),
+        };
+
+        let failed = Diagnostic::global(&format!("{} failed to compile the generated c ({how})", self.program));
+        if self.line_directives {
+            return Err(failed);
+        }
+        Err(failed.with_note("build with --line-directives to have errors point at the .imp files instead of the generated c"))
+    }
+}
+
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+        return arg.to_string();
+    }
+
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -1,9 +1,13 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Lang {
     C,
     Cpp,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Types {
     U8,
     I8,
@@ -44,7 +48,6 @@ pub enum Types {
     },
 
     Pointer(Box<Types>),
-    Address,
 
     // Dynam(Box<Types>),
     Void,
@@ -56,6 +59,51 @@ pub enum Types {
     None,
 }
 
+// how the type is written in impulse, for error messages
+impl fmt::Display for Types {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Types::U8 => "u8",
+            Types::I8 => "i8",
+            Types::Char => "char",
+            Types::U16 => "u16",
+            Types::I16 => "i16",
+            Types::U32 => "u32",
+            Types::I32 => "i32",
+            Types::U64 => "u64",
+            Types::I64 => "i64",
+            Types::UInt => "uint",
+            Types::Int => "int",
+            Types::Usize => "usize",
+            Types::F32 => "f32",
+            Types::F64 => "f64",
+            Types::Bool => "bool",
+            Types::TypeId => "typeid",
+            Types::Any => "any",
+            Types::Let => "let",
+            Types::Void => "_",
+            Types::None => "nothing",
+            Types::Generic(name) => return write!(f, "${name}"),
+            Types::Arr { typ, length } => return write!(f, "[{length}]{typ}"),
+            Types::ArrIndex { arr_typ, .. } => match &**arr_typ {
+                Types::Arr { typ, .. } | Types::Pointer(typ) => return write!(f, "{typ}"),
+                other => return write!(f, "{other}"),
+            },
+            Types::Pointer(to) => return write!(f, "^{to}"),
+            Types::TypeDef { type_name, generics } => {
+                write!(f, "{}", type_name.trim_start_matches("struct "))?;
+                if let Some(generics) = generics.as_ref().filter(|generics| !generics.is_empty()) {
+                    let generics: Vec<String> = generics.iter().map(|generic| generic.to_string()).collect();
+                    write!(f, "[{}]", generics.join(" "))?;
+                }
+                return Ok(());
+            },
+        };
+
+        write!(f, "{name}")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Keyword {
     U8,
@@ -87,11 +135,6 @@ pub enum Keyword {
 
     Generic(String),
 
-    Println,
-    Print,
-    ReadIn,
-
-    Underscore,
     Return,
     Break,
     Continue,
@@ -108,7 +151,6 @@ pub enum Keyword {
     For,
 
     Pointer(Types, Types),
-    Address,
 
     Arr {
         typ: Box<Types>,
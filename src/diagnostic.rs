@@ -0,0 +1,215 @@
+use std::{collections::HashMap, fmt, path::Path};
+
+use crate::{tokeniser::Span, vfs::{FileSystem, RealFs}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    #[allow(dead_code)] // nothing in the pipeline warns yet
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn label(&self) -> &'static str {
+        match self {
+            Severity::Error => "\x1b[91merror\x1b[0m",
+            Severity::Warning => "\x1b[93mwarning\x1b[0m",
+            Severity::Note => "\x1b[96mnote\x1b[0m",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    pub line: u32,
+    pub column: u32, // 0 when the column isn't known
+    pub span: Option<Span>,
+    pub message: String,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, file: &str, line: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            severity,
+            file: file.to_owned(),
+            line,
+            column: 0,
+            span: None,
+            message: message.to_owned(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(file: &str, line: u32, message: &str) -> Diagnostic {
+        Diagnostic::new(Severity::Error, file, line, message)
+    }
+
+    // for errors that don't belong to a source file, like failing to read a directory
+    pub fn global(message: &str) -> Diagnostic {
+        Diagnostic::new(Severity::Error, "", 0, message)
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        self.line = span.line;
+        self.column = span.col;
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Diagnostic {
+        self.notes.push(note.to_owned());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    // the offending line with a caret underline, the span is cut off at the end of its first line
+    fn render_snippet(&self, source: &str) -> Option<String> {
+        let span = self.span?;
+        if span.start > source.len() {
+            return None;
+        }
+
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[line_start..].find('\n').map_or(source.len(), |i| line_start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+
+        let underline_end = span.end.clamp(span.start, line_start + line.len());
+        let padding: String = source[line_start..span.start].chars()
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat(source[span.start..underline_end].chars().count().max(1));
+
+        let gutter = " ".repeat(span.line.to_string().len());
+        Some(format!("{gutter} |\n{} | {line}\n{gutter} | {padding}\x1b[91m{carets}\x1b[0m", span.line))
+    }
+
+    fn write_header(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = self.severity.label();
+
+        if !self.file.is_empty() {
+            if self.column > 0 {
+                writeln!(f, "{label}: {}:{}:{}", self.file, self.line, self.column)?;
+            } else {
+                writeln!(f, "{label}: {}:{}", self.file, self.line)?;
+            }
+        }
+        write!(f, "{label}: {}", self.message)
+    }
+
+    fn write_notes(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for note in &self.notes {
+            write!(f, "\n{}: {note}", Severity::Note.label())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_header(f)?;
+        self.write_notes(f)
+    }
+}
+
+// a diagnostic together with the source it points into
+struct WithSource<'a>(&'a Diagnostic, Option<&'a String>);
+
+impl fmt::Display for WithSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let WithSource(diagnostic, source) = self;
+        diagnostic.write_header(f)?;
+
+        if let Some(snippet) = source.and_then(|source| diagnostic.render_snippet(source)) {
+            write!(f, "\n{snippet}")?;
+        }
+
+        diagnostic.write_notes(f)
+    }
+}
+
+// every stage pushes into this instead of exiting, the caller decides what to do with them
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics { items: Vec::new() }
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.items.push(diagnostic);
+    }
+
+    pub fn extend(&mut self, other: Diagnostics) {
+        self.items.extend(other.items);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(|d| d.is_error())
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.items.iter().filter(|d| d.is_error()).count()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.items.iter()
+    }
+
+    // every diagnostic with the line it points at, sources are read back through `fs`
+    pub fn render(&self, fs: &dyn FileSystem) -> String {
+        // sources are only read back in when there's something to point at
+        let mut sources: HashMap<&str, Option<String>> = HashMap::new();
+        let mut rendered = String::new();
+
+        for diagnostic in &self.items {
+            let source = if diagnostic.span.is_some() {
+                sources.entry(&diagnostic.file)
+                    .or_insert_with(|| fs.read_to_string(Path::new(&diagnostic.file)).ok())
+                    .as_ref()
+            } else {
+                None
+            };
+
+            rendered.push_str(&format!("{}\n", WithSource(diagnostic, source)));
+        }
+
+        let count = self.error_count();
+        if count == 1 {
+            rendered.push_str(&format!("{}: aborting due to previous error\n", Severity::Error.label()));
+        } else if count > 1 {
+            rendered.push_str(&format!("{}: aborting due to {count} previous errors\n", Severity::Error.label()));
+        }
+
+        rendered
+    }
+
+    pub fn report(&self) {
+        eprint!("{}", self.render(&RealFs));
+    }
+}
+
+impl From<Diagnostic> for Diagnostics {
+    fn from(diagnostic: Diagnostic) -> Diagnostics {
+        Diagnostics { items: vec![diagnostic] }
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
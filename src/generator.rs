@@ -1,22 +1,28 @@
-use std::{collections::HashMap, fs, process::{exit, Command}};
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}};
+use crate::cache::{fingerprint, object_is_fresh, record_object};
+use crate::compiler::Compiler;
 use crate::declare_types::*;
+use crate::diagnostic::Diagnostic;
 use crate::parser::*;
-use rand::Rng;
+use crate::tokeniser::Span;
 
 pub struct Gen {
     imports: String,
-    comp_imports:  String,
+    comp_imports: Vec<String>, // .c files of local imports, compiled alongside the generated c
+    pub source_dir: PathBuf, // where local .c imports are, the project directory
+    c_flags: Vec<String>, // from the @link and @cflags for the target's os
 
     indent: i32,
     code: String,
 
     in_file: String,
-    line_num: u32,
+    span: Option<Span>, // where the expression being generated came from
     out_file: String,
     compile: bool,
-    keep_gen: bool,
     lang: Lang,
-    c_flags: Vec<String>,
+    compiler: Compiler,
+    line_directives: bool, // emit #line so gcc and gdb point at the .imp files
+    last_line_directive: Option<(String, u32, usize, usize)>, // file, line and where it sits in code
 
     libc_map: HashMap<String, bool>,
     defs_location: Vec<usize>,
@@ -26,24 +32,72 @@ pub struct Gen {
 
     in_macro_func: bool,
     mutate_func_args: bool,
-    curl_rc: i32,
+
+    def_name: String, // function or macro being generated, names its loop iterators
+    loop_count: usize,
+
+    unit_items: Vec<UnitItem>,
+
+    // generic functions are made again for every set of types they're called with
+    templates: HashMap<String, Template>,
+    instances: HashSet<String>, // made or being made
+    pending: Vec<Instance>,
+    func_names: HashSet<String>,
+    func_types: HashMap<String, Types>,
+    links: HashMap<String, String>, // @extern functions, to the c function a call goes to
+    type_args: HashMap<String, Types>, // typeids of the instance being generated, any is under "any"
+    by_ref: Vec<String>, // @mut params of the instance, they're changed in place
+    func_prelude: String,
+    item_start: usize, // where the top-level item being generated starts, instances go in front of it
+    item_line: (String, u32),
 }
 
-fn rand_varname() -> String {
-    let alphabet = String::from("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ");
-    let mut varname = String::new();
-    let mut rng = rand::thread_rng();
+// a generic function as it was written, kept until it's called
+#[derive(Debug, Clone)]
+struct Template {
+    typ: Types,
+    params: Vec<Expr>,
+    body: Vec<(Expr, String, Span)>,
+    by_ref: Vec<String>,
+    file: String,
+    span: Span,
+}
 
-    for _ in 0..6 {
-        let r = rng.gen_range(0..alphabet.len());
-        varname.push(alphabet.chars().nth(r).unwrap());
-    }
+#[derive(Debug, Clone)]
+struct Instance {
+    template: String,
+    name: String,
+    type_args: HashMap<String, Types>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnitItemKind {
+    Func,
+    Global,
+    CEmbed,
+}
+
+// a top-level definition in the generated c, when building it goes in its module's translation
+// unit and the header shared by every unit only gets a declaration of it
+#[derive(Debug, Clone)]
+struct UnitItem {
+    kind: UnitItemKind,
+    file: String,
+    start: usize,
+    end: usize, // usize::MAX while it's still being generated
+}
 
-    varname
+// the header every unit includes, split up so a unit is only rebuilt when something it uses changes
+#[derive(Debug, Default)]
+struct Header {
+    text: String, // what's written to <out>.h
+    shared: String, // types, macros and embeds, every unit depends on these
+    declarations: Vec<(String, String)>, // the name and declaration of each function and global
 }
 
 impl Gen {
-    pub fn new(in_file: &str, out_file: &str, compile: bool, keep_gen: bool, lang: Lang, c_flags: &[String]) -> Gen {
+    pub fn new(in_file: &str, out_file: &str, compile: bool, lang: Lang, mut compiler: Compiler, line_directives: bool) -> Gen {
+        compiler.line_directives = line_directives;
         let libc_map = HashMap::from([
             ("stdio".to_string(), true),
             ("stdlib".to_string(), true),
@@ -62,11 +116,13 @@ impl Gen {
             ("locale".to_string(), true),
         ]);
 
-        return Gen {
+        Gen {
             imports: String::new(),
-            comp_imports: String::new(),
+            comp_imports: Vec::new(),
+            source_dir: PathBuf::new(),
+            c_flags: Vec::new(),
 
-            line_num: 0,
+            span: None,
             indent: 0,
             code: String::new(),
 
@@ -74,9 +130,10 @@ impl Gen {
             out_file: out_file.to_string(),
 
             compile,
-            keep_gen,
             lang,
-            c_flags: c_flags.to_vec(),
+            compiler,
+            line_directives,
+            last_line_directive: None,
 
             libc_map,
             defs_location: Vec::new(),
@@ -84,13 +141,58 @@ impl Gen {
             default_structs: Vec::new(),
             in_macro_func: false,
             mutate_func_args: false,
-            curl_rc: 0,
+
+            def_name: String::new(),
+            loop_count: 0,
+
+            unit_items: Vec::new(),
+
+            templates: HashMap::new(),
+            instances: HashSet::new(),
+            pending: Vec::new(),
+            func_names: HashSet::new(),
+            func_types: HashMap::new(),
+            links: HashMap::new(),
+            type_args: HashMap::new(),
+            by_ref: Vec::new(),
+            func_prelude: String::new(),
+            item_start: 0,
+            item_line: (String::new(), 0),
         }
     }
 
-    fn comp_err(&self, error_msg: &str) {
-        println!("\x1b[91merror\x1b[0m: {}:{}", self.in_file, self.line_num);
-        println!("\x1b[91merror\x1b[0m: {error_msg}");
+    fn comp_err(&self, error_msg: &str) -> Diagnostic {
+        match self.span {
+            Some(span) => Diagnostic::error(&self.in_file, span.line, error_msg).with_span(span),
+            None => Diagnostic::error(&self.in_file, 0, error_msg),
+        }
+    }
+
+    fn add_line_directive(&mut self, file: &str, line: u32) {
+        // a directive inside a macro would end the #define early
+        if !self.line_directives || self.in_macro_func || line == 0 {
+            return;
+        }
+
+        if let Some((last_file, last_line, start, end)) = &self.last_line_directive {
+            if last_file == file && *last_line == line {
+                return;
+            }
+
+            // nothing was generated since the last one so it can just be replaced
+            if *end == self.code.len() {
+                self.code.truncate(*start);
+            }
+        }
+
+        if !self.code.is_empty() && !self.code.ends_with('\n') {
+            self.code.push('\n');
+        }
+
+        let start = self.code.len();
+        let escaped = file.replace('\\', "\\\\").replace('"', "\\\"");
+        self.code.push_str(&format!("#line {line} \"{escaped}\"\n"));
+        self.last_line_directive = Some((file.to_string(), line, start, self.code.len()));
     }
 
     fn add_spaces(&mut self, indent: i32) {
@@ -109,7 +211,7 @@ impl Gen {
             }
         }
 
-        return false;
+        false
     }
 
     fn update_struct_definitions(&mut self, mut line_num: usize, offset: usize) {
@@ -118,14 +220,331 @@ impl Gen {
                 *i += offset;
             }
         }
+
+        if self.item_start >= line_num {
+            self.item_start += offset;
+        }
+
+        // code inserted right where an item starts goes before it, so it stays in the header
+        for item in self.unit_items.iter_mut() {
+            if item.start >= line_num {
+                item.start += offset;
+            }
+            if item.end != usize::MAX && item.end > line_num {
+                item.end += offset;
+            }
+        }
     }
 
-    fn generate_new_struct(&mut self, struct_name: &String, type_names: String, types: Vec<String>) {
+    // iterators are named after the function they're in so the same code always generates the same c
+    fn loop_name(&mut self) -> String {
+        let name = format!("{}_it{}", self.def_name, self.loop_count);
+        self.loop_count += 1;
+        name
+    }
+
+    fn open_unit_item(&mut self, expr: &Expr) {
+        if self.indent != 0 || self.in_macro_func {
+            return;
+        }
+
+        let kind = match expr {
+            Expr::Func { is_inline: false, link_name: None, .. } => UnitItemKind::Func,
+            Expr::CEmbed(_) => UnitItemKind::CEmbed,
+            Expr::Variable { info, .. } => match info.as_ref() {
+                // typeid variables become typedefs
+                Expr::VariableName { typ: Types::TypeId, .. } => return,
+                Expr::VariableName { .. } => UnitItemKind::Global,
+                _ => return,
+            },
+            Expr::VariableName { .. } => UnitItemKind::Global,
+            _ => return,
+        };
+
+        self.unit_items.push(UnitItem {
+            kind,
+            file: self.in_file.clone(),
+            start: self.code.len(),
+            end: usize::MAX,
+        });
+    }
+
+    // functions close once their body does, everything else right after it's generated
+    fn close_unit_item(&mut self) {
+        if let Some(item) = self.unit_items.last_mut() {
+            if item.end == usize::MAX && (item.kind != UnitItemKind::Func || self.indent == 0) {
+                item.end = self.code.len();
+            }
+        }
+    }
+
+    // copies the params changed in place back to the caller before the instance returns
+    fn write_back(&mut self) {
+        for name in self.by_ref.clone() {
+            self.add_spaces(self.indent);
+            self.code.push_str(&format!("*{name}__ref = {name};\n"));
+        }
+    }
+
+    // the instances the last item called go in front of it, it's cut off and written again after them
+    fn flush_instances(&mut self) -> Result<(), Diagnostic> {
+        let pending: Vec<Instance> = std::mem::take(&mut self.pending).into_iter()
+            .filter(|instance| !self.instances.contains(&instance.name))
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        // the item's #line goes with it
+        let mut start = self.item_start;
+        if let Some(directive) = last_line_directive(&self.code[..start]) {
+            start -= directive.len();
+        }
+        let (file, line) = self.item_line.clone();
+        let tail = self.code.split_off(start);
+        let items_at = self.unit_items.iter().position(|item| item.start >= start).unwrap_or(self.unit_items.len());
+        let mut tail_items = self.unit_items.split_off(items_at);
+        let defs_at = self.defs_location.iter().position(|def| *def >= start).unwrap_or(self.defs_location.len());
+        let mut tail_defs = self.defs_location.split_off(defs_at);
+        self.last_line_directive = None;
+
+        for instance in pending {
+            // it could have been made for one that came before it
+            if self.instances.insert(instance.name.clone()) {
+                self.generate_instance(instance)?;
+            }
+        }
+
+        if !tail.starts_with("#line ") {
+            self.add_line_directive(&file, line);
+        }
+        let offset = self.code.len() - start;
+        for item in tail_items.iter_mut() {
+            item.start += offset;
+            if item.end != usize::MAX {
+                item.end += offset;
+            }
+        }
+        for def in tail_defs.iter_mut() {
+            *def += offset;
+        }
+
+        self.unit_items.append(&mut tail_items);
+        self.defs_location.append(&mut tail_defs);
+        self.code.push_str(&tail);
+        self.item_start = start + offset;
+        self.last_line_directive = None;
+
+        Ok(())
+    }
+
+    fn generate_instance(&mut self, instance: Instance) -> Result<(), Diagnostic> {
+        let template = self.templates[&instance.template].clone();
+        let type_args = std::mem::replace(&mut self.type_args, instance.type_args);
+        let by_ref = std::mem::replace(&mut self.by_ref, template.by_ref.clone());
+
+        // typeids are typedefs inside so @c in the body can still use them
+        let mut prelude = String::new();
+        let mut params = Vec::new();
+        for param in template.params {
+            match &param {
+                Expr::VariableName { typ: Types::TypeId, name, .. } => {
+                    let typ = self.handle_typ(Types::Generic(name.clone()))?.0;
+                    if &typ != name {
+                        prelude.push_str(&format!("    typedef {typ} {name};\n"));
+                    }
+                    continue;
+                },
+                Expr::VariableName { typ, name, .. } if template.by_ref.contains(name) => {
+                    let typ = self.handle_typ(typ.clone())?.0;
+                    prelude.push_str(&format!("    {typ} {name} = *{name}__ref;\n"));
+                },
+                _ => (),
+            }
+            params.push(param);
+        }
+        self.func_prelude = prelude;
+
+        let mut expressions = vec![(Expr::Func { typ: template.typ, params, name: instance.name, is_inline: false, link_name: None, variadic: false }, template.file, template.span)];
+        expressions.extend(template.body);
+        let generated = self.generate_exprs(expressions);
+
+        self.type_args = type_args;
+        self.by_ref = by_ref;
+        generated
+    }
+
+    // a call to a generic function calls its instance for the types it was given instead, the
+    // typeids aren't passed and the params changed in place are passed by pointer
+    fn instantiate_call(&mut self, name: String, gave_params: Vec<Expr>) -> Result<(String, Vec<Expr>), Diagnostic> {
+        let Some(template) = self.templates.get(&name).cloned() else {
+            return Ok((name, gave_params));
+        };
+
+        let mut type_args = HashMap::new();
+        let mut type_names = Vec::new();
+        let mut any: Option<Types> = None;
+        let mut args = Vec::new();
+        for (i, (param, arg)) in template.params.iter().zip(gave_params).enumerate() {
+            let Expr::VariableName { typ, name: param_name, .. } = param else {
+                continue;
+            };
+
+            if let Types::TypeId = typ {
+                let given = self.type_arg(&arg)?;
+                type_names.push(self.handle_typ(given.clone())?.0.replace("*", "ptr"));
+                type_args.insert(param_name.clone(), given);
+                continue;
+            }
+
+            if takes_any(typ) {
+                let display_name = name.replace("__", ".");
+                let Some(given) = self.arg_type(&arg).and_then(|given| find_any(typ, &given)) else {
+                    return Err(self.comp_err(&format!("can't work out which type argument {} of {display_name} is, give it a variable first", i + 1)));
+                };
+
+                match &any {
+                    Some(first) if self.handle_typ(first.clone())?.0 != self.handle_typ(given.clone())?.0 => {
+                        return Err(self.comp_err(&format!("every any given to {display_name} has to be the same type, got {first} and {given}")));
+                    },
+                    Some(_) => (),
+                    None => any = Some(given),
+                }
+            }
+
+            args.push((param_name.clone(), typ.clone(), arg));
+        }
+
+        if let Some(given) = any {
+            type_names.push(self.handle_typ(given.clone())?.0.replace("*", "ptr"));
+            type_args.insert(String::from("any"), given);
+        }
+
+        // named like generic structs, unless a function already has that name
+        let mut instance_name = format!("{name}_{}", type_names.join("_"));
+        while self.func_names.contains(&instance_name) {
+            instance_name.insert(name.len(), '_');
+        }
+
+        let mut gave_params = Vec::new();
+        for (param_name, typ, arg) in args {
+            if !template.by_ref.contains(&param_name) {
+                gave_params.push(arg);
+                continue;
+            }
+
+            match arg {
+                Expr::VariableName { .. } | Expr::DerefPointer(_) => gave_params.push(Expr::Address(Box::new(arg))),
+                // a value that isn't a variable gets somewhere to live for the call
+                value => {
+                    let outer_args = std::mem::replace(&mut self.type_args, type_args.clone());
+                    let typ = self.handle_typ(typ);
+                    self.type_args = outer_args;
+
+                    let value = self.handle_value(value)?;
+                    gave_params.push(Expr::CEmbed(format!("({}[]){{{value}}}", typ?.0)));
+                },
+            }
+        }
+
+        // one that's waiting on an earlier item is asked for again, so it's made before this one too
+        if !self.instances.contains(&instance_name) && !self.pending.iter().any(|instance| instance.name == instance_name) {
+            self.pending.push(Instance { template: name, name: instance_name.clone(), type_args });
+        }
+
+        Ok((instance_name, gave_params))
+    }
+
+    // the type given for a typeid param
+    fn type_arg(&self, arg: &Expr) -> Result<Types, Diagnostic> {
+        fn named(this: &Gen, typ: &Types, name: &String) -> Types {
+            match typ {
+                Types::Pointer(to) => Types::Pointer(Box::new(named(this, to, name))),
+                // keywords and typeid variables are already named the same in c
+                Types::TypeId => this.type_args.get(name).cloned().unwrap_or(Types::TypeDef { type_name: name.clone(), generics: None }),
+                typ => typ.clone(),
+            }
+        }
+
+        match arg {
+            Expr::VariableName { typ, name, .. } => Ok(named(self, typ, name)),
+            Expr::StructDef { struct_name, .. } => match struct_name.as_ref() {
+                Expr::StructName { name, .. } => Ok(Types::TypeDef { type_name: name.clone(), generics: None }),
+                _ => unreachable!(),
+            },
+            Expr::EnumDef { enum_name, .. } => match enum_name.as_ref() {
+                Expr::EnumName(name) => Ok(Types::TypeDef { type_name: name.clone(), generics: None }),
+                _ => unreachable!(),
+            },
+            unexpected => Err(self.comp_err(&format!("expected a type, got {unexpected:?}"))),
+        }
+    }
+
+    // the type of an argument, as far as the generator can tell
+    fn arg_type(&self, arg: &Expr) -> Option<Types> {
+        let typ = match arg {
+            Expr::VariableName { typ: Types::ArrIndex { arr_typ, .. }, .. } => match arr_typ.as_ref() {
+                Types::Arr { typ, .. } | Types::Pointer(typ) => *typ.clone(),
+                _ => return None,
+            },
+            Expr::VariableName { typ: Types::TypeId, .. } => return None,
+            Expr::VariableName { typ, .. } | Expr::Cast { typ, .. } => typ.clone(),
+            Expr::StrLit(_) => Types::Pointer(Box::new(Types::Char)),
+            Expr::CharLit(_) => Types::Char,
+            Expr::True | Expr::False => Types::Bool,
+            Expr::Address(value) => Types::Pointer(Box::new(self.arg_type(value)?)),
+            Expr::DerefPointer(value) => match self.arg_type(value)? {
+                Types::Pointer(to) => *to,
+                _ => return None,
+            },
+            Expr::FuncCall { name, gave_params } => {
+                let mut typ = self.func_types.get(name)?.clone();
+                if let Some(template) = self.templates.get(name) {
+                    let mut type_args = HashMap::new();
+                    for (param, arg) in template.params.iter().zip(gave_params) {
+                        if let Expr::VariableName { typ: Types::TypeId, name, .. } = param {
+                            type_args.insert(name.clone(), self.type_arg(arg).ok()?);
+                        }
+                    }
+                    typ = with_type_args(&typ, &type_args);
+                }
+                typ
+            },
+            _ => return None,
+        };
+
+        Some(with_type_args(&typ, &self.type_args))
+    }
+
+    // `#T` in c code is the name of the type T is, like it was when generic functions were macros
+    fn embed_type_names(&mut self, mut embed: String) -> Result<String, Diagnostic> {
+        let names: Vec<String> = self.type_args.keys().filter(|name| name.as_str() != "any").cloned().collect();
+        for name in names {
+            let pattern = format!("#{name}");
+            let mut from = 0;
+            while let Some(found) = embed[from..].find(&pattern).map(|i| i + from) {
+                let end = found + pattern.len();
+                if embed[end..].starts_with(|ch: char| ch.is_alphanumeric() || ch == '_') {
+                    from = end;
+                    continue;
+                }
+
+                let typ = self.handle_typ(Types::Generic(name.clone()))?.0;
+                let quoted = format!("\"{typ}\"");
+                embed.replace_range(found..end, &quoted);
+                from = found + quoted.len();
+            }
+        }
+
+        Ok(embed)
+    }
+
+    fn generate_new_struct(&mut self, struct_name: &String, type_names: String, types: Vec<String>) -> Result<(), Diagnostic> {
         let fullname = format!("{struct_name}_{type_names}");
 
         for gen_struct in &self.generated_structs {
             if gen_struct == &fullname {
-                return;
+                return Ok(());
             }
         }
 
@@ -144,18 +563,27 @@ impl Gen {
         let index = match index_op {
             Some(i) => *i,
             None => {
-                self.comp_err("failed to generate generic during compilation");
-                exit(1);
+                return Err(self.comp_err("failed to generate generic during compilation"));
             }
         };
 
         self.code.insert_str(index, &gen_code);
         self.update_struct_definitions(index, gen_code.len());
         self.generated_structs.push(fullname);
+
+        Ok(())
     }
 
-    fn handle_typ(&mut self, typ: Types) -> (String, String) {
-        match typ {
+    // arrays that aren't declared in place are passed around as an array_T
+    fn array_struct(&mut self, arr_typ: Types) -> Result<String, Diagnostic> {
+        let elem = self.handle_typ(arr_typ)?.0;
+        let elem_name = elem.replace("*", "ptr");
+        self.generate_new_struct(&String::from("array"), elem_name.clone(), vec![elem])?;
+        Ok(format!("array_{elem_name}"))
+    }
+
+    fn handle_typ(&mut self, typ: Types) -> Result<(String, String), Diagnostic> {
+        let handled = match typ {
             Types::U8 => (String::from("u8"), String::new()),
             Types::I8 => (String::from("i8"), String::new()),
             Types::Char => (String::from("char"), String::new()),
@@ -181,81 +609,92 @@ impl Gen {
                 }
                 (String::from("bool"), String::new())
             },
+            Types::TypeDef { type_name, generics: None } if self.type_args.contains_key(&type_name) => {
+                return self.handle_typ(self.type_args[&type_name].clone())
+            },
             Types::TypeDef { type_name: user_def, generics: generics_op } => {
                 let replaced_def = user_def.replace(".", "__");
-                let mut typ = format!("{replaced_def}");
+                let mut typ = replaced_def.to_string();
 
-                match generics_op {
-                    Some(generics) => {
-                        for (i, generic) in generics.iter().enumerate() {
-                            let mut gen_typ = self.handle_typ(generic.clone()).0;
-                            if self.in_macro_func {
-                                gen_typ = gen_typ.replace("*", "##ptr");
-                                if i == 0 {
-                                    typ.push_str(&format!("_##{gen_typ}"));
-                                } else {
-                                    typ.push_str(&format!("##{gen_typ}"));
-                                }
+                if let Some(generics) = generics_op {
+                    for (i, generic) in generics.iter().enumerate() {
+                        let mut gen_typ = self.handle_typ(generic.clone())?.0;
+                        if self.in_macro_func {
+                            gen_typ = gen_typ.replace("*", "##ptr");
+                            if i == 0 {
+                                typ.push_str(&format!("_##{gen_typ}"));
                             } else {
-                                gen_typ = gen_typ.replace("*", "ptr");
-                                if i == 0 {
-                                    typ.push_str(&format!("_{gen_typ}"));
-                                } else {
-                                    typ.push_str(&format!("{gen_typ}"));
-                                }
+                                typ.push_str(&format!("##{gen_typ}"));
+                            }
+                        } else {
+                            gen_typ = gen_typ.replace("*", "ptr");
+                            if i == 0 {
+                                typ.push_str(&format!("_{gen_typ}"));
+                            } else {
+                                typ.push_str(&gen_typ.to_string());
                             }
                         }
-                    },
-                    None => (),
+                    }
                 }
-                return (typ, String::new())
+                return Ok((typ, String::new()))
             },
-            Types::Void => return (String::from("void"), String::new()),
+            Types::Void => return Ok((String::from("void"), String::new())),
             Types::Arr { typ: arr_typ, length } => {
-                let newstr_typ = self.handle_typ(*arr_typ);
+                let newstr_typ = self.handle_typ(*arr_typ)?;
                 // MIGHT NOT WORK WITH MULTI-DIMENSIONAL ARRAYS
-                return (format!("{}", newstr_typ.0), format!("[{length}]"))
+                return Ok((newstr_typ.0.to_string(), format!("[{length}]")))
             },
             Types::ArrIndex { arr_typ: _, index_at } => {
-                return (String::new(), index_at)
+                return Ok((String::new(), index_at))
             },
             Types::Pointer(ptotyp) => {
-                let sub_typ = self.handle_typ(*ptotyp).0;
-                return (format!("{sub_typ}*"), String::new())
+                if let Types::Arr { typ: arr_typ, .. } = *ptotyp {
+                    return Ok((format!("{}*", self.array_struct(*arr_typ)?), String::new()))
+                }
+
+                let sub_typ = self.handle_typ(*ptotyp)?.0;
+                return Ok((format!("{sub_typ}*"), String::new()))
             },
             Types::Generic(typeid) => {
+                if let Some(typ) = self.type_args.get(&typeid) {
+                    return self.handle_typ(typ.clone());
+                }
+
                 if self.mutate_func_args {
-                    return (String::new(), String::new())
+                    return Ok((String::new(), String::new()))
                 }
 
-                return (format!("{typeid}"), String::new())
+                return Ok((typeid.to_string(), String::new()))
             },
             Types::Any => {
+                if let Some(typ) = self.type_args.get("any") {
+                    return self.handle_typ(typ.clone());
+                }
+
                 if self.mutate_func_args {
-                    return (String::new(), String::new())
+                    return Ok((String::new(), String::new()))
                 }
 
                 if self.in_macro_func {
-                    self.comp_err(&format!("cannot make variable of type any outside of function declaration"));
-                    exit(1);
+                    return Err(self.comp_err("cannot make variable of type any outside of function declaration"));
                 }
 
-                self.comp_err(&format!("failed to handle any type at compile time."));
-                exit(1);
+                return Err(self.comp_err("failed to handle any type at compile time."));
             }
             Types::Let => (String::from("let"), String::new()),
             Types::None => (String::new(), String::new()),
             unimpl => {
-                self.comp_err(&format!("{unimpl:?} is not implemented yet"));
-                exit(1);
+                return Err(self.comp_err(&format!("{unimpl:?} is not implemented yet")));
             }
-        }
+        };
+
+        Ok(handled)
     }
 
-    fn handle_varname(&mut self, varname: Expr) -> String {
+    fn handle_varname(&mut self, varname: Expr) -> Result<String, Diagnostic> {
         match varname {
             Expr::Variable { info, .. } => {
-                return self.handle_varname(*info);
+                self.handle_varname(*info)
             },
             Expr::VariableName { ref typ, reassign, constant, .. } => {
                 let mut vardec = if constant && !reassign {
@@ -264,112 +703,109 @@ impl Gen {
                     String::new()
                 };
 
-                let new_name = self.handle_sanitise_varname(varname.clone());
+                let new_name = self.handle_sanitise_varname(varname.clone())?;
                 if let Types::ArrIndex { index_at, .. } = typ {
                     vardec.push_str(&format!("{new_name}[{index_at}]"));
-                    return vardec
-                } else if let Types::TypeDef { type_name: struct_name, generics: generics_op } = typ {
-                    match generics_op {
-                        Some(generics) => {
-                            if !generics.is_empty() {
-                                let mut type_names = String::new();
-                                let mut types = Vec::new();
-
-                                for (i, generic) in generics.iter().enumerate() {
-                                    let mut gen_typ = self.handle_typ(generic.clone()).0;
-                                    types.push(gen_typ.clone());
-
-                                    gen_typ = gen_typ.replace("*", "ptr");
-                                    if i == 0 {
-                                        type_names.push_str(&format!("{gen_typ}"));
-                                    } else {
-                                        type_names.push_str(&format!("_{gen_typ}"));
-                                    }
-                                }
+                    return Ok(vardec)
+                } else if let Types::TypeDef { type_name: struct_name, generics: Some(generics) } = typ {
+                    if !generics.is_empty() {
+                        let mut type_names = String::new();
+                        let mut types = Vec::new();
 
-                                if !self.in_macro_func {
-                                    self.generate_new_struct(struct_name, type_names, types);
-                                }
+                        for (i, generic) in generics.iter().enumerate() {
+                            let mut gen_typ = self.handle_typ(generic.clone())?.0;
+                            types.push(gen_typ.clone());
+
+                            gen_typ = gen_typ.replace("*", "ptr");
+                            if i == 0 {
+                                type_names.push_str(&gen_typ.to_string());
+                            } else {
+                                type_names.push_str(&format!("_{gen_typ}"));
                             }
-                        },
-                        None => (),
+                        }
+
+                        if !self.in_macro_func {
+                            self.generate_new_struct(struct_name, type_names, types)?;
+                        }
                     }
                 }
 
-                if reassign == false {
-                    let mut subtyp = self.handle_typ(typ.clone());
+                if !reassign {
+                    let mut subtyp = self.handle_typ(typ.clone())?;
                     if !subtyp.1.is_empty() {
                         if let Types::Arr { .. } = typ {
                             let types = vec![subtyp.0.clone()];
                             subtyp.0 = subtyp.0.replace("*", "ptr");
 
-                            self.generate_new_struct(&String::from("array"), subtyp.0.clone(), types);
+                            self.generate_new_struct(&String::from("array"), subtyp.0.clone(), types)?;
                             vardec.push_str(&format!("array_{} {new_name} = {{.data = ({}{})A", subtyp.0, subtyp.0, subtyp.1));
-                            return vardec
+                            return Ok(vardec)
                         }
                     }
 
                     vardec.push_str(&format!("{} {new_name}{}", subtyp.0, subtyp.1));
-                    return vardec
+                    Ok(vardec)
                 } else {
-                    vardec.push_str(&format!("{new_name}"));
-                    return vardec
+                    vardec.push_str(&new_name.to_string());
+                    Ok(vardec)
                 }
             },
             Expr::DerefPointer(value) => {
-                let derefed = self.handle_varname(*value);
-                return format!("*{derefed}")
+                let derefed = self.handle_varname(*value)?;
+                Ok(format!("*{derefed}"))
             },
             Expr::CEmbed(mut value) => {
-                if value.chars().last().unwrap() == ';' {
+                if value.ends_with(';') {
                     value.pop();
                 }
-                return value;
+                Ok(value)
             },
             unexpected => {
-                self.comp_err(&format!("unexpected expression: {unexpected:?}"));
-                exit(1);
+                Err(self.comp_err(&format!("unexpected expression: {unexpected:?}")))
             },
         }
     }
 
-    pub fn handle_funccall(&mut self, funccall: Expr) -> String {
-        fn handle_local_pointer(this: &mut Gen, name: String, typ: Types) -> (String, bool) {
+    pub fn handle_funccall(&mut self, funccall: Expr) -> Result<String, Diagnostic> {
+        fn handle_local_pointer(name: String, typ: Types) -> (String, bool) {
             if let Types::Pointer(subtyp) = typ {
-                let handled = handle_local_pointer(this, name, *subtyp);
-                return (format!("{}*", handled.0), handled.1);
+                let handled = handle_local_pointer(name, *subtyp);
+                (format!("{}*", handled.0), handled.1)
             } else if let Types::TypeId = typ {
-                return (name, true);
+                (name, true)
             } else if let Types::TypeDef { ref type_name, .. } = typ {
                 if type_name == &name {
-                    return (name, true);
+                    (name, true)
                 } else {
-                    return (name, false);
+                    (name, false)
                 }
             } else {
-                return (name, false);
+                (name, false)
             }
         }
 
         match funccall {
             Expr::FuncCall { name, gave_params } => {
+                let (name, gave_params) = self.instantiate_call(name, gave_params)?;
                 let mut funccall_code = String::new();
                 let mut add_newline = false;
 
-                if name == String::from("print")  {
+                if name == "print"  {
                     funccall_code.push_str("printf(");
-                } else if name == String::from("println") {
+                } else if name == "println" {
                     funccall_code.push_str("printf(");
                     add_newline = true;
-                } else if name == String::from("string__format") {
+                } else if name == "string__format" {
                     funccall_code.push_str("__IMPULSE__STRING__FORMAT__(");
+                } else if let Some(link_name) = self.links.get(&name) {
+                    funccall_code.push_str(&format!("{link_name}("));
                 } else {
                     funccall_code.push_str(&format!("{name}("));
                 }
 
                 if gave_params.is_empty() {
                     funccall_code.push(')');
-                    return funccall_code
+                    return Ok(funccall_code)
                 } 
 
                 for (i, param) in gave_params.iter().enumerate() {
@@ -382,14 +818,16 @@ impl Gen {
                             }
                         },
                         Expr::VariableName { typ, .. } => {
-                            let mut sanitised_name = self.handle_sanitise_varname(param.clone());
+                            let mut sanitised_name = self.handle_sanitise_varname(param.clone())?;
                             if let Types::Pointer(_) = typ {
-                                let handled_varname = handle_local_pointer(self, sanitised_name.clone(), typ.clone());
+                                let handled_varname = handle_local_pointer(sanitised_name.clone(), typ.clone());
                                 sanitised_name = if handled_varname.1 {
                                     handled_varname.0
                                 } else {
                                     sanitised_name
                                 };
+                            } else if let Types::ArrIndex { index_at, .. } = typ {
+                                sanitised_name = format!("{sanitised_name}[{index_at}]");
                             }
                             if i == 0 {
                                 funccall_code.push_str(&sanitised_name)
@@ -399,20 +837,20 @@ impl Gen {
                         },
                         Expr::FuncCall { .. } => {
                             if i == 0 {
-                                funccall_code.push_str(&self.handle_funccall(param.clone()))
+                                funccall_code.push_str(&self.handle_funccall(param.clone())?)
                             } else {
-                                funccall_code.push_str(&format!(", {}", self.handle_funccall(param.clone())))
+                                funccall_code.push_str(&format!(", {}", self.handle_funccall(param.clone())?))
                             }
                         },
                         Expr::Address(_) => {
                             if i == 0 {
-                                funccall_code.push_str(&self.handle_value(param.clone()));
+                                funccall_code.push_str(&self.handle_value(param.clone())?);
                             } else {
-                                funccall_code.push_str(&format!(", {}", self.handle_value(param.clone())));
+                                funccall_code.push_str(&format!(", {}", self.handle_value(param.clone())?));
                             }
                         },
                         Expr::StrLit(_) => {
-                            let mut string = self.handle_value(param.clone());
+                            let mut string = self.handle_value(param.clone())?;
                             if i == 0 {
                                 if add_newline {
                                     string.insert_str(string.len()-1, "\\n");
@@ -426,9 +864,9 @@ impl Gen {
                         },
                         Expr::CharLit(_) => {
                             if i == 0 {
-                                funccall_code.push_str(&self.handle_value(param.clone()));
+                                funccall_code.push_str(&self.handle_value(param.clone())?);
                             } else {
-                                funccall_code.push_str(&format!(", {}", self.handle_value(param.clone())));
+                                funccall_code.push_str(&format!(", {}", self.handle_value(param.clone())?));
                             }
                         },
                         Expr::True => {
@@ -448,7 +886,7 @@ impl Gen {
                         Expr::StructDef { struct_name, .. } => {
                             if let Expr::StructName { name, .. } = *struct_name.clone() {
                                 if i == 0 {
-                                    funccall_code.push_str(&format!("{name}"));
+                                    funccall_code.push_str(&name.to_string());
                                 } else {
                                     funccall_code.push_str(&format!(", {name}"));
                                 }
@@ -457,36 +895,39 @@ impl Gen {
                         Expr::EnumDef { enum_name, .. } => {
                             if let Expr::EnumName(name) = *enum_name.clone() {
                                 if i == 0 {
-                                    funccall_code.push_str(&format!("{name}"));
+                                    funccall_code.push_str(&name.to_string());
                                 } else {
                                     funccall_code.push_str(&format!(", {name}"));
                                 }
                             }
                         },
-                        unimpl => {
-                            self.comp_err(&format!("expression {unimpl:?} not implemented yet"));
-                            exit(1);
-                        }
+                        other => {
+                            let value = self.handle_value(other.clone())?;
+                            if i == 0 {
+                                funccall_code.push_str(&value);
+                            } else {
+                                funccall_code.push_str(&format!(", {value}"));
+                            }
+                        },
                     }
                 }
 
                 funccall_code.push(')');
-                return funccall_code
+                Ok(funccall_code)
             },
             unexpected => {
-                self.comp_err(&format!("unexpected expression: {unexpected:?}"));
-                exit(1);
+                Err(self.comp_err(&format!("unexpected expression: {unexpected:?}")))
             },
         }
 
     }
 
-    fn handle_arraylit(&mut self, arrlit: Vec<Expr>, length: String) -> String {
+    fn handle_arraylit(&mut self, arrlit: Vec<Expr>, length: String) -> Result<String, Diagnostic> {
         let mut arrlit_code = String::new();
-        arrlit_code.push_str("{");
+        arrlit_code.push('{');
 
         for (i, elem) in arrlit.iter().enumerate() {
-            let literal = self.handle_value(elem.clone());
+            let literal = self.handle_value(elem.clone())?;
 
             if i == 0 {
                 arrlit_code.push_str(&literal);
@@ -500,59 +941,81 @@ impl Gen {
         } else {
             arrlit_code.push_str(&format!("}}, .len = {}}}", length));
         }
-        arrlit_code
+        Ok(arrlit_code)
     }
 
-    fn handle_sanitise_varname(&mut self, value: Expr) -> String {
+    fn handle_sanitise_varname(&mut self, value: Expr) -> Result<String, Diagnostic> {
         match value {
-            Expr::VariableName { name, field_data, .. } => {
+            Expr::VariableName { name, field_data, typ, .. } => {
                 // we do this cuz it should be a enum at this point
                 let mut sanitised = name.clone();
                 if !field_data.0 && name.contains(".") {
                     sanitised = name.replace(".", "_")
                 }
 
-                return sanitised 
+                // arrays are array_T structs, indexing one goes through its data
+                if let Types::ArrIndex { arr_typ, .. } = typ {
+                    if let Types::Arr { .. } = *arr_typ {
+                        sanitised.push_str(".data");
+                    }
+                }
+
+                Ok(sanitised)
             },
             unexpected => {
-                self.comp_err(&format!("can't deference {unexpected:?}"));
-                exit(1);
+                Err(self.comp_err(&format!("can't deference {unexpected:?}")))
             }
         }
     }
 
-    fn handle_value(&mut self, value: Expr) -> String {
-        match value {
+    pub fn handle_value(&mut self, value: Expr) -> Result<String, Diagnostic> {
+        let handled = match value {
             Expr::IntLit(intlit) => format!("({intlit})"),
+            Expr::Maths(parts) => {
+                let mut maths = String::new();
+                for part in parts {
+                    match part {
+                        Expr::IntLit(text) => maths.push_str(&text),
+                        call => maths.push_str(&self.handle_funccall(call)?),
+                    }
+                }
+                format!("({maths})")
+            },
             Expr::CharLit(charlit) => format!("'{charlit}'"),
             Expr::StrLit(content) => format!("\"{content}\""),
             Expr::True => String::from("true"),
             Expr::False => String::from("false"),
+            Expr::Condition(conditions) => format!("({})", self.handle_boolean_condition(&conditions)?),
             Expr::Variable { value, .. } => {
                 return self.handle_value(*value);
             },
             Expr::VariableName { ref typ, .. } => {
-                let new_name = self.handle_sanitise_varname(value.clone());
+                let new_name = self.handle_sanitise_varname(value.clone())?;
                 if let Types::ArrIndex { index_at, .. } = typ {
-                    return format!("{new_name}[{index_at}]")
+                    return Ok(format!("{new_name}[{index_at}]"))
                 } else {
-                    return new_name
+                    return Ok(new_name)
                 }
             },
             Expr::FuncCall { .. } => return self.handle_funccall(value.clone()),
             Expr::Address(atoval) =>{
-                let sub_val = self.handle_value(*atoval);
-                return format!("&{sub_val}")
+                let sub_val = self.handle_value(*atoval)?;
+                return Ok(format!("&{sub_val}"))
+            },
+            Expr::Cast { typ, value } => {
+                let typ = self.handle_typ(typ)?.0;
+                let sub_val = self.handle_value(*value)?;
+                return Ok(format!("(({typ}){sub_val})"))
             },
             Expr::DerefPointer(dptoval) => {
-                let sub_val = self.handle_value(*dptoval);
-                return format!("*{sub_val}")
+                let sub_val = self.handle_value(*dptoval)?;
+                return Ok(format!("*{sub_val}"))
             },
             Expr::None => String::new(),
             Expr::ArrayLit(arraylit) => {
                 let mut values = String::from("{");
                 for (i, elem) in arraylit.iter().enumerate() {
-                    let val = self.handle_value(elem.to_owned());
+                    let val = self.handle_value(elem.to_owned())?;
                     if i == 0 {
                         values.push_str(&val);
                         continue;
@@ -562,63 +1025,58 @@ impl Gen {
                 }
 
                 values.push('}');
-                return values
+                return Ok(values)
             },
             Expr::StructDef { struct_name, .. } => {
                 match *struct_name {
                     Expr::StructName { name: structname, .. } => {
-                        return structname.replace(".", "__")
+                        return Ok(structname.replace(".", "__"))
                     },
                     _ => unreachable!(),
                 }
             },
-            Expr::CEmbed(code) => code,
+            Expr::CEmbed(code) => self.embed_type_names(code)?,
             Expr::DefaultValue => String::new(),
             Expr::GarbageValue => String::from("impulse_garbage"),
             unimpl => {
-                self.comp_err(&format!("expression {unimpl:?} not implemented yet"));
-                exit(1);
+                return Err(self.comp_err(&format!("expression {unimpl:?} not implemented yet")));
             }
-        }
+        };
+
+        Ok(handled)
     }
 
-    fn handle_boolean_condition(&mut self, conditions: &Vec<Expr>) -> String {
+    fn handle_boolean_condition(&mut self, conditions: &[Expr]) -> Result<String, Diagnostic> {
         let mut boolean_condition_code = String::new();
-        let mut had_angled = false;
-        for condition in conditions {
+        for (i, condition) in conditions.iter().enumerate() {
             match condition {
                 Expr::FuncCall { .. } => {
-                    let func_call_code = self.handle_funccall(condition.clone());
-                    boolean_condition_code.push_str(&format!("{func_call_code}"));
+                    let func_call_code = self.handle_funccall(condition.clone())?;
+                    boolean_condition_code.push_str(&func_call_code.to_string());
                 },
                 Expr::VariableName { typ, .. } => {
-                    let new_name = self.handle_sanitise_varname(condition.clone());
+                    let new_name = self.handle_sanitise_varname(condition.clone())?;
 
                     if let Types::ArrIndex { index_at, .. } = typ {
                         boolean_condition_code.push_str(&format!("{new_name}[{index_at}]"));
                     } else {
-                        boolean_condition_code.push_str(&format!("{new_name}"))
+                        boolean_condition_code.push_str(&new_name.to_string())
                     }
                 },
                 Expr::Equal => {
-                    if had_angled {
-                        boolean_condition_code.push_str("=");
-                        had_angled = false;
+                    // !=, <= and >= come through as two expressions
+                    if i > 0 && matches!(conditions[i - 1], Expr::SmallerThan | Expr::BiggerThan | Expr::Exclaim) {
+                        boolean_condition_code.push('=');
                     } else {
                         boolean_condition_code.push_str("==");
                     }
                 },
-                Expr::SmallerThan => {
-                    boolean_condition_code.push_str("<");
-                    had_angled = true;
-                },
-                Expr::BiggerThan => {
-                    boolean_condition_code.push_str(">");
-                    had_angled = true;
-                },
-                Expr::Exclaim => {
-                    boolean_condition_code.push_str("!");
-                    had_angled = true;
+                Expr::SmallerThan => boolean_condition_code.push('<'),
+                Expr::BiggerThan => boolean_condition_code.push('>'),
+                Expr::Exclaim => boolean_condition_code.push('!'),
+                Expr::Address(_) | Expr::DerefPointer(_) | Expr::Cast { .. } | Expr::Maths(_) => {
+                    let value = self.handle_value(condition.clone())?;
+                    boolean_condition_code.push_str(&value);
                 },
                 Expr::IntLit(intlit) => {
                     boolean_condition_code.push_str(&format!("({intlit})"))
@@ -627,6 +1085,10 @@ impl Gen {
                     boolean_condition_code.push_str(&format!("'{charlit}'"));
                 },
                 // Expr::StrLit(string) => boolean_condition_code.push_str(&format!("{string}")),
+                Expr::Condition(grouped) => {
+                    let grouped = self.handle_boolean_condition(grouped)?;
+                    boolean_condition_code.push_str(&format!("({grouped})"));
+                },
                 Expr::Or => boolean_condition_code.push_str("||"),
                 Expr::And => boolean_condition_code.push_str("&&"),
                 Expr::True => {
@@ -642,32 +1104,26 @@ impl Gen {
                     boolean_condition_code.push_str("false")
                 },
                 unexpected => {
-                    self.comp_err(&format!("unexpected expression {unexpected:?} in branch / boolean condition"));
-                    exit(1);
+                    return Err(self.comp_err(&format!("unexpected expression {unexpected:?} in branch / boolean condition")));
                 },
             }
         }
 
-        boolean_condition_code
+        Ok(boolean_condition_code)
     }
 
-    fn handle_branch(&mut self, branch_typ: &String, conditions: Vec<Expr>) -> String {
+    fn handle_branch(&mut self, branch_typ: &String, conditions: Vec<Expr>) -> Result<String, Diagnostic> {
         let mut branch_code = String::new();
         branch_code.push_str(&format!("{branch_typ}("));
 
-        let condition_str = self.handle_boolean_condition(&conditions);
+        let condition_str = self.handle_boolean_condition(&conditions)?;
         branch_code.push_str(&condition_str);
 
-        self.curl_rc += 1;
-        if self.in_macro_func {
-            branch_code.push_str(") {\\\n");
-        } else {
-            branch_code.push_str(") {\n");
-        }
-        branch_code
+        branch_code.push_str(") {\n");
+        Ok(branch_code)
     }
 
-    fn handle_loop(&mut self, conditions: Vec<Expr>, modifier: Expr) -> String {
+    fn handle_loop(&mut self, conditions: Vec<Expr>, modifier: Expr) -> Result<String, Diagnostic> {
         let mut loop_code = String::new();
         let mut varname = String::new();
         loop_code.push_str("for (");
@@ -676,45 +1132,37 @@ impl Gen {
             Expr::VariableName { typ, name, reassign, .. } => {
                 varname = name.to_owned();
                 if reassign == &true {
-                    loop_code.push_str(&format!("{} {name} = 0; ", self.handle_typ(typ.clone()).0));
+                    loop_code.push_str(&format!("{} {name} = 0; ", self.handle_typ(typ.clone())?.0));
                 } else {
-                    loop_code.push_str(";");
+                    loop_code.push(';');
                 }
             },
-            Expr::Exclaim | Expr::True | Expr::False => loop_code.push_str(";"),
+            Expr::Exclaim | Expr::True | Expr::False => loop_code.push(';'),
             _ => (),
         }
 
-        let condition_str = self.handle_boolean_condition(&conditions);
+        let condition_str = self.handle_boolean_condition(&conditions)?;
         loop_code.push_str(&format!("{condition_str};"));
 
-        match modifier {
-            Expr::IntLit(modif) => {
-                match modif.as_str() {
-                    "+" => loop_code.push_str(&format!(" {varname}++")),
-                    "-" => loop_code.push_str(&format!(" {varname}--")),
-                    "_" => (),
-                    _ => (),
-                }
+        if let Expr::IntLit(modif) = modifier {
+            match modif.as_str() {
+                "+" => loop_code.push_str(&format!(" {varname}++")),
+                "-" => loop_code.push_str(&format!(" {varname}--")),
+                "_" => (),
+                _ => (),
             }
-            _ => (),
         }
 
-        self.curl_rc += 1;
 
-        if self.in_macro_func {
-            loop_code.push_str(") {\\\n");
-        } else {
-            loop_code.push_str(") {\n");
-        }
-        loop_code
+        loop_code.push_str(") {\n");
+        Ok(loop_code)
     }
 
-    fn handle_for(&mut self,  in_this: Box<Expr>, iterator: String) -> (String, String, String, bool) {
+    fn handle_for(&mut self,  in_this: Expr, iterator: String) -> Result<(String, String, String, bool), Diagnostic> {
         let mut for_code = String::new();
         let arr_name;
         let mut is_addr = false;
-        let length: String = match *in_this {
+        let length: String = match in_this {
             Expr::Address(var) => {
                 is_addr = true;
                 if let Expr::VariableName { typ, name, .. } = *var {
@@ -722,20 +1170,17 @@ impl Gen {
                         arr_name = name.clone();
                         format!("{name}.len")
                     } else if let Types::TypeDef { type_name, .. } = typ {
-                        if type_name == String::from("dyn") || type_name == String::from("string") || type_name == String::from("str") || type_name == String::from("array")  {
+                        if type_name == "dyn" || type_name == "string" || type_name == "str" || type_name == "array"  {
                             arr_name = name.clone();
                             format!("{name}.len")
                         } else {
-                            self.comp_err(&format!("unable to handle {name} in for loop as it's of type {type_name:?}"));
-                            exit(1);
+                            return Err(self.comp_err(&format!("unable to handle {name} in for loop as it's of type {type_name:?}")));
                         }
                     } else {
-                        self.comp_err(&format!("unable to handle {name} in for loop as it's of type {typ:?}"));
-                        exit(1);
+                        return Err(self.comp_err(&format!("unable to handle {name} in for loop as it's of type {typ:?}")));
                     }
                 } else {
-                    self.comp_err(&format!("{var:?} is not implemented yet"));
-                    exit(1);
+                    return Err(self.comp_err(&format!("{var:?} is not implemented yet")));
                 }
             },
             Expr::VariableName { typ, name, .. } => {
@@ -743,44 +1188,37 @@ impl Gen {
                     arr_name = name.clone();
                     format!("{name}.len")
                 } else if let Types::TypeDef { type_name, .. } = typ {
-                    if type_name == String::from("dyn") || type_name == String::from("string") || type_name == String::from("str") || type_name == String::from("array")  {
+                    if type_name == "dyn" || type_name == "string" || type_name == "str" || type_name == "array"  {
                         arr_name = name.clone();
                         format!("{name}.len")
                     } else {
-                        self.comp_err(&format!("unable to handle {name} in for loop as it's of type {type_name:?}"));
-                        exit(1);
+                        return Err(self.comp_err(&format!("unable to handle {name} in for loop as it's of type {type_name:?}")));
                     }
                 } else {
-                    self.comp_err(&format!("unable to handle {name} in for loop as it's of type {typ:?}"));
-                    exit(1);
+                    return Err(self.comp_err(&format!("unable to handle {name} in for loop as it's of type {typ:?}")));
                 }
             },
             unexpected => {
-                self.comp_err(&format!("{unexpected:?} is not implemented yet"));
-                exit(1);
+                return Err(self.comp_err(&format!("{unexpected:?} is not implemented yet")));
             }
         };
 
 
         let mut return_tuple;
         if iterator.is_empty() {
-            let randname = rand_varname();
-            for_code.push_str(&format!("for (size_t {randname} = 0; {randname} < {length}; {randname}++) {{"));
-            return_tuple = (for_code, arr_name, randname, is_addr)
+            let loop_name = self.loop_name();
+            for_code.push_str(&format!("for (size_t {loop_name} = 0; {loop_name} < {length}; {loop_name}++) {{"));
+            return_tuple = (for_code, arr_name, loop_name, is_addr)
         } else {
             for_code.push_str(&format!("for (size_t {iterator} = 0; {iterator} < {length}; {iterator}++) {{"));
             return_tuple = (for_code, arr_name, iterator, is_addr)
         }
 
-        if self.in_macro_func {
-            return_tuple.0.push_str("\\\n");
-        } else {
-            return_tuple.0.push_str("\n");
-        }
-        return_tuple
+        return_tuple.0.push('\n');
+        Ok(return_tuple)
     }
 
-    fn generate_c(&mut self, expressions: Vec<(Expr, String, u32)>) {
+    fn generate_c(&mut self, expressions: Vec<(Expr, String, Span)>) -> Result<(), Diagnostic> {
         self.imports.push_str("#include <stddef.h>\n");
         self.imports.push_str("#include <stdint.h>\n");
         self.imports.push_str("#include <stdbool.h>\n");
@@ -799,15 +1237,47 @@ impl Gen {
         self.code.push_str("#define let __auto_type /* currently only for enums */\n");
         self.code.push_str("#define $inline static inline __attribute__((always_inline))\n");
 
+        for (expr, _, _) in &expressions {
+            match expr {
+                Expr::Func { typ, name, link_name, .. } => {
+                    self.func_names.insert(name.clone());
+                    self.func_types.insert(name.clone(), typ.clone());
+                    if let Some(link_name) = link_name {
+                        self.links.insert(name.clone(), link_name.clone());
+                    }
+                },
+                Expr::MacroFunc { typ, name, .. } => {
+                    self.func_types.insert(name.clone(), typ.clone());
+                },
+                _ => (),
+            }
+        }
+
+        self.generate_exprs(expressions)
+    }
+
+    fn generate_exprs(&mut self, expressions: Vec<(Expr, String, Span)>) -> Result<(), Diagnostic> {
         let mut struct_generics = Vec::new();
         let mut struct_default_initaliser = Vec::new();
         let mut first_case = false;
         let mut fall_case = false;
 
-        for (_index, info) in expressions.into_iter().enumerate() {
+        let mut expressions = expressions.into_iter();
+        while let Some(info) = expressions.next() {
             let expr = info.0;
+            self.close_unit_item();
+            if self.indent == 0 {
+                self.flush_instances()?;
+            }
+
             self.in_file = info.1;
-            self.line_num = info.2;
+            self.span = Some(info.2);
+            self.add_line_directive(&self.in_file.clone(), info.2.line);
+            if self.indent == 0 {
+                self.item_start = self.code.len();
+                self.item_line = (self.in_file.clone(), info.2.line);
+            }
+            self.open_unit_item(&expr);
 
             match expr {
                 Expr::Import(loc) => {
@@ -819,32 +1289,26 @@ impl Gen {
                             },
                             None => {
                                 self.imports.push_str(&format!("#include \"{loc}.h\"\n"));
-                                self.comp_imports.push_str(&format!("{loc}.c "))
+                                self.comp_imports.push(self.source_dir.join(format!("{loc}.c")).to_string_lossy().to_string())
                             },
                         }
                     }
                 },
+                Expr::CFlags { os, flags } => {
+                    // a library imported twice shouldn't be linked twice
+                    let for_target = os.is_none_or(|os| os == self.compiler.target_os());
+                    if for_target && flags.iter().any(|flag| !self.c_flags.contains(flag)) {
+                        self.c_flags.extend(flags);
+                    }
+                },
                 Expr::CEmbed(embed) => {
+                    let embed = self.embed_type_names(embed)?;
                     let mut clean_embed = String::new();
                     let mut added_space = false;
 
                     for (i, ch) in embed.chars().enumerate() {
-                        if ch == '\n' {
-                            if self.in_macro_func {
-                                clean_embed.push_str("\\\n");
-                            } else {
-                                clean_embed.push(ch);
-                            }
-
-                            if !added_space {self.add_spaces(self.indent); added_space = true}
-                            self.code.push_str(&clean_embed);
-                            clean_embed.clear();
-                        } else if i == embed.len() - 1 {
-                            if self.in_macro_func {
-                                clean_embed.push_str(&format!("{ch}\\"));
-                            } else {
-                                clean_embed.push(ch);
-                            }
+                        if ch == '\n' || i == embed.len() - 1 {
+                            clean_embed.push(ch);
 
                             if !added_space {self.add_spaces(self.indent); added_space = true}
                             self.code.push_str(&clean_embed);
@@ -869,13 +1333,13 @@ impl Gen {
                     for field in enum_fields {
                         match field {
                             Expr::Variable { info, value } => {
-                                let mut varname = self.handle_varname(*info);
+                                let mut varname = self.handle_varname(*info)?;
                                 varname = varname.strip_prefix("const  ").unwrap().to_owned();
-                                let varvalue = self.handle_value(*value);
+                                let varvalue = self.handle_value(*value)?;
                                 fields.push_str(&format!("    {varname} = {varvalue},\n"));
                             },
                             Expr::VariableName { .. } => {
-                                let mut varname = self.handle_varname(field).replace(".", "_");
+                                let mut varname = self.handle_varname(field)?.replace(".", "_");
                                 varname = varname.strip_prefix("const  ").unwrap().to_owned();
                                 fields.push_str(&format!("    {varname},\n"));
                             },
@@ -887,51 +1351,35 @@ impl Gen {
                 },
                 Expr::StructDef { struct_name, struct_fields } => {
                     let mut def_code = String::new();
-                    match *struct_name {
-                        Expr::StructName { name, is_shared } => {
-                            if is_shared {
-                                def_code.push_str(&format!("typedef union {name} {{\n"));
-                            } else {
-                                def_code.push_str(&format!("typedef struct {name} {{\n"));
-                            }
-                        },
-                        _ => (),
+                    if let Expr::StructName { name, is_shared } = *struct_name {
+                        if is_shared {
+                            def_code.push_str(&format!("typedef union {name} {{\n"));
+                        } else {
+                            def_code.push_str(&format!("typedef struct {name} {{\n"));
+                        }
                     }
 
                     let mut fields = String::new();
                     for field in struct_fields {
-                        let mut varname = self.handle_varname(field.clone());
-                        match field {
-                            Expr::Variable { .. } => {
-                                struct_default_initaliser.push(field.clone());
-                            },
-                            _ => (),
+                        let mut varname = self.handle_varname(field.clone())?;
+                        if let Expr::Variable { .. } = field {
+                            struct_default_initaliser.push(field.clone());
                         }
 
-                        if varname.chars().last().unwrap() == 'A' {
+                        if varname.ends_with('A') {
                             match field {
                                 Expr::Variable { info, .. } => {
-                                    if let Expr::VariableName { typ, .. } = *info {
-                                        if let Types::Arr { .. } = typ {
-                                            let varname_eq_index = varname.find("=");
-                                            match varname_eq_index {
-                                                Some(index) => {
-                                                    varname.truncate(index-1);
-                                                },
-                                                None => (),
-                                            }
+                                    if let Expr::VariableName { typ: Types::Arr { .. }, .. } = *info {
+                                        let varname_eq_index = varname.find("=");
+                                        if let Some(index) = varname_eq_index {
+                                            varname.truncate(index-1);
                                         }
                                     }
                                 }
-                                Expr::VariableName { typ, .. } => {
-                                    if let Types::Arr { .. } = typ {
-                                        let varname_eq_index = varname.find("=");
-                                        match varname_eq_index {
-                                            Some(index) => {
-                                                varname.truncate(index-1);
-                                            },
-                                            None => (),
-                                        }
+                                Expr::VariableName { typ: Types::Arr { .. }, .. } => {
+                                    let varname_eq_index = varname.find("=");
+                                    if let Some(index) = varname_eq_index {
+                                        varname.truncate(index-1);
                                     }
                                 },
                                 _ => (),
@@ -950,19 +1398,19 @@ impl Gen {
                         self.code.push_str(&format!("#define {name}_default {{"));
 
                         for (i, default) in struct_default_initaliser.iter().enumerate() {
-                            let mut field = self.handle_varname(default.clone());
+                            let mut field = self.handle_varname(default.clone())?;
                             let space_index = field.find(' ').unwrap();
                             for _ in 0..=space_index {
                                 field.remove(0);
                             }
                             field.insert(0, '.');
 
-                            let mut value = self.handle_value(default.clone());
+                            let mut value = self.handle_value(default.clone())?;
                             if value.is_empty() {
                                 // have to do all this just to get the type out
                                 if let Expr::Variable { info, .. } = default {
                                     if let Expr::VariableName { typ, .. } = *info.clone() {
-                                        let typename = self.handle_typ(typ).0;
+                                        let typename = self.handle_typ(typ)?.0;
                                         value = if self.has_default_struct(&typename) {
                                             format!("{typename}_default")
                                         } else {
@@ -980,7 +1428,7 @@ impl Gen {
 
                         self.code.push_str("}\n");
                         struct_default_initaliser.clear();
-                        self.default_structs.push(format!("{name}"));
+                        self.default_structs.push(name.to_string());
                     }
 
                     self.defs_location.push(self.code.len());
@@ -991,44 +1439,35 @@ impl Gen {
                     let mut og_name = String::new();
                     let mut def_code = String::new();
                     let mut def_name = String::new();
-                    match *struct_name {
-                        Expr::MacroStructName { name, generics, is_shared } => {
-                            og_name = name.clone();
-                            def_code.push_str(&format!("#define {name}("));
-
-                            def_name = format!("{name}_##imp_struct_type_name");
-                            for (i, generic) in generics.iter().enumerate() {
-                                match generic {
-                                    Expr::Variable { info, .. } => {
-                                        match *info.clone() {
-                                            Expr::VariableName { name: generic_name, .. } => {
-                                                struct_generics.push(generic_name.clone());
-                                                if i == 0 {
-                                                    def_code.push_str(&format!("{generic_name}"));
-                                                } else {
-                                                    def_code.push_str(&format!(", {generic_name}"));
-                                                }
-                                            },
-                                            _ => (),
-                                        }
-                                    },
-                                    _ => (),
+                    if let Expr::MacroStructName { name, generics, is_shared } = *struct_name {
+                        og_name = name.clone();
+                        def_code.push_str(&format!("#define {name}("));
+
+                        def_name = format!("{name}_##imp_struct_type_name");
+                        for (i, generic) in generics.iter().enumerate() {
+                            if let Expr::Variable { info, .. } = generic {
+                                if let Expr::VariableName { name: generic_name, .. } = *info.clone() {
+                                    struct_generics.push(generic_name.clone());
+                                    if i == 0 {
+                                        def_code.push_str(&generic_name.to_string());
+                                    } else {
+                                        def_code.push_str(&format!(", {generic_name}"));
+                                    }
                                 }
                             }
-                            def_code.push_str(", imp_struct_type_name");
-                            def_code.push_str(&format!(")\\\n"));
-                            if is_shared {
-                                def_code.push_str(&format!("typedef union {def_name} {{\\\n"));
-                            } else {
-                                def_code.push_str(&format!("typedef struct {def_name} {{\\\n"));
-                            }
-                        },
-                        _ => (),
+                        }
+                        def_code.push_str(", imp_struct_type_name");
+                        def_code.push_str(")\\\n");
+                        if is_shared {
+                            def_code.push_str(&format!("typedef union {def_name} {{\\\n"));
+                        } else {
+                            def_code.push_str(&format!("typedef struct {def_name} {{\\\n"));
+                        }
                     }
 
                     let mut fields = String::new();
                     for field in struct_fields {
-                        let varname = self.handle_varname(field);
+                        let varname = self.handle_varname(field)?;
                         if varname.contains(&og_name) {
                             let generic_varname = varname.replace(&og_name, &def_name);
                             fields.push_str(&format!("    {generic_varname};\\\n"));
@@ -1047,46 +1486,42 @@ impl Gen {
                     struct_generics.clear();
                     self.in_macro_func = false;
                 },
-                Expr::Func { typ, params, name, is_inline } => {
+                // the c function is declared by its header
+                Expr::Func { link_name: Some(_), .. } => (),
+                Expr::Func { typ, params, name, is_inline, .. } => {
                     self.indent += 1;
+                    self.def_name = name.clone();
+                    self.loop_count = 0;
                     let mut func_code = String::new();
                     let mut main_func = false;
 
-                    if is_inline && name == String::from("main") {
-                        self.comp_err("can't make main function inline");
-                        exit(1);
+                    if is_inline && name == "main" {
+                        return Err(self.comp_err("can't make main function inline"));
                     }
 
                     if is_inline {
                         func_code.push_str("$inline ");
                     }
 
-                    if name == String::from("main") {
+                    if name == "main" {
                         main_func = true;
                         func_code.push_str("int");
+                    } else if let Types::Arr { typ: arr_typ, .. } = typ {
+                        func_code.push_str(&self.array_struct(*arr_typ)?);
                     } else {
-                        // fix this to allow array returns
-                        func_code.push_str(&self.handle_typ(typ).0);
+                        func_code.push_str(&self.handle_typ(typ)?.0);
                     }
 
                     func_code.push_str(&format!(" {name}("));
                     for (i, param) in params.iter().enumerate() {
-                        let mut varname = self.handle_varname(param.clone());
+                        let mut varname = self.handle_varname(param.clone())?;
 
-                        if varname.chars().last().unwrap() == 'A' {
-                            match param {
-                                Expr::VariableName { typ, .. } => {
-                                    if let Types::Arr { .. } = typ {
-                                        let varname_eq_index = varname.find("=");
-                                        match varname_eq_index {
-                                            Some(index) => {
-                                                varname.truncate(index-1);
-                                            },
-                                            None => (),
-                                        }
-                                    }
-                                },
-                                _ => (),
+                        if varname.ends_with('A') {
+                            if let Expr::VariableName { typ: Types::Arr { .. }, .. } = param {
+                                let varname_eq_index = varname.find("=");
+                                if let Some(index) = varname_eq_index {
+                                    varname.truncate(index-1);
+                                }
                             }
                         }
 
@@ -1097,6 +1532,12 @@ impl Gen {
                         //     None => (),
                         // }
 
+                        if let Expr::VariableName { typ, name, .. } = param {
+                            if self.by_ref.contains(name) {
+                                varname = format!("{}* {name}__ref", self.handle_typ(typ.clone())?.0);
+                            }
+                        }
+
                         if i == 0 {
                             func_code.push_str(&varname);
                         } else {
@@ -1111,126 +1552,123 @@ impl Gen {
                         func_code.push_str("#elif __linux__\n    setlocale(LC_ALL, \"\");\n");
                         func_code.push_str("#endif\n");
                     }
-    
+                    func_code.push_str(&std::mem::take(&mut self.func_prelude));
+
                     self.defs_location.push(self.code.len());
                     self.code.push_str(&func_code);
                 },
-                Expr::MacroFunc { params, name, .. } => {
-                    self.indent += 1;
-                    self.curl_rc += 1;
-                    self.in_macro_func = true;
+                Expr::MacroFunc { typ, params, name } => {
+                    // nothing is generated until it's called, the body is kept up to its closing }
+                    let mut body = Vec::new();
+                    let mut depth = 1;
+                    while depth > 0 {
+                        let Some(next) = expressions.next() else {
+                            break;
+                        };
+
+                        match next.0 {
+                            Expr::Func { .. } | Expr::MacroFunc { .. } | Expr::Switch(_) | Expr::Case(_) | Expr::Fall(_) |
+                            Expr::If(..) | Expr::OrIf(..) | Expr::Else | Expr::Loop { .. } | Expr::For { .. } | Expr::StartBlock => depth += 1,
+                            Expr::EndBlock => depth -= 1,
+                            _ => (),
+                        }
+                        body.push(next);
+                    }
 
-                    let mut func_code = String::from("#define ");
-                    func_code.push_str(&format!("{name}("));
+                    // params that are @mut'd are changed in place like they were when this was a macro,
+                    // pointers can already do that
+                    let mut by_ref = Vec::new();
+                    for (expr, _, _) in &body {
+                        if let Expr::MutateVars(variables) = expr {
+                            for variable in variables {
+                                let mutated = match variable {
+                                    Expr::Variable { info, .. } => info.as_ref(),
+                                    variable => variable,
+                                };
 
-                    for (i, param) in params.iter().enumerate() {
-                        if i == 0 {
-                            func_code.push_str(&self.handle_value(param.clone()));
-                        } else {
-                            let comma_separated = format!(", {}", self.handle_value(param.clone()));
-                            func_code.push_str(&comma_separated);
+                                for param in &params {
+                                    if let (Expr::VariableName { name: mutated, .. }, Expr::VariableName { typ, name: param, .. }) = (mutated, param) {
+                                        if mutated == param && !matches!(typ, Types::Pointer(_)) && !by_ref.contains(param) {
+                                            by_ref.push(param.clone());
+                                        }
+                                    }
+                                }
+                            }
                         }
                     }
-                    func_code.push_str(") ({\\\n");
-                    self.defs_location.push(self.code.len());
-                    self.code.push_str(&func_code);
+
+                    self.templates.insert(name, Template { typ, params, body, by_ref, file: self.in_file.clone(), span: info.2 });
                 },
                 Expr::VariableName { typ, name, reassign, constant, field_data, func_arg } => {
                     self.add_spaces(self.indent);
 
-                    let mut varname = self.handle_varname(Expr::VariableName { typ: typ.clone(), name, reassign, constant, field_data, func_arg });
+                    let mut varname = self.handle_varname(Expr::VariableName { typ: typ.clone(), name, reassign, constant, field_data, func_arg })?;
                     let last_varname = varname.chars().last().unwrap();
                     if last_varname == 'A' {
                         if let Types::Arr { length, .. } = typ {
                             varname.pop();
-                            if self.in_macro_func {
-                                self.code.push_str(&format!("{varname}{{}}, .len = {length}}};\\\n"));
-                            } else {
-                                self.code.push_str(&format!("{varname}{{}}, .len = {length}}};\n"));
-                            }
+                            self.code.push_str(&format!("{varname}{{}}, .len = {length}}};\n"));
                         }
                         continue;
                     }
 
-                    if self.in_macro_func {
-                        self.code.push_str(&format!("{varname} = {{0}};\\\n"));
-                    } else {
-                        self.code.push_str(&format!("{varname} = {{0}};\n"));
-                    }
+                    self.code.push_str(&format!("{varname} = {{0}};\n"));
                 },
                 Expr::Variable { info, value } => {
                     self.add_spaces(self.indent);
 
                     let mut var_typ = Types::None;
-                    match *info {
-                        Expr::VariableName { ref typ, ref name, .. } => {
-                            var_typ = typ.clone();
-                            if let Types::TypeId = typ {
-                                let typeid_type = self.handle_value(*value.clone());
-                                self.code.push_str(&format!("typedef {typeid_type} {name};\n"));
-                                continue;
-                            }
-                        },
-                        _ => (),
+                    if let Expr::VariableName { ref typ, ref name, .. } = *info {
+                        var_typ = typ.clone();
+                        if let Types::TypeId = typ {
+                            let typeid_type = self.handle_value(*value.clone())?;
+                            self.code.push_str(&format!("typedef {typeid_type} {name};\n"));
+                            continue;
+                        }
                     }
 
-                    let mut varname = self.handle_varname(*info.clone());
-                    if varname.chars().last().unwrap() == 'A' {
+                    let mut varname = self.handle_varname(*info.clone())?;
+                    if varname.ends_with('A') {
                         match (*value.clone(), *info) {
-                            (Expr::ArrayLit(arrlit), Expr::VariableName { typ, .. }) => {
-                                if let Types::Arr { length, .. } = typ {
-                                    varname.pop();
-                                    let var_val = self.handle_arraylit(arrlit, length);
-                                    if self.in_macro_func {
-                                        self.code.push_str(&format!("{varname}{var_val};\\\n"));
-                                    } else {
-                                        self.code.push_str(&format!("{varname}{var_val};\n"));
-                                    }
-                                    continue;
-                                }
+                            (Expr::ArrayLit(arrlit), Expr::VariableName { typ: Types::Arr { length, .. }, .. }) => {
+                                varname.pop();
+                                let var_val = self.handle_arraylit(arrlit, length)?;
+                                self.code.push_str(&format!("{varname}{var_val};\n"));
+                                continue;
                             }
-                            (_, Expr::VariableName { typ, .. })=> {
-                                if let Types::Arr { .. } = typ {
-                                    self.comp_err("unable to handle array macro");
-                                    exit(1);
+                            (value, Expr::VariableName { typ: Types::Arr { .. }, .. }) => {
+                                // anything but a literal already is an array_T, like what a function returns
+                                if let Some(index) = varname.find(" = ") {
+                                    varname.truncate(index);
                                 }
+                                let var_val = self.handle_value(value)?;
+                                self.code.push_str(&format!("{varname} = {var_val};\n"));
+                                continue;
                             },
                             _ => (),
                         }
                     }
 
-                    let mut var_val = self.handle_value(*value);
+                    let mut var_val = self.handle_value(*value)?;
                     if var_val.is_empty() {
-                        let typename = self.handle_typ(var_typ).0;
+                        let typename = self.handle_typ(var_typ)?.0;
                         var_val = if self.has_default_struct(&typename) {
                             format!("{typename}_default")
                         } else {
                             String::from("{0}")
                         };
-                    } else if var_val == String::from("impulse_garbage") {
-                        if self.in_macro_func {
-                            self.code.push_str(&format!("{varname};\\\n"));
-                        } else {
-                            self.code.push_str(&format!("{varname};\n"));
-                        }
+                    } else if var_val == "impulse_garbage" {
+                        self.code.push_str(&format!("{varname};\n"));
                         continue
                     }
                     
-                    if self.in_macro_func {
-                        self.code.push_str(&format!("{varname} = {var_val};\\\n"));
-                    } else {
-                        self.code.push_str(&format!("{varname} = {var_val};\n"));
-                    }
+                    self.code.push_str(&format!("{varname} = {var_val};\n"));
                 },
                 Expr::FuncCall { name, gave_params } => {
                     self.add_spaces(self.indent);
 
-                    let call = self.handle_funccall(Expr::FuncCall { name, gave_params });
-                    if self.in_macro_func {
-                        self.code.push_str(&format!("{call};\\\n"));
-                    } else {
-                        self.code.push_str(&format!("{call};\n"));
-                    }
+                    let call = self.handle_funccall(Expr::FuncCall { name, gave_params })?;
+                    self.code.push_str(&format!("{call};\n"));
                 },
                 Expr::Switch(conditions) => {
                     self.add_spaces(self.indent);
@@ -1238,7 +1676,7 @@ impl Gen {
 
                     first_case = true;
 
-                    let switch_code = self.handle_branch(&String::from("switch "), conditions);
+                    let switch_code = self.handle_branch(&String::from("switch "), conditions)?;
                     self.code.push_str(&switch_code);
                 },
                 Expr::Case(conditions) => {
@@ -1249,7 +1687,7 @@ impl Gen {
                     let case_code = if let Expr::None = conditions[0] {
                         String::from("default: {\n")
                     } else {
-                        let condition_str = self.handle_boolean_condition(&conditions);
+                        let condition_str = self.handle_boolean_condition(&conditions)?;
                         format!("case {condition_str}: {{\n")
                     };
 
@@ -1270,7 +1708,7 @@ impl Gen {
                     let case_code = if let Expr::None = conditions[0] {
                         String::from("default: {\n")
                     } else {
-                        let condition_str = self.handle_boolean_condition(&conditions);
+                        let condition_str = self.handle_boolean_condition(&conditions)?;
                         format!("case {condition_str}: {{\n")
                     };
 
@@ -1289,44 +1727,27 @@ impl Gen {
                     self.add_spaces(self.indent);
                     self.indent += 1;
 
-                    match (&conditions[0], *capture) {
-                        (Expr::VariableName { typ, name, .. }, Expr::Variable { info, value }) => {
-                            if let Types::TypeDef { type_name, .. } = typ {
-                                if type_name == &String::from("option") {
-                                    let if_code = if self.in_macro_func {
-                                        format!("if (!{name}.none) {{\\\n")
-                                    } else {
-                                        format!("if (!{name}.none) {{\n")
-                                    };
-
-                                    self.curl_rc += 1;
-                                    self.code.push_str(&if_code);
-                                    self.add_spaces(self.indent);
-
-                                    match *info {
-                                        Expr::VariableName { ref name, .. } => {
-                                            if name != "_" {
-                                                let varname = self.handle_varname(*info);
-                                                let varvalue = self.handle_value(*value);
-                                                
-                                                let capture_code = if self.in_macro_func {
-                                                    format!("{varname} = {varvalue};\\\n")
-                                                } else {
-                                                    format!("{varname} = {varvalue};\n")
-                                                };
-                                                self.code.push_str(&capture_code);
-                                            }
-                                        },
-                                        _ => (),
-                                    }
-                                    continue;
+                    if let (Expr::VariableName { typ: Types::TypeDef { type_name, .. }, name, .. }, Expr::Variable { info, value }) = (&conditions[0], *capture) {
+                        if type_name == &String::from("option") {
+                            let if_code = format!("if (!{name}.none) {{\n");
+
+                            self.code.push_str(&if_code);
+                            self.add_spaces(self.indent);
+
+                            if let Expr::VariableName { ref name, .. } = *info {
+                                if name != "_" {
+                                    let varname = self.handle_varname(*info)?;
+                                    let varvalue = self.handle_value(*value)?;
+                                    
+                                    let capture_code = format!("{varname} = {varvalue};\n");
+                                    self.code.push_str(&capture_code);
                                 }
                             }
-                        },
-                        _ => (),
+                            continue;
+                        }
                     }
                     
-                    let if_code = self.handle_branch(&String::from("if "), conditions);
+                    let if_code = self.handle_branch(&String::from("if "), conditions)?;
                     // don't need to add \n, handle_branch does it
                     self.code.push_str(&if_code);
 
@@ -1335,70 +1756,48 @@ impl Gen {
                     self.add_spaces(self.indent);
                     self.indent += 1;
 
-                    match (&conditions[0], *capture) {
-                        (Expr::VariableName { typ, name, .. }, Expr::Variable { info, value }) => {
-                            if let Types::TypeDef { type_name, .. } = typ {
-                                if type_name == &String::from("option") {
-                                    let if_code = if self.in_macro_func {
-                                        format!("else if (!{name}.none) {{\\\n")
-                                    } else {
-                                        format!("else if (!{name}.none) {{\n")
-                                    };
-
-                                    self.curl_rc += 1;
-                                    self.code.push_str(&if_code);
-                                    self.add_spaces(self.indent);
-
-                                    match *info {
-                                        Expr::VariableName { ref name, .. } => {
-                                            if name != "_" {
-                                                let varname = self.handle_varname(*info);
-                                                let varvalue = self.handle_value(*value);
-                                                
-                                                let capture_code = if self.in_macro_func {
-                                                    format!("{varname} = {varvalue};\\\n")
-                                                } else {
-                                                    format!("{varname} = {varvalue};\n")
-                                                };
-                                                self.code.push_str(&capture_code);
-                                            }
-                                        },
-                                        _ => (),
-                                    }
-                                    continue;
+                    if let (Expr::VariableName { typ: Types::TypeDef { type_name, .. }, name, .. }, Expr::Variable { info, value }) = (&conditions[0], *capture) {
+                        if type_name == &String::from("option") {
+                            let if_code = format!("else if (!{name}.none) {{\n");
+
+                            self.code.push_str(&if_code);
+                            self.add_spaces(self.indent);
+
+                            if let Expr::VariableName { ref name, .. } = *info {
+                                if name != "_" {
+                                    let varname = self.handle_varname(*info)?;
+                                    let varvalue = self.handle_value(*value)?;
+                                    
+                                    let capture_code = format!("{varname} = {varvalue};\n");
+                                    self.code.push_str(&capture_code);
                                 }
                             }
-                        },
-                        _ => (),
+                            continue;
+                        }
                     }
                     
-                    let orif_code = self.handle_branch(&String::from("else if "), conditions);
+                    let orif_code = self.handle_branch(&String::from("else if "), conditions)?;
                     // don't need to add \n, handle_branch does it
                     self.code.push_str(&orif_code);
                 },
                 Expr::Else => {
                     self.add_spaces(self.indent);
                     self.indent += 1;
-                    self.curl_rc += 1;
 
-                    if self.in_macro_func {
-                        self.code.push_str("else {\\\n");
-                    } else {
-                        self.code.push_str("else {\n");
-                    }
+                    self.code.push_str("else {\n");
                 },
                 Expr::Loop { condition, modifier } => {
                     self.add_spaces(self.indent);
                     self.indent += 1;
 
-                    let loop_code = self.handle_loop(condition, *modifier);
+                    let loop_code = self.handle_loop(condition, *modifier)?;
                     self.code.push_str(&loop_code);
                 },
                 Expr::For { for_this, in_this, iterator } => {
                     self.add_spaces(self.indent);
                     self.indent += 1;
 
-                    let for_code = self.handle_for(in_this, iterator);
+                    let for_code = self.handle_for(*in_this, iterator)?;
                     self.code.push_str(&for_code.0);
 
                     let mut skip_extract = false;
@@ -1408,12 +1807,11 @@ impl Gen {
                             if let Types::None = typ {
                                 (format!("typeof({}.data[0])", for_code.1), name)
                             } else {
-                                (self.handle_typ(typ).0, name)
+                                (self.handle_typ(typ)?.0, name)
                             }
                         },
                         unexpected => {
-                            self.comp_err(&format!("{unexpected:?} is not implemented yet for for loops"));
-                            exit(1);
+                            return Err(self.comp_err(&format!("{unexpected:?} is not implemented yet for for loops")));
                         },
                     };
 
@@ -1432,14 +1830,11 @@ impl Gen {
                     self.code.push_str(&var);
                 },
                 Expr::Return(value) => {
+                    let val = self.handle_value(*value)?;
+                    self.write_back();
+
                     self.add_spaces(self.indent);
-                    
-                    let val = self.handle_value(*value);
-                    if self.in_macro_func {
-                        self.code.push_str(&format!("{val};\\\n"))
-                    } else {
-                        self.code.push_str(&format!("return {val};\n"))
-                    }
+                    self.code.push_str(&format!("return {val};\n"));
                 },
                 Expr::StartBlock => {
                     self.add_spaces(self.indent);
@@ -1447,24 +1842,14 @@ impl Gen {
                     self.code.push_str("{\n");
                 },
                 Expr::EndBlock => {
+                    // nothing after a return at the end of the function would run
+                    let returned = self.code.trim_end().rsplit('\n').next().is_some_and(|line| line.trim_start().starts_with("return"));
+                    if self.indent == 1 && !returned {
+                        self.write_back();
+                    }
                     self.indent -= 1;
                     self.add_spaces(self.indent);
-
-                    if self.curl_rc > 0 {
-                        self.curl_rc -= 1;
-
-                        // WATCH THIS CAREFULLY, MIGHT BREAK
-                        if self.curl_rc == 0 && self.in_macro_func {
-                            self.in_macro_func = false;
-                            self.code.push_str("})\n");
-                        } else if self.in_macro_func {
-                            self.code.push_str("}\\\n");
-                        } else {
-                            self.code.push_str("}\n");
-                        }
-                    } else {
-                        self.code.push_str("}\n");
-                    }
+                    self.code.push_str("}\n");
                 },
                 Expr::Break => {
                     self.add_spaces(self.indent);
@@ -1477,99 +1862,439 @@ impl Gen {
                 Expr::MutateVars(variables) => {
                     self.mutate_func_args = true;
                     for variable in variables {
-                        let varname = self.handle_varname(variable.clone());
+                        // the instance already has its own copy of these
+                        if let Expr::Variable { info, .. } = &variable {
+                            if let Expr::VariableName { name, .. } = info.as_ref() {
+                                if self.by_ref.contains(name) {
+                                    continue;
+                                }
+                            }
+                        }
+
+                        let varname = self.handle_varname(variable.clone())?;
                         let new_varname = varname.strip_prefix("const ").unwrap();
-                        let pos = self.code.rfind(&varname);
-
-                        match pos {
-                            Some(index) => {
-                                let before = &self.code[..index];
-                                let after = &self.code[index..];
-                                let replaced = after.replace(&varname, new_varname);
-                                self.code = format!("{before}{replaced}");
-                            },
-                            None => (),
+                        let pos = self.code[self.item_start..].rfind(&varname).map(|index| index + self.item_start);
+
+                        if let Some(index) = pos {
+                            let before = &self.code[..index];
+                            let after = &self.code[index..];
+                            let replaced = after.replace(&varname, new_varname);
+                            self.code = format!("{before}{replaced}");
                         }
                     }
                     self.mutate_func_args = false;
                 },
                 unimpl => {
-                    self.comp_err(&format!("{unimpl:?} is not implemented yet"));
-                    exit(1);
+                    return Err(self.comp_err(&format!("{unimpl:?} is not implemented yet")));
                 }
             }
         }
+        self.close_unit_item();
+
+        self.flush_instances()
+    }
+
+    // splits the generated c into a header that every unit includes and a unit per module
+    // with that module's function bodies and variables
+    fn split_units(&self, header_name: &str) -> (Header, Vec<(String, String)>) {
+        let mut header = Header { text: self.imports.clone(), shared: self.imports.clone(), ..Header::default() };
+        let mut units: Vec<(String, String)> = Vec::new();
+        let mut last = 0;
+
+        for item in &self.unit_items {
+            let end = item.end.min(self.code.len());
+            header.text.push_str(&self.code[last..item.start]);
+            header.shared.push_str(&self.code[last..item.start]);
+            let text = &self.code[item.start..end];
+            last = end;
+
+            let (declaration, definition) = match item.kind {
+                UnitItemKind::Func => (function_prototype(text), text.to_string()),
+                UnitItemKind::Global => (extern_declaration(text), text.to_string()),
+                UnitItemKind::CEmbed => split_c_embed(text),
+            };
+            header.text.push_str(&declaration);
+            match item.kind {
+                UnitItemKind::CEmbed => header.shared.push_str(&declaration),
+                _ => match declared_name(&declaration) {
+                    Some(name) => header.declarations.push((name, declaration)),
+                    None => header.shared.push_str(&declaration),
+                },
+            }
+
+            if definition.trim().is_empty() {
+                continue;
+            }
+
+            let name = unit_name(&item.file);
+            let index = match units.iter().position(|(unit, _)| unit == &name) {
+                Some(index) => index,
+                None => {
+                    units.push((name, format!("#include \"{header_name}\"\n")));
+                    units.len() - 1
+                },
+            };
+
+            // the #line in front of it keeps pointing errors at the .imp file
+            if let Some(directive) = last_line_directive(&self.code[..item.start]) {
+                units[index].1.push_str(directive);
+            }
+            units[index].1.push_str(&definition);
+        }
+
+        header.text.push_str(&self.code[last..]);
+        header.shared.push_str(&self.code[last..]);
+        (header, units)
     }
 
-    fn generate_cpp(&mut self, _expressions: Vec<(Expr, String, u32)>) {
-        println!("lmao not ready");
-        exit(1);
+    // only the units whose code, or the part of the header they use, changed since the last build
+    // are compiled again
+    fn build_units(&self) -> Result<(), Diagnostic> {
+        let units_dir = format!("{}.units", self.out_file);
+        if let Err(err) = fs::create_dir_all(&units_dir) {
+            return Err(Diagnostic::global(&format!("unable to create {units_dir} with error: {err}")));
+        }
+
+        let out_name = Path::new(&self.out_file).file_name().map_or(String::from("output"), |name| name.to_string_lossy().to_string());
+        let header_name = format!("{out_name}.h");
+        let (header, units) = self.split_units(&header_name);
+        write_file(&Path::new(&units_dir).join(&header_name).to_string_lossy(), &header.text)?;
+
+        let mut inputs = Vec::new();
+        for (name, code) in &units {
+            let source = Path::new(&units_dir).join(format!("{name}.c")).to_string_lossy().to_string();
+            let object = Path::new(&units_dir).join(format!("{name}.o")).to_string_lossy().to_string();
+            write_file(&source, code)?;
+
+            // a function or global added to another module isn't in this unit's key
+            let used = identifiers(&header.shared).chain(identifiers(code)).collect::<HashSet<&str>>();
+            let declarations: Vec<&String> = header.declarations.iter()
+                .filter(|(name, _)| used.contains(name.as_str()))
+                .map(|(_, declaration)| declaration)
+                .collect();
+            let key = fingerprint(&(&header.shared, declarations, code, self.compiler.object_command_line(&source, &object)));
+            if !object_is_fresh(Path::new(&object), key) {
+                self.compiler.compile_object(&source, &object)?;
+                record_object(Path::new(&object), key);
+            }
+            inputs.push(object);
+        }
+
+        // modules that aren't imported anymore
+        if let Ok(entries) = fs::read_dir(&units_dir) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let path = entry.path();
+                let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+                if stem != out_name && !units.iter().any(|(name, _)| name == &stem) {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+
+        inputs.extend(self.comp_imports.iter().cloned());
+        self.compiler.compile(&inputs, &self.out_file)
     }
 
-    pub fn generate(&mut self, expressions: Vec<(Expr, String, u32)>) {
-        // let c_flags_res = fs::read_to_string("c_flags.txt");
-        // let c_flags = match c_flags_res {
-        //     Ok(file) => file,
-        //     Err(_) => String::new(),
-        // };
+    fn generate_cpp(&mut self, _expressions: Vec<(Expr, String, Span)>) -> Result<(), Diagnostic> {
+        Err(Diagnostic::global("transpiling to c++ is not supported yet"))
+    }
 
-        let c_flags = self.c_flags.join(" ");
+    // the whole program as one c file, nothing is written or compiled
+    pub fn generate_source(&mut self, expressions: Vec<(Expr, String, Span)>) -> Result<String, Diagnostic> {
         match self.lang {
-            Lang::C => {
-                self.generate_c(expressions);
-
-                let c_code = format!("{}{}", self.imports, self.code);
-                if self.compile {
-                    match fs::write("./output.c", c_code) {
-                        Ok(_) => (),
-                        Err(err) => {
-                            self.comp_err(&format!("{err}"));
-                            exit(1);
-                        }
-                    }
+            Lang::C => self.generate_c(expressions)?,
+            Lang::Cpp => self.generate_cpp(expressions)?,
+        }
 
-                    let com = format!("gcc output.c {}-o {} {}", self.comp_imports, self.out_file, c_flags);
-                    println!("{com}");
-                    if cfg!(target_os = "windows") {
-                        Command::new("cmd")
-                            .args(["/C", &com])
-                            .stdout(std::process::Stdio::inherit())
-                            .stderr(std::process::Stdio::inherit())
-                            .status()
-                            .expect("failed to run gcc to compile")
-                    } else {
-                        Command::new("sh")
-                            .args(["-c", &com])
-                            .stdout(std::process::Stdio::inherit())
-                            .stderr(std::process::Stdio::inherit())
-                            .status()
-                            .expect("failed to run gcc to compile")
-                    };
-                } else {
-                    match fs::write(&format!("./{}.c", self.out_file), c_code) {
-                        Ok(_) => (),
-                        Err(err) => {
-                            self.comp_err(&format!("{err}"));
-                            exit(1);
-                        }
-                    }
+        Ok(format!("{}{}", self.imports, self.code))
+    }
+
+    // .c files next to imported .h files, they need compiling alongside the generated c
+    pub fn c_sources(&self) -> &[String] {
+        &self.comp_imports
+    }
+
+    // what the imported modules need the c compiler to be given
+    pub fn c_flags(&self) -> &[String] {
+        &self.c_flags
+    }
+
+    pub fn generate(&mut self, expressions: Vec<(Expr, String, Span)>) -> Result<(), Diagnostic> {
+        let source = self.generate_source(expressions)?;
+        self.compiler.add_flags(&self.c_flags);
+        if self.compile {
+            return self.build_units();
+        }
+
+        // transpiling gives one c file, it sits in the build dir so nothing is written to the cwd
+        let c_file = format!("{}.c", self.out_file);
+        write_file(&c_file, &source)?;
+
+        let mut sources = vec![c_file];
+        sources.extend(self.comp_imports.iter().cloned());
+        println!("{}", self.compiler.command_line(&sources, &self.out_file));
+
+        Ok(())
+    }
+}
+
+fn takes_any(typ: &Types) -> bool {
+    match typ {
+        Types::Any => true,
+        Types::Pointer(to) | Types::Arr { typ: to, .. } => takes_any(to),
+        Types::TypeDef { generics: Some(generics), .. } => generics.iter().any(takes_any),
+        _ => false,
+    }
+}
+
+// what the any in a param's type is, going by the type of the argument given for it
+fn find_any(param: &Types, given: &Types) -> Option<Types> {
+    match (param, given) {
+        (Types::Any, given) => Some(given.clone()),
+        (Types::Pointer(param), Types::Pointer(given)) => find_any(param, given),
+        (Types::Arr { typ: param, .. }, Types::Arr { typ: given, .. }) => find_any(param, given),
+        (Types::TypeDef { generics: Some(params), .. }, Types::TypeDef { generics: Some(given), .. }) => {
+            params.iter().zip(given).find_map(|(param, given)| find_any(param, given))
+        },
+        _ => None,
+    }
+}
+
+fn with_type_args(typ: &Types, type_args: &HashMap<String, Types>) -> Types {
+    match typ {
+        Types::Generic(name) => type_args.get(name).cloned().unwrap_or(typ.clone()),
+        Types::TypeDef { type_name, generics: None } if type_args.contains_key(type_name) => type_args[type_name].clone(),
+        Types::Any => type_args.get("any").cloned().unwrap_or(Types::Any),
+        Types::Pointer(to) => Types::Pointer(Box::new(with_type_args(to, type_args))),
+        Types::Arr { typ, length } => Types::Arr { typ: Box::new(with_type_args(typ, type_args)), length: length.clone() },
+        Types::TypeDef { type_name, generics } => Types::TypeDef {
+            type_name: type_name.clone(),
+            generics: generics.as_ref().map(|generics| generics.iter().map(|generic| with_type_args(generic, type_args)).collect()),
+        },
+        typ => typ.clone(),
+    }
+}
+
+fn write_file(path: &str, content: &str) -> Result<(), Diagnostic> {
+    match fs::write(path, content) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Diagnostic::global(&format!("unable to write {path} with error: {err}"))),
+    }
+}
+
+// <file stem>-<hash of the full path>, so two modules called string.imp don't share a unit
+fn unit_name(file: &str) -> String {
+    let stem = Path::new(file).file_stem().map_or(String::from("module"), |stem| stem.to_string_lossy().to_string());
+    format!("{stem}-{:08x}", fingerprint(file) as u32)
+}
+
+fn last_line_directive(code: &str) -> Option<&str> {
+    let start = code.strip_suffix('\n')?.rfind('\n').map_or(0, |i| i + 1);
+    let line = &code[start..];
+    line.starts_with("#line ").then_some(line)
+}
+
+fn function_prototype(function: &str) -> String {
+    match function.find(") {\n") {
+        Some(index) => format!("{};\n", &function[..=index]),
+        None => String::new(),
+    }
+}
+
+fn extern_declaration(variable: &str) -> String {
+    let declaration = match variable.find(" = ") {
+        Some(index) => &variable[..index],
+        None => variable.trim_end().trim_end_matches(';'),
+    };
 
-                    let com = format!("gcc {}.c {}-o {} {}", self.out_file, self.comp_imports, self.out_file, c_flags);
-                    println!("{com}");
+    format!("extern {};\n", declaration.trim())
+}
+
+// the name a prototype or extern declaration is for, the last identifier before its ( or [
+fn declared_name(declaration: &str) -> Option<String> {
+    let end = declaration.find(['(', '[', ';']).unwrap_or(declaration.len());
+    identifiers(&declaration[..end]).last().map(|name| name.to_string())
+}
+
+fn identifiers(code: &str) -> impl Iterator<Item = &str> {
+    code.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+}
+
+// where the initializer of a c declaration starts, the first = that isn't in brackets
+fn initializer(declaration: &str) -> Option<usize> {
+    let code = declaration.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < code.len() {
+        match code[i] {
+            b'/' if code.get(i + 1) == Some(&b'/') => {
+                i = declaration[i..].find('\n').map_or(code.len(), |end| i + end);
+            },
+            b'/' if code.get(i + 1) == Some(&b'*') => {
+                i = declaration[i + 2..].find("*/").map_or(code.len(), |end| i + 2 + end + 1);
+            },
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b'=' if depth == 0 => return Some(i),
+            _ => (),
+        }
+        i += 1;
+    }
+
+    None
+}
+
+// a top-level @c can hold whole c functions and initialised globals, they go in the unit and
+// everything else (includes, macros, declarations) stays in the header. preprocessor lines go in
+// both so an #ifdef around a function still applies to it
+fn split_c_embed(embed: &str) -> (String, String) {
+    let mut header = String::new();
+    let mut unit = String::new();
+    let mut pending = String::new(); // everything since the last complete declaration
+    let mut found_definition = false;
+    let mut in_function = false;
+    let mut depth = 0;
+    let mut line_start = true;
+
+    let mut chars = embed.chars().peekable();
+    while let Some(c) = chars.next() {
+        let out = if in_function { &mut unit } else { &mut pending };
+
+        if c == '#' && line_start && depth == 0 {
+            let mut line = String::from(c);
+            while let Some(next) = chars.next() {
+                line.push(next);
+                if next == '\\' && chars.peek() == Some(&'\n') {
+                    line.push(chars.next().unwrap());
+                } else if next == '\n' {
+                    break;
                 }
+            }
 
-                // this can only happen after build so there's no problem if the file isn't output.c 
-                if !self.keep_gen {
-                    match fs::remove_file("output.c") {
-                        Ok(_) => (),
-                        Err(_) => {
-                            self.comp_err("error handling code generation");
-                            exit(1)
-                        },
+            header.push_str(&pending);
+            pending.clear();
+            header.push_str(&line);
+            unit.push_str(&line);
+            continue;
+        }
+
+        if c == '/' && (chars.peek() == Some(&'/') || chars.peek() == Some(&'*')) {
+            let block = chars.next() == Some('*');
+            out.push(c);
+            out.push(if block { '*' } else { '/' });
+            while let Some(next) = chars.next() {
+                if !block && next == '\n' {
+                    out.push(next);
+                    line_start = true;
+                    break;
+                }
+                out.push(next);
+                if block && next == '*' && chars.peek() == Some(&'/') {
+                    out.push(chars.next().unwrap());
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if c == '"' || c == '\'' {
+            out.push(c);
+            while let Some(next) = chars.next() {
+                out.push(next);
+                if next == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        out.push(escaped);
+                    }
+                } else if next == c {
+                    break;
+                }
+            }
+            line_start = false;
+            continue;
+        }
+
+        match c {
+            '{' if depth == 0 => {
+                let declaration = pending.trim_end();
+                if declaration.ends_with(')') {
+                    found_definition = true;
+                    in_function = true;
+                    // static functions are private to their unit, so the header doesn't declare them
+                    if !without_comments(declaration).trim_start().starts_with("static") {
+                        header.push_str(&format!("{declaration};\n"));
                     }
+                    unit.push_str(&pending);
+                    unit.push(c);
+                    pending.clear();
+                } else {
+                    pending.push(c);
                 }
+                depth += 1;
             },
-            Lang::Cpp => self.generate_cpp(expressions),
+            '{' => {
+                out.push(c);
+                depth += 1;
+            },
+            '}' => {
+                out.push(c);
+                depth -= 1;
+                if depth == 0 && in_function {
+                    in_function = false;
+                    unit.push('\n');
+                }
+            },
+            ';' if depth == 0 => {
+                pending.push(c);
+                match initializer(&pending) {
+                    // a global defined in every unit that includes the header wouldn't link
+                    Some(index) => {
+                        found_definition = true;
+                        let declaration = without_comments(&pending[..index]);
+                        let declaration = declaration.trim();
+                        if !declaration.starts_with("static") {
+                            header.push_str(&format!("extern {declaration};\n"));
+                        }
+                        unit.push_str(&pending);
+                        unit.push('\n');
+                    },
+                    None => header.push_str(&pending),
+                }
+                pending.clear();
+            },
+            _ => out.push(c),
+        }
+
+        if c == '\n' {
+            line_start = true;
+        } else if !c.is_whitespace() {
+            line_start = false;
         }
     }
+    header.push_str(&pending);
+
+    if !found_definition {
+        return (embed.to_string(), String::new());
+    }
+
+    (header, unit)
+}
+
+// the declaration before a function body can have comments above it
+fn without_comments(code: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = code;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("//") {
+            rest = after.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.split_once("*/").map_or("", |(_, after)| after);
+        } else {
+            let c = rest.chars().next().unwrap();
+            stripped.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    stripped
 }
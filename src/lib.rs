@@ -0,0 +1,20 @@
+// the compiler itself, main.rs is the command line on top of it
+
+pub mod ast;
+pub mod bindgen;
+pub mod cache;
+pub mod compiler;
+pub mod declare_types;
+pub mod diagnostic;
+pub mod generator;
+pub mod lib_path;
+pub mod manifest;
+pub mod parser;
+pub mod session;
+pub mod tokeniser;
+pub mod type_checker;
+pub mod vfs;
+
+pub use diagnostic::{Diagnostic, Diagnostics};
+pub use session::{Output, Session};
+pub use vfs::{FileSystem, MemoryFs, RealFs};
@@ -0,0 +1,76 @@
+use std::{env, fs, path::{Path, PathBuf}};
+
+use crate::{diagnostic::Diagnostic, vfs::FileSystem};
+
+// where `base/` and `vendor/` imports are looked for, in order
+#[derive(Debug, Clone, Default)]
+pub struct LibPaths {
+    dirs: Vec<(PathBuf, &'static str)>, // and where each one came from, for the error
+}
+
+impl LibPaths {
+    // --lib-path first, then impulse.toml, then IMPULSE_HOME, then around the impulse executable
+    pub fn new(flags: &[String], manifest: &[String]) -> LibPaths {
+        let mut dirs = Vec::new();
+        dirs.extend(flags.iter().map(|dir| (PathBuf::from(dir), "--lib-path")));
+        dirs.extend(manifest.iter().map(|dir| (PathBuf::from(dir), "lib-path in impulse.toml")));
+
+        if let Some(home) = env::var_os("IMPULSE_HOME").filter(|home| !home.is_empty()) {
+            dirs.push((PathBuf::from(home), "IMPULSE_HOME"));
+        }
+
+        for dir in exe_dirs() {
+            dirs.push((dir, "next to the impulse executable"));
+        }
+
+        LibPaths { dirs }
+    }
+
+    // searched after the ones already there
+    pub fn push(&mut self, dir: PathBuf, from: &'static str) {
+        self.dirs.push((dir, from));
+    }
+
+    // `path` is what was imported, like base/string.imp or vendor/raylib
+    pub fn find(&self, fs: &dyn FileSystem, path: &str) -> Option<PathBuf> {
+        self.dirs.iter()
+            .map(|(dir, _)| dir.join(path))
+            .find(|candidate| fs.exists(candidate))
+    }
+
+    pub fn with_searched(&self, mut diagnostic: Diagnostic, path: &str) -> Diagnostic {
+        let mut searched = String::from("searched");
+        for (dir, from) in &self.dirs {
+            searched.push_str(&format!("\n    {} ({from})", dir.join(path).display()));
+        }
+
+        if self.dirs.is_empty() {
+            searched.push_str(" nowhere, there are no library paths");
+        }
+
+        diagnostic = diagnostic.with_note(&searched);
+        diagnostic.with_note("set IMPULSE_HOME to the directory with base/ in it, or pass --lib-path <dir>")
+    }
+}
+
+// the directory the binary is in, an install prefix around it, and the repo when it's run from target/<profile>
+fn exe_dirs() -> Vec<PathBuf> {
+    let exe = match env::current_exe().and_then(fs::canonicalize) {
+        Ok(exe) => exe,
+        Err(_) => return Vec::new(),
+    };
+
+    let Some(bin) = exe.parent() else {
+        return Vec::new();
+    };
+
+    let mut dirs = vec![bin.to_path_buf()];
+    if let Some(prefix) = bin.parent() {
+        dirs.push(prefix.to_path_buf());
+        dirs.push(prefix.join("lib").join("impulse"));
+        dirs.push(prefix.join("share").join("impulse"));
+        dirs.extend(prefix.parent().map(Path::to_path_buf));
+    }
+
+    dirs
+}
@@ -1,28 +1,38 @@
 use std::io::Write;
-use std::path::PathBuf;
-use std::{env, fs, process::exit};
-
-use crate::tokeniser::*;
-use crate::parser::*;
-use crate::generator::*;
-use crate::declare_types::Lang;
-
-mod tokeniser;
-mod parser;
-mod generator;
-mod declare_types;
-mod type_checker;
-
-fn initalise(dir: &String) {
-    let file_res = fs::File::create(format!("{dir}/c_flags.txt"));
+use std::path::{Path, PathBuf};
+use std::{env, fs, process::{exit, Command as Process, ExitStatus, Stdio}};
+
+use impulse::cache::ParseCache;
+use impulse::compiler::{Cfg, Compiler};
+use impulse::diagnostic::*;
+use impulse::generator::Gen;
+use impulse::lib_path::LibPaths;
+use impulse::manifest::*;
+use impulse::parser::Program;
+use impulse::{RealFs, Session};
+use crate::cli::*;
+
+mod cli;
+
+fn initalise(dir: &String) -> Result<(), Diagnostics> {
+    let manifest_path = Path::new(dir).join(MANIFEST_NAME);
+    if manifest_path.exists() {
+        return Err(Diagnostic::global(&format!("{} already exists", manifest_path.display())).into());
+    }
+
+    // the package is named after the directory, `impulse init .` needs the real name
+    let name = fs::canonicalize(dir).ok()
+        .and_then(|path| path.file_name().map(|name| name.to_string_lossy().to_string()))
+        .unwrap_or(String::from("main"));
+
+    let file_res = fs::File::create(&manifest_path);
     match file_res {
         Ok(mut file) => {
-            let _ = file.write_all(b"-O -Wall -Wextra -Wfloat-equal");
+            let _ = file.write_all(default_manifest(&name).as_bytes());
+            Ok(())
         },
         Err(e) => {
-            println!("{e:?}");
-            println!("\x1b[91merror\x1b[0m: unable to create c_flags config file");
-            exit(1);
+            Err(Diagnostic::global(&format!("unable to create {MANIFEST_NAME} with error: {e:?}")).into())
         },
     }
 }
@@ -34,200 +44,318 @@ fn get_imp_files(dir: &String) -> Vec<PathBuf> {
             let p = paths.filter_map(|res| res.ok())
             .map(|entry| entry.path())
             .filter_map(|path| {
-                if path.extension().map_or(false, |ext| ext == "imp") {
+                if path.extension().is_some_and(|ext| ext == "imp") {
                     Some(path)
                 } else {
                     None
                 }
             })
             .collect::<Vec<_>>();
-            return p
+            p
         }
-        Err(_) => return vec![PathBuf::from(dir)],
+        Err(_) => vec![PathBuf::from(dir)],
     }
 }
 
-fn find_entry_point(files: Vec<PathBuf>) -> Option<(PathBuf, String)> {
+fn find_entry_point(files: Vec<PathBuf>) -> Result<Option<(PathBuf, String)>, Diagnostic> {
     for file in files {
         let content = match fs::read_to_string(file.clone()) {
             Ok(c) => c,
             Err(_) => {
-                println!("\x1b[91merror\x1b[0m: unable to read file: {file:?}");
-                exit(1)
+                return Err(Diagnostic::global(&format!("unable to read file: {file:?}")));
             },
         };
 
         if content.contains("main :: (") {
-            return Some((file, content));
+            return Ok(Some((file, content)));
         }
     }
 
-    None
+    Ok(None)
+}
+
+// the cli reads from disk, copies vendors into the build dir and caches parsed imports there.
+// without a build dir nothing is written, vendors are read where they are and nothing is cached
+fn session(args: &BuildArgs, manifest: Option<&Manifest>, build_dir: Option<&Path>) -> Session {
+    let manifest_lib_paths = manifest.map(|manifest| manifest.lib_paths()).unwrap_or_default();
+    let session = Session::new(RealFs).with_lib_paths(LibPaths::new(&args.lib_paths, &manifest_lib_paths));
+    let session = match build_dir {
+        Some(build_dir) => session.with_vendor_dir(build_dir.join("vendor")).with_parse_cache(ParseCache::new(build_dir.join("cache"))),
+        None => session,
+    };
+
+    let session = session
+        .with_lang(args.lang)
+        .with_line_directives(args.line_directives)
+        .with_cfg(Cfg::new(target(args, manifest).as_deref(), &args.cfg));
+
+    match manifest {
+        Some(manifest) => session.with_vendors(manifest.vendors()),
+        None => session,
+    }
 }
 
-fn setup_step(dir: &String) -> (PathBuf, Vec<(Expr, String, u32)>) {
-    let (filename, content) = match find_entry_point(get_imp_files(dir)) {
-        Some(c) => c,
+fn setup_step(args: &BuildArgs, manifest: Option<&Manifest>, build_dir: Option<&Path>) -> Result<(PathBuf, Program), Diagnostics> {
+    let dir = match manifest.and_then(|manifest| manifest.entry()) {
+        Some(entry) => entry,
+        None => args.path.to_owned(),
+    };
+
+    let filename = match find_entry_point(get_imp_files(&dir))? {
+        Some((filename, _)) => filename,
         None => {
-            println!("\x1b[91merror\x1b[0m: unable to find main function.");
-            exit(1)
+            let e = Diagnostic::global("unable to find main function.")
+                .with_note("the entry point is the .imp file that declares `main :: () {`");
+            return Err(e.into());
         }
     };
 
-    let tokens = tokeniser(content.clone());
-    let mut parse = ExprWeights::new(tokens, filename.to_str().unwrap());
+    let expressions = session(args, manifest, build_dir).parse(filename.to_str().unwrap())?;
+    Ok((filename, expressions))
+}
 
-    if !content.contains("@import \"base/builtin.imp\";") {
-        parse.handle_import_macro(String::from("base/builtin.imp"));
+// c_flags.txt is what `impulse init` wrote before impulse.toml, still read for older projects
+fn read_c_flags(path: &str) -> Vec<String> {
+    match fs::read_to_string(project_dir(path).join("c_flags.txt")) {
+        Ok(content) => content.split_whitespace().map(|flag| flag.to_string()).collect(),
+        Err(_) => Vec::new(),
     }
+}
+
+fn c_flags(args: &BuildArgs, manifest: Option<&Manifest>) -> Vec<String> {
+    // the generated c lives in the build dir but its #includes are relative to the project
+    let mut flags = vec![format!("-I{}", project_dir(&args.path).display())];
+
+    if let Some(manifest) = manifest {
+        flags.extend(manifest.profile(args.release).c_flags.iter().cloned());
+        flags.extend(manifest.link_flags());
+    } else {
+        flags.extend(read_c_flags(&args.path));
 
-    let expressions = parse.parser();
+        if args.release {
+            flags.push(String::from("-O2"));
+        } else {
+            flags.push(String::from("-g"));
+        }
+    }
 
-    return (filename, expressions)
+    flags.extend(args.extra_args.iter().cloned());
+    flags
 }
 
-fn build(dir: &String, keep_gen: bool, lang: Lang, c_flags: &[String]) {
-    let current_dir = env::current_dir();
-    let path = match current_dir {
+fn output_name(args: &BuildArgs, filename: &Path, manifest: Option<&Manifest>) -> Result<String, Diagnostics> {
+    if let Some(out) = &args.out {
+        return Ok(out.to_owned());
+    }
+
+    if args.as_file {
+        return Ok(filename.file_stem().unwrap().to_str().unwrap().to_string());
+    }
+
+    if let Some(manifest) = manifest {
+        return Ok(manifest.output());
+    }
+
+    // `.` has no name of its own
+    let path = match fs::canonicalize(project_dir(&args.path)) {
         Ok(path) => path,
         Err(e) => {
-            println!("\x1b[91merror\x1b[0m: failed to get current directory name with error: {e:?}");
-            exit(1)
+            return Err(Diagnostic::global(&format!("failed to get project directory name with error: {e:?}")).into());
         }
     };
 
-    let out_filename = if let Some(folder_name) = path.file_name() {
-        folder_name.to_str()
-    } else {
-        println!("\x1b[91merror\x1b[0m: can't find current directory name.");
-        exit(1)
+    match path.file_name().and_then(|folder_name| folder_name.to_str()) {
+        Some(folder_name) => Ok(folder_name.to_string()),
+        None => Err(Diagnostic::global("can't find project directory name.").into()),
+    }
+}
+
+// --cc wins over $CC, which wins over impulse.toml
+fn compiler(args: &BuildArgs, manifest: Option<&Manifest>) -> Result<Compiler, Diagnostic> {
+    let build_config = manifest.map(|manifest| &manifest.build);
+
+    let cc = args.cc.clone()
+        .or(env::var("CC").ok().filter(|cc| !cc.trim().is_empty()))
+        .or(build_config.and_then(|build| build.cc.clone()))
+        .unwrap_or(String::from("gcc"));
+
+    Compiler::new(&cc, target(args, manifest).as_deref(), &c_flags(args, manifest))
+}
+
+// --target wins over impulse.toml
+fn target(args: &BuildArgs, manifest: Option<&Manifest>) -> Option<String> {
+    args.target.clone()
+        .or(manifest.and_then(|manifest| manifest.build.target.clone()))
+}
+
+// everything impulse writes for a project goes in here
+fn build_root(path: &str, manifest: Option<&Manifest>) -> PathBuf {
+    let project = match manifest {
+        Some(manifest) => manifest.dir.as_path(),
+        None => project_dir(path),
     };
 
-    let (filename, expressions) = setup_step(dir);
-    // for expr in &expressions {
-    //     println!("{:?}", expr.0);
-    // }
+    project.join("target").join("impulse")
+}
+
+// target/impulse/<profile>, unless --out-dir says otherwise
+fn build_dir(args: &BuildArgs, manifest: Option<&Manifest>) -> Result<PathBuf, Diagnostic> {
+    let dir = match &args.out_dir {
+        Some(out_dir) => PathBuf::from(out_dir),
+        None => build_root(&args.path, manifest).join(if args.release { "release" } else { "debug" }),
+    };
 
-    let mut gen = Gen::new(filename.to_str().unwrap(), out_filename.unwrap(), true, keep_gen, lang, c_flags);
-    gen.generate(expressions);
+    match fs::create_dir_all(&dir) {
+        Ok(_) => Ok(dir),
+        Err(e) => Err(Diagnostic::global(&format!("unable to create build directory {} with error: {e}", dir.display()))),
+    }
 }
 
-fn build_as_file(dir: &String, keep_gen: bool, lang: Lang, c_flags: &[String]) {
-    let (filename, expressions) = setup_step(dir);
+// returns the path of the binary that was built
+fn build(args: &BuildArgs) -> Result<PathBuf, Diagnostics> {
+    let manifest = Manifest::find(&args.path)?;
+    let build_dir = build_dir(args, manifest.as_ref())?;
+    let (filename, expressions) = setup_step(args, manifest.as_ref(), Some(&build_dir))?;
     // for expr in &expressions {
     //     println!("{:?}", expr.0);
     // }
 
-    let out_filename = filename.file_stem().unwrap().to_str().unwrap();
+    let out_filename = build_dir.join(output_name(args, &filename, manifest.as_ref())?);
+    let out_filename = out_filename.to_string_lossy().to_string();
+    let compiler = compiler(args, manifest.as_ref())?;
+    let mut gen = Gen::new(filename.to_str().unwrap(), &out_filename, true, args.lang, compiler, args.line_directives);
+    gen.source_dir = project_dir(&args.path).to_path_buf();
+    gen.generate(expressions)?;
+
+    Ok(PathBuf::from(out_filename))
+}
+
+// what the shell would report, a program killed by a signal exits with 128 + the signal
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
 
-    let mut gen = Gen::new(filename.to_str().unwrap(), out_filename, true, keep_gen, lang, c_flags);
-    gen.generate(expressions);
+    status.code().unwrap_or(1)
+}
+
+fn run(mut args: BuildArgs) -> Result<(), Diagnostics> {
+    // after `--` is for the program, not the c compiler
+    let program_args = std::mem::take(&mut args.extra_args);
+    let binary = build(&args)?;
+
+    let status = Process::new(&binary)
+        .args(&program_args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status();
+
+    match status {
+        Ok(status) => exit(exit_code(status)),
+        Err(e) => Err(Diagnostic::global(&format!("unable to run {} with error: {e}", binary.display())).into()),
+    }
 }
 
-fn transpile(dir: &String, lang: Lang) {
-    let (filename, expressions) = setup_step(dir);
+fn transpile(args: &BuildArgs) -> Result<(), Diagnostics> {
+    let manifest = Manifest::find(&args.path)?;
+    let build_dir = build_dir(args, manifest.as_ref())?;
+    let (filename, expressions) = setup_step(args, manifest.as_ref(), Some(&build_dir))?;
     // for expr in &expressions {
     //     println!("{:?}", expr.0);
     // }
 
-    let mut gen = Gen::new(filename.to_str().unwrap(), "output", false, true, lang, &[]);
-    gen.generate(expressions);
-}
+    let out_filename = build_dir.join(args.out.as_deref().unwrap_or("output"));
+    let out_filename = out_filename.to_string_lossy().to_string();
+    let compiler = compiler(args, manifest.as_ref())?;
+    let mut gen = Gen::new(filename.to_str().unwrap(), &out_filename, false, args.lang, compiler, args.line_directives);
+    gen.source_dir = project_dir(&args.path).to_path_buf();
+    gen.generate(expressions)?;
 
-fn usage() {
-    println!("Usage:");
-    println!("impulse <command> [arguments]");
-    println!();
-    println!("-----------------------------------------------------");
-    println!();
-    println!("COMMANDS:");
-    // println!("| -h: help | impulse -h |");
-    println!("| init <directory>: initalise new project | impulse init . |");
-    // println!("| -r: run | impulse -r FILE.imp OUTPUT_NAME |");
-    println!("| build [--keep] <directory>: build project | impulse build . |");
-    println!();
-    println!("-----------------------------------------------------");
-    println!();
+    Ok(())
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        usage();
-        println!("\x1b[91merror\x1b[0m: invalid usage");
-        exit(1)
-    }
-
-    match args[1].as_str() {
-        "init" => initalise(&args[2]),
-        "build" => {
-            if &args[2] == "--keep" {
-                if args.len() < 4 {
-                    println!("\x1b[91merror\x1b[0m: expected path after --keep");
-                    exit(1)
-                }
+fn check(args: &BuildArgs) -> Result<(), Diagnostics> {
+    let manifest = Manifest::find(&args.path)?;
+    // checking doesn't leave a target/ behind
+    let (filename, _) = setup_step(args, manifest.as_ref(), None)?;
+    println!("{}: no errors found", filename.display());
 
-                if &args[3] == "-file" {
-                    if args.len() < 5 {
-                        println!("\x1b[91merror\x1b[0m: expected path after -file");
-                        exit(1)
-                    }
+    Ok(())
+}
 
-                    // build with keep and as file
-                    build_as_file(&args[4], true, Lang::C, &args[5..]);
-                    return
-                }
+fn clean(args: &BuildArgs) -> Result<(), Diagnostics> {
+    let manifest = Manifest::find(&args.path)?;
+    let root = build_root(&args.path, manifest.as_ref());
+    if !root.exists() {
+        println!("{} doesn't exist, nothing to clean", root.display());
+        return Ok(());
+    }
 
-                // build with keep
-                build(&args[3], true, Lang::C, &args[4..]);
-                return
-            }
+    if let Err(e) = fs::remove_dir_all(&root) {
+        return Err(Diagnostic::global(&format!("unable to remove {} with error: {e}", root.display())).into());
+    }
 
-            if &args[2] == "-file" {
-                if args.len() < 4 {
-                    println!("\x1b[91merror\x1b[0m: expected path after -file");
-                    exit(1)
-                }
+    // target/ goes as well if impulse was the only thing using it
+    if let Some(target) = root.parent() {
+        let _ = fs::remove_dir(target);
+    }
 
-                if &args[3] == "--keep" {
-                    if args.len() < 5 {
-                        println!("\x1b[91merror\x1b[0m: expected path after --keep");
-                        exit(1)
-                    }
+    println!("removed {}", root.display());
+    Ok(())
+}
 
-                    // build as file with keep
-                    build_as_file(&args[4], true, Lang::C, &args[5..]);
-                    return
-                }
+fn bindgen(args: &BindgenArgs) -> Result<(), Diagnostics> {
+    let source = match fs::read_to_string(&args.header) {
+        Ok(source) => source,
+        Err(e) => return Err(Diagnostic::global(&format!("unable to read {} with error: {e}", args.header)).into()),
+    };
 
-                // build as file
-                build_as_file(&args[3], false, Lang::C, &args[4..]);
-                return
+    let prefix = args.prefix.clone().unwrap_or(impulse::bindgen::default_prefix(&args.header));
+    let binding = impulse::bindgen::generate(&args.header, &source, &prefix)?;
+    match &args.out {
+        Some(out) => {
+            if let Err(e) = fs::write(out, binding) {
+                return Err(Diagnostic::global(&format!("unable to write {out} with error: {e}")).into());
             }
+            println!("wrote {out}");
+        },
+        None => print!("{binding}"),
+    }
+
+    Ok(())
+}
 
-            // build normally
-            build(&args[2], false, Lang::C, &args[3..]);
+fn execute(command: Command) -> Result<(), Diagnostics> {
+    match command {
+        Command::Init(dir) => initalise(&dir),
+        Command::Build(args) => build(&args).map(|_| ()),
+        Command::Run(args) => run(args),
+        Command::Transpile(args) => transpile(&args),
+        Command::Check(args) => check(&args),
+        Command::Clean(args) => clean(&args),
+        Command::Bindgen(args) => bindgen(&args),
+        Command::Help(help) => {
+            print!("{help}");
+            Ok(())
         },
-        "transpile" => {
-            // TODO: tidy this up and maybe use a hashmap with the available languages
-            if &args[2] == "c" {
-                if args.len() < 4 {
-                    println!("\x1b[91merror\x1b[0m: expected path after language option");
-                    exit(1)
-                }
+    }
+}
 
-                transpile(&args[3], Lang::C);
-            } else if &args[2] == "cpp" {
-                if args.len() < 4 {
-                    println!("\x1b[91merror\x1b[0m: expected path after language option");
-                    exit(1)
-                }
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-                transpile(&args[3], Lang::Cpp);
-            } else {
-                transpile(&args[2], Lang::C);
-            }
-        }
-        _ => usage(),
+    let result = match cli::parse(&args) {
+        Ok(command) => execute(command),
+        Err(e) => Err(e.into()),
+    };
+
+    if let Err(diagnostics) = result {
+        diagnostics.report();
+        exit(1)
     }
 }
@@ -0,0 +1,214 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use serde::Deserialize;
+
+use crate::{diagnostic::Diagnostic, tokeniser::Span};
+
+pub const MANIFEST_NAME: &str = "impulse.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: Package,
+    #[serde(default)]
+    pub build: BuildConfig,
+    #[serde(default)]
+    pub profile: Profiles,
+    #[serde(default)]
+    pub dependencies: HashMap<String, Dependency>,
+
+    #[serde(skip)]
+    pub dir: PathBuf, // where impulse.toml was found, every path in it is relative to this
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Package {
+    pub name: String,
+    pub entry: Option<String>, // when missing, the file with `main :: (` is used
+    pub output: Option<String>, // binary name, defaults to the package name
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildConfig {
+    pub cc: Option<String>,
+    pub target: Option<String>, // triple to cross compile to
+    #[serde(default)]
+    pub libs: Vec<String>,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(rename = "lib-path", default)]
+    pub lib_path: Vec<String>, // searched for base/ and vendor/ before IMPULSE_HOME
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profiles {
+    #[serde(default = "Profile::debug")]
+    pub debug: Profile,
+    #[serde(default = "Profile::release")]
+    pub release: Profile,
+}
+
+impl Default for Profiles {
+    fn default() -> Profiles {
+        Profiles {
+            debug: Profile::debug(),
+            release: Profile::release(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(rename = "c-flags", default)]
+    pub c_flags: Vec<String>,
+}
+
+impl Profile {
+    fn debug() -> Profile {
+        Profile { c_flags: vec![String::from("-g")] }
+    }
+
+    fn release() -> Profile {
+        Profile { c_flags: vec![String::from("-O2")] }
+    }
+}
+
+// a vendored library, `@import "vendor/<name>/..."` copies it from `path` instead of the install
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+    pub path: String,
+}
+
+// `path` can be the project directory or a file in it
+pub fn project_dir(path: &str) -> &Path {
+    let path = Path::new(path);
+    if path.is_dir() {
+        path
+    } else {
+        path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."))
+    }
+}
+
+impl Manifest {
+    // looks for impulse.toml in the project directory of `path`
+    pub fn find(path: &str) -> Result<Option<Manifest>, Diagnostic> {
+        let dir = project_dir(path);
+
+        let manifest_path = dir.join(MANIFEST_NAME);
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+
+        let content = match fs::read_to_string(&manifest_path) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(Diagnostic::global(&format!("unable to read {} with error: {e}", manifest_path.display())));
+            },
+        };
+
+        let mut manifest: Manifest = match toml::from_str(&content) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let file = manifest_path.to_string_lossy();
+                let message = format!("invalid {MANIFEST_NAME}: {}", e.message());
+
+                return Err(match e.span() {
+                    Some(range) => {
+                        let span = span_from_range(&content, range.start, range.end);
+                        Diagnostic::error(&file, span.line, &message).with_span(span)
+                    },
+                    None => Diagnostic::error(&file, 0, &message),
+                });
+            },
+        };
+
+        manifest.dir = dir.to_path_buf();
+        Ok(Some(manifest))
+    }
+
+    pub fn entry(&self) -> Option<String> {
+        self.package.entry.as_ref().map(|entry| self.relative(entry))
+    }
+
+    pub fn output(&self) -> String {
+        self.package.output.clone().unwrap_or(self.package.name.clone())
+    }
+
+    pub fn profile(&self, release: bool) -> &Profile {
+        if release {
+            &self.profile.release
+        } else {
+            &self.profile.debug
+        }
+    }
+
+    // include paths and libraries as compiler flags
+    pub fn link_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+
+        for include in &self.build.include {
+            flags.push(format!("-I{}", self.relative(include)));
+        }
+
+        for lib in &self.build.libs {
+            flags.push(format!("-l{lib}"));
+        }
+
+        flags
+    }
+
+    pub fn vendors(&self) -> HashMap<String, String> {
+        self.dependencies.iter()
+            .map(|(name, dependency)| (name.to_owned(), self.relative(&dependency.path)))
+            .collect()
+    }
+
+    pub fn lib_paths(&self) -> Vec<String> {
+        self.build.lib_path.iter().map(|dir| self.relative(dir)).collect()
+    }
+
+    fn relative(&self, path: &str) -> String {
+        self.dir.join(path).to_string_lossy().to_string()
+    }
+}
+
+fn span_from_range(content: &str, start: usize, end: usize) -> Span {
+    let start = start.min(content.len());
+    let before = &content[..start];
+
+    let line = before.matches('\n').count() as u32 + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let col = content[line_start..start].chars().count() as u32 + 1;
+
+    Span { start, end: end.max(start), line, col }
+}
+
+pub fn default_manifest(name: &str) -> String {
+    format!("\
+[package]
+name = \"{name}\"
+# entry = \"main.imp\" # defaults to the file that has `main :: (`
+# output = \"{name}\" # defaults to the package name
+
+[build]
+# cc = \"gcc\" # or clang, tcc, cc
+# target = \"x86_64-w64-mingw32\"
+libs = []
+include = []
+# lib-path = [\"../impulse\"] # where base/ and vendor/ are, checked before IMPULSE_HOME
+
+[profile.debug]
+c-flags = [\"-g\", \"-Wall\", \"-Wextra\", \"-Wfloat-equal\"]
+
+[profile.release]
+c-flags = [\"-O2\", \"-Wall\", \"-Wextra\", \"-Wfloat-equal\"]
+
+[dependencies]
+# raylib = {{ path = \"vendor/raylib\" }}
+")
+}
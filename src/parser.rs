@@ -1,16 +1,17 @@
-use std::{path::Path, fs, collections::HashMap, process::exit};
-use crate::{check_ident_as_token, declare_types::*, tokeniser::{tokeniser, Token}, type_checker::*, Gen};
+use std::{path::{Path, PathBuf}, fs, collections::HashMap, sync::Arc};
+use serde::{Deserialize, Serialize};
+use crate::{ast::{self, BinOp, ExprKind, ItemKind, LoopStep, StmtKind, UnOp}, cache::{fingerprint, ParseCache, ParsedModule}, compiler::{Cfg, Compiler, OSES}, declare_types::*, diagnostic::*, generator::Gen, lib_path::LibPaths, tokeniser::{tokeniser, Span}, type_checker::*, vfs::{FileSystem, RealFs}};
 use fs_extra::{dir::CopyOptions, copy_items};
 
-const CUR_PATH: &str = env!("current_path");
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub enum Expr {
     Func {
         typ: Types,
         params: Vec<Expr>,
         name: String,
         is_inline: bool,
+        link_name: Option<String>, // @extern functions are called by this and never generated
+        variadic: bool,
     },
     MacroFunc {
         typ: Types,
@@ -35,6 +36,10 @@ pub enum Expr {
     },
     DerefPointer(Box<Expr>),
     Address(Box<Expr>),
+    Cast {
+        typ: Types,
+        value: Box<Expr>,
+    },
 
     If(Vec<Expr>, Box<Expr>), // last expr is for the capture
     OrIf(Vec<Expr>, Box<Expr>),
@@ -64,13 +69,19 @@ pub enum Expr {
 
     True,
     False,
+    Condition(Vec<Expr>), // a condition used as a value or grouped in ( ), it's a bool
 
     IntLit(String),
+    Maths(Vec<Expr>), // maths with a generic function called in it, IntLit and FuncCall in order
     CharLit(String),
     StrLit(String),
 
     Import(String),
     CEmbed(String),
+    CFlags {
+        os: Option<String>, // only for builds targeting this os
+        flags: Vec<String>,
+    },
 
     Return(Box<Expr>),
     Break,
@@ -120,20 +131,21 @@ pub enum Expr {
     None,
 }
 
-struct DeferInfo {
-    scope: usize,
-    exprs: Vec<Expr>,
+// where a type is written, each place resolves names a little differently
+#[derive(Clone, Copy)]
+enum TypeAt<'a> {
+    Decl,
+    Return,
+    Param(&'a [String]), // the typeids declared by the params before this one
 }
 
+// every lowered expression with the file and span it came from, in order
+pub type Program = Vec<(Expr, String, Span)>;
+
 #[derive(Debug, Clone)]
 pub struct ExprWeights {
-    token_stack: Vec<Token>,
-    expr_stack: Vec<Expr>,
-
-    tokens: Vec<Token>,
-    current_token: usize,
+    span: Span, // the item or statement being lowered
     keyword_map: HashMap<String, Keyword>,
-    macros_map: HashMap<String, Macros>,
 
     pub functions: Vec<Expr>,
     structures: Vec<Expr>,
@@ -154,19 +166,22 @@ pub struct ExprWeights {
 
     in_enum_def: bool,
 
-    in_defer: bool,
-    one_defer: bool,
-    defer_scope: usize,
-
-    line_num: u32,
-    
     imports: Vec<String>,
-    program: Vec<(Expr, String, u32)>,
+    pub vendors: HashMap<String, String>, // vendor name to where impulse.toml says it lives
+    pub vendor_dir: Option<PathBuf>, // where vendors are copied to, inside the build dir, or None to use them in place
+    pub fs: Arc<dyn FileSystem>, // every import is read through this
+    pub lib_paths: LibPaths, // where base/ and vendor/ come from
+    pub parse_cache: Option<ParseCache>,
+    pub cfg: Cfg, // the os and --cfg names conditional code is kept for
+    sources: Vec<(String, u64)>, // imported files that were read, for invalidating the parse cache
+    program: Vec<(Expr, String, Span)>,
     filename: String,
+
+    diagnostics: Diagnostics,
 }
 
 impl ExprWeights {
-    pub fn new(tokens: Vec<Token>, filename: &str) -> ExprWeights {
+    pub fn new(filename: &str) -> ExprWeights {
         let func_to_vars: HashMap<String, Vec<Vec<Expr>>> = HashMap::new();
         let keyword_map: HashMap<String, Keyword> = HashMap::from([
             // ("println".to_string(), Keyword::Println),
@@ -221,24 +236,9 @@ impl ExprWeights {
             ("defer".to_string(), Keyword::Defer),
         ]);
 
-        let macros_map: HashMap<String, Macros> = HashMap::from([
-            ("c".to_string(), Macros::C),
-            ("mut".to_string(), Macros::Mut),
-            ("import".to_string(), Macros::Import),
-            ("inline".to_string(), Macros::Inline),
-            ("shared".to_string(), Macros::Shared),
-            ("default".to_string(), Macros::Default),
-            ("garbage".to_string(), Macros::Garbage),
-        ]);
-
         ExprWeights {
-            token_stack: Vec::new(),
-            expr_stack: Vec::new(),
-
-            tokens,
-            current_token: 0,
+            span: Span { line: 1, ..Span::default() },
             keyword_map,
-            macros_map,
 
             functions: Vec::new(),
             func_to_vars,
@@ -256,41 +256,32 @@ impl ExprWeights {
             in_struct_def: false,
             is_struct_generic: false,
             in_enum_def: false,
-            in_defer: false,
-            one_defer: false,
-            defer_scope: 0,
 
-            line_num: 1,
-            
             imports: Vec::new(),
+            vendors: HashMap::new(),
+            vendor_dir: None,
+            fs: Arc::new(RealFs),
+            lib_paths: LibPaths::default(),
+            parse_cache: None,
+            cfg: Cfg::default(),
+            sources: Vec::new(),
             program: Vec::new(),
             filename: filename.to_owned(),
+
+            diagnostics: Diagnostics::new(),
         }
     }
 
-    // fn comp_warn(&self, warning_msg: &str) {
-    //     println!("\x1b[93mwarning\x1b[0m: {}:{}", self.filename, self.line_num);
-    //     println!("\x1b[93mwarning\x1b[0m: {warning_msg}");
-    // }
-
-    fn comp_err(&self, error_msg: &str) {
-        println!("\x1b[91merror\x1b[0m: {}:{}", self.filename, self.line_num);
-        println!("\x1b[91merror\x1b[0m: {error_msg}");
+    fn comp_err(&self, error_msg: &str) -> Diagnostic {
+        Diagnostic::error(&self.filename, self.span.line, error_msg).with_span(self.span)
     }
 
     fn program_push(&mut self, expr: Expr) {
-        self.program.push((expr, self.filename.clone(), self.line_num));
-    }
-
-    fn error_if_token_stack_not_empty(&self) {
-        if !self.token_stack.is_empty() {
-            self.comp_err("might be a missing `;`. stack is not empty when it should be");
-            exit(1);
-        }
+        self.program.push((expr, self.filename.clone(), self.span));
     }
 
-    fn keyword_to_type(&self, kw: Keyword) -> Types {
-        match kw {
+    fn keyword_to_type(&self, kw: Keyword) -> Result<Types, Diagnostic> {
+        let typ = match kw {
             Keyword::U8 => Types::U8,
             Keyword::I8 => Types::I8,
             Keyword::Char => Types::Char,
@@ -319,20 +310,13 @@ impl ExprWeights {
             Keyword::TypeDef { type_name, generics } =>  Types::TypeDef { type_name, generics },
             Keyword::Pointer(pointer_to, _) => Types::Pointer(Box::new(pointer_to)),
             Keyword::Arr { typ, length } => Types::Arr { typ, length },
-            Keyword::Address => Types::Address,
-            Keyword::None => {
-                if self.in_enum_def {
-                    Types::None
-                } else {
-                    self.comp_err(&format!("can't convert {:?} to a type. type might not be reimplemented yet or defined.", kw));
-                    exit(1);
-                }
-            },
+            Keyword::None if self.in_enum_def => Types::None,
             _ => {
-                self.comp_err(&format!("can't convert {:?} to a type. type might not be reimplemented yet or defined.", kw));
-                exit(1);
+                return Err(self.comp_err(&format!("can't convert {:?} to a type. type might not be reimplemented yet or defined.", kw)));
             },
-        }
+        };
+
+        Ok(typ)
     }
 
     fn new_scope(&mut self, new_var: Expr) {
@@ -391,359 +375,6 @@ impl ExprWeights {
         }
     }
 
-    fn check_intlit(&self, intlit: String) -> Expr {
-        // TODO: MAKE THIS SUPPORT OTHER NUMBER TYPES LIKE U8
-
-        let symb_to_token: HashMap<char, Token> = HashMap::from([
-            ('(', Token::Lbrack),
-            (')', Token::Rbrack),
-            ('[', Token::Lsquare),
-            (']', Token::Rsquare),
-            ('+', Token::Plus),
-            ('-', Token::Minus),
-            ('*', Token::Multiply),
-            ('/', Token::Divide),
-            ('|', Token::Pipe),
-            ('%', Token::Mod),
-            ('!', Token::Exclaim)
-            // ('.', Token::Dot),
-        ]);
-
-        let mut tokens: Vec<Token> = Vec::new();
-        let mut buf = String::new();
-
-        for (i, ch) in intlit.chars().enumerate() {
-            if ch == ' ' || ch == '\n' || ch == '\r' {
-                if buf.len() > 0 {
-                    tokens.push(check_ident_as_token(&buf));
-                    buf.clear();
-                }
-
-                continue;
-            }
-
-            if i == intlit.len()-1 {
-                let token_res = symb_to_token.get(&ch);
-                match token_res {
-                    Some(token) => {
-                        if buf.len() > 0 {
-                            tokens.push(check_ident_as_token(&buf));
-                            buf.clear();
-                        }
-                        tokens.push(token.clone());
-                    },
-                    None => {
-                        buf.push(ch);
-                        tokens.push(check_ident_as_token(&buf));
-                        buf.clear();
-                    },
-                }
-
-                break;
-            }
-
-            let integer_res = ch.to_digit(10);
-            match integer_res {
-                Some(_) => {
-                    buf.push(ch);
-                    continue;
-                },
-                None => (),
-            }
-
-            let token_res = symb_to_token.get(&ch);
-            match token_res {
-                Some(token) => {
-                    if buf.len() > 0 {
-                        tokens.push(check_ident_as_token(&buf));
-                        buf.clear();
-                    }
-                    tokens.push(token.clone());
-                },
-                None => {
-                    buf.push(ch);
-                },
-            }
-        }
-
-        let mut clean = String::new();
-        let mut has_func = Expr::None;
-        let mut params = Vec::new();
-        let mut brack_rc = 0;
-
-        for token in tokens {
-            match token {
-                Token::Rbrack => {
-                    brack_rc -= 1;
-                    if brack_rc > 0 {
-                        params.push(token);
-                        continue;
-                    } else {
-                        match has_func {
-                            Expr::None => {
-                                self.comp_err(&format!("found () without a function name. did you mean to use []?"));
-                                exit(1);
-                            },
-                            Expr::Func { .. } | Expr::MacroFunc { .. } => {
-                                let func_call = self.create_func_call(&has_func, params.clone());
-
-                                // we need to make a new generator instance and only call the
-                                // `handle_funccall` function to get back a clean string of this
-                                // func call, this does not affect the compilation except for maybe
-                                // error messages but not sure.
-                                let mut gen = Gen::new(&self.filename, &String::from("output.c"), true, false, Lang::C, &[]);
-                                let clean_func_call = gen.handle_funccall(func_call);
-                                params.clear();
-                                clean.push_str(&clean_func_call)
-                            },
-                            ref unexpected => {
-                                self.comp_err(&format!("unexpected expression {unexpected:?} in integer literal: {:?}", has_func));
-                                exit(1);
-                            },
-                        }
-                    };
-                },
-                _ => {
-                    if brack_rc > 0 {
-                        params.push(token.clone());
-                        continue;
-                    }
-                },
-            }
-
-            match token {
-                Token::Plus => clean.push('+'),
-                Token::Minus => clean.push('-'),
-                Token::Multiply => clean.push('*'),
-                Token::Divide => clean.push('/'),
-                Token::Lsquare => clean.push('('),
-                Token::Rsquare => clean.push(')'),
-                Token::Mod => clean.push('%'),
-                Token::And => clean.push('&'),
-                Token::Or => clean.push('|'),
-                Token::Xor => clean.push('^'),
-                Token::Lshift => clean.push_str("<<"),
-                Token::Rshift => clean.push_str(">>"),
-                Token::Exclaim => clean.push('~'),
-                Token::Ident(ident) => {
-                    // TODO: LATER CHECK IF ERROR IS NUM TOO LARGE
-                    let is_num = if ident.len() > 2 && (&ident[0..2].to_lowercase() == "0x" || &ident[0..2].to_lowercase() == "0b") {
-                        let ident_num = usize::from_str_radix(&ident[2..], 16);
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
-                    } else {
-                        let ident_num = ident.parse::<f64>();
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
-                    };
-
-                    if is_num {
-                        clean.push_str(&ident);
-                        continue;
-                    }
-
-                    let expr = self.find_ident(ident.to_string());
-                    match expr {
-                        Expr::None => {
-                            self.comp_err(&format!("unknown identifier: {}", ident));
-                            exit(1);
-                        },
-                        Expr::VariableName { typ, name, field_data, .. } => {
-                            match typ {
-                                Types::I32 | Types::U32 | Types::U8 | Types::I8 | Types::UInt | Types::Int | Types::U16 | Types::I16 |
-                                Types::U64 | Types::I64 |Types::Usize | Types::Pointer(_) | Types::Generic(_) |
-                                Types::F32 | Types::F64 => {
-                                    let mut sanitised = name.clone();
-                                    if !field_data.0 && name.contains(".") {
-                                        sanitised = name.replace(".", "_");
-                                    }
-                                    clean.push_str(&sanitised)
-                                },
-                                _ => {
-                                    self.comp_err(&format!("variable {name} is not an integer. {typ:?}:{name}"));
-                                    exit(1);
-                                }
-                            }
-                        },
-                        Expr::Func { typ, params, name, is_inline } => {
-                            match typ {
-                                Types::I32 | Types::U32 | Types::U8 | Types::I8 | Types::UInt | Types::Int | Types::U16 | Types::I16 |
-                                Types::U64 | Types::I64 |Types::Usize | Types::Pointer(_) | Types::Generic(_) |
-                                Types::F32 | Types::F64 => {
-                                    has_func = Expr::Func { typ, params, name, is_inline };
-                                },
-                                _ => {
-                                    self.comp_err(&format!("function {name} does not return integer. {typ:?}:{name}"));
-                                    exit(1);
-                                },
-                            }
-                        },
-                        Expr::MacroFunc { typ, params, name } => {
-                            match typ {
-                                Types::I32 | Types::U32 | Types::U8 | Types::I8 | Types::UInt | Types::Int | Types::U16 | Types::I16 |
-                                Types::U64 | Types::I64 |Types::Usize | Types::Pointer(_) | Types::Generic(_) |
-                                Types::F32 | Types::F64 => {
-                                    has_func = Expr::MacroFunc { typ, params, name };
-                                },
-                                _ => {
-                                    self.comp_err(&format!("function {name} does not return integer. {typ:?}:{name}"));
-                                    exit(1);
-                                },
-                            }
-                        }
-                        _ => {
-                            self.comp_err(&format!("unexpected expression in integer literal: {}", ident));
-                            exit(1);
-                        }
-                    }
-                },
-                Token::Lbrack => {
-                    brack_rc += 1;
-                },
-                _ => (),
-            }
-        }
-
-        Expr::IntLit(clean)
-    }
-
-    fn parse_generics_to_pass(&mut self, typs: &String, generic_not_defined_yet: bool) -> Vec<Types> {
-        let mut name_buf = String::new();
-        let mut pass_typs = Vec::new();
-        let mut pointer_counter = 0;
-
-        for (i, ch) in typs.chars().enumerate() {
-            if ch == ' ' || ch == '\n' || i == typs.len()-1 {
-                if i == typs.len() - 1 {
-                    name_buf.push(ch);
-                }
-
-                let keyword_rs = self.keyword_map.get(&name_buf);
-                match keyword_rs {
-                    Some(keyword) => {
-                        let typ = self.keyword_to_type(keyword.clone());
-                        if pointer_counter > 0 {
-                            let kw; (kw, pointer_counter) = self.create_keyword_pointer(typ, pointer_counter);
-                            pass_typs.push(self.keyword_to_type(kw));
-                        } else {
-                            pass_typs.push(typ);
-                        }
-                    },
-                    None => {
-                        if name_buf.chars().last().unwrap() == '_' {
-                            if pointer_counter > 0 {
-                                let kw; (kw, pointer_counter) = self.create_keyword_pointer(Types::Void, pointer_counter);
-                                pass_typs.push(self.keyword_to_type(kw));
-                                name_buf.clear();
-                                continue;
-                            } else {
-                                self.comp_err("can't have a _ (void) generic parameter, did you mean ^_ (void pointer)?");
-                                exit(1);
-                            }
-                        }
-
-                        let found_var = self.find_variable(&name_buf);
-                        if let Expr::VariableName { typ, name, .. } = found_var {
-                            if let Types::TypeId = typ {
-                                let typedef = Types::TypeDef { type_name: name, generics: None };
-                                if pointer_counter > 0 {
-                                    let kw; (kw, pointer_counter) = self.create_keyword_pointer(typedef, pointer_counter);
-                                    pass_typs.push(self.keyword_to_type(kw));
-                                    name_buf.clear();
-                                    continue;
-                                }
-                                pass_typs.push(typedef);
-                                name_buf.clear();
-                                continue;
-                            }
-                        } else if generic_not_defined_yet {
-                            let generic = Types::Generic(name_buf.clone());
-                            // let typedef = Types::TypeDef { type_name: name_buf.clone(), generics: None };
-                            if pointer_counter > 0 {
-                                let kw; (kw, pointer_counter) = self.create_keyword_pointer(generic, pointer_counter);
-                                pass_typs.push(self.keyword_to_type(kw));
-                                name_buf.clear();
-                                continue;
-                            }
-                            pass_typs.push(generic);
-                            name_buf.clear();
-                            continue;
-                        } else {
-                            // this is done for the second function declared in a
-                            // struct that uses a generic type. like option[T] at :: () {}
-                            // the "T" is defined by the struct above it in the scope
-                            if !self.previous_func.is_empty() {
-                                let temp = self.current_func.clone();
-                                self.current_func = self.previous_func.clone();
-                                let found_var_again = self.find_variable(&name_buf);
-                                self.current_func = temp;
-                                if let Expr::VariableName { typ, name, .. } = found_var_again {
-                                    if let Types::TypeId = typ {
-                                        let typedef = Types::TypeDef { type_name: name, generics: None };
-                                        if pointer_counter > 0 {
-                                            let kw; (kw, pointer_counter) = self.create_keyword_pointer(typedef, pointer_counter);
-                                            pass_typs.push(self.keyword_to_type(kw));
-                                            name_buf.clear();
-                                            continue;
-                                        }
-                                        pass_typs.push(typedef);
-                                        name_buf.clear();
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
-
-                        let found_typ = self.find_structure(&name_buf);
-                        if let Expr::StructDef { struct_name, .. } |
-                            Expr::MacroStructDef { struct_name, .. } = found_typ {
-                                if let Expr::StructName { name, .. } = *struct_name {
-                                    let typedef = Types::TypeDef { type_name: name, generics: None };
-                                    if pointer_counter > 0 {
-                                        let kw; (kw, pointer_counter) = self.create_keyword_pointer(typedef, pointer_counter);
-                                        pass_typs.push(self.keyword_to_type(kw));
-                                        name_buf.clear();
-                                        continue;
-                                    }
-                                    pass_typs.push(typedef);
-                                    name_buf.clear();
-                                    continue;
-                                } else if let Expr::MacroStructName { .. } = *struct_name {
-                                    self.comp_err("generic struct with type of a generic struct not supported yet");
-                                    exit(1);
-                                }
-                        } else {
-                            self.comp_err(&format!("expected type, got {name_buf}"));
-                            exit(1);
-                        }
-                    },
-                }
-
-                name_buf.clear();
-            } else if ch == '^' {
-                pointer_counter += 1;
-            } else {
-                name_buf.push(ch);
-            }
-        }
-
-        pass_typs
-    }
-
-    fn create_keyword_pointer(&self, typ: Types, pointer_counter: i32) -> (Keyword, i32) {
-        let mut tmp = typ.clone();
-
-        for _ in 0..pointer_counter-1 {
-            tmp = Types::Pointer(Box::new(tmp));
-        }
-
-        return (Keyword::Pointer(tmp, typ), 0)
-    }
-
     fn new_scope_propagate_field(&mut self, fname: String, user_def: String, is_ptr: bool, is_constant: bool, is_func_arg: bool) -> Vec<Expr> {
         let mut expr_param = Vec::new();
 
@@ -806,3916 +437,1985 @@ impl ExprWeights {
                 }
             }
         }
-        return expr_param;
+        expr_param
     }
 
-    fn create_func(&mut self, typ: Types, params: Vec<Token>, name: String, is_inline: bool) {
-        let mut variables = Vec::new();
-        let mut expr_param = Vec::new();
-        let mut array_lens: Vec<String> = Vec::new();
+    fn find_global_variable(&self, ident: &String) -> Expr {
+        for var in &self.global_vars {
+            if let Expr::Variable { info, .. } = var {
+                if let Expr::VariableName { name, .. } = *info.clone() {
+                    if &name == ident {
+                        return *info.clone();
+                    }
+                }
+            }
+        }
 
-        let mut typeid_names: Vec<String> = Vec::new();
-        let mut kw_buf = Keyword::None;
-        let mut pointer_counter = 0;
-        let mut is_macro_func = false;
-        let mut is_generic = false;
+        Expr::None
+    }
 
-        match typ {
-            Types::Any | Types::TypeId => is_macro_func = true,
-            _ => (),
+    fn find_variable(&self, ident: &String) -> Expr {
+        let variables_res = self.func_to_vars.get(&self.current_func);
+        let variables = match variables_res {
+            Some(vars) => vars.clone(),
+            None => return Expr::None,
+        };
+
+        for vars in &variables[self.current_scope] {
+            if let Expr::Variable { info, .. } = vars {
+                if let Expr::VariableName { name, .. } = *info.clone() {
+                    if &name.replace("->", ".") == ident {
+                        return *info.clone();
+                    }
+                }
+            }
         }
 
-        for (i, param) in params.iter().enumerate() {
-            match param {
-                Token::Ident(ident) => {
-                    if let Keyword::None = kw_buf {
-                        let keyword_res = self.keyword_map.get(ident);
-                        match keyword_res {
-                            Some(kw) => {
-                                let mut updated_kw_buf = false;
-                                if !array_lens.is_empty() {
-                                    // TODO: make this support mutli-dimensional arrays
-                                    let typ = self.keyword_to_type(kw.clone());
-                                    kw_buf = Keyword::Arr { typ: Box::new(typ), length: array_lens[0].clone() };
-                                    array_lens.clear();
-                                    updated_kw_buf = true;
-                                }
+        Expr::None
+    }
 
-                                if pointer_counter > 0 {
-                                    (kw_buf, pointer_counter) = self.create_keyword_pointer(self.keyword_to_type(kw.clone()), pointer_counter);
-                                    updated_kw_buf = true;
-                                }
+    fn find_func(&self, ident: &String) -> Expr {
+        for func in &self.functions {
+            match func {
+                Expr::Func { name, .. } | Expr::MacroFunc { name, .. } if name == ident => {
+                    return func.clone()
+                },
+                _ => (),
+            }
+        }
 
-                                if is_generic {
-                                    self.comp_err(&format!("declaring generic but {} is already an identifier", ident));
-                                    exit(1);
-                                }
+        Expr::None
+    }
 
-                                if !updated_kw_buf {
-                                    kw_buf = kw.clone();
-                                    if let Keyword::TypeId | Keyword::Any = kw_buf {is_macro_func = true}
-                                }
-                            },
-                            None => {
-                                let found_ident = self.find_ident(ident.to_string());
-                                match found_ident {
-                                    Expr::StructDef { .. } => {
-                                        if !array_lens.is_empty() {
-                                            // TODO: make this support mutli-dimensional arrays
-                                            let typ = Types::TypeDef {
-                                                type_name: ident.to_string(),
-                                                generics: None,
-                                            };
-                                            kw_buf = Keyword::Arr { typ: Box::new(typ), length: array_lens[0].clone() };
-                                            array_lens.clear();
-                                        } else if pointer_counter > 0 {
-                                            (kw_buf, pointer_counter) = self.create_keyword_pointer(Types::TypeDef {
-                                                type_name: ident.to_owned(),
-                                                generics: None,
-                                            }, pointer_counter);
-                                        } else {
-                                            kw_buf = Keyword::TypeDef {
-                                                type_name: ident.to_string(),
-                                                generics: None,
-                                            };
-                                        }
-                                    },
-                                    Expr::MacroStructDef { .. } => {
-                                        let mut pass_typs = Vec::new();
-                                        let mut name_buf = String::new();
-
-                                        if i == params.len() {
-                                            self.comp_err(&format!("expected more tokens when creating function"));
-                                            exit(1);
-                                        }
-
-                                        if let Token::Int(typs) = &params[i+1] {
-                                            for (index, ch) in typs.chars().enumerate() {
-                                                if ch == ' ' || ch == '\n' || index == typs.len()-1 {
-                                                    if index == typs.len() - 1 {
-                                                        name_buf.push(ch);
-                                                    }
-                                                    let keyword_rs = self.keyword_map.get(&name_buf);
-                                                    match keyword_rs {
-                                                        Some(keyword) => {
-                                                            let typ = self.keyword_to_type(keyword.clone());
-                                                            pass_typs.push(typ);
-                                                            if let Keyword::TypeId | Keyword::Any = keyword {is_macro_func = true}
-                                                        },
-                                                        None => {
-                                                            let mut found = false;
-                                                            for name in &typeid_names {
-                                                                if name == &name_buf {
-                                                                    pass_typs.push(Types::TypeDef { type_name: name_buf.clone(), generics: None });
-                                                                    found = true;
-                                                                    break;
-                                                                }
-                                                            }
-
-                                                            if !found {
-                                                                let found_struc = self.find_structure(&name_buf);
-                                                                if let Expr::StructDef { .. } = found_struc {
-                                                                    pass_typs.push(Types::TypeDef { type_name: name_buf.clone(), generics: None });
-                                                                    found = true;
-                                                                }
-                                                            }
-
-                                                            if !found {
-                                                                self.comp_err(&format!("expected a defined typeid, found undefined `{name_buf}`"));
-                                                                exit(1);
-                                                            }
-                                                        },
-                                                    }
-
-                                                    name_buf.clear();
-                                                } else {
-                                                    name_buf.push(ch);
-                                                }
-                                            }
-                                        } else {
-                                            self.comp_err(&format!("expected a typeid after struct type, e.g. `my_struct[type]`"));
-                                            exit(1);
-                                        }
-
-                                        kw_buf = Keyword::TypeDef {
-                                            type_name: ident.to_string(),
-                                            generics: Some(pass_typs),
-                                        };
-
-                                        if pointer_counter > 0 {
-                                            (kw_buf, pointer_counter) = self.create_keyword_pointer(self.keyword_to_type(kw_buf), pointer_counter);
-                                        }
-                                    },
-                                    Expr::EnumDef { .. } => {
-                                        if pointer_counter > 0 {
-                                            (kw_buf, pointer_counter) = self.create_keyword_pointer(Types::TypeDef {
-                                                type_name: ident.to_owned(),
-                                                generics: None,
-                                            }, pointer_counter);
-                                        } else {
-                                            kw_buf = Keyword::TypeDef {
-                                                type_name: ident.to_string(),
-                                                generics: None,
-                                            };
-                                        }
-                                    },
-                                    _ => {
-                                        if is_generic {
-                                            for name in &typeid_names {
-                                                if name == ident {
-                                                    kw_buf = Keyword::Generic(ident.to_owned());
-                                                    is_generic = false;
-                                                    break;
-                                                }
-                                            }
-
-                                            if is_generic {
-                                                self.comp_err(&format!("generic type {ident} hasn't been defined as a typeid yet."));
-                                                exit(1);
-                                            }
-                                        } else {
-                                            self.comp_err(&format!("expected a type, got {}", ident));
-                                            exit(1);
-                                        }
-                                    }
-                                }
-                            },
-                        }
-                    } else {
-                        let typ = self.keyword_to_type(kw_buf.clone());
-                        if let Types::TypeDef {type_name: ref user_def, .. } = typ {
-                            // THIS NEEDS TO BE DONE HERE BECAUSE WE ARE ENTERING A NEW SCOPE, THE
-                            // "propagate_struct_fields" FUNCTION WORKS FOR THE CURRENT SCOPE
-                            expr_param.append(&mut self.new_scope_propagate_field(ident.clone(), user_def.to_owned(), false, true, true));
-                        } else if let Types::Arr { .. } = typ {
-                            expr_param.append(&mut self.new_scope_propagate_field(ident.clone(), String::from("array"), false, true, true));
-                        } else if let Keyword::Pointer(.., last) = kw_buf {
-                            if let Types::TypeDef { type_name: ref user_def, .. } = last {
-                                expr_param.append(&mut self.new_scope_propagate_field(ident.clone(), user_def.to_owned(), true, true, true));
-                            }
-                        } else if let Types::TypeId = typ {
-                            typeid_names.push(ident.to_owned());
+    pub(crate) fn find_structure(&self, ident: &String) -> Expr {
+        for struc in &self.structures {
+            match struc {
+                Expr::StructDef { struct_name, .. } => {
+                    if let Expr::StructName { name: acc_name, .. } = *struct_name.clone() {
+                        if &acc_name == ident {
+                            return struc.clone()
                         }
-
-                        let final_expr = Expr::VariableName {
-                            typ,
-                            name: ident.to_owned(),
-                            reassign: false,
-                            constant: true,
-                            func_arg: true,
-                            field_data: (false, false),
-                        };
-
-                        variables.push(final_expr.clone());
-                        expr_param.push(
-                            Expr::Variable {
-                                info: Box::new(final_expr),
-                                value: Box::new(Expr::None),
-                            }
-                        );
-                        kw_buf = Keyword::None;
-                    }
-                },
-                Token::Caret => {
-                    pointer_counter += 1;
-                },
-                Token::Underscore => {
-                    if pointer_counter > 0 {
-                        (kw_buf, pointer_counter) = self.create_keyword_pointer(Types::Void, pointer_counter);
-                    } else {
-                        self.comp_err(&format!("can't use void as type. void pointers work tho."));
-                        exit(1);
                     }
                 },
-                Token::Dollar => {
-                    is_generic = true;
-                },
-                Token::Int(intlit_str) => {
-                    if intlit_str.is_empty() {
-                        array_lens.push(intlit_str.clone());
-                        continue;
+                Expr::MacroStructDef { struct_name, .. } => {
+                    match *struct_name.clone() {
+                        Expr::MacroStructName { name: acc_name, .. } | Expr::StructName { name: acc_name, .. } if &acc_name == ident => {
+                            return struc.clone()
+                        },
+                        _ => (),
                     }
-
-                    if intlit_str.len() > 2 && (&intlit_str[0..2].to_lowercase() == "0x" || &intlit_str[0..2].to_lowercase() == "0b") {
-                        let ident_num = usize::from_str_radix(&intlit_str[2..], 16);
-                        match ident_num {
-                            Ok(_) => {
-                                array_lens.push(intlit_str.clone());
-                            },
-                            Err(_) => continue,
-                        }
-                    } else {
-                        let ident_num = intlit_str.parse::<f64>();
-                        match ident_num {
-                            Ok(_) => {
-                                array_lens.push(intlit_str.clone());
-                            },
-                            Err(_) => continue,
-                        }
-                    };
                 },
-                _ => {
-                    self.comp_err(&format!("unexpected token in function argument: {param:?}"));
-                    exit(1);
-                }
+                _ => (),
             }
         }
 
-        let found_func = self.find_func(&name);
-        if let Expr::Func { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as another function"));
-            exit(1);
-        } else if let Expr::MacroFunc { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as another function"));
-            exit(1);
-        } else if let Expr::StructDef { .. } = self.find_structure(&name) {
-            self.comp_err(&format!("identifier {name} already declared as struct"));
-            exit(1);
+        Expr::None
+    }
+
+    fn find_enum(&self, ident: &String) -> Expr {
+        for enu in &self.enums {
+            if let Expr::EnumDef { enum_name, .. } = enu {
+                if let Expr::EnumName(name) = *enum_name.clone() {
+                    if &name == ident {
+                        return enu.clone()
+                    }
+                }
+            }
         }
 
-        let expr = if is_macro_func {
-            Expr::MacroFunc { typ, params: variables, name: name.clone() }
-        } else {
-            Expr::Func { typ, params: variables, name: name.clone(), is_inline }
-        };
-        self.functions.push(expr.clone());
-        self.current_func = name.clone();
-        self.func_to_vars.entry(name.clone()).or_insert(vec![expr_param]);
-        self.in_func = true;
+        Expr::None
+    }
 
-        let sanitised_expr = match expr {
-            Expr::Func { ref typ, ref params, ref name, is_inline } => {
-                let san_name = name.replace(".", "__");
-                Expr::Func { typ: typ.clone(), params: params.clone(), name: san_name, is_inline }
-            },
-            Expr::MacroFunc { ref typ, ref params, ref name } => {
-                let san_name = name.replace(".", "__");
-                Expr::MacroFunc { typ: typ.clone(), params: params.clone(), name: san_name }
+    fn find_enum_fields(&self, ident: &String) -> Expr {
+        for enum_field in &self.enums_fields {
+            match enum_field {
+                Expr::VariableName { name, .. } if name == ident => {
+                    return enum_field.clone()
+                },
+                Expr::Variable { info, .. } => {
+                    if let Expr::VariableName { name, .. } = *info.clone() {
+                        if &name == ident {
+                            return *info.clone();
+                        }
+                    }
+                }
+                _ => (),
             }
-            _ => unreachable!(),
-        };
-        self.program_push(sanitised_expr);
-        self.token_stack.clear();
+        }
+
+        Expr::None
     }
 
-    // just definition, not the body of the enum yet
-    fn create_enum_def(&mut self, name: String) {
-        if self.in_func {
-            self.comp_err(&format!("cannot make enum {name} inside a function"));
-            exit(1);
+    fn find_ident(&self, ident: String) -> Expr {
+        match self.find_variable(&ident) {
+            Expr::None => self.find_global_ident(ident),
+            found => found,
         }
+    }
 
-        let keyword_res = self.keyword_map.get(&name);
-        match keyword_res {
-            Some(kw) => {
-                self.comp_err(&format!("can't use keyword {kw:?} as enum name"));
-                exit(1);
-            },
-            None => (),
+    // like find_ident but without the locals of the function being lowered
+    pub(crate) fn find_global_ident(&self, ident: String) -> Expr {
+        let mut found: Expr;
+        found = self.find_global_variable(&ident);
+        if let Expr::None = found {}
+        else {
+            return found
         }
 
-        let found_func = self.find_func(&name);
-        if let Expr::Func { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as another function"));
-            exit(1);
-        } else if let Expr::MacroFunc { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as another function"));
-            exit(1);
-        } else if let Expr::StructDef { .. } = self.find_structure(&name) {
-            self.comp_err(&format!("identifier {name} already declared as struct"));
-            exit(1);
+        found = self.find_func(&ident);
+        if let Expr::None = found {}
+        else {
+            return found
         }
 
-        self.current_func = name.clone();
-        self.func_to_vars.entry(name.clone()).or_insert(vec![vec![]]);
-        self.in_enum_def = true;
-
-        self.expr_stack.push(Expr::EnumName(name));
-        self.token_stack.clear();
-    }
-
-    // create the final enum with the body
-    fn create_enum(&mut self) {
-        let name = self.expr_stack.remove(0);
-        let exprs = self.expr_stack.clone();
-        self.expr_stack.clear();
-
-        for ex in &exprs {
-            self.enums_fields.push(ex.clone());
+        found = self.find_structure(&ident);
+        if let Expr::None = found {}
+        else {
+            return found
         }
 
-        let sanitised_name = if let Expr::EnumName(ref enumname) = name {
-            enumname.replace(".", "__")
-        } else {unreachable!()};
+        found = self.find_enum(&ident);
+        if let Expr::None = found {}
+        else {
+            return found
+        }
 
-        self.program_push(Expr::EnumDef {
-            enum_name: Box::new(Expr::EnumName(sanitised_name.clone())),
-            enum_fields: exprs.clone()
-        });
+        found = self.find_enum_fields(&ident);
+        if let Expr::None = found {}
+        else {
+            return found
+        }
+        
+        found
+    }
 
+    pub fn handle_import_macro(&mut self, mut path: String) -> Result<Expr, Diagnostic> {
+        if path.is_empty() {
+            return Err(self.comp_err("@import needs a path"));
+        }
 
-        match name {
-            Expr::EnumName(ref enum_name) => {
-                self.program_push(Expr::EndStruct(sanitised_name.clone()));
-                self.func_to_vars.remove(enum_name);
-            },
-            _ => {
-                self.comp_err(&format!("unexpected expression when creating struct"));
-                exit(1);
+        if path.ends_with(".h") {
+            self.imports.push(path.to_string());
+
+            // headers of a vendor that was copied into the build dir come from the copy
+            if let (Some(in_vendor), Some(vendor_dir)) = (path.strip_prefix("vendor/"), &self.vendor_dir) {
+                let copied = vendor_dir.join(in_vendor);
+                if copied.exists() {
+                    path = copied.to_string_lossy().to_string();
+                }
             }
+
+            let no_extension = path.split_at(path.len()-2);
+            return Ok(Expr::Import(no_extension.0.to_string()))
         }
 
-        self.in_enum_def = false;
-        let expr = Expr::EnumDef { enum_name: Box::new(name.clone()), enum_fields: exprs.clone() };
-        self.enums.push(expr);
+        // local imports are relative to the file importing them, not to where impulse was run
+        if !path.starts_with("base/") && !path.starts_with("vendor/") && Path::new(&path).is_relative() {
+            let dir = Path::new(&self.filename).parent().unwrap_or(Path::new(""));
+            path = dir.join(&path).to_string_lossy().to_string();
+        }
 
-        let field_count = Expr::Variable {
-            info: Box::new(Expr::VariableName {
-                typ: Types::Usize,
-                name: format!("{sanitised_name}.field_count"),
-                reassign: false,
-                constant: true,
-                func_arg: false,
-                field_data: (false, false),
-            }),
-            value: Box::new(Expr::IntLit(format!("{}", exprs.len()))),
-        };
-        self.enums_fields.push(field_count.clone());
-        self.program_push(field_count);
-    }
+        if self.imports.contains(&path) {
+            return Ok(Expr::None)
+        }
+        self.imports.push(path.to_string());
 
-    // this starts defining a struct
-    fn create_struct_def(&mut self, name: String, generics: Expr, is_shared: bool) {
-        if self.in_func {
-            self.comp_err(&format!("cannot make struct {name} inside a function"));
-            exit(1);
+        if path.starts_with("base/") {
+            path = match self.lib_paths.find(self.fs.as_ref(), &path) {
+                Some(found) => found.to_string_lossy().to_string(),
+                None => {
+                    let e = self.comp_err(&format!("unable to find {path}"));
+                    return Err(self.lib_paths.with_searched(e, &path));
+                },
+            };
+        } else if path.starts_with("vendor/") {
+            path = self.resolve_vendor(&path)?;
         }
 
-        // don't need to check if num, already checked when getting name param
-        // edit: no clue what i meant by "if num", but it's fine... right?
-        let keyword_res = self.keyword_map.get(&name);
-        match keyword_res {
-            Some(kw) => {
-                self.comp_err(&format!("can't use keyword {kw:?} as struct name"));
-                exit(1);
+        let file_res = self.fs.read_to_string(Path::new(&path));
+        let content = match file_res {
+            Ok(content) => content,
+            Err(err) => {
+                return Err(self.comp_err(&format!("unable to read file {path} with error: {err}")));
             },
-            None => (),
-        }
+        };
+
+        // the same file parsed with the same symbols in scope gives the same result
+        let mut vendors: Vec<_> = self.vendors.iter().collect();
+        vendors.sort();
+        let cache_key = fingerprint(&(
+            &path, &content, &self.imports, &self.functions, &self.structures,
+            &self.enums, &self.enums_fields, &self.global_vars, vendors, &self.vendor_dir, &self.cfg,
+        ));
+
+        if let Some(module) = self.parse_cache.as_ref().and_then(|cache| cache.load(cache_key, self.fs.as_ref())) {
+            // a cached module skips the vendor copies its own imports would have done
+            for import in &module.imports {
+                if import.starts_with("vendor/") && !import.ends_with(".h") {
+                    self.resolve_vendor(import)?;
+                }
+            }
 
-        let found_func = self.find_ident(name.clone());
-        if let Expr::Func { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as function"));
-            exit(1);
-        } else if let Expr::MacroFunc { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as function"));
-            exit(1);
-        } else if let Expr::StructDef { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as struct"));
-            exit(1);
-        } else if let Expr::MacroStructDef { .. } = found_func {
-            self.comp_err(&format!("identifier {name} already declared as struct"));
-            exit(1);
+            self.merge_module(module);
+            return Ok(Expr::None)
         }
 
-        let mut pass_generic = Vec::new();
-        match generics {
-            Expr::None => (),
-            Expr::IntLit(generic) => {
-                let mut name_buf = String::new();
-                for (i, ch) in generic.chars().enumerate() {
-                    if ch == ' ' || ch == '\n' || i == generic.len()-1 {
-                        if i == generic.len() - 1 {
-                            name_buf.push(ch);
-                        }
+        let mut diagnostics = Diagnostics::new();
+        let tokens = tokeniser(content.clone(), &path, &mut diagnostics);
+        if diagnostics.has_errors() {
+            self.diagnostics.extend(diagnostics);
+            return Ok(Expr::None)
+        }
 
-                        let gen_var = Expr::Variable {
-                            info: Box::new(Expr::VariableName {
-                                typ: Types::TypeId,
-                                name: name_buf.clone(),
-                                reassign: false,
-                                constant: false,
-                                func_arg: false,
-                                field_data: (false, false),
-                            }),
-                            value: Box::new(Expr::None)
-                        };
-                        pass_generic.push(gen_var);
-                        name_buf.clear();
-                    } else {
-                        name_buf.push(ch);
-                    }
-                }
+        let module = match ast::parse(tokens, &path, &self.cfg) {
+            Ok(module) => module,
+            Err(diagnostics) => {
+                self.diagnostics.extend(diagnostics);
+                return Ok(Expr::None)
             },
-            unexpected => {
-                self.comp_err(&format!("unexpected expression {unexpected:?} when making struct {name}"));
-                exit(1);
+        };
+
+        let mut parse = ExprWeights::new(&path);
+        // REMEMBER TO DO ADD THIS IF YOU NEED INFO TO CARRY OVER FROM OTHER FILES
+        parse.functions = self.functions.clone();
+        parse.imports = self.imports.clone();
+        parse.vendors = self.vendors.clone();
+        parse.vendor_dir = self.vendor_dir.clone();
+        parse.fs = self.fs.clone();
+        parse.lib_paths = self.lib_paths.clone();
+        parse.parse_cache = self.parse_cache.clone();
+        parse.cfg = self.cfg.clone();
+        parse.structures = self.structures.clone();
+        parse.enums = self.enums.clone();
+        parse.enums_fields = self.enums_fields.clone();
+        parse.global_vars = self.global_vars.clone();
+        // symbols from a broken import are still merged so the importing file doesn't cascade errors
+        let (program, parsed) = match parse.lower(&module) {
+            Ok(expressions) => (expressions, true),
+            Err(diagnostics) => {
+                self.diagnostics.extend(diagnostics);
+                (Vec::new(), false)
             },
-        }
+        };
 
-        self.current_func = name.clone();
-        self.func_to_vars.entry(name.clone()).or_insert(vec![pass_generic.clone()]);
-        self.in_struct_def = true;
+        let mut sources = vec![(path.clone(), fingerprint(&content))];
+        sources.append(&mut parse.sources);
+
+        let module = ParsedModule {
+            sources,
+            program,
+            imports: parse.imports[self.imports.len()..].to_vec(),
+            functions: parse.functions[self.functions.len()..].to_vec(),
+            structures: parse.structures[self.structures.len()..].to_vec(),
+            enums: parse.enums[self.enums.len()..].to_vec(),
+            enums_fields: parse.enums_fields[self.enums_fields.len()..].to_vec(),
+            global_vars: parse.global_vars[self.global_vars.len()..].to_vec(),
+        };
 
-        if pass_generic.is_empty() {
-            self.expr_stack.push(Expr::StructName{ name, is_shared });
-        } else {
-            self.expr_stack.push(Expr::MacroStructName { name, generics: pass_generic, is_shared });
-            self.is_struct_generic = true;
+        if let (Some(cache), true) = (&self.parse_cache, parsed) {
+            cache.store(cache_key, &module);
         }
-        self.token_stack.clear();
+
+        self.merge_module(module);
+        Ok(Expr::None)
     }
 
-    // this creates the final struct after defining reaches }
-    fn create_struct(&mut self) {
-        let name = self.expr_stack.remove(0);
-        let exprs = self.expr_stack.clone();
-        self.expr_stack.clear();
+    // the symbols and code an import adds on top of what was already there
+    fn merge_module(&mut self, mut module: ParsedModule) {
+        self.sources.append(&mut module.sources);
+        self.imports.append(&mut module.imports);
+        self.functions.append(&mut module.functions);
+        self.structures.append(&mut module.structures);
+        self.enums.append(&mut module.enums);
+        self.enums_fields.append(&mut module.enums_fields);
+        self.global_vars.append(&mut module.global_vars);
+        self.program.append(&mut module.program);
+    }
 
-        // push to a list of structs
-        let expr = if let Expr::MacroStructName { .. } = name {
-            Expr::MacroStructDef { struct_name: Box::new(name.clone()), struct_fields: exprs }
+    // finds where `path` in vendor/<name> really is, copying the vendor into the build dir first if there is one
+    fn resolve_vendor(&self, path: &String) -> Result<String, Diagnostic> {
+        let mut split_path = path.splitn(3, '/');
+        let which_vendor = if let (Some(first), Some(second)) = (split_path.next(), split_path.next()) {
+            format!("{first}/{second}")
         } else {
-            Expr::StructDef { struct_name: Box::new(name.clone()), struct_fields: exprs }
+            return Err(self.comp_err(&format!("unknown vendor target in import: {path}")));
         };
-        self.structures.push(expr.clone());
-
-        let sanitised_name: String;
-        let sanitised_expr = match expr {
-            Expr::StructDef { ref struct_name, ref struct_fields } => {
-                if let Expr::StructName { name: sname, is_shared } = *struct_name.clone() {
-                    sanitised_name = sname.replace(".", "__");
-                    Expr::StructDef { struct_name: Box::new(Expr::StructName{name: sanitised_name.clone(), is_shared}), struct_fields: struct_fields.to_vec() }
-                } else {
-                    unreachable!()
-                }
-            },
-            Expr::MacroStructDef { ref struct_name, ref struct_fields } => {
-                if let Expr::StructName {name: sname, is_shared} = *struct_name.clone() {
-                    sanitised_name = sname.replace(".", "__");
-                    Expr::MacroStructDef { struct_name: Box::new(Expr::StructName { name: sanitised_name.clone(), is_shared }), struct_fields: struct_fields.to_vec() }
-                } else if let Expr::MacroStructName { name: sname, generics, is_shared } = *struct_name.clone() {
-                    sanitised_name = sname.replace(".", "__");
-                    Expr::MacroStructDef { struct_name: Box::new(Expr::MacroStructName { name: sanitised_name.clone(), generics, is_shared }), struct_fields: struct_fields.to_vec() }
-                } else {
-                    unreachable!()
-                }
+        let vendor_name = which_vendor.trim_start_matches("vendor/");
+        let path_to_vendor = match self.vendors.get(vendor_name) {
+            Some(vendor_path) => PathBuf::from(vendor_path),
+            None => match self.lib_paths.find(self.fs.as_ref(), &which_vendor) {
+                Some(found) => found,
+                None => {
+                    let e = self.comp_err(&format!("unable to find vendor {vendor_name}"));
+                    return Err(self.lib_paths.with_searched(e, &which_vendor)
+                        .with_note(&format!("or add it under [dependencies] in impulse.toml, like {vendor_name} = {{ path = \"...\" }}")));
+                },
             },
-            _ => unreachable!(),
+        };
+        let in_vendor = path.trim_start_matches("vendor/");
+        let Some(vendor_dir) = &self.vendor_dir else {
+            let rest = in_vendor.strip_prefix(vendor_name).unwrap_or_default().trim_start_matches('/');
+            return Ok(path_to_vendor.join(rest).to_string_lossy().to_string());
         };
 
-        self.program_push(sanitised_expr);
-        match name {
-            Expr::StructName { name: struct_name, .. } => {
-                self.program_push(Expr::EndStruct(sanitised_name));
-                self.func_to_vars.remove(&struct_name);
-            },
-            Expr::MacroStructName { .. } => {
-                self.program_push(Expr::MacroEndStruct(sanitised_name));
-            },
-            _ => {
-                self.comp_err(&format!("unexpected expression when creating struct"));
-                exit(1);
+        if !vendor_dir.exists() {
+            match fs::create_dir_all(vendor_dir) {
+                Ok(_) => (),
+                Err(e) => {
+                    return Err(self.comp_err(&format!("unable to to create vendor folder {} with error: {e:?}", vendor_dir.display())));
+                }
             }
         }
 
-        self.in_struct_def = false;
-        self.is_struct_generic = false;
-    }
-
-    fn boolean_conditions(&self, params: &Vec<Token>, is_loop: bool) -> (Vec<Expr>, Expr) {
-        let mut func_call = Expr::None;
-        let mut func_just_got = false;
-        let mut func_brack_rc = 0;
-        let mut func_params = Vec::new();
-
-        let mut expr_params = Vec::new();
-        let mut side_affect = Expr::None;
-        let mut had_index = false;
-
-        let mut found_amper = false;
-        let mut pointer_counter = 0;
+        match copy_items(&[&path_to_vendor], vendor_dir, &CopyOptions::new()) {
+            Ok(_) => (),
+            Err(e) => match e.kind {
+                fs_extra::error::ErrorKind::AlreadyExists => (),
+                _ => {
+                    return Err(self.comp_err(&format!("unable to copy vendor {path} to {} with error: {e:?}", vendor_dir.display())));
+                }
+            }
 
-        if params.is_empty() {
-            expr_params.push(Expr::IntLit(String::from(";")));
         }
 
-        for (i, param) in params.iter().enumerate() {
-            match param {
-                Token::Equal => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::Equal)
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::SmallerThan => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::SmallerThan)
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::BiggerThan => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::BiggerThan)
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                } ,
-                Token::Exclaim => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::Exclaim)
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::True => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::True)
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::False => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::False)
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::Caret => {
-                    if func_brack_rc == 0 {
-                        pointer_counter += 1;
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::Ampersand => {
-                    if func_brack_rc == 0 {
-                        found_amper = true;
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::And => expr_params.push(Expr::And),
-                Token::Or => expr_params.push(Expr::Or),
-                Token::Ident(ident) => {
-                    if func_brack_rc != 0 {
-                        func_params.push(param.clone());
-                        continue;
-                    }
+        Ok(vendor_dir.join(in_vendor).to_string_lossy().to_string())
+    }
 
-                    // TODO: LATER CHECK IF ERROR IS NUM TOO LARGE
-                    let is_num = if ident.len() > 2 && (&ident[0..2].to_lowercase() == "0x" || &ident[0..2].to_lowercase() == "0b") {
-                        let ident_num = usize::from_str_radix(&ident[2..], 16);
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
-                    } else {
-                        let ident_num = ident.parse::<f64>();
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
+    fn handle_mutate_func_arg(&mut self, varname: &String) -> Result<Vec<Expr>, Diagnostic> {
+        for func in self.functions.iter_mut() {
+            if let Expr::Func { params, name: func_name, .. } | Expr::MacroFunc { params, name: func_name, .. } = func {
+                if func_name == &self.current_func {
+                    for param in params {
+                        if let Expr::VariableName { name: param_name, constant, .. } = param {
+                            if param_name == varname {
+                                *constant = false;
+                            }
                         }
-                    };
-
-                    if is_num {
-                        expr_params.push(Expr::IntLit(ident.to_string()));
-                        continue;
-                    }
-
-                    let keyword_res = self.keyword_map.get(ident);
-                    let keyword = match keyword_res {
-                        Some(k) => k.clone(),
-                        None => Keyword::None,
-                    };
-
-                    match keyword {
-                        Keyword::None => (),
-                        _ => {
-                            self.comp_err(&format!("unexpected keyword {keyword:?} in boolean condition"));
-                            exit(1);
-                        },
                     }
+                }
+            }
+        }
 
-                    // TODO: add dereferncing inside of boolean condition
-                    let expr = self.find_ident(ident.to_string());
-                    match expr {
-                        Expr::Func { ref name, .. } | Expr::MacroFunc { ref name, .. } => {
-                            if let Token::Lbrack = params[i+1] {
-                                func_call = expr;
-                                func_brack_rc += 1;
-                                func_just_got = true;
-                            } else {
-                                self.comp_err(&format!("can't compare to function. did you mean to call {name}?. do `{name}()`"));
-                                exit(1);
-                            }
-                        },
-                        Expr::VariableName { typ, name, reassign, constant, field_data, func_arg } => {
-                            let var = if found_amper {
-                                if constant {
-                                    self.comp_err(&format!("using constant \"{name}\" as address dequalifies it to a variable, did you mean for \"{name}\" to be a variable?"));
-                                    exit(1)
-                                }
+        let variables_res = self.func_to_vars.get_mut(&self.current_func);
+        match variables_res {
+            Some(variables) => {
+                let mut new_vars = Vec::new();
+                let mut idxs: Vec<usize> = Vec::new();
 
-                                found_amper = false;
-                                Expr::Address(Box::new(Expr::VariableName { typ: typ.clone(), name: name.clone(), reassign, constant, field_data, func_arg }))
-                            } else {
-                                Expr::VariableName { typ: typ.clone(), name: name.clone(), reassign, constant, field_data, func_arg }
-                            };
-                            if i != params.len()-1 {
-                                if let Token::Int(index) = &params[i+1] {
-                                    had_index = true;
-                                    expr_params.push(Expr::VariableName {
-                                        typ: Types::ArrIndex {
-                                            arr_typ: Box::new(typ),
-                                            index_at: index.to_owned(),
-                                        },
-                                        name,
-                                        reassign,
-                                        constant,
+                for (idx, vars) in variables[self.current_scope].iter().enumerate() {
+                    if let Expr::Variable { info, value } = vars {
+                        if let Expr::VariableName { typ, name, field_data, func_arg, .. } = *info.clone() {
+                            if name.replace("->", ".").contains(varname) && func_arg {
+                                new_vars.push(Expr::Variable {
+                                    info: Box::new(Expr::VariableName {
+                                        typ: typ.clone(),
+                                        name: name.clone(),
+                                        reassign: false,
+                                        constant: false,
+                                        func_arg: true,
                                         field_data,
-                                        func_arg
-                                    });
-                                } else {
-                                    expr_params.push(var);
-                                }
-                            } else {
-                                expr_params.push(var);
-                            }
-                        },
-                        Expr::None => {
-                            if is_loop && expr_params.is_empty() {
-                                let new_varname = Expr::VariableName {
-                                    typ: Types::Usize,
-                                    name: ident.to_owned(),
-                                    reassign: true,
-                                    constant: false,
-                                    func_arg: false,
-                                    field_data: (false, false),
-                                };
-
-                                expr_params.push(new_varname.clone());
-                                side_affect = Expr::Variable {
-                                    info: Box::new(new_varname),
-                                    value: Box::new(Expr::IntLit(String::from("0"))),
-                                };
-                            } else {
-                                self.comp_err(&format!("unknown identifier: {}", ident));
-                                exit(1);
+                                    }),
+                                    value: value.clone(),
+                                });
+                                idxs.push(idx);
                             }
-                        },
-                        _ => {
-                            self.comp_err(&format!("unexpected expression: {:?}", expr));
-                            exit(1);
                         }
                     }
-                },
-                Token::Int(intlit) => {
-                    if func_brack_rc != 0 {
-                        func_params.push(param.clone());
-                        continue;
-                    }
+                }
 
-                    if had_index {
-                        had_index = false;
-                        continue;
-                    }
-                    expr_params.push(self.check_intlit(intlit.to_owned()));
-                },
-                Token::Lbrack => {
-                    if func_just_got {
-                        func_just_got = false;
-                        continue;
-                    }
-                    func_brack_rc += 1;
-                },
-                Token::Rbrack => {
-                    if func_brack_rc > 0 {
-                        func_brack_rc -= 1;
-                        if func_brack_rc == 0 {
-                            expr_params.push(self.create_func_call(&func_call, func_params.clone()));
-                            func_call = Expr::None;
-                            func_params.clear();
-                        }
-                    }
-                },
-                Token::Str(word) => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::StrLit(word.to_owned()))
-                    } else {
-                        func_params.push(param.clone());
-                    }
-                },
-                Token::Char(charlit) => {
-                    if func_brack_rc == 0 {
-                        expr_params.push(Expr::CharLit(charlit.clone()))
-                    } else {
-                        func_params.push(param.clone());
+                if !new_vars.is_empty() {
+                    let mut old_vars = Vec::new();
+                    for (i, old_var_idx) in idxs.iter().enumerate() {
+                        let old_var = variables[self.current_scope].remove(*old_var_idx);
+                        variables[self.current_scope].insert(*old_var_idx, new_vars[i].clone());
+
+                        old_vars.push(old_var);
                     }
-                },
-                _ => {
-                    self.comp_err(&format!("unexpected token in boolean condition: {:?}", param));
-                    exit(1);
-                },
-            }
-        }
 
-        match expr_params[expr_params.len()-1] {
-            Expr::Equal | Expr::SmallerThan |
-            Expr::BiggerThan | Expr::Exclaim |
-            Expr::And | Expr::Or => {
-                self.comp_err("incomplete condition");
-                exit(1);
+                    Ok(old_vars)
+                } else {
+                    Err(self.comp_err(&format!("expected {varname} to be a function argument but wasn't. @mut is used to make an argument mutable")))
+                }
+            },
+            None => {
+                Err(self.comp_err(&format!("expected {varname} to be a function argument but wasn't. @mut is used to make an argument mutable")))
             },
-            _ => (),
-        }
-
-        (expr_params, side_affect)
-    }
-
-    fn create_case(&mut self, params: &Vec<Token>, is_fall: bool) {
-        self.token_stack.clear();
-        self.new_scope(Expr::None);
-
-        let expr_params = if params.is_empty() {
-            vec![Expr::None]
-        } else {
-            self.boolean_conditions(&params, false).0
-        };
-
-        if self.in_defer {
-            if is_fall {
-                self.expr_stack.push(Expr::Fall(expr_params));
-            } else {
-                self.expr_stack.push(Expr::Case(expr_params));
-            }
-        } else {
-            if is_fall {
-                self.program_push(Expr::Fall(expr_params));
-            } else {
-                self.program_push(Expr::Case(expr_params));
-            }
         }
     }
 
-    fn create_branch(&mut self, branch_typ: Keyword, params: Vec<Token>, capture: &String) {
-        self.token_stack.clear();
-
-        let captured = if params.len() == 1 && !capture.is_empty() {
-            let mut captures = Vec::new();
-
-            if let Token::Ident(variable) = &params[0] {
-                let found = self.find_variable(&variable);
-                match found {
-                    Expr::VariableName { typ, name, .. } => {
-                        if let Types::TypeDef { type_name, generics: generics_op } = typ {
-                            if type_name == String::from("option") {
-                                let generics = match generics_op {
-                                    Some(g) => g,
-                                    None => {
-                                        self.comp_err(&format!("{type_name} unsupported in for loops currently"));
-                                        exit(1);
-                                    },
+    fn propagate_struct_fields(&mut self, fname: String, user_def: String, is_ptr: bool, is_constant: bool) -> Result<(), Diagnostic> {
+        match self.find_ident(user_def) {
+            Expr::StructDef { struct_fields, .. } | Expr::MacroStructDef { struct_fields, .. } => {
+                for field in struct_fields {
+                    match field {
+                        Expr::Variable { info, .. } => {
+                            if let Expr::VariableName { typ, name, field_data, .. } = *info {
+                                let new_name = if is_ptr {
+                                    format!("{fname}->{name}")
+                                } else {
+                                    format!("{fname}.{name}")
                                 };
-
-                                let subtyp = generics[0].clone();
-
-                                match subtyp {
-                                    Types::TypeDef { ref type_name, .. } => captures.append(&mut self.new_scope_propagate_field(capture.to_owned(), type_name.to_string(), false, true, false)),
-                                    _ => (),
-                                }
-
-                                let value = self.find_variable(&format!("{name}.value"));
-                                if let Expr::None = value {
-                                    self.comp_err(&format!("expected variable {name}.value to exist when it doesn't. can't be used in if statement capture"));
-                                    exit(1);
+                                if let Types::TypeDef { ref type_name, .. } = typ {
+                                    self.propagate_struct_fields(new_name.clone(), type_name.to_string(), field_data.1, is_constant)?;
+                                } else if let Types::Arr { .. } = typ {
+                                    self.propagate_struct_fields(new_name.clone(), String::from("array"), field_data.1, is_constant)?;
                                 }
 
-                                captures.push(Expr::Variable {
+                                let new_expr = Expr::Variable {
                                     info: Box::new(Expr::VariableName {
-                                        typ: subtyp,
-                                        name: capture.to_owned(),
+                                        typ,
+                                        name: new_name,
                                         reassign: false,
-                                        constant: true,
+                                        constant: is_constant, // CAN BREAK, testing rn
                                         func_arg: false,
-                                        field_data: (false, false)
+                                        field_data: (true, is_ptr),
                                     }),
-                                    value: Box::new(value),
-                                });
-                                captures
-                            } else {
-                                self.comp_err(&format!("{type_name} unsupported in if statement capture"));
-                                exit(1);
-                            }
-                        } else {
-                            self.comp_err(&format!("{typ:?} unsupported in if statement capture"));
-                            exit(1);
-                        }
-                    },
-                    _ => unreachable!(),
-                }
-            } else {
-                self.comp_err(&format!("expected one identifier in condition, found {:?}", params[0]));
-                exit(1);
-            }
-        } else {
-            vec![Expr::None]
-        };
+                                    value: Box::new(Expr::None),
+                                };
+                                if !self.in_enum_def && !self.in_func && !self.in_struct_def {
+                                    self.global_vars.push(new_expr.clone());
+                                } else if let Some(vars) = self.func_to_vars.get_mut(&self.current_func) {
+                                    vars[self.current_scope].push(new_expr);
+                                }
+                            }
+                        },
+                        Expr::VariableName { typ, name, field_data, .. } => {
+                            let new_name = if is_ptr {
+                                format!("{fname}->{name}")
+                            } else {
+                                format!("{fname}.{name}")
+                            };
+                            if let Types::TypeDef { ref type_name, .. } = typ {
+                                self.propagate_struct_fields(new_name.clone(), type_name.to_string(), field_data.1, is_constant)?;
+                            } else if let Types::Arr { .. } = typ {
+                                self.propagate_struct_fields(new_name.clone(), String::from("array"), field_data.1, is_constant)?;
+                            }
 
-        match branch_typ {
-            Keyword::If => {
-                let expr_params = self.boolean_conditions(&params, false).0; // only getting the array
-                self.new_scope_vars(captured.clone());
-                if self.in_defer {
-                    self.expr_stack.push(Expr::If(expr_params, Box::new(captured.last().unwrap().clone())));
-                } else {
-                    self.program_push(Expr::If(expr_params, Box::new(captured.last().unwrap().clone())));
+                            let new_expr = Expr::Variable {
+                                info: Box::new(Expr::VariableName {
+                                    typ,
+                                    name: new_name,
+                                    reassign: false,
+                                    constant: is_constant, // CAN BREAK, testing rn
+                                    func_arg: false,
+                                    field_data: (true, is_ptr),
+                                }),
+                                value: Box::new(Expr::None),
+                            };
+                            if !self.in_enum_def && !self.in_func && !self.in_struct_def {
+                                self.global_vars.push(new_expr.clone());
+                            } else if let Some(vars) = self.func_to_vars.get_mut(&self.current_func) {
+                                vars[self.current_scope].push(new_expr);
+                            }
+                        },
+                        _ => (),
+                    }
                 }
             },
-            Keyword::OrIf => {
-                let expr_params = self.boolean_conditions(&params, false).0; // only getting the array
-                self.new_scope_vars(captured.clone());
-                if self.in_defer {
-                    self.expr_stack.push(Expr::OrIf(expr_params, Box::new(captured.last().unwrap().clone())));
-                } else {
-                    self.program_push(Expr::OrIf(expr_params, Box::new(captured.last().unwrap().clone())));
-                }
+            // Enums will be checked for propagation but they've already been handling
+            Expr::EnumDef { .. } => (),
+            unexpected => {
+                return Err(self.comp_err(&format!("unexpected expression {unexpected:?} during field propagation")));
             },
-            Keyword::Else => {
-                if !params.is_empty() {
-                    self.comp_err("expected no conditions for else branch, got one");
-                    exit(1);
-                }
+        }
 
-                if !capture.is_empty() {
-                    self.comp_err("expected no capture for else branch, got one");
-                    exit(1);
-                }
+        Ok(())
+    }
 
-                self.new_scope(Expr::None);
-                if self.in_defer {
-                    self.expr_stack.push(Expr::Else);
-                } else {
-                    self.program_push(Expr::Else);
-                }
+    fn handle_type_mask(&mut self, type_name: String, masked_type: Expr) -> Result<(), Diagnostic> {
+        match masked_type {
+            Expr::StructDef { struct_fields, struct_name } => {
+                let is_shared = match *struct_name {
+                    Expr::StructName { is_shared, .. } => is_shared,
+                    _ => false,
+                };
+                let expr = Expr::StructDef {
+                    struct_name: Box::new(Expr::StructName { name: type_name, is_shared }),
+                    struct_fields
+                };
+                self.structures.push(expr);
             },
-            Keyword::Switch => {
-                let expr_params = self.boolean_conditions(&params, false).0; // only getting the array
-                self.new_scope(Expr::None);
-                if self.in_defer {
-                    self.expr_stack.push(Expr::Switch(expr_params));
-                } else {
-                    self.program_push(Expr::Switch(expr_params));
-                }
+            Expr::MacroStructDef { struct_name, struct_fields } => {
+                let (generics, is_shared) = if let Expr::MacroStructName { generics, is_shared, .. } = *struct_name {
+                    (generics, is_shared) 
+                } else {(vec![], false)};
+                let expr = Expr::MacroStructDef {
+                    struct_name: Box::new(Expr::MacroStructName {
+                        name: type_name,
+                        generics,
+                        is_shared
+                    }),
+                    struct_fields
+                };
+                self.structures.push(expr);
+            },
+            Expr::EnumDef { .. } => {
+                return Err(self.comp_err(&format!("masking an enum is not supported, tried to mask `{masked_type:?}`")));
             },
             _ => (),
         }
+
+        Ok(())
     }
 
-    fn create_loop(&mut self, params: Vec<Token>, mut modifier: String) {
-        let expr_params = self.boolean_conditions(&params, true);
+    // turns the tree of a file into the flat program the generator walks. every item is lowered
+    // even after an error so they all get reported
+    pub fn lower(&mut self, module: &ast::Module) -> Result<Vec<(Expr, String, Span)>, Diagnostics> {
+        for item in &module.items {
+            self.span = item.span;
+            if let Err(e) = self.lower_item(item) {
+                self.diagnostics.push(e);
+            }
+        }
+
+        if !self.diagnostics.has_errors() {
+            let checked = TypeChecker::new(self, &self.filename).check(module);
+            self.diagnostics.extend(checked);
+        }
 
-        if modifier.is_empty() {
-            modifier = "_".to_string();
+        if self.diagnostics.has_errors() {
+            return Err(std::mem::take(&mut self.diagnostics));
         }
 
-        match modifier.as_str() {
-            "+" | "-" | "_" => {
-                self.token_stack.clear();
-                match expr_params.1 {
-                    Expr::None | Expr::Variable { .. } => self.new_scope(expr_params.1),
-                    _ => {
-                        self.comp_err(&format!("unexpected expression in loop. {:?}", expr_params.1));
-                        exit(1);
-                    },
-                }
+        Ok(self.program.clone())
+    }
 
-                if self.in_defer {
-                    self.expr_stack.push(Expr::Loop {
-                        condition: expr_params.0,
-                        modifier: Box::new(Expr::IntLit(modifier.to_owned())),
-                    });
-                } else {
-                    self.program_push(Expr::Loop {
-                        condition: expr_params.0,
-                        modifier: Box::new(Expr::IntLit(modifier.to_owned())),
-                    });
+    fn lower_item(&mut self, item: &ast::Item) -> Result<(), Diagnostic> {
+        match &item.kind {
+            ItemKind::Import(path) => {
+                let import = self.handle_import_macro(path.clone())?;
+                if let Expr::Import(_) = import {
+                    self.program_push(import);
                 }
             },
-            _ => {
-                self.comp_err(&format!("unexpected loop modifier: {modifier}"));
-                exit(1);
+            ItemKind::CEmbed(code) => {
+                let embed = self.c_embed(code)?;
+                self.program_push(embed);
+            },
+            ItemKind::Link { os, libs } => {
+                let flags = libs.iter().map(|lib| format!("-l{lib}")).collect();
+                self.lower_cflags(os, flags)?;
+            },
+            ItemKind::CFlags { os, flags } => self.lower_cflags(os, flags.clone())?,
+            ItemKind::Func(func) => self.lower_func(func, None)?,
+            ItemKind::Extern(external) => self.lower_extern(external)?,
+            ItemKind::Struct(structure) => self.lower_struct(structure)?,
+            ItemKind::Enum(enumeration) => self.lower_enum(enumeration)?,
+            ItemKind::Var(decl) => {
+                self.lower_var(decl)?;
+            },
+            ItemKind::EnumField { name, .. } => {
+                return Err(self.comp_err(&format!("expected a type for {name}, only enums have fields without one")));
+            },
+        }
+
+        Ok(())
+    }
+
+    // -I and -L paths are relative to the file, so a library's flags work wherever it's imported from
+    fn lower_cflags(&mut self, os: &Option<String>, mut flags: Vec<String>) -> Result<(), Diagnostic> {
+        if let Some(os) = os.as_deref().filter(|os| !OSES.contains(os)) {
+            return Err(self.comp_err(&format!("unknown os {os}, expected one of {}", OSES.join(", "))));
+        }
+
+        let dir = Path::new(&self.filename).parent().unwrap_or(Path::new(""));
+        for flag in flags.iter_mut() {
+            let Some((option, path)) = ["-I", "-L"].iter().find_map(|option| flag.strip_prefix(option).map(|path| (option, path))) else {
+                continue;
+            };
+            if !path.is_empty() && Path::new(path).is_relative() {
+                *flag = format!("{option}{}", dir.join(path).to_string_lossy());
             }
         }
+
+        self.program_push(Expr::CFlags { os: os.clone(), flags });
+        Ok(())
     }
 
-    fn create_for(&mut self, params: Vec<Token>, modifier: String) {
-        if params.len() > 1 {
-            match params[0] {
-                Token::Ampersand => (),
-                _ => {
-                    self.comp_err("expected only one expression to loop through");
-                    exit(1);
-                }
+    fn lower_func(&mut self, func: &ast::Func, owner: Option<&String>) -> Result<(), Diagnostic> {
+        let name = match owner {
+            Some(owner) => format!("{owner}.{}", func.name),
+            None => func.name.clone(),
+        };
+
+        let typ = self.lower_type(&func.ret, TypeAt::Return)?;
+        let mut is_macro_func = matches!(typ, Types::Any | Types::TypeId);
+
+        let mut variables = Vec::new();
+        let mut expr_param = Vec::new();
+        let mut typeid_names: Vec<String> = Vec::new();
+        for param in &func.params {
+            let typ = self.lower_type(&param.typ, TypeAt::Param(&typeid_names))?;
+            if self.takes_any_type(&param.typ) {
+                is_macro_func = true;
+            }
+
+            // the fields have to be added here since the function's scope doesn't exist yet
+            match &typ {
+                Types::TypeDef { type_name, .. } => {
+                    expr_param.append(&mut self.new_scope_propagate_field(param.name.clone(), type_name.clone(), false, true, true));
+                },
+                Types::Arr { .. } => {
+                    expr_param.append(&mut self.new_scope_propagate_field(param.name.clone(), String::from("array"), false, true, true));
+                },
+                Types::Pointer(_) => match pointee(&typ) {
+                    Types::TypeDef { type_name, .. } => {
+                        expr_param.append(&mut self.new_scope_propagate_field(param.name.clone(), type_name.clone(), true, true, true));
+                    },
+                    Types::Arr { .. } => {
+                        expr_param.append(&mut self.new_scope_propagate_field(param.name.clone(), String::from("array"), true, true, true));
+                    },
+                    _ => (),
+                },
+                Types::TypeId => typeid_names.push(param.name.clone()),
+                _ => (),
             }
+
+            let final_expr = Expr::VariableName {
+                typ,
+                name: param.name.clone(),
+                reassign: false,
+                constant: true,
+                func_arg: true,
+                field_data: (false, false),
+            };
+            variables.push(final_expr.clone());
+            expr_param.push(Expr::Variable { info: Box::new(final_expr), value: Box::new(Expr::None) });
         }
 
-        if modifier.is_empty() {
-            self.comp_err("expected new variable names e.g. `[elem]` or `[elem i]`");
-            exit(1);
+        if let Expr::Func { .. } | Expr::MacroFunc { .. } = self.find_func(&name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as another function")));
+        } else if let Expr::StructDef { .. } = self.find_structure(&name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as struct")));
         }
 
-        let mut new_varnames: Vec<&str> = modifier.split(' ').collect();
-        if new_varnames.len() > 2 {
-            self.comp_err(&format!("expected at most two variables to extract in for loop, got {}", new_varnames.len()));
-            exit(1);
+        let san_name = name.replace(".", "__");
+        let (expr, sanitised_expr) = if is_macro_func {
+            (
+                Expr::MacroFunc { typ: typ.clone(), params: variables.clone(), name: name.clone() },
+                Expr::MacroFunc { typ, params: variables, name: san_name },
+            )
+        } else {
+            (
+                Expr::Func { typ: typ.clone(), params: variables.clone(), name: name.clone(), is_inline: func.is_inline, link_name: None, variadic: false },
+                Expr::Func { typ, params: variables, name: san_name, is_inline: func.is_inline, link_name: None, variadic: false },
+            )
+        };
+
+        self.functions.push(expr);
+        self.current_func = name.clone();
+        self.func_to_vars.entry(name).or_insert(vec![expr_param]);
+        self.in_func = true;
+        self.program_push(sanitised_expr);
+
+        self.lower_body(&func.body);
+        Ok(())
+    }
+
+    fn lower_extern(&mut self, external: &ast::Extern) -> Result<(), Diagnostic> {
+        let name = &external.name;
+        let typ = self.lower_type(&external.ret, TypeAt::Return)?;
+        if self.takes_any_type(&external.ret) {
+            return Err(self.comp_err(&format!("@extern function {name} can't return typeid or any, c functions aren't generic")));
         }
 
-        let is_elem_pointer = if new_varnames[0].starts_with('^') {
-            let mut chars = new_varnames[0].chars();
-            chars.next();
-            new_varnames[0] = chars.as_str();
-            true
-        } else { false };
+        let mut params = Vec::new();
+        for param in &external.params {
+            if self.takes_any_type(&param.typ) {
+                return Err(self.comp_err(&format!("@extern function {name} can't take typeid or any, c functions aren't generic")));
+            }
 
-        let mut expr = Expr::None;
-        let mut side_effects = Vec::new();
-        let in_this = self.boolean_conditions(&params, false);
-
-        let for_this_typ = match &in_this.0[0] {
-            Expr::Address(var) => {
-                if let Expr::VariableName { typ, constant, .. } = *var.clone() {
-                    if let Types::Arr { typ: subtyp, .. } = typ {
-                        if let Types::TypeDef { type_name, .. } = *subtyp.clone() {
-                            side_effects.append(&mut self.new_scope_propagate_field(new_varnames[0].to_owned(), type_name, is_elem_pointer, constant, false));
-                        }
-                        if is_elem_pointer {
-                            Types::Pointer(Box::new(*subtyp.clone()))
-                        } else {
-                            *subtyp.clone()
-                        }
-                    } else if let Types::TypeDef { type_name, generics: generics_op } = typ {
-                        if type_name == "dyn" || type_name == "array" {
-                            let generics = match generics_op {
-                                Some(g) => g,
-                                None => unreachable!(),
-                            };
-                            let subtyp = generics[0].clone();
-                            match subtyp {
-                                Types::TypeDef { ref type_name, .. } => side_effects.append(&mut self.new_scope_propagate_field(new_varnames[0].to_owned(), type_name.to_string(), is_elem_pointer, constant, false)),
-                                _ => (),
-                            }
-                            if is_elem_pointer {
-                                Types::Pointer(Box::new(subtyp))
-                            } else {
-                                subtyp
-                            }
-                        } else if type_name == "string" || type_name == "str" {
-                            Types::Char
-                        } else {
-                            self.comp_err(&format!("{type_name} unsupported in for loops currently"));
-                            exit(1);
-                        }
-                    } else {
-                        self.comp_err(&format!("{typ:?} unsupported in for loops currently"));
-                        exit(1);
-                    }
-                } else {
-                    self.comp_err(&format!("{var:?} unsupported in for loops currently"));
-                    exit(1);
-                }
+            params.push(Expr::VariableName {
+                typ: self.lower_type(&param.typ, TypeAt::Param(&[]))?,
+                name: param.name.clone(),
+                reassign: false,
+                constant: true,
+                func_arg: true,
+                field_data: (false, false),
+            });
+        }
+
+        if let Expr::Func { .. } | Expr::MacroFunc { .. } = self.find_func(name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as another function")));
+        } else if let Expr::StructDef { .. } = self.find_structure(name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as struct")));
+        }
+
+        let link_name = external.link_name.clone().unwrap_or_else(|| name.rsplit('.').next().unwrap_or(name).to_string());
+        let variadic = external.variadic;
+        self.functions.push(Expr::Func { typ: typ.clone(), params: params.clone(), name: name.clone(), is_inline: false, link_name: Some(link_name.clone()), variadic });
+        self.program_push(Expr::Func { typ, params, name: name.replace(".", "__"), is_inline: false, link_name: Some(link_name), variadic });
+        Ok(())
+    }
+
+    fn c_embed(&self, code: &str) -> Result<Expr, Diagnostic> {
+        Ok(Expr::CEmbed(self.interpolate(code.trim())?.replace("\r", "")))
+    }
+
+    // swaps every `{name}` outside of c strings for how the generated c spells it
+    fn interpolate(&self, code: &str) -> Result<String, Diagnostic> {
+        let mut interpolated = String::new();
+        let mut copied = 0;
+        let mut quote = None;
+        let mut escaped = false;
+        for (i, ch) in code.char_indices() {
+            if i < copied {
+                continue;
             }
-            Expr::VariableName { typ, constant, .. } => {
-                if let Types::Arr { typ: subtyp, .. } = typ {
-                    if let Types::TypeDef { type_name, .. } = *subtyp.clone() {
-                        side_effects.append(&mut self.new_scope_propagate_field(new_varnames[0].to_owned(), type_name, is_elem_pointer, *constant, false));
-                    }
-                    if is_elem_pointer {
-                        Types::Pointer(Box::new(*subtyp.clone()))
-                    } else {
-                        *subtyp.clone()
-                    }
-                } else if let Types::TypeDef { type_name, generics: generics_op } = typ {
-                    if type_name == "dyn" || type_name == "array" {
-                        let generics = match generics_op {
-                            Some(g) => g,
-                            None => unreachable!(),
-                        };
-                        let subtyp = generics[0].clone();
-                        match subtyp {
-                            Types::TypeDef { ref type_name, .. } => side_effects.append(&mut self.new_scope_propagate_field(new_varnames[0].to_owned(), type_name.to_string(), is_elem_pointer, *constant, false)),
-                            _ => (),
-                        }
-                        if is_elem_pointer {
-                            Types::Pointer(Box::new(subtyp))
-                        } else {
-                            subtyp
-                        }
-                    } else if type_name == "string" || type_name == "str" {
-                        Types::Char
-                    } else {
-                        self.comp_err(&format!("{type_name} unsupported in for loops currently"));
-                        exit(1);
+
+            match (quote, ch) {
+                (Some(_), _) if escaped => escaped = false,
+                (Some(_), '\\') => escaped = true,
+                (Some(open), ch) if ch == open => quote = None,
+                (Some(_), _) => (),
+                (None, '"' | '\'') => quote = Some(ch),
+                (None, '{') => {
+                    let Some(end) = code[i..].find('}').map(|end| i + end + 1) else {
+                        continue;
+                    };
+                    if let Some(name) = embedded_name(&code[i..end]) {
+                        interpolated.push_str(&code[copied..i]);
+                        interpolated.push_str(&self.c_spelling(name)?);
+                        copied = end;
                     }
-                } else {
-                    self.comp_err(&format!("{typ:?} unsupported in for loops currently"));
-                    exit(1);
-                }
-            },
-            unexpected => {
-                self.comp_err(&format!("{unexpected:?} unsupported in for loops currently"));
-                exit(1);
+                },
+                _ => (),
             }
-        };
+        }
 
-        let found_var = self.find_variable(&new_varnames[0].to_owned());
-        match found_var {
-            Expr::VariableName { constant, .. } => {
-                if !constant {
-                    self.comp_err(&format!("{} is constant. can't be used in for loop", new_varnames[0]));
-                    exit(1);
-                }
-                if new_varnames.len() == 2 {
-                    expr = Expr::For { 
-                        for_this: Box::new(found_var.clone()),
-                        in_this: Box::new(in_this.0[0].clone()),
-                        iterator: new_varnames[1].to_string()
-                    };
-                    side_effects.push(Expr::Variable {
-                        info: Box::new(Expr::VariableName {
-                            typ: Types::Usize,
-                            name: new_varnames[1].to_string(),
-                            reassign: false,
-                            constant: false,
-                            func_arg: false,
-                            field_data: (false, false),
-                        }),
-                        value: Box::new(Expr::IntLit(String::from("0")))
-                    });
-                } else {
-                    expr = Expr::For { 
-                        for_this: Box::new(found_var.clone()),
-                        in_this: Box::new(in_this.0[0].clone()),
-                        iterator: String::new()
-                    };
-                }
+        interpolated.push_str(&code[copied..]);
+        Ok(interpolated)
+    }
+
+    fn c_spelling(&self, name: &str) -> Result<String, Diagnostic> {
+        let spelling = match self.find_ident(name.to_string()) {
+            // constants and enum fields have their `.` turned into `_`, fields keep them
+            Expr::VariableName { name, field_data: (false, _), .. } => name.replace(".", "_"),
+            Expr::VariableName { name, .. } => name,
+            Expr::Func { link_name: Some(link_name), .. } => link_name,
+            Expr::Func { name, .. } => name.replace(".", "__"),
+            Expr::StructDef { .. } => name.replace(".", "__"),
+            Expr::MacroFunc { .. } | Expr::MacroStructDef { .. } => {
+                return Err(self.comp_err(&format!("{name} is generic, it's only made in c once it's used so @c can't name it")));
             },
             Expr::None => {
-                let new_var = Expr::VariableName {
-                    typ: for_this_typ,
-                    name: new_varnames[0].to_string(),
-                    reassign: false,
-                    constant: !is_elem_pointer,
-                    func_arg: false,
-                    field_data: (is_elem_pointer, false)
-                };
-
-                if new_varnames.len() == 2 {
-                    expr = Expr::For {
-                        for_this: Box::new(new_var.clone()),
-                        in_this: Box::new(in_this.0[0].clone()),
-                        iterator: new_varnames[1].to_string()
-                    };
-                    side_effects.push(Expr::Variable { info: Box::new(new_var), value: Box::new(Expr::None) });
-                    side_effects.push(Expr::Variable {
-                        info: Box::new(Expr::VariableName {
-                            typ: Types::Usize,
-                            name: new_varnames[1].to_string(),
-                            reassign: false,
-                            constant: false,
-                            func_arg: false,
-                            field_data: (false, false),
-                        }),
-                        value: Box::new(Expr::IntLit(String::from("0")))
-                    });
-                } else {
-                    expr = Expr::For { 
-                        for_this: Box::new(new_var.clone()),
-                        in_this: Box::new(in_this.0[0].clone()),
-                        iterator: String::new()
-                    };
-                    side_effects.push(Expr::Variable { info: Box::new(new_var), value: Box::new(Expr::None) });
-                }
+                return Err(self.comp_err(&format!("unknown identifier in @c: {name}"))
+                    .with_note(&format!("for c braces around a name, put spaces inside them, `{{ {name} }}`")));
             },
-            _ => (),
-        }
+            unexpected => return Err(self.comp_err(&format!("{name} can't be used in @c, it's {unexpected:?}"))),
+        };
 
-        if self.in_defer {
-            self.expr_stack.push(expr);
-        } else {
-            self.program_push(expr);
-        }
-        self.token_stack.clear();
-        self.new_scope_vars(side_effects);
+        Ok(spelling)
     }
 
-    fn create_defer(&mut self, exprs: Vec<Expr>) {
-        for expr in exprs {
-            self.program_push(expr)
+    // typeid and any params make a function generic, it's generated again for every type it's called with
+    fn takes_any_type(&self, typ: &ast::Type) -> bool {
+        match typ {
+            ast::Type::Name { name, generics: None } => matches!(self.keyword_map.get(name), Some(Keyword::TypeId | Keyword::Any)),
+            ast::Type::Name { generics: Some(generics), .. } => generics.iter().any(|generic| self.takes_any_type(generic)),
+            _ => false,
         }
     }
 
-    fn handle_lcurl(&mut self) {
-        let mut typ: Types = Types::None;
-        let mut name = String::new();
-        let mut keyword = Keyword::None;
+    fn lower_struct(&mut self, structure: &ast::Struct) -> Result<(), Diagnostic> {
+        let span = self.span;
+        let name = &structure.name;
+        if let Some(kw) = self.keyword_map.get(name) {
+            return Err(self.comp_err(&format!("can't use keyword {kw:?} as struct name")));
+        }
 
-        let mut is_inline = false;
+        if let Expr::Func { .. } | Expr::MacroFunc { .. } = self.find_func(name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as function")));
+        } else if let Expr::StructDef { .. } | Expr::MacroStructDef { .. } = self.find_structure(name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as struct")));
+        }
 
-        let mut brack_rc = 0;
-        let mut in_bracks = false;
-        let mut seen_colon = 0;
-        let mut pointer_counter = 0;
+        let generics: Vec<Expr> = structure.generics.iter().map(|generic| Expr::Variable {
+            info: Box::new(Expr::VariableName {
+                typ: Types::TypeId,
+                name: generic.clone(),
+                reassign: false,
+                constant: false,
+                func_arg: false,
+                field_data: (false, false),
+            }),
+            value: Box::new(Expr::None),
+        }).collect();
 
-        let mut params = Vec::new();
+        self.current_func = name.clone();
+        self.func_to_vars.insert(name.clone(), vec![generics.clone()]);
+        self.in_struct_def = true;
+        self.is_struct_generic = !generics.is_empty();
+
+        let mut fields = Vec::new();
+        let mut funcs = Vec::new();
+        for item in &structure.body {
+            self.span = item.span;
+            let field = match &item.kind {
+                ItemKind::Func(func) => {
+                    funcs.push((func, item.span));
+                    continue;
+                },
+                _ if !funcs.is_empty() => Err(self.comp_err("struct fields have to come before its functions")),
+                ItemKind::Var(decl) => self.lower_var(decl),
+                ItemKind::CEmbed(code) => self.c_embed(code),
+                _ => Err(self.comp_err(&format!("expected a field or function in struct {name}"))),
+            };
 
-        let mut create_branch = false;
-        let mut create_case = false;
-        let mut create_fall = false;
+            match field {
+                Ok(field) => fields.push(field),
+                Err(e) => self.diagnostics.push(e),
+            }
+        }
 
-        let mut create_loop = false;
-        let mut loop_modifier = String::new();
+        self.in_struct_def = false;
+        self.is_struct_generic = false;
+        self.span = span;
+
+        let san_name = name.replace(".", "__");
+        let is_shared = structure.is_shared;
+        if generics.is_empty() {
+            self.structures.push(Expr::StructDef {
+                struct_name: Box::new(Expr::StructName { name: name.clone(), is_shared }),
+                struct_fields: fields.clone(),
+            });
+            self.program_push(Expr::StructDef {
+                struct_name: Box::new(Expr::StructName { name: san_name.clone(), is_shared }),
+                struct_fields: fields,
+            });
+            self.program_push(Expr::EndStruct(san_name));
+            self.func_to_vars.remove(name);
+        } else {
+            self.structures.push(Expr::MacroStructDef {
+                struct_name: Box::new(Expr::MacroStructName { name: name.clone(), generics: generics.clone(), is_shared }),
+                struct_fields: fields.clone(),
+            });
+            self.program_push(Expr::MacroStructDef {
+                struct_name: Box::new(Expr::MacroStructName { name: san_name.clone(), generics, is_shared }),
+                struct_fields: fields,
+            });
+            self.program_push(Expr::MacroEndStruct(san_name));
+        }
 
-        let mut create_for = false;
+        // the functions of a generic struct can still use its typeids
+        self.previous_func = name.clone();
+        for (func, span) in funcs {
+            self.span = span;
+            if let Err(e) = self.lower_func(func, Some(name)) {
+                self.diagnostics.push(e);
+            }
+        }
+        self.previous_func.clear();
+        self.func_to_vars.remove(name);
 
-        let mut create_defer = false;
+        Ok(())
+    }
 
-        let mut create_struct = false;
-        let mut is_shared = false;
-        let mut create_generic = false;
-        let mut generic_subtype = Expr::None;
+    fn lower_enum(&mut self, enumeration: &ast::Enum) -> Result<(), Diagnostic> {
+        let span = self.span;
+        let name = &enumeration.name;
+        if let Some(kw) = self.keyword_map.get(name) {
+            return Err(self.comp_err(&format!("can't use keyword {kw:?} as enum name")));
+        }
 
-        let mut create_enum = false;
+        if let Expr::Func { .. } | Expr::MacroFunc { .. } = self.find_func(name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as another function")));
+        } else if let Expr::StructDef { .. } = self.find_structure(name) {
+            return Err(self.comp_err(&format!("identifier {name} already declared as struct")));
+        }
 
-        for (i, token) in self.token_stack.clone().iter().enumerate() {
-            match token {
-                Token::Dollar => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        create_generic = true;
-                    }
-                },
-                Token::Caret => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        pointer_counter += 1;
-                    }
-                },
-                // TODO: NOT SURE IF THIS IS NEEDED, WILL REMOVE
-                Token::Underscore => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        typ = Types::Void;
+        self.current_func = name.clone();
+        self.func_to_vars.entry(name.clone()).or_insert(vec![vec![]]);
+        self.in_enum_def = true;
 
-                        if pointer_counter > 0 {
-                            let tmp_kw = self.create_keyword_pointer(Types::Void, pointer_counter).0;
-                            typ = self.keyword_to_type(tmp_kw);
-                        }
-                    }
+        let mut fields = Vec::new();
+        let mut funcs = Vec::new();
+        for item in &enumeration.body {
+            self.span = item.span;
+            let field = match &item.kind {
+                ItemKind::Func(func) => {
+                    funcs.push((func, item.span));
+                    continue;
                 },
-                Token::Ident(ident) => {
-                    if is_inline {
-                        if let Token::Macro = self.token_stack[i-1] {
-                            continue;
-                        }
-                    } else if is_shared {
-                        if let Token::Macro = self.token_stack[i-1] {
-                            continue;
-                        }
-                    }
-
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        // TODO: LATER CHECK IF ERROR IS NUM TOO LARGE
-                        if ident.len() > 2 && (&ident[0..2].to_lowercase() == "0x" || &ident[0..2].to_lowercase() == "0b") {
-                            let ident_num = usize::from_str_radix(&ident[2..], 16);
-                            match ident_num {
-                                Ok(_) => {
-                                    self.comp_err(&format!("can't use a number as an identifier. try `name{ident}`?"));
-                                    exit(1);
-                                },
-                                Err(_) => (),
-                            }
-                        } else {
-                            let ident_num = ident.parse::<f64>();
-                            match ident_num {
-                                Ok(_) => {
-                                    self.comp_err(&format!("can't use a number as an identifier. try `name{ident}`?"));
-                                    exit(1);
-                                },
-                                Err(_) => (),
-                            }
-                        }
+                _ if !funcs.is_empty() => Err(self.comp_err("enum fields have to come before its functions")),
+                ItemKind::EnumField { name: field, value } => self.lower_enum_field(field, value.as_ref()),
+                _ => Err(self.comp_err(&format!("expected a field or function in enum {name}"))),
+            };
 
-                        if let Keyword::None = keyword {
-                            let keyword_res = self.keyword_map.get(ident);
-                            keyword = match keyword_res {
-                                Some(kw) => kw.clone(),
-                                None => Keyword::None,
-                            };
-
-                            let found_ident = self.find_ident(ident.clone());
-                            if let Expr::StructDef { .. } | Expr::EnumDef { .. } = found_ident {
-                                keyword = Keyword::TypeDef { type_name: ident.to_string(), generics: None };
-                            } else if let Expr::MacroStructDef { .. } = found_ident {
-                                keyword = Keyword::TypeDef { type_name: ident.to_string(), generics: Some(vec![]) };
-                            }
-
-                            match keyword {
-                                Keyword::If | Keyword::OrIf | Keyword::Else => create_branch = true,
-                                Keyword::Switch => create_branch = true,
-                                Keyword::Case => create_case = true,
-                                Keyword::Fall => create_fall = true,
-                                Keyword::For => create_for = true,
-                                Keyword::Loop => create_loop = true,
-                                Keyword::Struct => create_struct = true,
-                                Keyword::Enum => create_enum = true,
-                                Keyword::Defer => create_defer = true,
-                                _ => {
-                                    if let Types::None = typ {
-                                        typ = if create_generic {
-                                            // TODO: check if ident is already declared
-                                            Types::Generic(ident.to_owned()) // might cause errors idk lmao
-                                        } else if self.in_struct_def && self.previous_func.is_empty() && ident == &self.current_func {
-                                            if self.is_struct_generic {
-                                                Types::TypeDef { type_name: ident.to_owned(), generics: Some(vec![]) }
-                                            } else {
-                                                Types::TypeDef { type_name: ident.to_owned(), generics: None }
-                                            }
-                                        } else if self.in_enum_def && self.previous_func.is_empty() && ident == &self.current_func {
-                                            Types::TypeDef { type_name: ident.to_owned(), generics: None }
-                                        } else {
-                                            self.keyword_to_type(keyword.clone())
-                                        };
-
-                                        if pointer_counter > 0 {
-                                            let tmp_kw = self.create_keyword_pointer(typ, pointer_counter).0;
-                                            typ = self.keyword_to_type(tmp_kw);
-                                        }
-                                    } else {
-                                        name.push_str(&ident);
-                                    }
-                                },
-                            }
-                        } else {
-                            name.push_str(&ident);
-                        }
-                    }
-                },
-                Token::Lbrack => {
-                    brack_rc += 1;
-                    if brack_rc > 0 {
-                        if in_bracks {
-                            params.push(token.clone());
-                        }
-                        in_bracks = true;
-                    }
+            match field {
+                Ok(field) => fields.push(field),
+                Err(e) => self.diagnostics.push(e),
+            }
+        }
 
-                },
-                Token::Rbrack => {
-                    brack_rc -= 1;
-                    if brack_rc == 0 {
-                        in_bracks = false;
-                    } else {
-                        params.push(token.clone());
-                    }
-                },
-                Token::Colon => {
-                    seen_colon += 1;
-                },
-                Token::Equal => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("`=` needs to be inside brackets");
-                        exit(1);
-                    }
-                },
-                Token::SmallerThan => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("`<` needs to be inside brackets");
-                        exit(1);
-                    }
-                },
-                Token::BiggerThan => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("`>` needs to be inside brackets");
-                        exit(1);
-                    }
-                },
-                Token::Exclaim => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("`!` needs to be inside brackets");
-                        exit(1);
-                    }
-                },
-                Token::True => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("expected `true` in brackets");
-                        exit(1);
-                    }
-                },
-                Token::False => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("expected `false` in brackets");
-                        exit(1);
-                    }
-                },
-                Token::And => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("expected `and` in brackets");
-                        exit(1);
-                    }
-                },
-                Token::Or => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err("expected `or` in brackets");
-                        exit(1);
-                    }
-                },
-                Token::Int(symbols) => {
-                    if create_loop && !in_bracks {
-                        loop_modifier = symbols.to_owned();
-                    } else if create_for {
-                        loop_modifier = symbols.to_owned();
-                    } else if create_branch && !in_bracks {
-                        loop_modifier = symbols.to_owned();
-                    } else if in_bracks {
-                        params.push(token.clone());
-                    } else if create_struct {
-                        generic_subtype = Expr::IntLit(symbols.to_string());
-                    } else if let Types::TypeDef { type_name, .. } = typ {
-                        let pass_typs = self.parse_generics_to_pass(symbols, true);
-
-                        typ = Types::TypeDef { type_name: type_name.clone(), generics: Some(pass_typs) };
-                    } else {
-                        self.comp_err(&format!("unexpected integer literal in block definition: {symbols}"));
-                        exit(1);
-                    }
-                },
-                Token::Lsquare => (),
-                Token::Rsquare => (), // these might break stuff idk
-                Token::Macro => {
-                    if self.token_stack.len() < i + 1 {
-                        self.comp_err(&format!("expected more tokens after macro"));
-                        exit(1);
-                    }
+        self.in_enum_def = false;
+        self.span = span;
 
-                    match &self.token_stack[i+1] {
-                        Token::Ident(macro_ident) => {
-                            let macro_res = self.macros_map.get(macro_ident);
-                            let mac = match macro_res {
-                                Some(m) => m.clone(),
-                                None => {
-                                    self.comp_err(&format!("expected macro, got {macro_ident}"));
-                                    exit(1);
-                                }
-                            };
+        self.enums_fields.extend(fields.iter().cloned());
+        let san_name = name.replace(".", "__");
+        self.program_push(Expr::EnumDef {
+            enum_name: Box::new(Expr::EnumName(san_name.clone())),
+            enum_fields: fields.clone(),
+        });
+        self.program_push(Expr::EndStruct(san_name.clone()));
+        self.func_to_vars.remove(name);
 
-                            if let Macros::Inline = mac {
-                                is_inline = true;
-                            }
+        let field_count = Expr::Variable {
+            info: Box::new(Expr::VariableName {
+                typ: Types::Usize,
+                name: format!("{san_name}.field_count"),
+                reassign: false,
+                constant: true,
+                func_arg: false,
+                field_data: (false, false),
+            }),
+            value: Box::new(Expr::IntLit(format!("{}", fields.len()))),
+        };
+        self.enums.push(Expr::EnumDef { enum_name: Box::new(Expr::EnumName(name.clone())), enum_fields: fields });
+        self.enums_fields.push(field_count.clone());
+        self.program_push(field_count);
 
-                            if let Macros::Shared = mac {
-                                is_shared = true;
-                            }
-                        },
-                        _ => (),
-                    }
-                }                Token::Quote => (),
-                Token::Str(word) => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err(&format!("unexpected token String token: `{word}`"));
-                        exit(1);
-                    }
-                },
-                Token::SingleQuote => (),
-                Token::Char(charlit) => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err(&format!("unexpected token Char token: `{charlit}`"));
-                        exit(1);
-                    }
-                },
-                Token::Ampersand => {
-                    if in_bracks {
-                        params.push(token.clone());
-                    } else {
-                        self.comp_err(&format!("unexpected token: {:?}", Token::Ampersand));
-                        exit(1);
-                    }
-                },
-                unexpected => {
-                    self.comp_err(&format!("unexpected token: {unexpected:?}"));
-                    exit(1);
-                },
+        self.previous_func = name.clone();
+        for (func, span) in funcs {
+            self.span = span;
+            if let Err(e) = self.lower_func(func, Some(name)) {
+                self.diagnostics.push(e);
             }
         }
+        self.previous_func.clear();
 
-        if create_for {
-            if (self.in_struct_def || self.in_enum_def) && !self.in_func {
-                self.comp_err("can't use loops inside structs or enums");
-                exit(1);
-            }
+        Ok(())
+    }
 
-            self.create_for(params, loop_modifier);
-            return
+    fn lower_enum_field(&self, field: &String, value: Option<&ast::Expr>) -> Result<Expr, Diagnostic> {
+        if let Some(kw) = self.keyword_map.get(field) {
+            return Err(self.comp_err(&format!("expected identifier, found keyword {kw:?}")));
         }
 
-        if create_loop {
-            if (self.in_struct_def || self.in_enum_def) && !self.in_func {
-                self.comp_err("can't use loops inside structs or enums");
-                exit(1);
-            }
-
-            self.create_loop(params, loop_modifier);
-            return
+        let name = format!("{}.{field}", self.current_func);
+        if !matches!(self.find_ident(name.clone()), Expr::None) {
+            return Err(self.comp_err(&format!("identifier {name:?} already declared")));
         }
 
-        if create_branch {
-            if (self.in_struct_def || self.in_enum_def) && !self.in_func {
-                self.comp_err("can't use branches inside structs or enums");
-                exit(1);
-            }
+        let info = Expr::VariableName { typ: Types::None, name, reassign: false, constant: true, func_arg: false, field_data: (false, false) };
+        let Some(value) = value else {
+            return Ok(info)
+        };
 
-            self.create_branch(keyword, params, &loop_modifier);
-            return
+        match self.lower_value(value)? {
+            value @ Expr::IntLit(_) => Ok(Expr::Variable { info: Box::new(info), value: Box::new(value) }),
+            value => Err(self.comp_err(&format!("enum field must be an integer, found {value:?}"))),
         }
+    }
 
-        if create_case || create_fall {
-            // TODO: check if inside switch as well, could do it inside generator
-            if (self.in_struct_def || self.in_enum_def) && !self.in_func {
-                self.comp_err("can't use cases inside structs or enums");
-                exit(1);
-            }
+    // a declaration, where it ends up depends on if it's a struct field, a global or in a function
+    fn lower_var(&mut self, decl: &ast::VarDecl) -> Result<Expr, Diagnostic> {
+        let mut typ = self.lower_type(&decl.typ, TypeAt::Decl)?;
+        self.check_new_name(&decl.name)?;
 
-            self.create_case(&params, create_fall);
-            return
-        }
+        let name = decl.name.clone();
+        let is_field = self.in_struct_def && !self.in_func;
+        let is_global = !self.in_func && !self.in_struct_def && !self.in_enum_def;
 
-        if create_struct {
-            if self.in_struct_def || self.in_enum_def {
-                self.comp_err("can't create a struct inside an enum or struct");
-                exit(1);
+        let Some(value) = &decl.value else {
+            let info = Expr::VariableName { typ: typ.clone(), name: name.clone(), reassign: false, constant: self.in_enum_def, func_arg: false, field_data: (false, false) };
+            if is_field {
+                return Ok(info)
             }
 
-            if seen_colon != 2 {
-                self.comp_err(&format!("expected assigment operator `:`. did you mean `struct {name} :: {{`?"));
-                exit(1);
+            self.propagate_fields(&name, &typ, false)?;
+            let var = Expr::Variable { info: Box::new(info.clone()), value: Box::new(Expr::None) };
+            if is_global {
+                self.global_vars.push(var);
             } else {
-                self.create_struct_def(name.clone(), generic_subtype, is_shared);
-                return
+                self.push_scope_var(var);
             }
+
+            self.program_push(info.clone());
+            return Ok(info)
+        };
+
+        let value = self.lower_value(value)?;
+        // mismatches are left to the type checker, this only works out what `let` is
+        let (type_checked, inferred) = compare_type_and_expr(&typ, &value, &self.functions);
+        if !type_checked && typ == Types::Let {
+            return Err(self.comp_err(&format!("can't work out the type of {name}, give it one")));
+        }
+        if !matches!(inferred, Types::None) {
+            typ = inferred;
         }
 
-        if create_enum {
-            if self.in_struct_def || self.in_enum_def {
-                self.comp_err("can't create an enum inside an enum or struct");
-                exit(1);
+        if let Types::TypeId = typ {
+            if !decl.constant {
+                return Err(self.comp_err(&format!("type masking must be constant. did you mean `typeid {name} :: <value>`?")));
             }
 
-            if seen_colon != 2 {
-                self.comp_err(&format!("expected assigment operator `:`. did you mean `enum {name} :: {{`?"));
-                exit(1);
-            } else {
-                self.create_enum_def(name.clone());
-                return
+            if let Expr::StructDef { .. } = value {
+                self.handle_type_mask(name.clone(), value.clone())?;
+            } else if let Expr::IntLit(_) = value {
+                self.structures.push(Expr::StructDef {
+                    struct_name: Box::new(Expr::StructName { name: name.clone(), is_shared: false }),
+                    struct_fields: vec![]
+                });
             }
+
+            let expr = Expr::Variable {
+                info: Box::new(Expr::VariableName {
+                    typ,
+                    name: name.replace(".", "__"),
+                    reassign: false,
+                    constant: true,
+                    func_arg: false,
+                    field_data: (false, false)
+                }),
+                value: Box::new(value)
+            };
+            self.program_push(expr.clone());
+            return Ok(expr)
         }
 
-        if create_defer {
-            self.token_stack.clear();
-            self.in_defer = true;
-            self.defer_scope = self.current_scope;
-            self.new_scope(Expr::None);
-            return
+        let info = Expr::VariableName { typ: typ.clone(), name: name.clone(), reassign: false, constant: decl.constant, func_arg: false, field_data: (false, false) };
+        let var = Expr::Variable { info: Box::new(info), value: Box::new(value) };
+        if is_field {
+            return Ok(var)
         }
 
-        if seen_colon == 2 {
-            if self.in_struct_def {
-                // struct hasn't been generated yet so even if we made it here, it would make a
-                // function that has the struct inside which is not what we want
+        self.propagate_fields(&name, &typ, decl.constant)?;
+        if is_global {
+            self.global_vars.push(var.clone());
+        } else {
+            self.push_scope_var(var.clone());
+        }
 
-                if self.previous_func.is_empty() {
-                    self.previous_func = self.current_func.clone();
-                    // self.prev_scope(); not sure to keep this or not
-                    self.create_struct();
-                    self.in_struct_def = true;
-                }
+        self.program_push(var.clone());
+        Ok(var)
+    }
 
-                let namespaced_name = format!("{}.{name}", self.previous_func);
-                self.create_func(typ.clone(), params.clone(), namespaced_name, is_inline);
-                return
-            } else if self.in_enum_def {
-                if self.previous_func.is_empty() {
-                    self.previous_func = self.current_func.clone();
-                    self.create_enum();
-                    self.in_enum_def = true;
-                }
+    fn check_new_name(&self, name: &String) -> Result<(), Diagnostic> {
+        if let Some(kw) = self.keyword_map.get(name) {
+            return Err(self.comp_err(&format!("expected identifier, found keyword {kw:?}")));
+        }
 
-                let namespaced_name = format!("{}.{name}", self.previous_func);
-                self.create_func(typ.clone(), params.clone(), namespaced_name, is_inline);
-                return
-            }
+        match self.find_ident(name.clone()) {
+            Expr::None => Ok(()),
+            Expr::StructDef { .. } | Expr::MacroStructDef { .. } => Err(self.comp_err(&format!("expected identifier, found struct name: {name}"))),
+            Expr::VariableName { .. } => Err(self.comp_err(&format!("variable {name:?} already declared"))),
+            _ => Err(self.comp_err(&format!("identifier {name:?} already declared"))),
+        }
+    }
 
-            self.create_func(typ.clone(), params.clone(), name.clone(), is_inline);
-            return
-        } else if seen_colon > 2 {
-            self.comp_err("unexpected assignment operator `:`");
-            exit(1);
+    // the fields of a new variable are variables too, like v.x for a vec2 v
+    fn propagate_fields(&mut self, name: &str, typ: &Types, is_constant: bool) -> Result<(), Diagnostic> {
+        match typ {
+            Types::TypeDef { type_name, .. } => self.propagate_struct_fields(name.to_string(), type_name.to_string(), false, is_constant),
+            Types::Arr { .. } => self.propagate_struct_fields(name.to_string(), String::from("array"), false, is_constant),
+            Types::Pointer(_) => match pointee(typ) {
+                Types::TypeDef { type_name, .. } => self.propagate_struct_fields(name.to_string(), type_name.to_string(), true, is_constant),
+                Types::Arr { .. } => self.propagate_struct_fields(name.to_string(), String::from("array"), true, is_constant),
+                _ => Ok(()),
+            },
+            _ => Ok(()),
         }
+    }
 
-        self.new_scope(Expr::None);
-        self.program_push(Expr::StartBlock);
+    fn push_scope_var(&mut self, var: Expr) {
+        if let Some(vars) = self.func_to_vars.get_mut(&self.current_func) {
+            vars[self.current_scope].push(var);
+        }
     }
 
-    fn find_global_variable(&self, ident: &String) -> Expr {
-        for var in &self.global_vars {
-            match var {
-                Expr::Variable { info, .. } => {
-                    match *info.clone() {
-                        Expr::VariableName { name, .. } => {
-                            if &name == ident {
-                                return *info.clone();
-                            }
-                        },
-                        _ => (),
-                    }
-                },
-                _ => (),
+    fn lower_assign(&mut self, target: &ast::Expr, value: &ast::Expr) -> Result<(), Diagnostic> {
+        let mut left = self.lower_assign_target(target)?;
+        let right = self.lower_value(value)?;
+
+        if let Expr::VariableName { ref mut typ, ref name, .. } = left {
+            let (_, inferred) = compare_type_and_expr(typ, &right, &self.functions);
+            if let Types::TypeDef { type_name, .. } = &inferred {
+                self.propagate_struct_fields(name.clone(), type_name.clone(), false, false)?;
+            }
+            if !matches!(inferred, Types::None) {
+                *typ = inferred;
             }
         }
 
-        Expr::None
+        let expr = Expr::Variable { info: Box::new(left), value: Box::new(right) };
+        self.push_scope_var(expr.clone());
+        self.program_push(expr);
+        Ok(())
     }
 
-    fn find_variable(&self, ident: &String) -> Expr {
-        let variables_res = self.func_to_vars.get(&self.current_func);
-        let variables = match variables_res {
-            Some(vars) => vars.clone(),
-            None => return Expr::None,
+    // x, x^ or x[i] on the left of a `:`
+    fn lower_assign_target(&self, target: &ast::Expr) -> Result<Expr, Diagnostic> {
+        let (name, index) = match &target.kind {
+            ExprKind::Name(name) => (name, None),
+            ExprKind::Deref(inner) => return Ok(Expr::DerefPointer(Box::new(self.lower_assign_target(inner)?))),
+            ExprKind::Index { base, index } => match &base.kind {
+                ExprKind::Name(name) => (name, Some(index)),
+                _ => return Err(self.comp_err("only variables can be indexed on the left of `:`")),
+            },
+            _ => return Err(self.comp_err("expected a variable on the left of `:`")),
         };
 
-        for vars in &variables[self.current_scope] {
-            match vars {
-                Expr::Variable { info, .. } => {
-                    match *info.clone() {
-                        Expr::VariableName { name, .. } => {
-                            if &name.replace("->", ".") == ident {
-                                return *info.clone();
-                            }
-                        },
-                        _ => (),
-                    }
-                },
-                _ => (),
-            }
-        }
+        let (typ, name, field_data) = match self.find_ident(name.clone()) {
+            Expr::VariableName { typ, name, constant, field_data, .. } => {
+                if constant {
+                    return Err(self.comp_err(&format!("{name} is constant, can't be modified. try `@mut {name};` to make it mutable")));
+                }
+                (typ, name, field_data)
+            },
+            Expr::None => return Err(self.comp_err(&format!("undeclared identifier: {name}"))),
+            unexpected => return Err(self.comp_err(&format!("unexpected {unexpected:?} on left side of variable"))),
+        };
 
-        Expr::None
+        let typ = match index {
+            Some(index) => Types::ArrIndex { arr_typ: Box::new(typ), index_at: self.render_maths(index)? },
+            None => typ,
+        };
+
+        Ok(Expr::VariableName { typ, name, reassign: true, constant: false, func_arg: false, field_data })
     }
 
-    fn find_func(&self, ident: &String) -> Expr {
-        for func in &self.functions {
-            match func {
-                Expr::Func { name, .. } | Expr::MacroFunc { name, .. } => {
-                    if name == ident {
-                        return func.clone()
-                    }
-                },
-                _ => (),
+    // the statements of a block. defers are saved and pasted in backwards at the end of it
+    fn lower_block(&mut self, block: &ast::Block) {
+        let mut defers = Vec::new();
+        for stmt in &block.stmts {
+            let lowered = match &stmt.kind {
+                StmtKind::Defer(deferred) => {
+                    let outer = std::mem::take(&mut self.program);
+                    let lowered = self.lower_deferred(deferred);
+                    defers.push(std::mem::replace(&mut self.program, outer));
+                    lowered
+                },
+                _ => self.lower_stmt(stmt),
+            };
+
+            if let Err(e) = lowered {
+                self.diagnostics.push(e);
             }
         }
 
-        Expr::None
-    }
-
-    fn find_structure(&self, ident: &String) -> Expr {
-        for struc in &self.structures {
-            match struc {
-                Expr::StructDef { struct_name, .. } => {
-                    match *struct_name.clone() {
-                        Expr::StructName { name: acc_name, .. } => {
-                            if &acc_name == ident {
-                                return struc.clone()
-                            }
-                        },
-                        _ => (),
-                    }
-                },
-                Expr::MacroStructDef { struct_name, .. } => {
-                    match *struct_name.clone() {
-                        Expr::MacroStructName { name: acc_name, .. } => {
-                            if &acc_name == ident {
-                                return struc.clone()
-                            }
-                        },
-                        Expr::StructName { name: acc_name, .. } => {
-                            if &acc_name == ident {
-                                return struc.clone()
-                            }
-                        },
-                        _ => (),
-                    }
-                },
-                _ => (),
-            }
+        for deferred in defers.into_iter().rev() {
+            self.program.extend(deferred);
         }
+    }
 
-        Expr::None
+    // the scope was already opened by whatever the block belongs to
+    fn lower_body(&mut self, block: &ast::Block) {
+        self.lower_block(block);
+        self.span = block.close;
+        self.program_push(Expr::EndBlock);
+        self.prev_scope();
     }
 
-    fn find_enum(&self, ident: &String) -> Expr {
-        for enu in &self.enums {
-            match enu {
-                Expr::EnumDef { enum_name, .. } => {
-                    match *enum_name.clone() {
-                        Expr::EnumName(name) => {
-                            if &name == ident {
-                                return enu.clone()
-                            }
-                        },
-                        _ => (),
-                    }
-                },
-                _ => (),
-            }
+    // a deferred block is pasted without its braces
+    fn lower_deferred(&mut self, stmt: &ast::Stmt) -> Result<(), Diagnostic> {
+        match &stmt.kind {
+            StmtKind::Block(block) => {
+                self.new_scope(Expr::None);
+                self.lower_block(block);
+                self.prev_scope();
+                Ok(())
+            },
+            _ => self.lower_stmt(stmt),
         }
-
-        Expr::None
     }
 
-    fn find_enum_fields(&self, ident: &String) -> Expr {
-        for enum_field in &self.enums_fields {
-            match enum_field {
-                Expr::VariableName { name, .. } => {
-                    if name == ident {
-                        return enum_field.clone()
-                    }
+    fn lower_stmt(&mut self, stmt: &ast::Stmt) -> Result<(), Diagnostic> {
+        self.span = stmt.span;
+        match &stmt.kind {
+            StmtKind::Var(decl) => {
+                self.lower_var(decl)?;
+            },
+            StmtKind::Assign { target, value } => self.lower_assign(target, value)?,
+            StmtKind::Expr(expr) => match &expr.kind {
+                ExprKind::Call { name, args } => {
+                    let call = self.lower_call(name, args)?;
+                    self.program_push(call);
                 },
-                Expr::Variable { info, .. } => {
-                    if let Expr::VariableName { name, .. } = *info.clone() {
-                        if &name == ident {
-                            return *info.clone();
-                        }
+                _ => return Err(self.comp_err("expression does nothing, only function calls can be used on their own")),
+            },
+            StmtKind::If(branches) => {
+                for (i, branch) in branches.iter().enumerate() {
+                    self.span = branch.span;
+                    match self.lower_branch(branch, i == 0) {
+                        Ok(()) => self.lower_body(&branch.body),
+                        Err(e) => self.diagnostics.push(e),
                     }
                 }
-                _ => (),
-            }
-        }
-
-        Expr::None
-    }
-
-    fn find_ident(&self, ident: String) -> Expr {
-        let mut found: Expr;
-        found = self.find_variable(&ident);
+            },
+            StmtKind::Switch { cond, cases, close } => {
+                let (params, _) = self.lower_condition(cond, false)?;
+                self.new_scope(Expr::None);
+                self.program_push(Expr::Switch(params));
 
-        if let Expr::None = found {}
-        else {
-            return found
-        }
+                for case in cases {
+                    self.span = case.span;
+                    let params = match &case.cond {
+                        Some(cond) => self.lower_condition(cond, false).map(|(params, _)| params),
+                        None => Ok(vec![Expr::None]),
+                    };
 
-        found = self.find_global_variable(&ident);
-        if let Expr::None = found {}
-        else {
-            return found
-        }
+                    match params {
+                        Ok(params) => {
+                            self.new_scope(Expr::None);
+                            self.program_push(if case.fall { Expr::Fall(params) } else { Expr::Case(params) });
+                            self.lower_body(&case.body);
+                        },
+                        Err(e) => self.diagnostics.push(e),
+                    }
+                }
 
-        found = self.find_func(&ident);
-        if let Expr::None = found {}
-        else {
-            return found
-        }
+                self.span = *close;
+                self.program_push(Expr::EndBlock);
+                self.prev_scope();
+            },
+            StmtKind::Loop { cond, step, body } => {
+                let (condition, side_effect) = match cond {
+                    Some(cond) => self.lower_condition(cond, true)?,
+                    None => (vec![Expr::True], Expr::None),
+                };
+                let modifier = match step {
+                    Some(LoopStep::Up) => "+",
+                    Some(LoopStep::Down) => "-",
+                    None => "_",
+                };
 
-        found = self.find_structure(&ident);
-        if let Expr::None = found {}
-        else {
-            return found
+                self.new_scope(side_effect);
+                self.program_push(Expr::Loop { condition, modifier: Box::new(Expr::IntLit(String::from(modifier))) });
+                self.lower_body(body);
+            },
+            StmtKind::For { iter, elem, elem_ptr, index, body } => {
+                self.lower_for(iter, elem, *elem_ptr, index.as_ref())?;
+                self.lower_body(body);
+            },
+            StmtKind::Defer(deferred) => self.lower_deferred(deferred)?,
+            StmtKind::Return(value) => {
+                let value = match value {
+                    Some(value) => self.lower_value(value)?,
+                    None => Expr::None,
+                };
+                self.program_push(Expr::Return(Box::new(value)));
+            },
+            StmtKind::Break => self.program_push(Expr::Break),
+            StmtKind::Continue => self.program_push(Expr::Continue),
+            StmtKind::Mut(name) => {
+                let vars = self.handle_mutate_func_arg(name)?;
+                self.program_push(Expr::MutateVars(vars));
+            },
+            StmtKind::CEmbed(code) => {
+                let embed = self.c_embed(code)?;
+                self.program_push(embed);
+            },
+            StmtKind::Block(block) => {
+                self.new_scope(Expr::None);
+                self.program_push(Expr::StartBlock);
+                self.lower_body(block);
+            },
         }
 
-        found = self.find_enum(&ident);
-        if let Expr::None = found {}
-        else {
-            return found
-        }
+        Ok(())
+    }
 
-        found = self.find_enum_fields(&ident);
-        if let Expr::None = found {}
-        else {
-            return found
-        }
-        
-        found
-    }
-
-    fn create_func_call(&self, expr: &Expr, params: Vec<Token>) -> Expr {
-        if !self.in_func && !self.in_struct_def {
-            self.comp_err(&format!("cannot call function outside of a scope. function: {:?}", expr));
-            exit(1);
-        }
-
-        let mut nested_func = Expr::None;
-        let mut nested_brack_rs = 0;
-        let mut nested_params = Vec::new();
-        let mut square_rc = 0;
-        let mut intlit_buf = String::new();
-        let mut found_amper = false;
-        let mut pointer_counter = 0;
-
-        let mut expr_params = Vec::new();
-        for (i, param) in params.iter().enumerate() {
-            match param {
-                Token::Int(intlit) => {
-                    if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                    } else if square_rc > 0 {
-                        intlit_buf.push_str(intlit);
-                    } else {
-                        expr_params.push(self.check_intlit(intlit.to_owned()));
-                    }
-                },
-                Token::Char(charlit) => {
-                    if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                    } else if square_rc > 0 {
-                        self.comp_err(&format!("expected integers inside [], found token {:?}", param));
-                        exit(1);
-                    } else {
-                        expr_params.push(Expr::CharLit(charlit.clone()));
-                    }
-                },
-                Token::Str(strlit) => {
-                    if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                    } else if square_rc > 0 {
-                        self.comp_err(&format!("expected integers inside [], found token {:?}", param));
-                        exit(1);
-                    } else {
-                        expr_params.push(Expr::StrLit(strlit.to_owned()));
-                    }
-                },
-                Token::Ident(ident) => {
-                    if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                        continue;
-                    }
-                    if square_rc > 0 {
-                        intlit_buf.push_str(ident);
-                        continue;
-                    }
-
-                    if found_amper {
-                        expr_params.push(self.create_address(ident));
-                        found_amper = false;
-                        continue;
-                    }
-
-
-                    // TODO: LATER CHECK IF ERROR IS NUM TOO LARGE
-                    let is_num = if ident.len() > 2 && (&ident[0..2].to_lowercase() == "0x" || &ident[0..2].to_lowercase() == "0b") {
-                        let ident_num = usize::from_str_radix(&ident[2..], 16);
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
-                    } else {
-                        let ident_num = ident.parse::<f64>();
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
-                    };
-
-                    if is_num {
-                        expr_params.push(Expr::IntLit(ident.to_string()));
-                        continue;
-                    }
-
-                    let expr = self.find_ident(ident.to_string());
-                    if let Expr::None = expr {
-                        let keyword_rs = self.keyword_map.get(ident);
-                        match keyword_rs {
-                            Some(keyword) => {
-                                if pointer_counter > 0 {
-                                    let (kw, _) = self.create_keyword_pointer(Types::TypeId, pointer_counter);
-                                    let typ = self.keyword_to_type(kw);
-                                    expr_params.push(Expr::VariableName { typ, name: ident.clone(), reassign: false, constant: false, field_data: (false, false), func_arg: false });
-                                    pointer_counter = 0;
-                                    continue;
-                                }
-                                let _ = self.keyword_to_type(keyword.clone());
-                                expr_params.push(Expr::VariableName { typ: Types::TypeId, name: ident.clone(), reassign: false, constant: false, field_data: (false, false), func_arg: false })
-                            },
-                            None => {
-                                self.comp_err(&format!("unknown identifier: {}", ident));
-                                exit(1);
-                            }
-                        }
-                    } else if let Expr::StructDef { ref struct_name, .. } = expr {
-                        if pointer_counter > 0 {
-                            let name = if let Expr::StructName { name: n, .. } = *struct_name.clone() { n } else { unreachable!() };
-                            let (kw, _) = self.create_keyword_pointer(Types::TypeDef {
-                                type_name: name.clone(),
-                                generics: None,
-                            }, pointer_counter);
-                            pointer_counter = 0;
-                            let typ = self.keyword_to_type(kw);
-                            expr_params.push(Expr::VariableName { typ, name: ident.clone(), reassign: false, constant: false, field_data: (false, false), func_arg: false });
-                        } else {
-                            expr_params.push(expr);
-                        }
-                    } else if let Expr::Func { .. } = expr {
-                        nested_func = expr;
-                    } else if let Expr::MacroFunc { .. } = expr {
-                        nested_func = expr;
-                    } else {
-                        expr_params.push(expr);
-                    }
-                },
-                Token::Lsquare => {
-                    square_rc += 1;
-                },
-                Token::Rsquare => {
-                    square_rc -= 1;
-                    if square_rc == 0 {
-                        let intlit = self.check_intlit(intlit_buf.clone());
-                        intlit_buf.clear();
-                        expr_params.push(intlit);
-                    }
-                },
-                Token::Lbrack => {
-                    if i != 0 {
-                        nested_brack_rs += 1;
-                    }
-                },
-                Token::Rbrack => {
-                    if i > 0 && nested_brack_rs > 0 {
-                        nested_brack_rs -= 1;
-                        if nested_brack_rs == 0 {
-                            expr_params.push(self.create_func_call(&nested_func, nested_params.clone()));
-                            nested_params.clear();
-                        }
-                    } else {
-                        nested_brack_rs -= 1;
-                    }
-                },
-                Token::Minus => {
-                    if square_rc > 0 {
-                        intlit_buf.push('-');
-                    } else {
-                        self.comp_err(&format!("can't have `-` operator outside of []"));
-                        exit(1);
-                    }
-                },
-                Token::Plus => {
-                    if square_rc > 0 {
-                        intlit_buf.push('+');
-                    } else {
-                        self.comp_err(&format!("can't have `+` operator outside of []"));
-                        exit(1);
-                    }
-                },
-                Token::Multiply => {
-                    if square_rc > 0 {
-                        intlit_buf.push('*');
-                    } else {
-                        self.comp_err(&format!("can't have `*` operator outside of []"));
-                        exit(1);
-                    }
-                },
-                Token::Divide => {
-                    if square_rc > 0 {
-                        intlit_buf.push('/');
-                    } else {
-                        self.comp_err(&format!("can't have `/` operator outside of []"));
-                        exit(1);
-                    }
-                },
-                Token::Ampersand => {
-                    found_amper = true;
-                    if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                    } else if square_rc > 0 {
-                        intlit_buf.push('&');
-                    }
-                },
-                Token::Caret => {
-                    pointer_counter += 1;
-                    if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                    } else if square_rc > 0 {
-                        intlit_buf.push('^');
-                    }
-                },
-                Token::True => {
-                    if square_rc > 0 {
-                        self.comp_err(&format!("can't have `true` inside integer literal"));
-                        exit(1);
-                    } else if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                    } else {
-                        expr_params.push(Expr::True);
-                    }
-                },
-                Token::False => {
-                    if square_rc > 0 {
-                        self.comp_err(&format!("can't have `false` inside integer literal"));
-                        exit(1);
-                    } else if nested_brack_rs > 0 {
-                        nested_params.push(param.clone());
-                    } else {
-                        expr_params.push(Expr::False);
-                    }
-                },
-                Token::Quote => (),
-                Token::SingleQuote => (),
-                _ => {
-                    self.comp_err(&format!("unexpected token: {:?}", param));
-                    exit(1);
-                } 
-            }
-        }
-
-        match expr {
-            Expr::Func { name, .. } | Expr::MacroFunc { name, .. } => {
-                let san_name = name.replace(".", "__");
-
-                match compare_exprs_and_args(&expr_params, &san_name, &self.functions) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        match e.error {
-                            TCError::WrongArgLength(got, expected) => {
-                                self.comp_err(&format!("function {name} expects {expected} arguments, got {got} instead"));
-                                exit(1);
-                            },
-                            TCError::MismatchExprType(wrong_expr, expected_typ) => {
-                                self.comp_err(&format!("function {name} expected argument {} to be of type {expected_typ:?}, got expression {wrong_expr:?}", e.pos));
-                                exit(1);
-                            },
-                            TCError::FuncNotExist => {
-                                self.comp_err(&format!("function {name} does not exist... somehow"));
-                                exit(1);
-                            },
-                            TCError::Custom(custom) => {
-                                self.comp_err(&format!("function {name} {custom}"));
-                                exit(1);
-                            }
-                            _ => (),
-                        }
-                    }
-                }
-
-                return Expr::FuncCall { name: san_name, gave_params: expr_params }
-            },
-            _ => {
-                self.comp_err(&format!("expected a function, got {:?}", expr));
-                exit(1);
-            },
-        }
-    }
-
-    pub fn handle_import_macro(&mut self, mut path: String) -> Expr {
-        if path.chars().nth(path.len()-1).unwrap() == 'h' {
-            self.imports.push(path.to_string());
-            let no_extension = path.split_at(path.len()-2);
-            return Expr::Import(no_extension.0.to_string())
-        }
-
-        if !self.imports.is_empty() {
-            for imp in &self.imports {
-                if imp == &path {
-                    return Expr::None
-                }
-            }
-
-            self.imports.push(path.to_string());
-        } else {
-            self.imports.push(path.to_string());
-        }
-
-        if path.starts_with("base/") {
-            path = format!("{CUR_PATH}/{path}");
-        } else if path.starts_with("vendor/") {
-            let mut split_path = path.splitn(3, '/');
-            let which_vendor = if let (Some(first), Some(second)) = (split_path.next(), split_path.next()) {
-                format!("{first}/{second}")
-            } else {
-                self.comp_err(&format!("unknown vendor target in import: {path}"));
-                exit(1);
-            };
-            let str_to_vendor = format!("{CUR_PATH}/{which_vendor}");
-            let path_to_vendor = Path::new(&str_to_vendor);
-            let copy_options = CopyOptions::new();
-
-            if !Path::new("./vendor").exists() {
-                match fs::create_dir("./vendor") {
-                    Ok(_) => (),
-                    Err(e) => {
-                        self.comp_err(&format!("unable to to create vendor folder in current path with error: {e:?}"));
-                        exit(1);
-                    }
-                }
-            }
-
-            match copy_items(&vec![path_to_vendor], "./vendor", &copy_options) {
-                Ok(_) => (),
-                Err(e) => match e.kind {
-                    fs_extra::error::ErrorKind::AlreadyExists => (),
-                    _ => {
-                        self.comp_err(&format!("unable to copy vendor {path} to current path with error: {e:?}"));
-                        exit(1);
-                    }
-                }
-
-            }
-        }
-
-        let file_res = fs::read_to_string(path.clone());
-        let content = match file_res {
-            Ok(content) => content,
-            Err(err) => {
-                self.comp_err(&format!("unable to read file {path} with error: {err:?}"));
-                exit(1);
-            },
-        };
-
-        let tokens = tokeniser(content);
-        let mut parse = ExprWeights::new(tokens, &path);
-        // REMEMBER TO DO ADD THIS IF YOU NEED INFO TO CARRY OVER FROM OTHER FILES
-        parse.functions = self.functions.clone();
-        parse.imports = self.imports.clone();
-        parse.structures = self.structures.clone();
-        parse.enums = self.enums.clone();
-        parse.enums_fields = self.enums_fields.clone();
-        parse.global_vars = self.global_vars.clone();
-        let mut expressions = parse.parser();
-
-        if self.imports.len() != parse.imports.len() {
-            let mut new = parse.imports[self.imports.len()..].to_vec();
-            self.imports.append(&mut new);
-        }
-
-        if self.functions.len() != parse.functions.len() {
-            let mut new = parse.functions[self.functions.len()..].to_vec();
-            self.functions.append(&mut new);
-        }
-
-        if self.structures.len() != parse.structures.len() {
-            let mut new = parse.structures[self.structures.len()..].to_vec();
-            self.structures.append(&mut new);
-        }
-
-        if self.enums.len() != parse.enums.len() {
-            let mut new = parse.enums[self.enums.len()..].to_vec();
-            self.enums.append(&mut new);
-        }
-
-        if self.enums_fields.len() != parse.enums_fields.len() {
-            let mut new = parse.enums_fields[self.enums_fields.len()..].to_vec();
-            self.enums_fields.append(&mut new);
-        }
-
-        if self.global_vars.len() != parse.global_vars.len() {
-            let mut new = parse.global_vars[self.global_vars.len()..].to_vec();
-            self.global_vars.append(&mut new);
-        }
-
-        self.program.append(&mut expressions);
-        return Expr::None
-    }
-
-    fn handle_array_macro(&mut self, length: &String, tokens: Vec<Token>, mut is_constant: bool) -> Expr {
-        if tokens.len() < 1 {
-            self.comp_err(&format!("expected keyword and identifer (int x), got {tokens:?}"));
-            exit(1);
-        }
-
-        let mut colon_counter = 0;
-        for token in &tokens {
-            match token {
-                Token::Colon => {
-                    colon_counter += 1;
-                    if colon_counter == 2 {
-                        is_constant = true;
-                    }
-                }
-                _ => (),
-            }
-        }
-
-        let keyword: Keyword;
-        match &tokens[0] {
-            Token::Ident(ident) => {
-                let keyword_res = self.keyword_map.get(ident);
-                match keyword_res {
-                    Some(kw) => keyword = kw.clone(),
-                    None => {
-                        if let Expr::StructDef { .. } = self.find_structure(ident) {
-                            keyword = Keyword::TypeDef { type_name: ident.to_string(), generics: None };
-                        } else {
-                            self.comp_err(&format!("expected keyword, got {ident}"));
-                            exit(1);
-                        }
-                    }
-                }
-            },
-            unexpected => {
-                self.comp_err(&format!("expected identifier, got {unexpected:?}"));
-                exit(1);
-            }
-        }
-
-        let name: String;
-        match &tokens[1] {
-            Token::Ident(ident) => {
-                match self.find_ident(ident.to_owned()) {
-                    Expr::None => name = ident.to_owned(),
-                    declared => {
-                        self.comp_err(&format!("identifier {ident} already declared as {declared:?}"));
-                        exit(1);
-                    },
-                }
-            },
-            unexpected => {
-                self.comp_err(&format!("expected identifier, got {unexpected:?}"));
-                exit(1);
-            }
-        }
-
-
-        let typ = self.keyword_to_type(keyword);
-        self.propagate_struct_fields(name.clone(), String::from("array"), false, is_constant);
-        Expr::VariableName {
-            typ: Types::Arr {
-                typ: Box::new(typ),
-                length: length.to_owned(),
-            },
-            name,
-            reassign: false,
-            constant: is_constant,
-            func_arg: false,
-            field_data: (false, false),
-        }
-    }
-
-    fn handle_mutate_func_arg(&mut self, varname: &String) -> Vec<Expr> {
-        for func in self.functions.iter_mut() {
-            if let Expr::Func { params, name: func_name, .. } | Expr::MacroFunc { params, name: func_name, .. } = func {
-                if func_name == &self.current_func {
-                    for param in params {
-                        if let Expr::VariableName { name: param_name, constant, .. } = param {
-                            if param_name == varname {
-                                *constant = false;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        let variables_res = self.func_to_vars.get_mut(&self.current_func);
-        match variables_res {
-            Some(variables) => {
-                let mut new_vars = Vec::new();
-                let mut idxs: Vec<usize> = Vec::new();
-
-                for (idx, vars) in variables[self.current_scope].iter().enumerate() {
-                    if let Expr::Variable { info, value } = vars {
-                        if let Expr::VariableName { typ, name, field_data, func_arg, .. } = *info.clone() {
-                            if name.replace("->", ".").contains(varname) && func_arg {
-                                new_vars.push(Expr::Variable {
-                                    info: Box::new(Expr::VariableName {
-                                        typ: typ.clone(),
-                                        name: name.clone(),
-                                        reassign: false,
-                                        constant: false,
-                                        func_arg: true,
-                                        field_data,
-                                    }),
-                                    value: value.clone(),
-                                });
-                                idxs.push(idx);
-                            }
-                        }
-                    }
-                }
-
-                if !new_vars.is_empty() {
-                    let mut old_vars = Vec::new();
-                    for (i, old_var_idx) in idxs.iter().enumerate() {
-                        let old_var = variables[self.current_scope].remove(*old_var_idx);
-                        variables[self.current_scope].insert(*old_var_idx, new_vars[i].clone());
-
-                        old_vars.push(old_var);
-                    }
-
-                    return old_vars;
-                } else {
-                    self.comp_err(&format!("expected {varname} to be a function argument but wasn't. @mut is used to make an argument mutable"));
-                    exit(1);
-                }
-            },
-            None => {
-                self.comp_err(&format!("expected {varname} to be a function argument but wasn't. @mut is used to make an argument mutable"));
-                exit(1);
-            },
-        };
-    }
-
-    fn handle_macros(&mut self, ident: &String, index: &usize, value: &Vec<Token>) -> Expr {
-        let macro_res = self.macros_map.get(ident);
-        let mac = match macro_res {
-            Some(m) => m.clone(),
-            None => Macros::None,
+    fn lower_branch(&mut self, branch: &ast::Branch, is_first: bool) -> Result<(), Diagnostic> {
+        let Some(cond) = &branch.cond else {
+            self.new_scope(Expr::None);
+            self.program_push(Expr::Else);
+            return Ok(())
         };
-
-        if let Macros::None = mac {
-            self.comp_err(&format!("macro {ident} does not exist"));
-            exit(1);
-        }
-        
-        match mac {
-            Macros::C => {
-                if let Token::Int(embed) = &value[index+2] {
-                    let mut offset_line_num = 0;
-                    for ch in embed.chars() {
-                        if ch == '\n' {
-                            offset_line_num += 1;
-                        }
-                    }
-
-                    self.line_num += offset_line_num;
-                    let cleaned = embed.trim().replace("\r", "")/* .replace("    ", "") */;
-                    return Expr::CEmbed(cleaned)
-                } else {
-                    self.comp_err(&format!("expected [_code_], got {:?}", value[index+2]));
-                    exit(1);
-                }
-            },
-            Macros::Import => {
-                if let Token::Str(path) = &value[index+2] {
-                    return self.handle_import_macro(path.to_string());
-                } else {
-                    self.comp_err(&format!("expected \"path_to_file\", got {:?}", value[index+2]));
-                    exit(1);
-                }
-            },
-            Macros::Default => Expr::DefaultValue,
-            Macros::Garbage => Expr::GarbageValue,
-            Macros::Mut => {
-                if let Token::Ident(ident) = &value[index+1] {
-                    let old_vars = self.handle_mutate_func_arg(ident);
-                    return Expr::MutateVars(old_vars);
-                } else {
-                    self.comp_err(&format!("expected an identifier, got {:?}", value[index+1]));
-                    exit(1);
-                }
-            },
-            _ => {
-                self.comp_err(&format!("macro {mac:?} not reimplemented yet"));
-                exit(1);
-            }
-        }
-    }
-
-    fn handle_left_assign(&mut self, var_info: Vec<Token>, is_constant: bool) -> Expr {
-        let mut keyword = Keyword::None;
-        let mut pointer_counter = 0;
-
-        match &var_info[0] {
-            Token::Ident(ident) => {
-                let keyword_res = self.keyword_map.get(ident);
-                match keyword_res {
-                    Some(kw) => keyword = kw.clone(),
-                    None => {
-                        match self.find_ident(ident.to_string()) {
-                            Expr::EnumDef { .. } => {
-                                keyword = Keyword::TypeDef { type_name: ident.to_string(), generics: None };
-                            },
-                            Expr::StructDef { .. } => {
-                                keyword = Keyword::TypeDef { type_name: ident.to_string(), generics: None };
-                            },
-                            Expr::MacroStructDef { .. } => {
-                                let pass_typs = if let Token::Int(typs) = &var_info[2] {
-                                    self.parse_generics_to_pass(typs, false)
-                                } else {
-                                    self.comp_err(&format!("expected type for generic struct"));
-                                    exit(1);
-                                };
-                                keyword = Keyword::TypeDef { type_name: ident.to_string(), generics: Some(pass_typs) }
-                            },
-                            _ => (),
-                        };
-
-                        if let Keyword::None = keyword {
-                            let found_variable = self.find_ident(ident.to_string());
-                            match found_variable {
-                                Expr::None => {
-                                    if self.in_enum_def {
-                                        let new_name = format!("{}.{ident}", self.current_func);
-
-                                        if !is_constant {
-                                            self.comp_err(&format!("enum field {ident} must be constant if given value. did you mean `{ident} :: <value>`?"));
-                                            exit(1);
-                                        }
-                                        return Expr::VariableName { typ: Types::None, name: new_name, reassign: false, constant: true, field_data: (false, false), func_arg: false }
-                                    }
-                                    self.comp_err(&format!("undeclared identifier: {ident}"));
-                                    exit(1);
-                                },
-                                Expr::VariableName { typ, name, constant, field_data, .. } => {
-                                    if constant || is_constant {
-                                        self.comp_err(&format!("{name} is constant, can't be modified. try `@mut {name};` to make it mutable"));
-                                        exit(1);
-                                    }
-
-                                    if var_info.len() > 1 {
-                                        if let Token::Caret = &var_info[1] {
-                                            let mut deref = Expr::DerefPointer(Box::new(
-                                                Expr::VariableName { 
-                                                    typ,
-                                                    name,
-                                                    reassign: true,
-                                                    constant: false,
-                                                    func_arg: false,
-                                                    field_data 
-                                                }
-                                            ));
-
-                                            for token in &var_info[2..] {
-                                                if let Token::Caret = token {
-                                                    deref = Expr::DerefPointer(Box::new(deref));
-                                                } else {
-                                                    self.comp_err(&format!("unexpected token after ^"));
-                                                    exit(1);
-                                                }
-                                            }
-
-                                            return deref
-                                        }
-                                    }
-
-                                    if var_info.len() > 3 {
-                                        if let Token::Int(intlit) = &var_info[2] {
-                                            let at_expr = self.check_intlit(intlit.to_owned());
-                                            let at = match at_expr {
-                                                Expr::IntLit(lit) => lit,
-                                                _ => String::new(),
-                                            };
-                                            return Expr::VariableName { 
-                                                typ: Types::ArrIndex {
-                                                    arr_typ: Box::new(typ.clone()),
-                                                    index_at: at,
-                                                },
-                                                name: name.to_owned(),
-                                                reassign: true, 
-                                                constant: false,
-                                                func_arg: false,
-                                                field_data
-                                            }
-                                        } else {
-                                            self.comp_err(&format!("expected integer to index array, got {:?}", &var_info[2]));
-                                            exit(1);
-                                        }
-                                    }
-
-                                    return Expr::VariableName { typ: typ.clone(), name: name.to_owned(), reassign: true, constant: false, field_data, func_arg: false }
-                                }
-                                expr => {
-                                    return expr
-                                },
-                            }
-                        }
-                    },
-                }
-            },
-            Token::Lsquare => {
-                // this is for arrays
-                if var_info.len() < 5 {
-                    self.comp_err(&format!("expected more tokens after macro"));
-                    exit(1);
-                }
-
-                if let Token::Int(intlit) = &var_info[1] {
-                    return self.handle_array_macro(intlit, var_info[3..].to_vec(), is_constant);
-                } else {
-                    self.comp_err(&format!("unexpected token: {:?}", var_info[0]));
-                    exit(1);
-                }
-            },
-            Token::Underscore => {
-                self.comp_err(&format!("variables with type void are not supported. you can use a void pointer."));
-                exit(1);
-            },
-            Token::Caret => {
-                if var_info.len() < 3 {
-                    self.comp_err(&format!("expected more tokens after pointer"));
-                    exit(1);
-                }
-
-                if let Token::Ident(ident) = &var_info[1] {
-                    let keyword_res = self.keyword_map.get(ident);
-                    match keyword_res {
-                        Some(kw) => {
-                            let typ = self.keyword_to_type(kw.clone());
-                            keyword = Keyword::Pointer(typ.clone(), typ);
-                        },
-                        None => {
-                            match self.find_structure(ident) {
-                                Expr::StructDef { struct_name, .. } => {
-                                    match *struct_name {
-                                        Expr::StructName { name, .. } => {
-                                            let tmp = Types::TypeDef { type_name: name.clone(), generics: None };
-                                            keyword = Keyword::Pointer(tmp.clone(), tmp);
-                                        },
-                                        _ => {
-                                            self.comp_err(&format!("expected a type after ^, found {ident} instead"));
-                                            exit(1);
-                                        },
-                                    }
-                                },
-                                Expr::MacroStructDef { struct_name, .. } => {
-                                    match *struct_name {
-                                        Expr::MacroStructName { name, .. } => {
-                                            if var_info.len() < 6 {
-                                                self.comp_err(&format!("expected more tokens after pointer"));
-                                                exit(1);
-                                            }
-
-                                            let mut pass_typs = Vec::new();
-                                            if let Token::Int(typs) = &var_info[3] {
-                                                let mut name_buf = String::new();
-                                                for (i, ch) in typs.chars().enumerate() {
-                                                    if ch == ' ' || ch == '\n' || i == typs.len()-1 {
-                                                        if i == typs.len() - 1 {
-                                                            name_buf.push(ch);
-                                                        }
-                                                        let keyword_rs = self.keyword_map.get(&name_buf);
-                                                        match keyword_rs {
-                                                            Some(keyword) => {
-                                                                let typ = self.keyword_to_type(keyword.clone());
-                                                                pass_typs.push(typ);
-                                                            },
-                                                            None => {
-                                                                let found_var = self.find_variable(&name_buf);
-                                                                if let Expr::VariableName { typ, name, .. } = found_var {
-                                                                    if let Types::TypeId = typ {
-                                                                        pass_typs.push(Types::TypeDef { type_name: name, generics: None });
-                                                                        continue;
-                                                                    }
-                                                                }
-                                                                self.comp_err(&format!("expected type, got {name_buf}"));
-                                                                exit(1);
-                                                            },
-                                                        }
-
-                                                        name_buf.clear();
-                                                    } else {
-                                                        name_buf.push(ch);
-                                                    }
-                                                }
-                                            } else {
-                                                self.comp_err(&format!("expected type for generic {name}, got {:?} . e.g. `{name}[type]`", var_info[3]));
-                                                exit(1);
-                                            }
-
-                                            let tmp = Types::TypeDef { type_name: name.clone(), generics: Some(pass_typs) };
-                                            keyword = Keyword::Pointer(tmp.clone(), tmp);
-                                        },
-                                        _ => {
-                                            self.comp_err(&format!("expected a type after ^, found {ident} instead"));
-                                            exit(1);
-                                        }
-                                    }
-                                },
-                                _ => {
-                                    self.comp_err(&format!("expected a type after ^, found {ident} instead"));
-                                    exit(1);
-                                }
-                            }
-                        }
-                    }
-                } else if let Token::Caret = &var_info[1] {
-                    let mut pointer_counter = 2;
-                    for index in 2..var_info.len()-1 {
-                        match &var_info[index] {
-                            Token::Caret => {
-                                pointer_counter += 1;
-                            },
-                            Token::Ident(ident) => {
-                                let keyword_res = self.keyword_map.get(ident);
-                                match keyword_res {
-                                    Some(kw) => {
-                                        (keyword, pointer_counter) = self.create_keyword_pointer(self.keyword_to_type(kw.clone()), pointer_counter);
-                                    },
-                                    None => {
-                                        match self.find_structure(ident) {
-                                            Expr::StructDef { struct_name, .. } => {
-                                                match *struct_name {
-                                                    Expr::StructName { name, .. } => {
-                                                        (keyword, pointer_counter) = self.create_keyword_pointer(Types::TypeDef {
-                                                            type_name: name.clone(),
-                                                            generics: None,
-                                                        }, pointer_counter);
-                                                    },
-                                                    _ => {
-                                                        self.comp_err(&format!("expected a type after ^, found {ident} instead"));
-                                                        exit(1);
-                                                    },
-                                                }
-                                            },
-                                            _ => {
-                                                self.comp_err(&format!("expected a type after ^, found {ident} instead"));
-                                                exit(1);
-                                            }
-                                        }
-                                    }
-                                }
-                            },
-                            Token::Underscore => {
-                                (keyword, pointer_counter) = self.create_keyword_pointer(Types::Void, pointer_counter);
-                            },
-                            unexpected => {
-                                self.comp_err(&format!("expected identifier after ^, got {unexpected:?}"));
-                                exit(1);
-                            }
-                        }
-                    }
-                } else if let Token::Underscore = &var_info[1] {
-                    keyword = Keyword::Pointer(Types::Void, Types::Void);
-                } else {
-                    self.comp_err(&format!("expected identifier after ^, got {:?}", &var_info[2]));
-                    exit(1);
-                }
-            },
-            Token::Dollar => {
-                if var_info.len() < 3 {
-                    self.comp_err(&format!("expected more tokens after `$`"));
-                    exit(1);
-                }
-
-                if let Token::Ident(typeid_ident) = &var_info[1] {
-                    let found_var = self.find_variable(typeid_ident);
-                    if let Expr::VariableName { typ, name, .. } = found_var {
-                        if let Types::TypeId = typ {
-                            keyword = Keyword::Generic(name);
-                        } else {
-                            self.comp_err(&format!("expected {typeid_ident:?} to be typeid. instead is {typ:?}"));
-                            exit(1);
-                        }
-                    } else {
-                        self.comp_err(&format!("undefined typeid: {typeid_ident}"));
-                        exit(1);
-                    }
-                }
-
-                let typ = self.keyword_to_type(keyword.clone());
-                if let Token::Ident(varname) = &var_info[2] {
-                    return Expr::VariableName { typ, name: varname.to_owned(), reassign: false, constant: false, field_data: (false, false), func_arg: false }
-                }
-            },
-            _ => {
-                self.comp_err(&format!("unexpected token: {:?}", var_info[0]));
-                exit(1);
-            }
-        }
-
-        let name = match &var_info[var_info.len()-1] {
-            Token::Ident(ident) => ident,
-            unexpected => {
-                self.comp_err(&format!("unexpected token in variable name: {:?}", unexpected));
-                exit(1);
-            }
-        };
-
-        let keyword_res = self.keyword_map.get(name);
-        match keyword_res {
-            Some(k) => {
-                self.comp_err(&format!("expected identifier, found keyword {k:?}"));
-                exit(1);
-            },
-            None => (),
-        };
-
-        if let Expr::StructDef { .. } = self.find_structure(name) {
-            self.comp_err(&format!("expected identifier, found struct name: {name}"));
-            exit(1);
-        }
-
-        if name == &String::from(".") {
-            self.comp_err(&format!("can't name a variable `.`"));
-            exit(1);
-        }
-
-        let found_expr = self.find_ident(name.clone());
-        if let Expr::None = found_expr {
-            match keyword {
-                Keyword::TypeDef {type_name: ref user_def, ..} => {
-                    self.propagate_struct_fields(name.to_string(), user_def.to_string(), false, is_constant);
-                },
-                Keyword::Pointer(.., ref last_typ) => {
-                    if let Types::TypeDef {type_name: user_def, ..} = last_typ {
-                        self.propagate_struct_fields(name.to_string(), user_def.to_string(), true, is_constant);
-                    }
-                },
-                _ => (),
-            }
-
-            let typ = self.keyword_to_type(keyword.clone());
-            return Expr::VariableName { typ, name: name.to_owned(), reassign: false, constant: is_constant, field_data: (false, false), func_arg: false };
-        } else if let Expr::VariableName { typ, name, constant, field_data, .. } = found_expr {
-            if constant || is_constant {
-                self.comp_err(&format!("{name} is constant, can't be modified. try `@mut {name};` to make it mutable"));
-                exit(1);
-            }
-            match keyword {
-                Keyword::None => {
-                    return Expr::VariableName { typ, name, reassign: true, constant: false, field_data, func_arg: false };
-                },
-                _ => {
-                    self.comp_err(&format!("variable {:?} already declared", name));
-                    exit(1);
-                }
-            }
-        } else if let Expr::StructDef {..}  = found_expr {
-            self.comp_err(&format!("{name} already declared as a struct"));
-            exit(1);
-        } else {
-            self.comp_err(&format!("unexpected expression: {:?}", found_expr));
-            exit(1);
-        }
-    }
-
-    fn check_arraylit(&self, params: &Vec<Token>) -> Vec<Expr> {
-        let mut expr_params = Vec::new();
-        let mut brack_rc = 0;
-        let mut has_func = Expr::None;
-        
-        let mut in_func_params = false;
-        let mut func_params = Vec::new();
-
-        for param in params {
-            match param {
-                Token::Ident(ident) => {
-                    if in_func_params {
-                        func_params.push(param.clone());
-                        continue;
-                    }
-
-                    // TODO: LATER CHECK IF ERROR IS NUM TOO LARGE
-                    if ident.len() > 2 && (&ident[0..2].to_lowercase() == "0x" || &ident[0..2].to_lowercase() == "0b") {
-                        let ident_num = usize::from_str_radix(&ident[2..], 16);
-                        match ident_num {
-                            Ok(_) => {
-                                expr_params.push(Expr::IntLit(ident.to_owned()));
-                                continue;
-                            },
-                            Err(_) => (),
-                        }
-                    } else {
-                        let ident_num = ident.parse::<f64>();
-                        match ident_num {
-                            Ok(_) => {
-                                expr_params.push(Expr::IntLit(ident.to_owned()));
-                                continue;
-                            },
-                            Err(_) => (),
-                        }
-                    }
-
-                    let keyword_res = self.keyword_map.get(ident);
-                    match keyword_res {
-                        Some(kw) => {
-                            self.comp_err(&format!("expected literal or identifier, found keyword {kw:?}"));
-                            exit(1);
-                        },
-                        None => (),
-                    }
-
-                    match self.find_ident(ident.to_owned()) {
-                        Expr::None => {
-                            self.comp_err(&format!("expected declared identifier, got none: {ident}"));
-                            exit(1);
-                        },
-                        Expr::StructDef { .. } => {
-                            self.comp_err(&format!("expected declared identifier, got struct definiton: {ident}"));
-                            exit(1);
-                        },
-                        Expr::Func { typ, params, name, is_inline } => {
-                            has_func = Expr::Func { typ, params, name, is_inline }
-                        },
-                        Expr::MacroFunc { typ, params, name } => {
-                            has_func = Expr::MacroFunc { typ, params, name }
-                        }
-                        found => {
-                            expr_params.push(found);
-                        }
-                    }
-                },
-                Token::Int(intlit) => {
-                    if in_func_params {
-                        func_params.push(param.clone());
-                        continue;
-                    }
-                    expr_params.push(self.check_intlit(intlit.to_owned()));
-                },
-                Token::True => {
-                    if in_func_params {
-                        func_params.push(param.clone());
-                        continue;
-                    }
-                    expr_params.push(Expr::True);
-                },
-                Token::False => {
-                    if in_func_params {
-                        func_params.push(param.clone());
-                        continue;
-                    }
-                    expr_params.push(Expr::False);
-                },
-                Token::Lbrack => {
-                    brack_rc += 1;
-                    if let Expr::Func { .. } = has_func {
-                        in_func_params = true;
-                    }
-                },
-                Token::Rbrack => {
-                    brack_rc -= 1;
-                    if brack_rc == 0 {
-                        expr_params.push(self.create_func_call(&has_func, func_params.clone()));
-                        func_params.clear();
-                        in_func_params = false;
-                    }
-                },
-                Token::Quote => (),
-                Token::Str(word) => {
-                    if in_func_params {
-                        func_params.push(param.clone());
-                        continue;
-                    }
-                    expr_params.push(Expr::StrLit(word.to_owned()));
-                },
-                unexpected => {
-                    self.comp_err(&format!("unexpected token: {unexpected:?}"));
-                    exit(1);
-                }
-            }
-        }
-
-        expr_params
-    }
-
-    fn create_address(&self, ident: &String) -> Expr {
-        let found_ident = self.find_ident(ident.clone());
-        match found_ident {
-            Expr::VariableName { ref name, constant, .. } => {
-                if constant {
-                    self.comp_err(&format!("using constant \"{name}\" as address dequalifies it to a variable, did you mean for \"{name}\" to be a variable?"));
-                    exit(1)
-                }
-                return Expr::Address(Box::new(found_ident))
-            },
-            unexpected => {
-                self.comp_err(&format!("unexpected expression {unexpected:?} when getting address of identifier: {ident:?}"));
-                exit(1);
-            }
-        }
-    }
-
-    fn handle_right_assign(&mut self, value: Vec<Token>, is_right: bool) -> Expr {
-        let mut buffer = Vec::new();
-        let mut params = Vec::new();
-        let mut brack_rc = 0;
-        let mut pipe_rc = 0;
-        let mut pointer_counter = 0;
-        let mut colon_counter = 0;
-        let mut found_macro = false;
-        let mut found_amper = false;
-
-        let mut returning = false;
-        let mut defering = false;
-
-        let mut create_generic = false;
-        let mut is_constant = false;
-        let mut array_lens = Vec::new();
-
-        for (i, token) in value.iter().enumerate() {
-            // handle right bracket occurences
-            match token {
-                Token::Lbrack => {
-                    brack_rc += 1;
-                    params.push(token.clone());
-                },
-                Token::Rbrack => {
-                    brack_rc -= 1;
-                    params.push(token.clone());
-
-                    if brack_rc > 0 {
-                        continue;
-                    } else if pipe_rc == 1 {
-                        continue;
-                    } else {
-                        if buffer.len() > 1 {
-                            self.comp_err(&format!("unexpected multiple expressions outside of parameters: {:?}", buffer));
-                            exit(1);
-                        } else if (self.in_struct_def || self.in_enum_def) && !self.in_func {
-                            // self.comp_err(&format!("can't call function {:?} inside struct or enum", buffer[0]));
-                            // exit(1);
-                            return self.create_func_call(&buffer[0], params);
-                        } else if returning {
-                            buffer[0] = self.create_func_call(&buffer[0], params.clone());
-                        } else {
-                            return self.create_func_call(&buffer[0], params);
-                        }
-                    }
-                },
-                Token::Pipe => {
-                    pipe_rc += 1;
-                },
-                Token::Caret => {
-                    pointer_counter += 1;
-                },
-                Token::Colon => {
-                    colon_counter += 1;
-                    if colon_counter == 2 {
-                        is_constant = true;
-                    }
-                },
-                _ => {
-                    if brack_rc > 0 {
-                        params.push(token.clone());
-                        continue;
-                    } else if pipe_rc == 1 {
-                        params.push(token.clone());
-                        continue;
-                    }
-                },
-            }
-
-            match token {
-                Token::Int(intlit) => {
-                    // TODO: ARRAYS INDEXING WITH [i+1] WILL NOT WORK WITH THIS, edit: this might
-                    // be wrong, double check later
-                    if !is_right && !returning {
-                        array_lens.push(intlit.clone());
-                    } else {
-                        buffer.push(self.check_intlit(intlit.to_string()));
-                    }
-                },
-                Token::Char(charlit) => {
-                    buffer.push(Expr::CharLit(charlit.clone()));
-                },
-                Token::Str(strlit) => {
-                    buffer.push(Expr::StrLit(strlit.to_string()));
-                },
-                Token::Ident(ident) => {
-                    if found_macro {
-                        if (self.in_struct_def && !self.in_func) || self.in_defer {
-                            let mac = self.handle_macros(ident, &i, &value);
-
-                            // originally only c embeds and the old array macro was expected and
-                            // since these were whole liners, we would push to expr_stack but
-                            // Expr::DefaultValue is to be used as only a value so we need to make
-                            // sure it's returned
-                            if let Expr::DefaultValue = mac {
-                                return self.handle_macros(ident, &i, &value);
-                            }
-                            self.expr_stack.push(mac);
-                            return Expr::None;
-                        }
-                        return self.handle_macros(ident, &i, &value);
-                    } else if found_amper {
-                        buffer.push(self.create_address(ident));
-                        found_amper = false;
-                        continue;
-                    }
-
-                    // check if this is an expr without a :
-                    if !is_right {
-                        let keyword_res = self.keyword_map.get(ident);
-
-                        if !array_lens.is_empty() {
-                            if (self.in_struct_def && !self.in_func) || self.in_defer {
-                                // TODO: make this work with multi dimensional arrays
-                                let mac = self.handle_array_macro(&array_lens[0], value[i..].to_vec(), is_constant);
-                                self.expr_stack.push(mac);
-                                return Expr::None;
-                            }
-                            return self.handle_array_macro(&array_lens[0], value[i..].to_vec(), is_constant);
-                        }
-
-                        match keyword_res {
-                            Some(k) => {
-                                let mut keyword = k.clone();
-                                if let Keyword::Break = k {
-                                    return Expr::Break
-                                } else if let Keyword::Continue = k {
-                                    return Expr::Continue;
-                                } else if let Keyword::Return = k {
-                                    returning = true;
-                                    continue;
-                                } else if let Keyword::Defer = k {
-                                    defering = true;
-                                    continue;
-                                } else if pointer_counter > 0 {
-                                    (keyword, _) = self.create_keyword_pointer(self.keyword_to_type(k.clone()), pointer_counter);
-                                }
-
-                                // anything that needs an identifier after the keyword is handled
-                                // below
-                                if i + 1 == value.len() {
-                                    self.comp_err(&format!("expected identifier after keyword {k:?}, got nothing"));
-                                    exit(1);
-                                }
-
-                                if (self.in_struct_def && !self.in_func) || self.in_defer {
-                                    let expr = self.create_define_var(keyword, value[i+1].clone(), vec![]);
-                                    self.expr_stack.push(expr);
-                                    return Expr::None
-                                } else {
-                                    return self.create_define_var(keyword, value[i+1].clone(), vec![]);
-                                }
-                            },
-                            None => {
-                                let found_ident = self.find_ident(ident.to_owned());
-                                if returning {}
-                                else if create_generic {
-                                    if let Expr::VariableName { typ, name, .. } = found_ident {
-                                        if let Types::TypeId = typ {
-                                            let mut keyword = Keyword::Generic(name);
-
-                                            if i + 1 == value.len() {
-                                                self.comp_err(&format!("expected identifier after keyword {keyword:?}, got nothing"));
-                                                exit(1);
-                                            }
-
-                                            if pointer_counter > 0 {
-                                                (keyword, _) = self.create_keyword_pointer(self.keyword_to_type(keyword), pointer_counter);
-                                            }
-
-                                            if self.in_struct_def && !self.in_func {
-                                                // i should've commented this earlier, don't
-                                                // remember why this is needed lmao
-                                                let expr = if i + 2 < value.len() {
-                                                    let slice = value[i+1..value.len()-1].to_vec();
-                                                    let last = value.last().unwrap().clone();
-                                                    self.create_define_var(keyword, last, slice)
-                                                } else {
-                                                    self.create_define_var(keyword, value[i+1].clone(), vec![])
-                                                };
-                                                self.expr_stack.push(expr);
-                                                return Expr::None
-                                            } else {
-                                                return self.create_define_var(keyword, value[i+1].clone(), vec![]);
-                                            }
-                                        } else {
-                                            self.comp_err(&format!("expected typeid after `$`, got {typ:?}"));
-                                            exit(1);
-                                        }
-                                    }
-                                } else if let Expr::StructDef { .. } = found_ident {
-                                    let mut k = Keyword::TypeDef {
-                                        type_name: ident.clone(), 
-                                        generics: None, // watch this, changed from Some(vec![])
-                                    };
-
-                                    if pointer_counter > 0 {
-                                        (k, _) = self.create_keyword_pointer(self.keyword_to_type(k), pointer_counter);
-                                    }
-
-                                    if self.in_struct_def && !self.in_func {
-                                        let expr = self.create_define_var(k, value[i+1].clone(), vec![]);
-                                        self.expr_stack.push(expr);
-                                        return Expr::None;
-                                    }
-
-                                    return self.create_define_var(k, value[i+1].clone(), vec![]);
-                                } else if let Expr::MacroStructDef { .. } = found_ident {
-                                    let mut k = Keyword::TypeDef {
-                                        type_name: ident.clone(), 
-                                        generics: Some(vec![])
-                                    };
-
-                                    if pointer_counter > 0 {
-                                        (k, _) = self.create_keyword_pointer(self.keyword_to_type(k), pointer_counter);
-                                    }
-
-                                    let slice = value[i+1..value.len()-1].to_vec();
-                                    let last = value.last().unwrap().clone();
-
-                                    if self.in_struct_def && !self.in_func {
-                                        let expr = self.create_define_var(k, last, slice);
-                                        self.expr_stack.push(expr);
-                                        return Expr::None;
-                                    }
-
-                                    return self.create_define_var(k, last, slice)
-                                } else if let Expr::Func { .. } | Expr::MacroFunc { .. } = found_ident {
-                                    if self.in_defer || defering {
-                                        if defering {
-                                            self.one_defer = true;
-                                            self.defer_scope = self.current_scope;
-                                        }
-                                        let expr = self.create_func_call(&found_ident, value[i+1..].to_vec());
-                                        self.expr_stack.push(expr);
-                                        return Expr::None;
-                                    }
-                                    return self.create_func_call(&found_ident, value[i+1..].to_vec());
-                                } else if let Expr::EnumDef { .. } = found_ident {
-                                    let mut k = Keyword::TypeDef {
-                                        type_name: ident.clone(), 
-                                        generics: None,
-                                    };
-
-                                    if pointer_counter > 0 {
-                                        (k, _) = self.create_keyword_pointer(self.keyword_to_type(k), pointer_counter);
-                                    }
-
-                                    if self.in_struct_def && !self.in_func {
-                                        let expr = self.create_define_var(k, value[i+1].clone(), vec![]);
-                                        self.expr_stack.push(expr);
-                                        return Expr::None;
-                                    }
-
-                                    return self.create_define_var(k, value[i+1].clone(), vec![]);
-                                } else {
-                                    // if in struct it reference it's own name
-                                    if self.in_struct_def && ident == &self.current_func {
-                                        let mut k = if self.is_struct_generic {
-                                            Keyword::TypeDef {
-                                                type_name: format!("struct {}", self.current_func), 
-                                                generics: Some(vec![])
-                                            } 
-                                        } else {
-                                            Keyword::TypeDef {
-                                                type_name: format!("struct {}", self.current_func), 
-                                                generics: None
-                                            } 
-                                        };
-                                        if pointer_counter > 0 {
-                                            k = self.create_keyword_pointer(self.keyword_to_type(k), pointer_counter).0;
-                                        }
-
-                                        let expr = self.create_define_var(k, value[i+1].clone(), vec![]);
-                                        self.expr_stack.push(expr);
-                                        return Expr::None
-                                    } else if self.in_enum_def {
-                                        let new_value = match &value[i] {
-                                            Token::Ident(prev_name) => {
-                                                Token::Ident(format!("{}.{prev_name}", self.current_func))
-                                            },
-                                            _ => unreachable!()
-                                        };
-                                        let expr = self.create_define_var(Keyword::None, new_value, vec![]);
-                                        self.expr_stack.push(expr);
-                                        return Expr::None
-                                    }
-                                    self.comp_err(&format!("unknown identifier: {ident}"));
-                                    exit(1);
-                                }
-                            },
-                        }
-                    }
-
-                    // TODO: LATER CHECK IF ERROR IS NUM TOO LARGE
-                    let is_num = if ident.len() > 2 && (&ident[0..2].to_lowercase() == "0x" || &ident[0..2].to_lowercase() == "0b") {
-                        let ident_num = usize::from_str_radix(&ident[2..], 16);
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
-                    } else {
-                        let ident_num = ident.parse::<f64>();
-                        match ident_num {
-                            Ok(_) => true,
-                            Err(_) => false,
-                        }
-                    };
-
-                    if is_num {
-                        buffer.push(Expr::IntLit(ident.to_string()));
-                        continue;
-                    }
-
-                    let expr = self.find_ident(ident.to_string());
-                    match expr {
-                        Expr::VariableName { ref typ, ref name, reassign, constant: _, field_data, func_arg } => {
-                            buffer.push(Expr::VariableName { typ: typ.clone(), name: name.to_owned(), reassign, constant: is_constant, field_data, func_arg })
-                        },
-                        Expr::None => {
-                            let typ_kw_res = self.keyword_map.get(ident);
-                            match typ_kw_res {
-                                Some(typ_kw) => {
-                                    let typ = self.keyword_to_type(typ_kw.clone());
-                                    match typ {
-                                        Types::None => {
-                                            self.comp_err(&format!("unknown identifier: {}", ident));
-                                            exit(1);
-                                        },
-                                        _ => {
-                                            buffer.push(Expr::IntLit(format!("{ident}")));
-                                        },
-                                    }
-                                },
-                                None => {
-                                    self.comp_err(&format!("unknown identifier: {}", ident));
-                                    exit(1);
-                                }
-                            }
-                        },
-                        _ => buffer.push(expr),
-                    }
-                },
-                Token::Lbrack => {
-                    // handled above
-                },
-                Token::Rbrack => {
-                    // handled above
-                },
-                Token::Pipe => {
-                    // handled above
-                },
-                Token::Macro => {
-                    found_macro = true;
-                },
-                Token::Dollar => {
-                    create_generic = true;
-                },
-                Token::Quote => (),
-                Token::SingleQuote => (),
-                Token::Lsquare => (),
-                Token::Rsquare => (),
-                Token::Caret => {
-                    if is_right {
-                        let top_expr_res = buffer.pop();
-                        let top_expr = match top_expr_res {
-                            Some(ex) => ex,
-                            None => {
-                                self.comp_err(&format!("unexpected ^ operator as there's no expression in front it."));
-                                exit(1);
-                            }
-                        };
-
-                        buffer.push(Expr::DerefPointer(Box::new(top_expr)));
-                    }
-                },
-                Token::Ampersand => {
-                    found_amper = true;
-                },
-                Token::Underscore => {
-                    if !is_right {
-                        if pointer_counter > 0 {
-                            let keyword = self.create_keyword_pointer(Types::Void, pointer_counter).0;
-                            if i + 1 == value.len() {
-                                self.comp_err(&format!("expected identifier after keyword {keyword:?}, got nothing"));
-                                exit(1);
-                            }
-
-                            if self.in_struct_def || self.in_defer {
-                                let expr = self.create_define_var(keyword, value[i+1].clone(), vec![]);
-                                self.expr_stack.push(expr);
-                                return Expr::None
-                            } else {
-                                return self.create_define_var(keyword, value[i+1].clone(), vec![]);
-                            }
-                        } else {
-                            self.comp_err(&format!("can't make void type. void pointers are allowed"));
-                            exit(1);
-                        }
-                    } else {
-                        self.comp_err(&format!("can't make void type. void pointers are allowed"));
-                        exit(1);
-                    }
-                },
-                Token::True => {
-                    buffer.push(Expr::True);
-                },
-                Token::False => {
-                    buffer.push(Expr::False);
-                },
-                _ => {
-                    self.comp_err(&format!("unexpected token: {:?}", token));
-                    exit(1);
-                }
-            }
-        }
-
-        if buffer.is_empty() && params.is_empty() {
-            if returning {
-                return Expr::Return(Box::new(Expr::None));
-            }
-
-            self.comp_err(&format!("expected a token, got none"));
-            exit(1);
-        }
-
-        if returning && buffer.len() <= 1 {
-            if self.in_struct_def && !self.in_func {
-                self.comp_err(&format!("can't use return inside struct"));
-                exit(1);
-            }
-
-            return Expr::Return(Box::new(buffer[0].clone()))
-        }
-        
-        if buffer.len() > 1 {
-            match &buffer[0] {
-                Expr::Address(varname) => {
-                    if let Expr::VariableName { typ, name, constant, field_data, .. } = *varname.clone() {
-                        if let Types::Arr { .. } = typ {
-                        } else if let Types::Pointer { .. } = typ {
-                        } else {
-                            self.comp_err(&format!("couldn't handle expressions: {:?}", buffer));
-                            exit(1);
-                        }
-
-                        if let Expr::IntLit(intlit) = &buffer[1] {
-                            let expr = Expr::Address(Box::new(Expr::VariableName {
-                                typ: Types::ArrIndex {
-                                    arr_typ: Box::new(typ.clone()),
-                                    index_at: intlit.to_owned()
-                                },
-                                name: name.to_owned(),
-                                reassign: false,
-                                constant,
-                                func_arg: false,
-                                field_data
-                            }));
-                            if returning {
-                                return Expr::Return(Box::new(expr))
-                            } else {
-                                return expr;
-                            }
-                        } else {
-                            self.comp_err(&format!("couldn't handle expressions: {:?}", buffer));
-                            exit(1);
-                        }
-                    }
-                }
-                Expr::VariableName { typ, name, constant, field_data, .. } => {
-                    if let Types::Arr { .. } = typ {
-                    } else if let Types::Pointer { .. } = typ {
-                    } else {
-                        self.comp_err(&format!("couldn't handle expressions: {:?}", buffer));
-                        exit(1);
-                    }
-
-                    if let Expr::IntLit(intlit) = &buffer[1] {
-                        let expr = Expr::VariableName {
-                            typ: Types::ArrIndex {
-                                arr_typ: Box::new(typ.clone()),
-                                index_at: intlit.to_owned()
-                            },
-                            name: name.to_owned(),
-                            reassign: false,
-                            constant: constant.to_owned(),
-                            func_arg: false,
-                            field_data: (field_data.0, field_data.1),
-                        };
-                        if returning {
-                            return Expr::Return(Box::new(expr))
-                        } else {
-                            return expr;
-                        }
-                    } else {
-                        self.comp_err(&format!("couldn't handle expressions: {:?}", buffer));
-                        exit(1);
-                    }
-                },
-                _ => {
-                    self.comp_err(&format!("couldn't handle expressions: {:?}", buffer));
-                    exit(1);
-                },
-            }
-        }
-
-        if !params.is_empty() {
-            let arrlit = self.check_arraylit(&params);
-            return Expr::ArrayLit(arrlit)
-        }
-
-        buffer[0].clone()
-    }
-
-    fn propagate_struct_fields(&mut self, fname: String, user_def: String, is_ptr: bool, is_constant: bool) {
-        match self.find_ident(user_def) {
-            Expr::StructDef { struct_fields, .. } | Expr::MacroStructDef { struct_fields, .. } => {
-                for field in struct_fields {
-                    match field {
-                        Expr::Variable { info, .. } => {
-                            if let Expr::VariableName { typ, name, field_data, .. } = *info {
-                                let new_name = if is_ptr {
-                                    format!("{fname}->{name}")
-                                } else {
-                                    format!("{fname}.{name}")
-                                };
-                                if let Types::TypeDef { ref type_name, .. } = typ {
-                                    self.propagate_struct_fields(new_name.clone(), type_name.to_string(), field_data.1, is_constant);
-                                } else if let Types::Arr { .. } = typ {
-                                    self.propagate_struct_fields(new_name.clone(), String::from("array"), field_data.1, is_constant);
-                                }
-
-                                let new_expr = Expr::Variable {
-                                    info: Box::new(Expr::VariableName {
-                                        typ,
-                                        name: new_name,
-                                        reassign: false,
-                                        constant: is_constant, // CAN BREAK, testing rn
-                                        func_arg: false,
-                                        field_data: (true, is_ptr),
-                                    }),
-                                    value: Box::new(Expr::None),
-                                };
-                                if !self.in_enum_def && !self.in_func && !self.in_struct_def {
-                                    self.global_vars.push(new_expr.clone());
-                                } else if let Some(vars) = self.func_to_vars.get_mut(&self.current_func) {
-                                    vars[self.current_scope].push(new_expr);
-                                }
-                            }
-                        },
-                        Expr::VariableName { typ, name, field_data, .. } => {
-                            let new_name = if is_ptr {
-                                format!("{fname}->{name}")
-                            } else {
-                                format!("{fname}.{name}")
-                            };
-                            if let Types::TypeDef { ref type_name, .. } = typ {
-                                self.propagate_struct_fields(new_name.clone(), type_name.to_string(), field_data.1, is_constant);
-                            } else if let Types::Arr { .. } = typ {
-                                self.propagate_struct_fields(new_name.clone(), String::from("array"), field_data.1, is_constant);
-                            }
-
-                            let new_expr = Expr::Variable {
-                                info: Box::new(Expr::VariableName {
-                                    typ,
-                                    name: new_name,
-                                    reassign: false,
-                                    constant: is_constant, // CAN BREAK, testing rn
-                                    func_arg: false,
-                                    field_data: (true, is_ptr),
-                                }),
-                                value: Box::new(Expr::None),
-                            };
-                            if !self.in_enum_def && !self.in_func && !self.in_struct_def {
-                                self.global_vars.push(new_expr.clone());
-                            } else if let Some(vars) = self.func_to_vars.get_mut(&self.current_func) {
-                                vars[self.current_scope].push(new_expr);
-                            }
-                        },
-                        _ => (),
-                    }
-                }
-            },
-            // Enums will be checked for propagation but they've already been handling
-            Expr::EnumDef { .. } => (),
-            unexpected => {
-                self.comp_err(&format!("unexpected expression {unexpected:?} during field propagation"));
-                exit(1);
-            },
-        }
+
+        let captures = match &branch.capture {
+            Some(capture) => self.lower_capture(cond, capture)?,
+            None => vec![Expr::None],
+        };
+        let (params, _) = self.lower_condition(cond, false)?;
+        let capture = Box::new(captures.last().cloned().unwrap_or(Expr::None));
+
+        self.new_scope_vars(captures);
+        self.program_push(if is_first { Expr::If(params, capture) } else { Expr::OrIf(params, capture) });
+        Ok(())
     }
 
-    fn create_define_var(&mut self, kw: Keyword, ident: Token, generics: Vec<Token>) -> Expr {
-        let expr: Expr;
-        let fname: String;
-        let mut pass_typs: Vec<Types> = Vec::new();
-
-        for gen in generics {
-            match gen {
-                Token::Lsquare => (),
-                Token::Rsquare => (),
-                Token::Int(typs) => {
-                    pass_typs = self.parse_generics_to_pass(&typs, false);
-                },
-                unexpected => {
-                    self.comp_err(&format!("unexpected token {unexpected:?}. expecting `typedef[__type__] varname;`"));
-                    exit(1);
-                },
-            }
-        }
+    // `if (opt) [value]` gives the value of an option when it isn't none
+    fn lower_capture(&mut self, cond: &ast::Expr, capture: &str) -> Result<Vec<Expr>, Diagnostic> {
+        let ExprKind::Name(variable) = &cond.kind else {
+            return Err(self.comp_err("can only capture from a single variable"));
+        };
 
-        match ident {
-            Token::Ident(word) => {
-                // TODO: LATER CHECK IF ERROR IS NUM TOO LARGE
-                let is_num = if word.len() > 2 && (&word[0..2].to_lowercase() == "0x" || &word[0..2].to_lowercase() == "0b") {
-                    let ident_num = usize::from_str_radix(&word[2..], 16);
-                    match ident_num {
-                        Ok(_) => true,
-                        Err(_) => false,
-                    }
-                } else {
-                    let ident_num = word.parse::<f64>();
-                    match ident_num {
-                        Ok(_) => true,
-                        Err(_) => false,
-                    }
-                };
+        let (typ, name) = match self.find_variable(variable) {
+            Expr::VariableName { typ, name, .. } => (typ, name),
+            _ => return Err(self.comp_err(&format!("unknown identifier: {variable}"))),
+        };
 
-                if is_num {
-                    self.comp_err(&format!("expected identifier, found number {word}"));
-                    exit(1);
-                }
+        let subtyp = match &typ {
+            Types::TypeDef { type_name, generics: Some(generics) } if type_name == "option" && !generics.is_empty() => generics[0].clone(),
+            _ => return Err(self.comp_err(&format!("{typ:?} unsupported in if statement capture"))),
+        };
 
-                let keyword_res = self.keyword_map.get(&word);
-                match keyword_res {
-                    Some(k) => {
-                        self.comp_err(&format!("expected identifier, found keyword {k:?}"));
-                        exit(1);
-                    },
-                    None => (),
-                };
+        let mut captures = Vec::new();
+        if let Types::TypeDef { type_name, .. } = &subtyp {
+            captures.append(&mut self.new_scope_propagate_field(capture.to_string(), type_name.clone(), false, true, false));
+        }
 
-                let found_expr = self.find_ident(word.clone());
-                match found_expr {
-                    Expr::None => {
-                        let mut typ = self.keyword_to_type(kw.clone());
-                        fname = word.clone();
+        let value = self.find_variable(&format!("{name}.value"));
+        if let Expr::None = value {
+            return Err(self.comp_err(&format!("expected variable {name}.value to exist to capture it")));
+        }
 
-                        if let Types::TypeDef { ref mut generics, .. } = typ {
-                            if !pass_typs.is_empty() {
-                                *generics = Some(pass_typs);
-                            }
-                        }
-                        expr = if self.in_enum_def {
-                            Expr::VariableName { typ, name: word, reassign: false, constant: true, field_data: (false, false), func_arg: false }
-                        } else {
-                            Expr::VariableName { typ, name: word, reassign: false, constant: false, field_data: (false, false), func_arg: false }
-                        };
-                    },
-                    _ => {
-                        self.comp_err(&format!("identifier {:?} already declared", word));
-                        exit(1);
-                    },
-                }
-            },
-            _ => {
-                self.comp_err(&format!("unexpected token {ident:?} after a keyword {kw:?}"));
-                exit(1);
+        captures.push(Expr::Variable {
+            info: Box::new(Expr::VariableName { typ: subtyp, name: capture.to_string(), reassign: false, constant: true, func_arg: false, field_data: (false, false) }),
+            value: Box::new(value),
+        });
+        Ok(captures)
+    }
+
+    fn lower_for(&mut self, iter: &ast::Expr, elem: &String, elem_ptr: bool, index: Option<&String>) -> Result<(), Diagnostic> {
+        let in_this = match &iter.kind {
+            ExprKind::Name(name) => self.find_variable_or_global(name)?,
+            ExprKind::Address(inner) => match &inner.kind {
+                ExprKind::Name(name) => Expr::Address(Box::new(self.find_variable_or_global(name)?)),
+                _ => return Err(self.comp_err("expected a variable to loop over")),
             },
-        }
+            _ => return Err(self.comp_err("expected a variable to loop over")),
+        };
 
-        match kw {
-            Keyword::Char | Keyword::I8 | Keyword::U8 | Keyword::U16 | Keyword::I16 | Keyword::U32 | Keyword::I32 |
-            Keyword::F32 | Keyword::F64 | Keyword::Usize | Keyword::Bool | Keyword::UInt | Keyword::Int | Keyword::I64 | Keyword::U64 => (),
-            Keyword::None => (),
-            Keyword::Generic(_) => (),
-            Keyword::Pointer(.., last) => {
-                if self.in_struct_def && !self.in_func {
-                    return expr;
-                }
-                if let Types::TypeDef { type_name: user_def, .. } = last {
-                    self.propagate_struct_fields(fname, user_def.to_string(), true, false);
-                }
+        let (typ, constant) = match &in_this {
+            Expr::VariableName { typ, constant, .. } => (typ.clone(), *constant),
+            Expr::Address(var) => match &**var {
+                Expr::VariableName { typ, constant, .. } => (typ.clone(), *constant),
+                _ => unreachable!(),
             },
-            Keyword::TypeDef { type_name: ref user_def, .. } => {
-                if self.in_struct_def && !self.in_func {
-                    return expr;
+            _ => unreachable!(),
+        };
+
+        let elem_typ = match typ {
+            Types::Arr { typ: subtyp, .. } => *subtyp,
+            Types::TypeDef { ref type_name, ref generics } if type_name == "dyn" || type_name == "array" => {
+                match generics.as_ref().and_then(|generics| generics.first()) {
+                    Some(subtyp) => subtyp.clone(),
+                    None => return Err(self.comp_err(&format!("{type_name} unsupported in for loops currently"))),
                 }
-                self.propagate_struct_fields(fname, user_def.to_string(), false, false);
             },
+            Types::TypeDef { ref type_name, .. } if type_name == "string" || type_name == "str" => Types::Char,
+            Types::TypeDef { type_name, .. } => return Err(self.comp_err(&format!("{type_name} unsupported in for loops currently"))),
+            typ => return Err(self.comp_err(&format!("{typ:?} unsupported in for loops currently"))),
+        };
+
+        let mut side_effects = Vec::new();
+        if let Types::TypeDef { type_name, .. } = &elem_typ {
+            side_effects.append(&mut self.new_scope_propagate_field(elem.clone(), type_name.clone(), elem_ptr, constant, false));
+        }
+
+        let for_this = match self.find_variable(elem) {
+            found @ Expr::VariableName { constant: true, .. } => found,
+            Expr::VariableName { .. } => return Err(self.comp_err(&format!("{elem} is already a variable. can't be used in for loop"))),
             _ => {
-                self.comp_err(&format!("unexpected keyword: {kw:?}"));
-                exit(1);
+                let typ = if elem_ptr { Types::Pointer(Box::new(elem_typ)) } else { elem_typ };
+                let new_var = Expr::VariableName { typ, name: elem.clone(), reassign: false, constant: !elem_ptr, func_arg: false, field_data: (elem_ptr, false) };
+                side_effects.push(Expr::Variable { info: Box::new(new_var.clone()), value: Box::new(Expr::None) });
+                new_var
             },
+        };
+
+        if let Some(index) = index {
+            side_effects.push(Expr::Variable {
+                info: Box::new(Expr::VariableName { typ: Types::Usize, name: index.clone(), reassign: false, constant: false, func_arg: false, field_data: (false, false) }),
+                value: Box::new(Expr::IntLit(String::from("0"))),
+            });
         }
 
-        if !self.in_enum_def && !self.in_func && !self.in_struct_def {
-            self.global_vars.push(Expr::Variable { info: Box::new(expr.clone()), value: Box::new(Expr::None) });
+        self.program_push(Expr::For {
+            for_this: Box::new(for_this),
+            in_this: Box::new(in_this),
+            iterator: index.cloned().unwrap_or_default(),
+        });
+        self.new_scope_vars(side_effects);
+        Ok(())
+    }
+
+    fn find_variable_or_global(&self, name: &String) -> Result<Expr, Diagnostic> {
+        match self.find_ident(name.clone()) {
+            var @ Expr::VariableName { .. } => Ok(var),
+            Expr::None => Err(self.comp_err(&format!("unknown identifier: {name}"))),
+            unexpected => Err(self.comp_err(&format!("expected a variable, got {unexpected:?}"))),
         }
+    }
 
-        expr
+    // conditions stay a flat list, the generator writes them out in order
+    fn lower_condition(&self, cond: &ast::Expr, is_loop: bool) -> Result<(Vec<Expr>, Expr), Diagnostic> {
+        let mut params = Vec::new();
+        let mut side_effect = Expr::None;
+        self.flatten_condition(cond, is_loop, &mut params, &mut side_effect)?;
+        Ok((params, side_effect))
     }
 
-    fn handle_type_mask(&mut self, type_name: String, masked_type: Expr) {
-        match masked_type {
-            Expr::StructDef { struct_fields, struct_name } => {
-                let is_shared = match *struct_name {
-                    Expr::StructName { is_shared, .. } => is_shared,
-                    _ => false,
-                };
-                let expr = Expr::StructDef {
-                    struct_name: Box::new(Expr::StructName { name: type_name, is_shared }),
-                    struct_fields
+    fn flatten_condition(&self, cond: &ast::Expr, is_loop: bool, params: &mut Vec<Expr>, side_effect: &mut Expr) -> Result<(), Diagnostic> {
+        match &cond.kind {
+            ExprKind::Binary { op, lhs, rhs } if is_logical(cond) => {
+                let ops = match op {
+                    BinOp::Or => vec![Expr::Or],
+                    BinOp::And => vec![Expr::And],
+                    BinOp::Eq => vec![Expr::Equal],
+                    BinOp::Ne => vec![Expr::Exclaim, Expr::Equal],
+                    BinOp::Lt => vec![Expr::SmallerThan],
+                    BinOp::Gt => vec![Expr::BiggerThan],
+                    BinOp::Le => vec![Expr::SmallerThan, Expr::Equal],
+                    BinOp::Ge => vec![Expr::BiggerThan, Expr::Equal],
+                    _ => unreachable!(),
                 };
-                self.structures.push(expr);
+
+                self.flatten_condition(lhs, is_loop, params, side_effect)?;
+                params.extend(ops);
+                self.flatten_condition(rhs, is_loop, params, side_effect)
             },
-            Expr::MacroStructDef { struct_name, struct_fields } => {
-                let (generics, is_shared) = if let Expr::MacroStructName { generics, is_shared, .. } = *struct_name {
-                    (generics, is_shared) 
-                } else {(vec![], false)};
-                let expr = Expr::MacroStructDef {
-                    struct_name: Box::new(Expr::MacroStructName {
-                        name: type_name,
-                        generics,
-                        is_shared
-                    }),
-                    struct_fields
-                };
-                self.structures.push(expr);
+            ExprKind::Unary { op: UnOp::Not, expr } => {
+                params.push(Expr::Exclaim);
+                self.flatten_condition(expr, is_loop, params, side_effect)
             },
-            Expr::EnumDef { .. } => {
-                self.comp_err(&format!("masking an enum is not supported, tried to mask `{masked_type:?}`"));
-                exit(1);
+            _ => {
+                // only the first name of a loop can be new, it becomes the counter
+                let is_counter = is_loop && params.is_empty();
+                let operand = self.lower_cond_operand(cond, is_counter, side_effect)?;
+                params.push(operand);
+                Ok(())
             },
-            _ => (),
         }
     }
 
-    fn create_variable(&mut self, left: Vec<Token>, right: Vec<Token>, is_constant: bool) {
-        let mut left_expr = self.handle_left_assign(left, is_constant);
-        let right_expr = self.handle_right_assign(right, true);
-
-        match left_expr {
-            Expr::VariableName { ref mut typ, ref name, .. } => {
-                // type check
-                let type_checked = compare_type_and_expr(typ, &right_expr, &self.functions);
-                if !type_checked.0 {
-                    self.comp_err(&format!("mismatched types: {typ:?} and {right_expr:?}"));
-                    exit(1);
+    fn lower_cond_operand(&self, operand: &ast::Expr, is_counter: bool, side_effect: &mut Expr) -> Result<Expr, Diagnostic> {
+        let expr = match &operand.kind {
+            ExprKind::Number(number) => Expr::IntLit(number.to_c()),
+            ExprKind::Square(inner) => self.lower_maths(inner)?,
+            ExprKind::Str(string) => Expr::StrLit(string.clone()),
+            ExprKind::Char(charlit) => Expr::CharLit(charlit.clone()),
+            ExprKind::Bool(true) => Expr::True,
+            ExprKind::Bool(false) => Expr::False,
+            ExprKind::Name(name) => {
+                if let Some(kw) = self.keyword_map.get(name) {
+                    return Err(self.comp_err(&format!("unexpected keyword {kw:?} in boolean condition")));
+                }
+
+                match self.find_ident(name.clone()) {
+                    Expr::Func { .. } | Expr::MacroFunc { .. } => {
+                        return Err(self.comp_err(&format!("can't compare to function. did you mean to call {name}?. do `{name}()`")));
+                    },
+                    Expr::None if is_counter => {
+                        let counter = Expr::VariableName { typ: Types::Usize, name: name.clone(), reassign: true, constant: false, func_arg: false, field_data: (false, false) };
+                        *side_effect = Expr::Variable { info: Box::new(counter.clone()), value: Box::new(Expr::IntLit(String::from("0"))) };
+                        counter
+                    },
+                    Expr::None => return Err(self.comp_err(&format!("unknown identifier: {}", name))),
+                    found => found,
                 }
-                *typ = match type_checked.1 {
-                    Types::None => typ.clone(),
-                    Types::TypeDef { ref type_name, .. } => {
-                        self.propagate_struct_fields(name.clone(), type_name.to_owned(), false, is_constant);
-                        type_checked.1
-                    }
-                    _ => type_checked.1,
-                };
+            },
+            ExprKind::Call { name, args } => self.lower_call(name, args)?,
+            ExprKind::Address(inner) => match &inner.kind {
+                ExprKind::Name(name) => self.create_address(name)?,
+                _ => Expr::Address(Box::new(self.lower_value(inner)?)),
+            },
+            _ => self.lower_value(operand)?,
+        };
 
-                if let Types::TypeId = typ {
-                    if !is_constant {
-                        self.comp_err(&format!("type masking must be constant. did you mean `typeid {name} :: <value>`?"));
-                        exit(1);
-                    }
+        Ok(expr)
+    }
 
-                    if let Expr::StructDef { .. } = right_expr {
-                        self.handle_type_mask(name.to_owned(), right_expr.clone());
-                    } else if let Expr::IntLit(_) = right_expr {
-                        self.structures.push(Expr::StructDef {
-                            struct_name: Box::new(Expr::StructName { name: name.to_owned(), is_shared: false }),
-                            struct_fields: vec![]
-                        });
-                    }
+    fn create_address(&self, ident: &String) -> Result<Expr, Diagnostic> {
+        let found_ident = self.find_ident(ident.clone());
+        match found_ident {
+            Expr::VariableName { ref name, constant, .. } => {
+                if constant {
+                    return Err(self.comp_err(&format!("using constant \"{name}\" as address dequalifies it to a variable, did you mean for \"{name}\" to be a variable?")));
+                }
+                Ok(Expr::Address(Box::new(found_ident)))
+            },
+            unexpected => {
+                Err(self.comp_err(&format!("unexpected expression {unexpected:?} when getting address of identifier: {ident:?}")))
+            }
+        }
+    }
 
-                    let new_name = name.replace(".", "__");
-
-                    let expr = Expr::Variable {
-                        info: Box::new(Expr::VariableName {
-                            typ: typ.clone(),
-                            name: new_name,
-                            reassign: false,
-                            constant: true,
-                            func_arg: false,
-                            field_data: (false, false)
-                        }),
-                        value: Box::new(right_expr)
-                    };
+    // the right of a `:`, a return value or an argument
+    fn lower_value(&self, value: &ast::Expr) -> Result<Expr, Diagnostic> {
+        let expr = match &value.kind {
+            ExprKind::Number(number) => Expr::IntLit(number.to_c()),
+            ExprKind::Str(string) => Expr::StrLit(string.clone()),
+            ExprKind::Char(charlit) => Expr::CharLit(charlit.clone()),
+            ExprKind::Bool(true) => Expr::True,
+            ExprKind::Bool(false) => Expr::False,
+            ExprKind::Name(name) => match self.find_ident(name.clone()) {
+                Expr::VariableName { typ, name, reassign, func_arg, field_data, .. } => {
+                    Expr::VariableName { typ, name, reassign, constant: false, func_arg, field_data }
+                },
+                Expr::None => match self.keyword_map.get(name) {
+                    Some(_) => Expr::IntLit(name.clone()),
+                    None => return Err(self.comp_err(&format!("unknown identifier: {}", name))),
+                },
+                found => found,
+            },
+            ExprKind::Call { name, args } => self.lower_call(name, args)?,
+            ExprKind::Index { base, index } => self.lower_index(base, index)?,
+            ExprKind::Deref(inner) => Expr::DerefPointer(Box::new(self.lower_value(inner)?)),
+            ExprKind::Address(inner) => match &inner.kind {
+                ExprKind::Name(name) => self.create_address(name)?,
+                ExprKind::Index { base, index } => Expr::Address(Box::new(self.lower_index(base, index)?)),
+                _ => return Err(self.comp_err("can only take the address of a variable")),
+            },
+            ExprKind::Square(inner) => self.lower_maths(inner)?,
+            ExprKind::ArrayLit(elems) => {
+                let mut values = Vec::new();
+                for elem in elems {
+                    values.push(self.lower_value(elem)?);
+                }
+                Expr::ArrayLit(values)
+            },
+            // used as a value, so a `;` at the end would end up doubled
+            ExprKind::CEmbed(code) => Expr::CEmbed(self.interpolate(code.trim().trim_end_matches(';').trim_end())?.replace("\r", "")),
+            ExprKind::Default => Expr::DefaultValue,
+            ExprKind::Garbage => Expr::GarbageValue,
+            ExprKind::Cast { typ, expr } => Expr::Cast { typ: self.lower_cast_type(typ)?, value: Box::new(self.lower_value(expr)?) },
+            ExprKind::Type(_) => return Err(self.comp_err("types can only be passed to functions")),
+            ExprKind::Paren(inner) if is_logical(inner) => Expr::Condition(self.lower_condition(inner, false)?.0),
+            ExprKind::Paren(inner) => self.lower_value(inner)?,
+            ExprKind::Binary { .. } | ExprKind::Unary { .. } if is_logical(value) => Expr::Condition(self.lower_condition(value, false)?.0),
+            ExprKind::Binary { .. } | ExprKind::Unary { .. } => self.lower_maths(value)?,
+        };
 
-                    self.token_stack.clear();
+        Ok(expr)
+    }
 
-                    if self.in_defer {
-                        self.expr_stack.push(expr.clone());
-                    } else {
-                        self.program_push(expr);
-                    }
-                    return;
-                }
+    // the type in cast(T x), which can be a typeid of the macro function it's in
+    fn lower_cast_type(&self, typ: &ast::Type) -> Result<Types, Diagnostic> {
+        if let ast::Type::Name { name, generics: None } = typ {
+            if let Expr::VariableName { typ: Types::TypeId, .. } = self.find_variable(name) {
+                return Ok(Types::Generic(name.clone()));
+            }
+        }
+
+        match self.lower_type(typ, TypeAt::Decl)? {
+            Types::Arr { .. } => Err(self.comp_err("can't cast to an array, cast to a pointer instead")),
+            typ => Ok(typ),
+        }
+    }
+
+    // arr[i], the array itself keeps its type under the index
+    fn lower_index(&self, base: &ast::Expr, index: &ast::Expr) -> Result<Expr, Diagnostic> {
+        let ExprKind::Name(name) = &base.kind else {
+            return Err(self.comp_err("only variables can be indexed"));
+        };
+
+        match self.find_ident(name.clone()) {
+            Expr::VariableName { typ: typ @ (Types::Arr { .. } | Types::Pointer(_)), name, constant, field_data, .. } => {
+                Ok(Expr::VariableName {
+                    typ: Types::ArrIndex { arr_typ: Box::new(typ), index_at: self.render_maths(index)? },
+                    name,
+                    reassign: false,
+                    constant,
+                    func_arg: false,
+                    field_data,
+                })
             },
-            Expr::DerefPointer(ref varname) => {
-                match **varname {
-                    Expr::VariableName { ref typ, ref name, .. } => {
-                        if let Types::TypeId = typ {
-                            if !is_constant {
-                                self.comp_err(&format!("type masking must be constant. did you mean `typeid {name} :: <value>`?"));
-                                exit(1);
-                            }
+            Expr::VariableName { typ, name, .. } => Err(self.comp_err(&format!("can't index {name}, it is {typ:?} and not an array or pointer"))),
+            Expr::None => Err(self.comp_err(&format!("unknown identifier: {}", name))),
+            unexpected => Err(self.comp_err(&format!("expected a variable to index, got {unexpected:?}"))),
+        }
+    }
 
-                            if let Expr::StructDef { .. } = right_expr {
-                                self.handle_type_mask(name.to_owned(), right_expr.clone());
-                            } else if let Expr::IntLit(_) = right_expr {
-                                self.structures.push(Expr::StructDef {
-                                    struct_name: Box::new(Expr::StructName { name: name.to_owned(), is_shared: false }),
-                                    struct_fields: vec![]
-                                });
-                            }
+    fn lower_call(&self, name: &String, args: &[ast::Expr]) -> Result<Expr, Diagnostic> {
+        let func = match self.find_ident(name.clone()) {
+            func @ (Expr::Func { .. } | Expr::MacroFunc { .. }) => func,
+            Expr::None => return Err(self.comp_err(&format!("unknown identifier: {name}"))),
+            unexpected => return Err(self.comp_err(&format!("expected a function, got {:?}", unexpected))),
+        };
 
-                            let new_name = name.replace(".", "__");
+        if !self.in_func && !self.in_struct_def {
+            return Err(self.comp_err(&format!("cannot call function outside of a scope. function: {:?}", func)));
+        }
 
-                            let expr = Expr::Variable {
-                                info: Box::new(Expr::VariableName {
-                                    typ: typ.clone(),
-                                    name: new_name,
-                                    reassign: false,
-                                    constant: true,
-                                    func_arg: false,
-                                    field_data: (false, false)
-                                }),
-                                value: Box::new(right_expr)
-                            };
+        let mut expr_params = Vec::new();
+        for arg in args {
+            expr_params.push(self.lower_arg(arg)?);
+        }
+
+        let san_name = name.replace(".", "__");
+        if let Err(e) = compare_exprs_and_args(&expr_params, &san_name, &self.functions) {
+            let msg = match e.error {
+                TCError::WrongArgLength(got, expected) => format!("function {name} expects {expected} arguments, got {got} instead"),
+                // the type checker says which types they were
+                TCError::MismatchExprType(..) => return Ok(Expr::FuncCall { name: san_name, gave_params: expr_params }),
+                TCError::FuncNotExist => format!("function {name} does not exist... somehow"),
+                TCError::MismatchTypeType(expected_typ, got_typ) => format!("function {name} expected argument {} to be of type {expected_typ:?}, got type {got_typ:?}", e.pos),
+                TCError::GenericNotExist(generic) => format!("function {name} uses generic {generic} in argument {} but it isn't defined", e.pos),
+                TCError::Custom(custom) => format!("function {name} {custom}"),
+            };
+            return Err(self.comp_err(&msg));
+        }
 
-                            self.token_stack.clear();
+        Ok(Expr::FuncCall { name: san_name, gave_params: expr_params })
+    }
 
-                            if self.in_defer {
-                                self.expr_stack.push(expr.clone());
-                            } else {
-                                self.program_push(expr.clone());
-                            }
-                            return;
-                        }
+    fn lower_arg(&self, arg: &ast::Expr) -> Result<Expr, Diagnostic> {
+        match &arg.kind {
+            ExprKind::Name(name) => match self.find_ident(name.clone()) {
+                Expr::None => match self.keyword_map.get(name) {
+                    Some(kw) => {
+                        let _ = self.keyword_to_type(kw.clone())?;
+                        Ok(Expr::VariableName { typ: Types::TypeId, name: name.clone(), reassign: false, constant: false, field_data: (false, false), func_arg: false })
                     },
-                    _ => unreachable!()
-                }
+                    None => Err(self.comp_err(&format!("unknown identifier: {}", name))),
+                },
+                Expr::Func { .. } | Expr::MacroFunc { .. } => {
+                    Err(self.comp_err(&format!("can't pass function {name} as an argument. did you mean to call it? do `{name}()`")))
+                },
+                found => Ok(found),
+            },
+            ExprKind::Type(typ) => self.lower_type_arg(typ),
+            _ => self.lower_value(arg),
+        }
+    }
+
+    // a type given to a macro function, like ^char in `mem.alloc(^char 10)`
+    fn lower_type_arg(&self, typ: &ast::Type) -> Result<Expr, Diagnostic> {
+        let mut pointers = 0;
+        let mut inner = typ;
+        while let ast::Type::Pointer(to) = inner {
+            pointers += 1;
+            inner = to;
+        }
+
+        let (mut typ, name) = match inner {
+            ast::Type::Name { name, generics: None } if self.keyword_map.contains_key(name) => (Types::TypeId, name.clone()),
+            ast::Type::Name { name, generics: None } => match self.find_structure(name) {
+                Expr::StructDef { .. } => (Types::TypeDef { type_name: name.clone(), generics: None }, name.clone()),
+                _ => return Err(self.comp_err(&format!("unknown identifier: {}", name))),
             },
-            ref unexpected => {
-                self.comp_err(&format!("unexpected {unexpected:?} on left side of variable"));
-                exit(1);
+            _ => return Err(self.comp_err("only keyword and struct types can be passed to functions")),
+        };
+
+        for _ in 0..pointers {
+            typ = Types::Pointer(Box::new(typ));
+        }
+
+        Ok(Expr::VariableName { typ, name, reassign: false, constant: false, field_data: (false, false), func_arg: false })
+    }
+
+    // maths inside [] written out as c
+    fn render_maths(&self, expr: &ast::Expr) -> Result<String, Diagnostic> {
+        let mut calls = Vec::new();
+        let rendered = self.write_maths(expr, &mut calls)?;
+        match calls.first() {
+            Some(Expr::FuncCall { name, .. }) => {
+                Err(self.comp_err(&format!("generic function {} can't be called in an array length or index", name.replace("__", "."))))
             },
+            _ => Ok(rendered),
         }
+    }
 
-        let expr = Expr::Variable { info: Box::new(left_expr.clone()), value: Box::new(right_expr.clone()) };
-
-        if self.in_struct_def && !self.in_func  {
-            // if initalising struct fields
-            self.expr_stack.push(expr.clone());
-            self.token_stack.clear();
-            return;
-        } else if !self.in_func && !self.in_enum_def {
-            // if in global scope
-            self.global_vars.push(expr.clone());
-        } else if self.in_enum_def && !self.in_func {
-            // if enum field has numeric value
-            if let Expr::IntLit(_) = right_expr {
-                self.expr_stack.push(expr);
-                self.token_stack.clear();
-                return;
-            } else {
-                self.comp_err(&format!("enum field must be an integer, found {right_expr:?}"));
-                exit(1);
-            }
-        } else if let Some(vars) = self.func_to_vars.get_mut(&self.current_func) {
-            vars[self.current_scope].push(expr.clone());
+    // maths used as a value, the generator writes the calls to generic functions in it once it
+    // knows which instance they call
+    fn lower_maths(&self, expr: &ast::Expr) -> Result<Expr, Diagnostic> {
+        let mut calls = Vec::new();
+        let rendered = self.write_maths(expr, &mut calls)?;
+        if calls.is_empty() {
+            return Ok(Expr::IntLit(rendered));
         }
 
-        self.token_stack.clear();
-        if self.in_defer {
-            self.expr_stack.push(expr);
-        } else {
-            self.program_push(expr);
+        let mut parts = Vec::new();
+        let mut calls = calls.into_iter();
+        for text in rendered.split(MATHS_CALL) {
+            if !text.is_empty() {
+                parts.push(Expr::IntLit(text.to_string()));
+            }
+            parts.extend(calls.next());
         }
+        Ok(Expr::Maths(parts))
     }
 
-    fn handle_semicolon(&mut self) {
-        let mut left = Vec::new();
-        let mut right = Vec::new();
-        let mut seen_colon = 0;
-        let mut is_constant = false;
-
-        for (_i, token) in self.token_stack.iter().enumerate() {
-            match token {
-                Token::Colon => {
-                    seen_colon += 1;
-                    if seen_colon == 2 {
-                        is_constant = true;
+    // generic calls are pushed to `calls` and written as MATHS_CALL where they go
+    fn write_maths(&self, expr: &ast::Expr, calls: &mut Vec<Expr>) -> Result<String, Diagnostic> {
+        let rendered = match &expr.kind {
+            ExprKind::Number(number) => number.to_c(),
+            ExprKind::Char(charlit) => format!("'{charlit}'"),
+            ExprKind::Square(inner) | ExprKind::Paren(inner) => format!("({})", self.write_maths(inner, calls)?),
+            ExprKind::Binary { op, lhs, rhs } => {
+                let op = match op {
+                    BinOp::Add => "+",
+                    BinOp::Sub => "-",
+                    BinOp::Mul => "*",
+                    BinOp::Div => "/",
+                    BinOp::Mod => "%",
+                    BinOp::BitAnd => "&",
+                    BinOp::BitOr => "|",
+                    BinOp::BitXor => "^",
+                    BinOp::Shl => "<<",
+                    BinOp::Shr => ">>",
+                    _ => return Err(self.comp_err("comparisons, `and` and `or` give a bool, they can't be used as a number")),
+                };
+                format!("{}{op}{}", self.write_maths(lhs, calls)?, self.write_maths(rhs, calls)?)
+            },
+            ExprKind::Unary { op, expr } => {
+                let op = match op {
+                    UnOp::Neg => "-",
+                    UnOp::BitNot => "~",
+                    UnOp::Not => return Err(self.comp_err("`!` gives a bool, it can't be used as a number. put it in [] for bitwise not")),
+                };
+                format!("{op}{}", self.write_maths(expr, calls)?)
+            },
+            ExprKind::Name(ident) => match self.find_ident(ident.clone()) {
+                Expr::None => return Err(self.comp_err(&format!("unknown identifier: {}", ident))),
+                Expr::VariableName { typ, name, field_data, .. } => {
+                    if !is_numeric(&typ) {
+                        return Err(self.comp_err(&format!("variable {name} is not an integer. {typ:?}:{name}")));
                     }
-                },
-                _ => {
-                    if seen_colon == 0 {
-                        left.push(token.clone());
-                    } else if seen_colon == 1 || seen_colon == 2 {
-                        right.push(token.clone());
+
+                    if !field_data.0 && name.contains(".") {
+                        name.replace(".", "_")
                     } else {
-                        self.comp_err("unexpected assignment operator `:`");
-                        exit(1);
+                        name
                     }
                 },
+                _ => return Err(self.comp_err(&format!("unexpected expression in integer literal: {}", ident))),
+            },
+            ExprKind::Deref(inner) => format!("*{}", self.write_maths(inner, calls)?),
+            ExprKind::Index { base, index } => {
+                let ExprKind::Name(name) = &base.kind else {
+                    return Err(self.comp_err("only variables can be indexed"));
+                };
+                let base = match self.find_ident(name.clone()) {
+                    Expr::VariableName { typ: Types::Arr { .. }, name, field_data, .. } => {
+                        let name = if !field_data.0 && name.contains(".") { name.replace(".", "_") } else { name };
+                        format!("{name}.data")
+                    },
+                    _ => self.write_maths(base, calls)?,
+                };
+                format!("{base}[{}]", self.write_maths(index, calls)?)
+            },
+            ExprKind::Call { name, args } => {
+                match self.find_ident(name.clone()) {
+                    Expr::Func { typ, .. } | Expr::MacroFunc { typ, .. } if !is_numeric(&typ) => {
+                        return Err(self.comp_err(&format!("function {name} does not return integer. {typ:?}:{name}")));
+                    },
+                    // the instance it calls is only known once the generator sees the types
+                    Expr::MacroFunc { .. } => {
+                        calls.push(self.lower_call(name, args)?);
+                        return Ok(MATHS_CALL.to_string());
+                    },
+                    _ => (),
+                }
+
+                // a throwaway generator writes out just this call, it doesn't touch the real output
+                let func_call = self.lower_call(name, args)?;
+                let mut gen = Gen::new(&self.filename, &String::from("output"), true, Lang::C, Compiler::default(), false);
+                gen.handle_funccall(func_call).map_err(|e| e.with_span(self.span))?
+            },
+            ExprKind::Cast { .. } => {
+                let cast = self.lower_value(expr)?;
+                let mut gen = Gen::new(&self.filename, &String::from("output"), true, Lang::C, Compiler::default(), false);
+                gen.handle_value(cast).map_err(|e| e.with_span(self.span))?
+            },
+            _ => return Err(self.comp_err("expected a number, variable or function call inside []")),
+        };
+
+        Ok(rendered)
+    }
+
+    // the type written in the source, `at` decides which names it can use
+    fn lower_type(&self, typ: &ast::Type, at: TypeAt) -> Result<Types, Diagnostic> {
+        match typ {
+            ast::Type::Void => match at {
+                TypeAt::Return => Ok(Types::Void),
+                TypeAt::Param(_) => Err(self.comp_err("can't use void as type. void pointers work tho.")),
+                TypeAt::Decl => Err(self.comp_err("can't make void type. void pointers are allowed")),
+            },
+            ast::Type::Pointer(to) => {
+                let to = match &**to {
+                    ast::Type::Void => Types::Void,
+                    to => self.lower_type(to, at)?,
+                };
+                Ok(Types::Pointer(Box::new(to)))
+            },
+            ast::Type::Array { len, elem } => {
+                let length = match len {
+                    Some(len) => self.render_maths(len)?,
+                    None => String::new(),
+                };
+                Ok(Types::Arr { typ: Box::new(self.lower_type(elem, at)?), length })
+            },
+            ast::Type::Generic(name) => self.lower_generic(name, at),
+            ast::Type::Name { name, generics } => self.lower_named_type(name, generics.as_deref(), at),
+        }
+    }
+
+    // $T, only once T is a typeid
+    fn lower_generic(&self, name: &String, at: TypeAt) -> Result<Types, Diagnostic> {
+        match at {
+            TypeAt::Return => Ok(Types::Generic(name.clone())),
+            TypeAt::Param(typeids) => {
+                if !typeids.contains(name) {
+                    return Err(self.comp_err(&format!("generic type {name} hasn't been defined as a typeid yet.")));
+                }
+                Ok(Types::Generic(name.clone()))
+            },
+            TypeAt::Decl => match self.find_variable(name) {
+                Expr::VariableName { typ: Types::TypeId, .. } => Ok(Types::Generic(name.clone())),
+                Expr::VariableName { typ, .. } => Err(self.comp_err(&format!("expected {name:?} to be typeid. instead is {typ:?}"))),
+                _ => Err(self.comp_err(&format!("undefined typeid: {name}"))),
+            },
+        }
+    }
+
+    fn lower_named_type(&self, name: &String, generics: Option<&[ast::Type]>, at: TypeAt) -> Result<Types, Diagnostic> {
+        if let Some(kw) = self.keyword_map.get(name) {
+            if generics.is_some() {
+                return Err(self.comp_err(&format!("{name} doesn't take generics")));
             }
+            return self.keyword_to_type(kw.clone());
         }
 
-        if !right.is_empty() {
-            self.create_variable(left, right, is_constant);
-            return
+        // fields can point back to their own struct before it exists
+        if self.in_struct_def && name == &self.current_func {
+            let generics = if self.is_struct_generic { Some(vec![]) } else { None };
+            return Ok(Types::TypeDef { type_name: format!("struct {name}"), generics });
         }
 
-        // this could be a funccall or whatever but if its variable def, we need to handle it.
-        let expr = self.handle_right_assign(left, false);
-        self.token_stack.clear();
-        match expr {
-            Expr::VariableName { .. } => {
-                let new_var = Expr::Variable { info: Box::new(expr.clone()), value: Box::new(Expr::None) };
-                if let Some(vars) = self.func_to_vars.get_mut(&self.current_func) {
-                    vars[self.current_scope].push(new_var);
+        match self.find_structure(name) {
+            Expr::StructDef { .. } => {
+                if generics.is_some() {
+                    return Err(self.comp_err(&format!("{name} isn't a generic struct")));
                 }
+                return Ok(Types::TypeDef { type_name: name.clone(), generics: None })
             },
-            // struct stuff
-            Expr::None => {
-                return
+            Expr::MacroStructDef { .. } => {
+                let Some(generics) = generics else {
+                    return Err(self.comp_err(&format!("expected type for generic struct, e.g. `{name}[type]`")));
+                };
+
+                let mut pass_typs = Vec::new();
+                for generic in generics {
+                    pass_typs.push(self.lower_generic_arg(generic, at)?);
+                }
+                return Ok(Types::TypeDef { type_name: name.clone(), generics: Some(pass_typs) })
             },
             _ => (),
         }
 
-        self.program_push(expr)
+        if let Expr::EnumDef { .. } = self.find_enum(name) {
+            return Ok(Types::TypeDef { type_name: name.clone(), generics: None })
+        }
+
+        match at {
+            TypeAt::Param(_) => Err(self.comp_err(&format!("expected a type, got {}", name))),
+            _ => Err(self.comp_err(&format!("unknown identifier: {}", name))),
+        }
     }
 
-    pub fn parser(&mut self) -> Vec<(Expr, String, u32)> {
-        let mut curl_rc = 0;
+    // one of the types given to a generic struct, like the int in dyn[int]
+    fn lower_generic_arg(&self, typ: &ast::Type, at: TypeAt) -> Result<Types, Diagnostic> {
+        let name = match typ {
+            ast::Type::Pointer(to) => {
+                let to = match &**to {
+                    ast::Type::Void => Types::Void,
+                    to => self.lower_generic_arg(to, at)?,
+                };
+                return Ok(Types::Pointer(Box::new(to)))
+            },
+            ast::Type::Void => return Err(self.comp_err("can't have a _ (void) generic parameter, did you mean ^_ (void pointer)?")),
+            ast::Type::Generic(name) => return self.lower_generic(name, at),
+            ast::Type::Name { name, generics: None } => name,
+            _ => return Err(self.comp_err("generic struct with type of a generic struct not supported yet")),
+        };
 
-        let mut defer_rc = 0;
-        let mut defer_paste_next_time = false;
-        let mut stored_defers: Vec<DeferInfo> = Vec::new();
+        if let Some(kw) = self.keyword_map.get(name) {
+            return self.keyword_to_type(kw.clone());
+        }
 
-        while self.current_token < self.tokens.len() {
-            if self.one_defer {
-                self.one_defer = false;
+        let is_typeid = match at {
+            TypeAt::Param(typeids) => typeids.contains(name),
+            _ => matches!(self.find_variable(name), Expr::VariableName { typ: Types::TypeId, .. }) || self.is_owner_typeid(name),
+        };
+        if is_typeid {
+            return Ok(Types::TypeDef { type_name: name.clone(), generics: None })
+        }
 
-                let defer_info = DeferInfo {
-                    scope: self.defer_scope,
-                    exprs: self.expr_stack.clone(),
-                };
+        match self.find_structure(name) {
+            Expr::StructDef { .. } => return Ok(Types::TypeDef { type_name: name.clone(), generics: None }),
+            Expr::MacroStructDef { .. } => return Err(self.comp_err("generic struct with type of a generic struct not supported yet")),
+            _ => (),
+        }
 
-                self.expr_stack.clear();
-                stored_defers.push(defer_info);
-                defer_paste_next_time = true;
-            }
+        match at {
+            TypeAt::Return => Ok(Types::Generic(name.clone())),
+            TypeAt::Param(_) => Err(self.comp_err(&format!("expected a defined typeid, found undefined `{name}`"))),
+            TypeAt::Decl => Err(self.comp_err(&format!("expected type, got {name}"))),
+        }
+    }
 
-            match self.tokens[self.current_token] {
-                Token::Lcurl => {
-                    curl_rc += 1;
+    // a type as written, for the type checker. typeids and anything else it can't name are `any`
+    pub(crate) fn resolve_type(&self, typ: &ast::Type) -> Types {
+        let ast::Type::Name { name, generics } = typ else {
+            return Types::Any
+        };
 
-                    if self.in_defer {
-                        defer_rc += 1;
-                    }
-                    self.handle_lcurl();
-                },
-                Token::Rcurl => {
-                    self.error_if_token_stack_not_empty();
-
-                    curl_rc -= 1;
-                    self.prev_scope();
-
-                    if defer_paste_next_time && !self.in_defer {
-                        // need to specifically check because you can be in scope 0 while inside
-                        // and outside a function
-
-                        'pasting: loop {
-                            let defer_info = match stored_defers.pop() {
-                                Some(info) => {
-                                    info
-                                },
-                                None => {
-                                    self.comp_err("failed to paste defered expression.");
-                                    exit(1);
-                                }
-                            };
+        if let Some(kw) = self.keyword_map.get(name) {
+            return self.keyword_to_type(kw.clone()).unwrap_or(Types::Any);
+        }
 
-                            let mut repeat_checking = false;
-                            if defer_info.scope == 0 && defer_info.scope == self.current_scope && !self.in_func {
-                                self.create_defer(defer_info.exprs);
-                                repeat_checking = true;
-                            } else if defer_info.scope > 0 && defer_info.scope == self.current_scope+1 {
-                                self.create_defer(defer_info.exprs);
-                                repeat_checking = true;
-                            } else {
-                                stored_defers.push(defer_info);
-                            }
+        match (self.find_structure(name), self.find_enum(name)) {
+            (Expr::StructDef { .. }, _) | (_, Expr::EnumDef { .. }) => Types::TypeDef { type_name: name.clone(), generics: None },
+            (Expr::MacroStructDef { .. }, _) => {
+                let generics = generics.iter().flatten().map(|generic| match generic {
+                    ast::Type::Pointer(to) => Types::Pointer(Box::new(self.resolve_type(to))),
+                    generic => self.resolve_type(generic),
+                }).collect();
+                Types::TypeDef { type_name: name.clone(), generics: Some(generics) }
+            },
+            _ => Types::Any,
+        }
+    }
 
-                            if stored_defers.is_empty() {
-                                defer_paste_next_time = false;
-                                repeat_checking = false;
-                            }
+    // the typeids of the struct whose functions are being lowered, like the T of dyn[T]
+    fn is_owner_typeid(&self, name: &String) -> bool {
+        let Some(scopes) = self.func_to_vars.get(&self.previous_func) else {
+            return false
+        };
 
-                            if !repeat_checking {
-                                break 'pasting;
-                            }
-                        }
-                    }
+        scopes.iter().flatten().any(|var| match var {
+            Expr::Variable { info, .. } => matches!(&**info, Expr::VariableName { typ: Types::TypeId, name: generic, .. } if generic == name),
+            _ => false,
+        })
+    }
+}
 
-                    if self.in_struct_def && curl_rc == 0 {
-                        if self.previous_func.is_empty() {
-                            self.create_struct();
-                        } else {
-                            self.previous_func.clear();
-                            self.in_struct_def = false;
-                            self.is_struct_generic = false;
-                        }
-                    } else if self.in_enum_def && curl_rc == 0 {
-                        if self.previous_func.is_empty() {
-                            self.create_enum();
-                        } else {
-                            self.previous_func.clear();
-                            self.in_enum_def = false;
-                        }
-                    } else if self.in_defer {
-                        let mut include_rcurl = false;
-                        if defer_rc != 0 {
-                            defer_rc -= 1;
-                            include_rcurl = true;
-                        }
+// `{name}` in @c, nothing but a name between the braces so `{ x }` and blocks are left as c
+pub(crate) fn embedded_name(braced: &str) -> Option<&str> {
+    let name = braced.strip_prefix('{')?.strip_suffix('}')?;
+    let starts = name.starts_with(|ch: char| ch.is_alphabetic() || ch == '_');
+    let valid = name.chars().all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '.');
+    (starts && valid && !name.ends_with('.')).then_some(name)
+}
 
-                        if self.defer_scope == self.current_scope {
-                            self.in_defer = false;
-
-                            if curl_rc == 0 {
-                                self.create_defer(self.expr_stack.clone());
-                                self.expr_stack.clear();
-                                self.program_push(Expr::EndBlock);
-                            } else if defer_rc == 0 {
-                                let defer_info = DeferInfo {
-                                    scope: self.defer_scope,
-                                    exprs: self.expr_stack.clone(),
-                                };
-                                self.expr_stack.clear();
-                                stored_defers.push(defer_info);
-                                defer_paste_next_time = true;
-                            }
-                        } else if include_rcurl {
-                            self.expr_stack.push(Expr::EndBlock);
-                        }
-                    } else if !self.in_defer {
-                        self.program_push(Expr::EndBlock);
-                    }
-                },
-                Token::SemiColon => {
-                    self.handle_semicolon();
-                },
-                Token::Newline => {
-                    self.error_if_token_stack_not_empty();
-                    self.line_num += 1;
-                },
-                _ => self.token_stack.push(self.tokens[self.current_token].clone()),
-            }
+fn pointee(typ: &Types) -> &Types {
+    match typ {
+        Types::Pointer(to) => pointee(to),
+        typ => typ,
+    }
+}
 
-            self.current_token += 1;
-        }
+// comparisons, `and`, `or` and `!` give a bool instead of a number
+fn is_logical(expr: &ast::Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Binary { op, .. } => matches!(op, BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::And | BinOp::Or),
+        ExprKind::Unary { op: UnOp::Not, .. } => true,
+        ExprKind::Paren(inner) => is_logical(inner),
+        _ => false,
+    }
+}
 
-        if curl_rc != 0 {
-            self.comp_err("missing a `{` or `}` somewhere");
-            exit(1);
-        }
+// where a generic call goes in maths written out by write_maths, it can't be in the source
+const MATHS_CALL: char = '\u{1}';
 
-        self.program.clone()
-    }
+fn is_numeric(typ: &Types) -> bool {
+    matches!(typ,
+        Types::I32 | Types::U32 | Types::U8 | Types::I8 | Types::UInt | Types::Int | Types::U16 | Types::I16 |
+        Types::U64 | Types::I64 | Types::Usize | Types::Pointer(_) | Types::Generic(_) |
+        Types::F32 | Types::F64
+    )
 }
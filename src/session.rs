@@ -0,0 +1,173 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::Arc};
+
+use crate::ast;
+use crate::cache::ParseCache;
+use crate::compiler::{Cfg, Compiler};
+use crate::declare_types::Lang;
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::generator::Gen;
+use crate::lib_path::LibPaths;
+use crate::parser::{Expr, ExprWeights};
+use crate::tokeniser::{tokeniser, Span};
+use crate::vfs::{FileSystem, MemoryFs};
+
+// one run of the compiler over files read from `fs`, it only touches the disk if `fs` does
+// or a vendor dir or parse cache is given
+pub struct Session {
+    fs: Arc<dyn FileSystem>,
+    lib_paths: LibPaths,
+    vendors: HashMap<String, String>, // vendor name to where it lives
+    vendor_dir: Option<PathBuf>,
+    parse_cache: Option<ParseCache>,
+    lang: Lang,
+    line_directives: bool,
+    cfg: Cfg,
+}
+
+#[derive(Debug)]
+pub struct Output {
+    pub c: Option<String>, // None when there were errors
+    pub c_sources: Vec<String>, // .c files of local imports, compiled alongside the generated c
+    pub c_flags: Vec<String>, // from @link and @cflags, for the os of the session's target
+    pub diagnostics: Diagnostics,
+}
+
+impl Session {
+    pub fn new(fs: impl FileSystem + 'static) -> Session {
+        Session {
+            fs: Arc::new(fs),
+            lib_paths: LibPaths::default(),
+            vendors: HashMap::new(),
+            vendor_dir: None,
+            parse_cache: None,
+            lang: Lang::C,
+            line_directives: false,
+            cfg: Cfg::default(),
+        }
+    }
+
+    // `sources` are (path, content) pairs, base/ is included and found from the root
+    pub fn from_sources<P: AsRef<Path>, S: Into<String>>(sources: impl IntoIterator<Item = (P, S)>) -> Session {
+        let mut fs = MemoryFs::with_base();
+        for (path, content) in sources {
+            fs.insert(path, content);
+        }
+
+        Session::new(fs).with_lib_path("")
+    }
+
+    pub fn with_lib_paths(mut self, lib_paths: LibPaths) -> Session {
+        self.lib_paths = lib_paths;
+        self
+    }
+
+    // searched for base/ and vendor/ after any given before it
+    pub fn with_lib_path(mut self, dir: impl Into<PathBuf>) -> Session {
+        self.lib_paths.push(dir.into(), "Session::with_lib_path");
+        self
+    }
+
+    pub fn with_vendors(mut self, vendors: HashMap<String, String>) -> Session {
+        self.vendors = vendors;
+        self
+    }
+
+    // vendors get copied in here, otherwise they're used where they are
+    pub fn with_vendor_dir(mut self, vendor_dir: PathBuf) -> Session {
+        self.vendor_dir = Some(vendor_dir);
+        self
+    }
+
+    pub fn with_parse_cache(mut self, parse_cache: ParseCache) -> Session {
+        self.parse_cache = Some(parse_cache);
+        self
+    }
+
+    pub fn with_lang(mut self, lang: Lang) -> Session {
+        self.lang = lang;
+        self
+    }
+
+    pub fn with_line_directives(mut self, line_directives: bool) -> Session {
+        self.line_directives = line_directives;
+        self
+    }
+
+    // which @windows, @linux, @macos, @cfg(name), @link and @cflags code is kept, the os impulse is running on otherwise
+    pub fn with_cfg(mut self, cfg: Cfg) -> Session {
+        self.cfg = cfg;
+        self
+    }
+
+    pub fn fs(&self) -> &dyn FileSystem {
+        self.fs.as_ref()
+    }
+
+    // tokenises and parses `entry` and everything it imports
+    pub fn parse(&self, entry: &str) -> Result<Vec<(Expr, String, Span)>, Diagnostics> {
+        let content = match self.fs.read_to_string(Path::new(entry)) {
+            Ok(content) => content,
+            Err(e) => return Err(Diagnostic::global(&format!("unable to read file {entry} with error: {e}")).into()),
+        };
+
+        let mut diagnostics = Diagnostics::new();
+        let tokens = tokeniser(content.clone(), entry, &mut diagnostics);
+        if diagnostics.has_errors() {
+            return Err(diagnostics);
+        }
+
+        let module = ast::parse(tokens, entry, &self.cfg)?;
+
+        let mut parse = ExprWeights::new(entry);
+        parse.fs = self.fs.clone();
+        parse.lib_paths = self.lib_paths.clone();
+        parse.vendors = self.vendors.clone();
+        parse.vendor_dir = self.vendor_dir.clone();
+        parse.parse_cache = self.parse_cache.clone();
+        parse.cfg = self.cfg.clone();
+
+        // every program needs builtin, nothing else makes sense without it
+        if self.lib_paths.find(self.fs.as_ref(), "base/builtin.imp").is_none() {
+            let e = Diagnostic::global("unable to find the standard library, base/builtin.imp");
+            return Err(self.lib_paths.with_searched(e, "base/builtin.imp").into());
+        }
+
+        if !content.contains("@import \"base/builtin.imp\";") {
+            if let Err(e) = parse.handle_import_macro(String::from("base/builtin.imp")) {
+                diagnostics.push(e);
+            }
+        }
+
+        match parse.lower(&module) {
+            Ok(expressions) if !diagnostics.has_errors() => Ok(expressions),
+            Ok(_) => Err(diagnostics),
+            Err(parse_diagnostics) => {
+                diagnostics.extend(parse_diagnostics);
+                Err(diagnostics)
+            },
+        }
+    }
+
+    pub fn check(&self, entry: &str) -> Diagnostics {
+        match self.parse(entry) {
+            Ok(_) => Diagnostics::new(),
+            Err(diagnostics) => diagnostics,
+        }
+    }
+
+    // the c for `entry` as a single file
+    pub fn transpile(&self, entry: &str) -> Output {
+        let expressions = match self.parse(entry) {
+            Ok(expressions) => expressions,
+            Err(diagnostics) => return Output { c: None, c_sources: Vec::new(), c_flags: Vec::new(), diagnostics },
+        };
+
+        // nothing is compiled, the compiler only decides which @link and @cflags are for the target
+        let compiler = Compiler::new("gcc", self.cfg.target.as_deref(), &[]).unwrap_or_default();
+        let mut gen = Gen::new(entry, "output", false, self.lang, compiler, self.line_directives);
+        match gen.generate_source(expressions) {
+            Ok(c) => Output { c: Some(c), c_sources: gen.c_sources().to_vec(), c_flags: gen.c_flags().to_vec(), diagnostics: Diagnostics::new() },
+            Err(e) => Output { c: None, c_sources: Vec::new(), c_flags: Vec::new(), diagnostics: e.into() },
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostic::{Diagnostic, Diagnostics};
 
 #[derive(Debug, Clone)]
 pub enum Token {
@@ -17,6 +21,7 @@ pub enum Token {
     Underscore,
     Colon,
     SemiColon,
+    Comma,
     Pipe,
     Newline,
 
@@ -46,8 +51,110 @@ pub enum Token {
     Ident(String),
     Str(String),
     Char(String),
-    Int(String),
-    // Digit(char), // this is a single digit inside an Int
+    Number(Number),
+    Square(Vec<SpannedToken>), // everything between a [ and its ], it's always between a Lsquare and Rsquare
+    CCode(String), // the inside of @c [...], as written
+}
+
+// a numeric literal, `digits` is already how c wants it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Number {
+    pub digits: String, // no `_`, binary and 0o octal are turned into hex and c octal
+    pub float: bool,
+    pub suffix: Option<String>, // the type it was given, like the u8 in 255u8
+}
+
+const INT_SUFFIXES: [&str; 11] = ["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "usize", "int", "uint"];
+const FLOAT_SUFFIXES: [&str; 2] = ["f32", "f64"];
+
+impl Number {
+    pub fn to_c(&self) -> String {
+        match &self.suffix {
+            Some(suffix) => format!("(({suffix}){})", self.digits),
+            None => self.digits.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lexeme = match self {
+            Token::Quote => "\"",
+            Token::SingleQuote => "'",
+            Token::Macro => "@",
+            Token::Lbrack => "(",
+            Token::Rbrack => ")",
+            Token::Lsquare => "[",
+            Token::Rsquare => "]",
+            Token::Lcurl => "{",
+            Token::Rcurl => "}",
+            Token::Underscore => "_",
+            Token::Colon => ":",
+            Token::SemiColon => ";",
+            Token::Comma => ",",
+            Token::Pipe => "|",
+            Token::Newline => "\n",
+            Token::Equal => "=",
+            Token::SmallerThan => "<",
+            Token::BiggerThan => ">",
+            Token::Exclaim => "!",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Multiply => "*",
+            Token::Divide => "/",
+            Token::Mod => "%",
+            Token::Caret => "^",
+            Token::Ampersand => "&",
+            Token::Dollar => "$",
+            Token::True => "true",
+            Token::False => "false",
+            Token::And => "and",
+            Token::Or => "or",
+            Token::Xor => "xor",
+            Token::Lshift => "lshift",
+            Token::Rshift => "rshift",
+            Token::Ident(text) | Token::Str(text) | Token::Char(text) | Token::CCode(text) => text,
+            Token::Number(number) => return write!(f, "{}", number.to_c()),
+            Token::Square(tokens) => return write!(f, "{}", tokens_text(tokens)),
+        };
+
+        write!(f, "{lexeme}")
+    }
+}
+
+// byte range into the source file plus the line and column (both starting at 1) it begins on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub col: u32,
+}
+
+impl Span {
+    // covers both spans, assumes `other` comes after `self`
+    pub fn to(self, other: Span) -> Span {
+        Span { end: other.end, ..self }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+// the tokens written back out, with a space wherever the source had whitespace between them
+pub fn tokens_text(tokens: &[SpannedToken]) -> String {
+    let mut text = String::new();
+    for (i, spanned) in tokens.iter().enumerate() {
+        if i > 0 && tokens[i-1].span.end < spanned.span.start {
+            text.push(' ');
+        }
+        text.push_str(&spanned.token.to_string());
+    }
+
+    text
 }
 
 pub fn check_ident_as_token(word: &String) -> Token {
@@ -63,163 +170,440 @@ pub fn check_ident_as_token(word: &String) -> Token {
     }
 }
 
-pub fn tokeniser(file: String) -> Vec<Token> {
-    let symb_to_token: HashMap<char, Token> = HashMap::from([
-        ('(', Token::Lbrack),
-        (')', Token::Rbrack),
-        ('{', Token::Lcurl),
-        ('}', Token::Rcurl),
-        ('[', Token::Lsquare),
-        (']', Token::Rsquare),
-        ('"', Token::Quote),
-        ('_', Token::Underscore),
-        (':', Token::Colon),
-        ('@', Token::Macro),
-        ('|', Token::Pipe),
-        ('=', Token::Equal),
-        ('<', Token::SmallerThan),
-        ('>', Token::BiggerThan),
-        ('!', Token::Exclaim),
-        ('^', Token::Caret),
-        ('&', Token::Ampersand),
-        ('$', Token::Dollar),
-        // ('.', Token::Dot),
-    ]);
-
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut buf = String::new();
-
-    let mut in_quotes = false;
-    let mut in_single_quotes = false;
-    let mut in_squares = false;
-    let mut square_occurences = 0;
-    let mut comment_line = false;
-
-    for c in file.chars() {
-        if c == '#' && !in_squares && !in_quotes && !in_single_quotes {
-            comment_line = true;
-            continue;
-        }
-
-        if comment_line && c == '\n' {
-            tokens.push(Token::Newline);
-            comment_line = false;
-            continue;
-        }
-
-        if comment_line {
-            continue;
-        }
-
-        if c == '[' && !in_quotes && !in_single_quotes {
-            square_occurences += 1;
-            if !in_squares {
-                if buf.len() > 0 {
-                    tokens.push(check_ident_as_token(&buf));
-                    buf.clear();
-                }
+fn symbol_token(c: char) -> Option<Token> {
+    let token = match c {
+        '(' => Token::Lbrack,
+        ')' => Token::Rbrack,
+        '{' => Token::Lcurl,
+        '}' => Token::Rcurl,
+        ':' => Token::Colon,
+        ';' => Token::SemiColon,
+        ',' => Token::Comma,
+        '@' => Token::Macro,
+        '|' => Token::Pipe,
+        '=' => Token::Equal,
+        '<' => Token::SmallerThan,
+        '>' => Token::BiggerThan,
+        '!' => Token::Exclaim,
+        '^' => Token::Caret,
+        '&' => Token::Ampersand,
+        '$' => Token::Dollar,
+        '+' => Token::Plus,
+        '-' => Token::Minus,
+        '*' => Token::Multiply,
+        '/' => Token::Divide,
+        '%' => Token::Mod,
+        _ => return None,
+    };
+
+    Some(token)
+}
 
-                in_squares = true;
-                tokens.push(Token::Lsquare);
-            } else {
-                buf.push(c);
-            }
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
 
-            continue;
-        }
+struct Lexer<'a> {
+    src: &'a str,
+    filename: &'a str,
+    pos: usize,
+    line: u32,
+    col: u32,
+    diagnostics: &'a mut Diagnostics,
+}
 
-        if in_squares && c != ']' {
-            buf.push(c);
-            continue;
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
         }
 
-        if c == ']' && !in_quotes && !in_single_quotes {
-            square_occurences -= 1;
-            if in_squares && square_occurences == 0 {
-                in_squares = false;
-                tokens.push(Token::Int(buf.clone()));
-                tokens.push(Token::Rsquare);
-                buf.clear();
-            } else if in_squares {
-                buf.push(c);
+        Some(c)
+    }
+
+    // a zero width span at the current position, grown with `from`
+    fn here(&self) -> Span {
+        Span { start: self.pos, end: self.pos, line: self.line, col: self.col }
+    }
+
+    fn from(&self, start: Span) -> Span {
+        Span { end: self.pos, ..start }
+    }
+
+    fn error(&mut self, span: Span, message: &str) {
+        self.diagnostics.push(Diagnostic::error(self.filename, span.line, message).with_span(span));
+    }
+
+    // lexes until the end of the file, or the `]` closing the square it's in
+    fn tokens(&mut self, in_square: bool) -> Vec<SpannedToken> {
+        let mut tokens: Vec<SpannedToken> = Vec::new();
+
+        while let Some(c) = self.peek() {
+            let start = self.here();
+
+            match c {
+                ' ' | '\t' | '\r' => {
+                    self.bump();
+                },
+                '\n' => {
+                    self.bump();
+                    // a [] can go over many lines, the statement it's in doesn't end
+                    if !in_square {
+                        tokens.push(SpannedToken { token: Token::Newline, span: self.from(start) });
+                    }
+                },
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                },
+                '"' => self.string(&mut tokens),
+                '\'' => self.char(&mut tokens),
+                '[' => {
+                    self.bump();
+                    tokens.push(SpannedToken { token: Token::Lsquare, span: self.from(start) });
+
+                    let is_c_embed = matches!(
+                        &tokens[..],
+                        [.., SpannedToken { token: Token::Macro, .. }, SpannedToken { token: Token::Ident(name), .. }, _] if name == "c"
+                    );
+                    let inner_start = self.here();
+                    let inner = if is_c_embed {
+                        self.c_code()
+                    } else {
+                        Token::Square(self.tokens(true))
+                    };
+                    tokens.push(SpannedToken { token: inner, span: self.from(inner_start) });
+
+                    let end = self.here();
+                    if self.peek() == Some(']') {
+                        self.bump();
+                        tokens.push(SpannedToken { token: Token::Rsquare, span: self.from(end) });
+                    } else {
+                        self.error(self.from(start), "unterminated `[`, missing a closing `]`");
+                    }
+                },
+                ']' => {
+                    if in_square {
+                        return tokens;
+                    }
+
+                    self.bump();
+                    self.error(self.from(start), "unexpected `]` without a `[` before it");
+                },
+                '-' if !in_square && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) => {
+                    // outside of [] there's no maths, so this can only be a negative number
+                    self.bump();
+                    let token = self.number(start, true);
+                    tokens.push(SpannedToken { token, span: self.from(start) });
+                },
+                '0'..='9' => {
+                    let token = self.number(start, false);
+                    tokens.push(SpannedToken { token, span: self.from(start) });
+                },
+                '_' if !self.peek_nth(1).is_some_and(is_ident_char) => {
+                    self.bump();
+                    tokens.push(SpannedToken { token: Token::Underscore, span: self.from(start) });
+                },
+                c if is_ident_char(c) => {
+                    let mut word = String::new();
+                    while let Some(c) = self.peek().filter(|c| is_ident_char(*c)) {
+                        word.push(c);
+                        self.bump();
+                    }
+                    tokens.push(SpannedToken { token: check_ident_as_token(&word), span: self.from(start) });
+                },
+                c => {
+                    self.bump();
+                    match symbol_token(c) {
+                        Some(token) => tokens.push(SpannedToken { token, span: self.from(start) }),
+                        None => self.error(self.from(start), &format!("unexpected character `{c}`")),
+                    }
+                },
             }
+        }
+
+        tokens
+    }
 
-            continue;
+    // takes the rest of an escape after its `\`, octal like `\101` is up to 3 digits and hex like `\x41` is every digit
+    fn escape(&mut self, start: Span) {
+        match self.bump() {
+            Some('n' | 't' | 'r' | '\\' | '\'' | '"' | 'a' | 'b' | 'f' | 'v' | 'e') => (),
+            Some('0'..='7') => {
+                for _ in 0..2 {
+                    if !self.peek().is_some_and(|c| ('0'..='7').contains(&c)) {
+                        break;
+                    }
+                    self.bump();
+                }
+            },
+            Some('x') => {
+                if !self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.error(self.from(start), "expected hex digits after `\\x`");
+                }
+                while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.bump();
+                }
+            },
+            Some('\n') | None => (), // the unterminated error covers it
+            Some(c) => self.error(self.from(start), &format!("unknown escape `\\{c}`")),
         }
+    }
 
-        if c == '\'' && !in_quotes {
-            in_single_quotes = !in_single_quotes;
+    fn string(&mut self, tokens: &mut Vec<SpannedToken>) {
+        let open = self.here();
+        self.bump();
+        tokens.push(SpannedToken { token: Token::Quote, span: self.from(open) });
+
+        let start = self.here();
+        loop {
+            match self.peek() {
+                Some('"') => break,
+                Some('\n') | None => {
+                    self.error(self.from(open), "unterminated string literal, missing a closing `\"`");
+                    return;
+                },
+                Some('\\') => {
+                    let escape = self.here();
+                    self.bump();
+                    self.escape(escape);
+                },
+                Some(_) => {
+                    self.bump();
+                },
+            }
+        }
+
+        tokens.push(SpannedToken { token: Token::Str(self.src[start.start..self.pos].to_string()), span: self.from(start) });
+        let close = self.here();
+        self.bump();
+        tokens.push(SpannedToken { token: Token::Quote, span: self.from(close) });
+    }
 
-            if !in_single_quotes {
-                tokens.push(Token::Char(buf.clone()));
-                buf.clear();
+    fn char(&mut self, tokens: &mut Vec<SpannedToken>) {
+        let open = self.here();
+        self.bump();
+        tokens.push(SpannedToken { token: Token::SingleQuote, span: self.from(open) });
+
+        let start = self.here();
+        let mut chars = 0;
+        loop {
+            match self.peek() {
+                Some('\'') => break,
+                Some('\n') | None => {
+                    self.error(self.from(open), "unterminated character literal, missing a closing `'`");
+                    return;
+                },
+                Some('\\') => {
+                    let escape = self.here();
+                    self.bump();
+                    self.escape(escape);
+                },
+                Some(_) => {
+                    self.bump();
+                },
             }
-            tokens.push(Token::SingleQuote);
-            continue;
+            chars += 1;
         }
 
-        if in_single_quotes {
-            buf.push(c);
-            continue;
+        let span = self.from(start);
+        if chars != 1 {
+            self.error(span, "a character literal has to be exactly one character");
         }
 
-        if c == '"' && !in_single_quotes {
-            in_quotes = !in_quotes;
+        tokens.push(SpannedToken { token: Token::Char(self.src[start.start..self.pos].to_string()), span });
+        let close = self.here();
+        self.bump();
+        tokens.push(SpannedToken { token: Token::SingleQuote, span: self.from(close) });
+    }
 
-            if !in_quotes {
-                tokens.push(Token::Str(buf.clone()));
-                buf.clear();
+    // c is left alone, only brackets are counted and strings, chars and comments skipped to find the `]`
+    fn c_code(&mut self) -> Token {
+        let start = self.pos;
+        let mut depth = 0;
+
+        while let Some(c) = self.peek() {
+            match c {
+                '[' => depth += 1,
+                ']' if depth == 0 => break,
+                ']' => depth -= 1,
+                '"' | '\'' => {
+                    self.bump();
+                    while let Some(inner) = self.peek().filter(|inner| *inner != c && *inner != '\n') {
+                        self.bump();
+                        if inner == '\\' {
+                            self.bump();
+                        }
+                    }
+                },
+                '/' if self.peek_nth(1) == Some('/') => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                    continue;
+                },
+                '/' if self.peek_nth(1) == Some('*') => {
+                    self.bump();
+                    self.bump();
+                    while self.peek().is_some() && !self.src[self.pos..].starts_with("*/") {
+                        self.bump();
+                    }
+                    self.bump();
+                },
+                _ => (),
             }
-            tokens.push(Token::Quote);
-            continue;
+            self.bump();
         }
 
-        if in_quotes {
-            buf.push(c);
-            continue;
-        }
+        Token::CCode(self.src[start..self.pos].to_string())
+    }
 
-        if c == '_' {
-            if !buf.is_empty() {
-                buf.push(c);
-            } else {
-                tokens.push(Token::Underscore);
+    fn digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_digit(radix) || *c == '_') {
+            if c != '_' {
+                digits.push(c);
             }
-            continue;
+            self.bump();
         }
 
-        if c == ' ' || c == '\n' || c == '\r' || c == ';' || c == '\t' {
-            if buf.len() > 0 {
-                tokens.push(check_ident_as_token(&buf));
-                buf.clear();
+        digits
+    }
+
+    // 10, 1_000, 0xff, 0b1010, 0o17, 1.5, 2e10 and any of them with a type after, like 255u8 or 1f32
+    fn number(&mut self, start: Span, negative: bool) -> Token {
+        let radix = match (self.peek(), self.peek_nth(1)) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('b' | 'B')) => 2,
+            (Some('0'), Some('o' | 'O')) => 8,
+            _ => 10,
+        };
+
+        let mut float = false;
+        let mut digits;
+        if radix == 10 {
+            digits = self.digits(10);
+            if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+                self.bump();
+                float = true;
+                digits.push('.');
+                digits.push_str(&self.digits(10));
             }
 
-            if c == '\n' {
-                tokens.push(Token::Newline);
-            } else if c == ';' {
-                tokens.push(Token::SemiColon);
+            let exponent = match (self.peek(), self.peek_nth(1), self.peek_nth(2)) {
+                (Some('e' | 'E'), Some(c), _) if c.is_ascii_digit() => true,
+                (Some('e' | 'E'), Some('+' | '-'), Some(c)) if c.is_ascii_digit() => true,
+                _ => false,
+            };
+            if exponent {
+                float = true;
+                digits.push(self.bump().unwrap());
+                if let Some(sign) = self.peek().filter(|c| *c == '+' || *c == '-') {
+                    digits.push(sign);
+                    self.bump();
+                }
+                digits.push_str(&self.digits(10));
             }
-            continue;
+        } else {
+            self.bump();
+            self.bump();
+            digits = self.digits(radix);
+        }
+
+        let mut suffix = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+            suffix.push(c);
+            self.bump();
         }
 
-        let token_res = symb_to_token.get(&c);
-        let token: (bool, Token);
-        match token_res {
-            Some(t) => token = (true, t.clone()),
-            None => {
-                buf.push(c);
-                token = (false, Token::Quote)
+        let span = self.from(start);
+        let mut number = Number { digits: String::new(), float, suffix: None };
+        if !suffix.is_empty() {
+            if FLOAT_SUFFIXES.contains(&suffix.as_str()) {
+                number.float = true;
+            } else if float || !INT_SUFFIXES.contains(&suffix.as_str()) {
+                let expected = if float { "f32 or f64" } else { "a number type like u8, i64 or f32" };
+                self.error(span, &format!("invalid suffix `{suffix}` on number literal, expected {expected}"));
             }
+            number.suffix = Some(suffix);
+        }
+
+        if digits.is_empty() {
+            self.error(span, "expected digits in number literal");
+            return Token::Number(number);
         }
 
-        if token.0 {
-            if buf.len() > 0 {
-                tokens.push(check_ident_as_token(&buf));
-                buf.clear();
+        if float {
+            number.digits = digits;
+        } else {
+            let value = match u64::from_str_radix(&digits, radix) {
+                Ok(value) => value,
+                Err(_) => {
+                    self.error(span, "integer literal is too large, it doesn't fit in 64 bits");
+                    0
+                },
+            };
+
+            if let Some(max) = number.suffix.as_deref().and_then(|suffix| suffix_max(suffix, negative)) {
+                if value > max {
+                    let suffix = number.suffix.as_deref().unwrap_or_default();
+                    self.error(span, &format!("integer literal is out of range for {suffix}"));
+                }
             }
-            tokens.push(token.1);
+
+            number.digits = match radix {
+                16 => format!("0x{digits}"),
+                2 => format!("{value:#x}"),
+                8 => format!("0{value:o}"),
+                _ => digits,
+            };
+        }
+
+        if negative {
+            number.digits.insert(0, '-');
         }
+
+        Token::Number(number)
     }
+}
+
+// the biggest value an integer suffix can hold, negative numbers of a signed type go one further
+fn suffix_max(suffix: &str, negative: bool) -> Option<u64> {
+    let (bits, signed) = match suffix {
+        "i8" => (8, true),
+        "i16" => (16, true),
+        "i32" | "int" => (32, true),
+        "i64" => (64, true),
+        "u8" => (8, false),
+        "u16" => (16, false),
+        "u32" | "uint" => (32, false),
+        "u64" | "usize" => (64, false),
+        _ => return None,
+    };
+
+    Some(match (signed, negative) {
+        (false, true) => 0,
+        (false, false) => u64::MAX >> (64 - bits),
+        (true, false) => (1 << (bits - 1)) - 1,
+        (true, true) => 1 << (bits - 1),
+    })
+}
 
-    tokens
+pub fn tokeniser(file: String, filename: &str, diagnostics: &mut Diagnostics) -> Vec<SpannedToken> {
+    let mut lexer = Lexer { src: &file, filename, pos: 0, line: 1, col: 1, diagnostics };
+    lexer.tokens(false)
 }
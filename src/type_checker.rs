@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use crate::{declare_types::Types, parser::*};
+use crate::{ast::{self, BinOp, ExprKind, ItemKind, StmtKind, UnOp}, declare_types::Types, diagnostic::{Diagnostic, Diagnostics}, parser::*, tokeniser::Span};
 use lazy_static::lazy_static;
 
 pub enum TCError<'a> {
@@ -19,7 +19,7 @@ pub struct ArgError<'a> {
 
 lazy_static! {
     static ref STRING_TYPES: HashMap<&'static str, Types> = {
-        let string_types = HashMap::from([
+        HashMap::from([
             ("u8", Types::U8),
             ("i8", Types::I8),
             ("char", Types::Char),
@@ -44,8 +44,7 @@ lazy_static! {
             ("bool", Types::Bool),
 
             ("any", Types::Any),
-        ]);
-        string_types
+        ])
     };
 }
 
@@ -61,12 +60,33 @@ fn create_pointer(typ: Types, pointer_counter: i32) -> Types {
 
 fn unwrap_pointer(t: &Types) -> &Types {
     match t {
-        Types::Pointer(pointer_to) => return unwrap_pointer(pointer_to),
-        _ => return t
+        Types::Pointer(pointer_to) => unwrap_pointer(pointer_to),
+        _ => t
     }
 }
 
-pub fn string_to_type(type_name: &String) -> Types {
+// the type of arr[i], an array gives its element and a pointer what it points to
+fn indexed_type(t: &Types) -> &Types {
+    match t {
+        Types::Arr { typ, .. } => typ,
+        _ => unwrap_pointer(t),
+    }
+}
+
+// the type of x^, following one pointer per ^
+fn deref_type(e: &Expr) -> Option<Types> {
+    match e {
+        Expr::DerefPointer(inner) => match deref_type(inner)? {
+            Types::Pointer(pointer_to) => Some(*pointer_to),
+            _ => None,
+        },
+        Expr::VariableName { typ: Types::ArrIndex { arr_typ, .. }, .. } => Some(indexed_type(arr_typ).clone()),
+        Expr::VariableName { typ, .. } => Some(typ.clone()),
+        _ => None,
+    }
+}
+
+pub fn string_to_type(type_name: &str) -> Types {
     let mut buffer = String::new();
     let mut pointer_counter = 0;
 
@@ -76,7 +96,7 @@ pub fn string_to_type(type_name: &String) -> Types {
 
             let typ = match STRING_TYPES.get(buffer.as_str()) {
                 Some(t) => t.clone(),
-                None => Types::TypeDef { type_name: type_name.clone(), generics: None },
+                None => Types::TypeDef { type_name: type_name.to_string(), generics: None },
             };
 
             return create_pointer(typ, pointer_counter);
@@ -87,10 +107,10 @@ pub fn string_to_type(type_name: &String) -> Types {
         }
     }
 
-    return Types::None
+    Types::None
 }
 
-fn get_return_type<'a, 'b>(func_name: &'a String, funcs: &'b Vec<Expr>) -> Option<&'b Types> {
+fn get_return_type<'b>(func_name: &String, funcs: &'b [Expr]) -> Option<&'b Types> {
     if funcs.is_empty() {
         return None
     }
@@ -110,7 +130,7 @@ fn get_return_type<'a, 'b>(func_name: &'a String, funcs: &'b Vec<Expr>) -> Optio
     None
 }
 
-fn get_args<'a, 'b>(func_name: &'a String, funcs: &'b Vec<Expr>) -> Option<&'b Vec<Expr>> {
+fn get_args<'b>(func_name: &String, funcs: &'b [Expr]) -> Option<&'b Vec<Expr>> {
     if funcs.is_empty() {
         return None;
     }
@@ -132,16 +152,16 @@ fn get_args<'a, 'b>(func_name: &'a String, funcs: &'b Vec<Expr>) -> Option<&'b V
 
 fn compare_type_and_type(t1: &Types, t2: &Types) -> bool {
     match (t1, t2) {
-        (Types::Any, _) | (_, Types::Any) => return true,
+        (Types::Any, _) | (_, Types::Any) => true,
         (
             Types::U8 | Types::I8 | Types::U16 | Types::I16 | Types::U32 | Types::I32 | Types::Usize |
             Types::U64 | Types::I64 | Types::Int | Types::F32 | Types::F64 | Types::Char | Types::UInt,
             Types::U8 | Types::I8 | Types::U16 | Types::I16 | Types::U32 | Types::I32 | Types::Usize |
             Types::U64 | Types::I64 | Types::Int | Types::F32 | Types::F64 | Types::Char | Types::UInt
-         ) => return true,
+         ) => true,
         (Types::Pointer(t1_subtype), Types::Pointer(t2_subtype)) => {
-            let t1_unwrapped = unwrap_pointer(&t1_subtype);
-            let t2_unwrapped = unwrap_pointer(&t2_subtype);
+            let t1_unwrapped = unwrap_pointer(t1_subtype);
+            let t2_unwrapped = unwrap_pointer(t2_subtype);
             if t1_unwrapped == &Types::Void || t2_unwrapped == &Types::Void { return true; }
             // else if t1_unwrapped == &Types::Generic || t2_unwrapped == &Types::Generic { return true; }
             else if let Types::Generic(_) = t1_unwrapped {
@@ -150,49 +170,52 @@ fn compare_type_and_type(t1: &Types, t2: &Types) -> bool {
                 return true;
             }
 
-            return t1_unwrapped == t2_unwrapped;
+            t1_unwrapped == t2_unwrapped
         },
         (Types::TypeDef { type_name: t1_name, generics: t1_generics }, Types::TypeDef { type_name: t2_name, generics: t2_generics }) => {
             if t1_name != t2_name { return false }
 
             match (t1_generics, t2_generics) {
-                (None, None) => return true,
+                (None, None) => true,
                 (Some(t1_gen), Some(t2_gen)) => {
-                    // TODO: actually compare the generics
-                    return t1_gen.len() == t2_gen.len()
+                    t1_gen.len() == t2_gen.len() && generic_difference(t1, t2).is_none()
                 }
-                (None, Some(_)) => return false,
-                (Some(_), None) => return false,
+                (None, Some(_)) => false,
+                (Some(_), None) => false,
             }
         },
         (Types::TypeDef { .. }, Types::ArrIndex { arr_typ, .. }) => {
-            let unwrap_arr_typ = unwrap_pointer(&arr_typ);
-            return compare_type_and_type(t1, unwrap_arr_typ);
+            let unwrap_arr_typ = indexed_type(arr_typ);
+            compare_type_and_type(t1, unwrap_arr_typ)
         },
         // TODO: maybe change the generic to the correct type when checking
-        (Types::Generic(_), _) => return true,
-        (_, Types::Generic(_)) => return true,
+        (Types::Generic(_), _) => true,
+        (_, Types::Generic(_)) => true,
         _ => {
-            return t1 == t2
+            t1 == t2
         },
     }
 }
 
-pub fn compare_type_and_expr(t: &Types, e: &Expr, funcs: &Vec<Expr>) -> (bool, Types) {
+pub fn compare_type_and_expr(t: &Types, e: &Expr, funcs: &[Expr]) -> (bool, Types) {
     match (t, e) {
         // TODO: double check, think this is fine as parser handles this?
-        (Types::Any, _) => return (true, Types::None),
-        (Types::Let, Expr::IntLit(_)) => return (true, Types::Int), 
-        (Types::Let, Expr::CharLit(_)) => return (true, Types::Char),
-        (Types::Let, Expr::StrLit(_)) => return (true, Types::Pointer(Box::new(Types::Char))),
-        (Types::Let, Expr::True | Expr::False) => return (true, Types::Bool),
+        (Types::Any, _) => (true, Types::None),
+        (Types::Let, Expr::IntLit(_) | Expr::Maths(_)) => (true, Types::Int),
+        (Types::Let, Expr::CharLit(_)) => (true, Types::Char),
+        (Types::Let, Expr::StrLit(_)) => (true, Types::Pointer(Box::new(Types::Char))),
+        (Types::Let, Expr::True | Expr::False | Expr::Condition(_)) => (true, Types::Bool),
         (Types::Let, Expr::VariableName { typ, .. }) => {
             if let Types::ArrIndex { arr_typ, .. } = typ {
-                let unwrap_arr_typ = unwrap_pointer(arr_typ);
+                let unwrap_arr_typ = indexed_type(arr_typ);
 
                 return (true, unwrap_arr_typ.clone())
             }
-            return (true, typ.clone())
+            (true, typ.clone())
+        },
+        (Types::Let, Expr::DerefPointer(_)) => match deref_type(e) {
+            Some(typ) => (true, typ),
+            None => (false, Types::None),
         },
         (Types::Let, Expr::FuncCall { name, gave_params }) => {
             let ret_type_op = get_return_type(name, funcs);
@@ -218,14 +241,12 @@ pub fn compare_type_and_expr(t: &Types, e: &Expr, funcs: &Vec<Expr>) -> (bool, T
                         }
                     }
 
-                    if let Types::TypeDef { generics, type_name } = ret_type {
-                        if let Some(gens) = generics {
-                            if types.len() != gens.len() {
-                                return (false, Types::None)
-                            }
-
-                            return (true, Types::TypeDef { type_name: type_name.to_owned(), generics: Some(types) })
+                    if let Types::TypeDef { generics: Some(gens), type_name } = ret_type {
+                        if types.len() != gens.len() {
+                            return (false, Types::None)
                         }
+
+                        return (true, Types::TypeDef { type_name: type_name.to_owned(), generics: Some(types) })
                     }
 
                     if let Types::Generic(_) = ret_type {
@@ -233,94 +254,100 @@ pub fn compare_type_and_expr(t: &Types, e: &Expr, funcs: &Vec<Expr>) -> (bool, T
                             return (true, types[0].clone())
                         }
                     }
-                    return (true, ret_type.clone())
+                    (true, ret_type.clone())
                 },
-                None => return (false, Types::None),
+                None => (false, Types::None),
             }
         },
-        (Types::TypeId, _) => return (true, Types::None),
-        (Types::Char, Expr::CharLit(_)) => return (true, Types::None),
+        (Types::TypeId, _) => (true, Types::None),
+        (Types::Char, Expr::CharLit(_)) => (true, Types::None),
         (
             Types::U8 | Types::I8 | Types::U16 | Types::I16 | Types::U32 | Types::I32 | Types::Usize |
             Types::U64 | Types::I64 | Types::Int | Types::F32 | Types::F64 | Types::Char | Types::UInt |
             Types::Pointer(_),
-            Expr::IntLit(_)
-        ) => return (true, Types::None),
+            Expr::IntLit(_) | Expr::Maths(_)
+        ) => (true, Types::None),
         // TODO: maybe change the generic to the correct type when checking
-        (Types::Generic(_), _) => return (true, Types::None),
+        (Types::Generic(_), _) => (true, Types::None),
         (Types::ArrIndex { arr_typ, .. }, _) => {
-            let typ = unwrap_pointer(arr_typ);
+            let typ = indexed_type(arr_typ);
             let compare = compare_type_and_expr(typ, e, funcs);
-            return (compare.0, compare.1);
+            (compare.0, compare.1)
         },
         (Types::TypeDef { generics, .. }, Expr::VariableName { typ, .. }) => {
             // this is for enums
-            if generics == &None && typ == &Types::None {
+            if generics.is_none() && typ == &Types::None {
                 return (true, Types::None);
             }
-            return (compare_type_and_type(t, typ), Types::None);
+            (compare_type_and_type(t, typ), Types::None)
+        },
+        (_, Expr::DerefPointer(_)) => match deref_type(e) {
+            Some(typ) => (compare_type_and_type(t, &typ), Types::None),
+            None => (false, Types::None),
         },
-        (_, Expr::GarbageValue) => return (true, Types::None),
-        (Types::TypeDef { .. }, Expr::DefaultValue) => return (true, Types::None),
+        (_, Expr::GarbageValue) => (true, Types::None),
+        (Types::TypeDef { .. }, Expr::DefaultValue) => (true, Types::None),
         (Types::Arr { typ: arr_typ, .. }, Expr::VariableName { typ: var_typ, .. }) => {
             if let Types::Arr { typ: var_arr_typ, .. } = var_typ {
                 if let Types::Any = **arr_typ {
                     return (true, Types::None)
-                } else if t == var_typ {
-                    return (true, Types::None);
-                } else if arr_typ == var_arr_typ {
+                } else if t == var_typ || arr_typ == var_arr_typ {
                     return (true, Types::None);
                 }
             }
 
-            return compare_type_and_expr(arr_typ, e, funcs)
+            compare_type_and_expr(arr_typ, e, funcs)
         },
         (_, Expr::VariableName { typ, .. }) => {
             if let Types::ArrIndex { arr_typ, .. } = typ {
-                let unwrap_arr_typ = unwrap_pointer(arr_typ);
+                let unwrap_arr_typ = indexed_type(arr_typ);
                 return (compare_type_and_type(t, unwrap_arr_typ), Types::None)
             }
-            return (compare_type_and_type(t, typ), Types::None);
+            (compare_type_and_type(t, typ), Types::None)
         },
         (_, Expr::FuncCall { name, .. }) => {
             let ret_type_op = get_return_type(name, funcs);
             match ret_type_op {
-                Some(ret_type) => return (compare_type_and_type(t, ret_type), Types::None),
-                None => return (false, Types::None),
+                Some(ret_type) => (compare_type_and_type(t, ret_type), Types::None),
+                None => (false, Types::None),
             }
         },
         (Types::Pointer(pointer_to), Expr::Address(address_to)) => {
-            let compare = compare_type_and_expr(&pointer_to, address_to, funcs);
-            return (compare.0, compare.1);
+            let compare = compare_type_and_expr(pointer_to, address_to, funcs);
+            (compare.0, compare.1)
         },
         (Types::Pointer(pointer_to), Expr::StrLit(_)) => {
-            return (compare_type_and_type(&pointer_to, &Types::Char), Types::None)
+            (compare_type_and_type(pointer_to, &Types::Char), Types::None)
         }
         (Types::Pointer(pointer_to), _) => {
-            let compare = compare_type_and_expr(&pointer_to, e, funcs);
-            return (compare.0, compare.1)
+            let compare = compare_type_and_expr(pointer_to, e, funcs);
+            (compare.0, compare.1)
         },
         // if you are using a CEmbed, you should know what you're doing. either way, gcc will pick it up
-        (_, Expr::CEmbed(_)) => return (true, Types::None),
+        (_, Expr::CEmbed(_)) => (true, Types::None),
 
         // TODO: typecheck all the contents in the literal
-        (Types::Arr { .. }, Expr::ArrayLit(_)) => return (true, Types::None),
-        (Types::TypeDef { .. }, Expr::ArrayLit(_)) => return (true, Types::None),
-        (Types::Bool, Expr::True | Expr::False) => return (true, Types::None),
+        (Types::Arr { .. }, Expr::ArrayLit(_)) => (true, Types::None),
+        (Types::TypeDef { .. }, Expr::ArrayLit(_)) => (true, Types::None),
+        (Types::Bool, Expr::True | Expr::False | Expr::Condition(_)) => (true, Types::None),
 
         // this is for enums
-        (Types::None, Expr::IntLit(_)) => return (true, Types::None),
-        _ => return (false, Types::None),
+        (Types::None, Expr::IntLit(_)) => (true, Types::None),
+        _ => (false, Types::None),
     }
 }
 
-pub fn compare_exprs_and_args<'a>(exprs: &'a Vec<Expr>, func_name: &'a String, funcs: &'a Vec<Expr>) -> Result<(), ArgError<'a>> {
+pub fn compare_exprs_and_args<'a>(exprs: &'a [Expr], func_name: &'a String, funcs: &'a [Expr]) -> Result<(), ArgError<'a>> {
     let args = match get_args(func_name, funcs) {
         Some(a) => a,
         None => return Err(ArgError { pos: 0, error: TCError::FuncNotExist }),
     };
 
-    if exprs.len() != args.len() && (func_name != "print" && func_name != "println" && func_name != "string__format") {
+    // @extern functions with `...` take anything after their params
+    let variadic = funcs.iter().any(|func| matches!(func, Expr::Func { name, variadic: true, .. } if &name.replace('.', "__") == func_name));
+    if variadic && exprs.len() < args.len() {
+        return Err(ArgError { pos: 0, error: TCError::Custom(format!("expects at least {} arguments, got {} instead", args.len(), exprs.len())) });
+    } else if !variadic && exprs.len() != args.len() && (func_name != "print" && func_name != "println" && func_name != "string__format") {
         return Err(ArgError { pos: 0, error: TCError::WrongArgLength(exprs.len(), args.len()) });
     }
 
@@ -336,8 +363,8 @@ pub fn compare_exprs_and_args<'a>(exprs: &'a Vec<Expr>, func_name: &'a String, f
 
     if skip_check { return Ok(()) }
     let mut typeid_name_to_type: HashMap<&String, &String> = HashMap::new();
-    for (i, expr) in exprs.iter().enumerate() {
-        match (&args[i], expr) {
+    for (i, (expr, arg)) in exprs.iter().zip(args).enumerate() {
+        match (arg, expr) {
             (Expr::VariableName { typ: arg_typ, name: arg_name, constant: arg_const, ..}, _) => {
                 if let Expr::VariableName { typ: expr_typ, name: expr_name, constant: expr_const, .. } = expr {
                     if let Types::TypeId = expr_typ {
@@ -361,7 +388,7 @@ pub fn compare_exprs_and_args<'a>(exprs: &'a Vec<Expr>, func_name: &'a String, f
                     }
                 }
 
-                if !compare_type_and_expr(&arg_typ, expr, funcs).0 {
+                if !compare_type_and_expr(arg_typ, expr, funcs).0 {
                     return Err(ArgError { pos: i + 1, error: TCError::MismatchExprType(expr, arg_typ) });
                 }
             }
@@ -371,3 +398,819 @@ pub fn compare_exprs_and_args<'a>(exprs: &'a Vec<Expr>, func_name: &'a String, f
 
     Ok(())
 }
+
+// the pass after lowering that works out the type of every expression in a module and checks
+// them against where they're used. every name is known to exist by now, so anything it can't
+// work out (c embeds, generics, @default) is let through as `any`
+pub struct TypeChecker<'a> {
+    weights: &'a ExprWeights,
+    filename: &'a str,
+    scopes: Vec<HashMap<String, Types>>,
+    func: String, // the function being checked
+    ret: Types,
+    diagnostics: Diagnostics,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(weights: &'a ExprWeights, filename: &'a str) -> TypeChecker<'a> {
+        TypeChecker { weights, filename, scopes: Vec::new(), func: String::new(), ret: Types::Void, diagnostics: Diagnostics::new() }
+    }
+
+    pub fn check(mut self, module: &ast::Module) -> Diagnostics {
+        for item in &module.items {
+            self.item(item, None);
+        }
+
+        self.diagnostics
+    }
+
+    fn err(&mut self, span: Span, message: &str) {
+        self.diagnostics.push(Diagnostic::error(self.filename, span.line, message).with_span(span));
+    }
+
+    // numbers that don't convert by themselves can still be cast
+    fn mismatch(&mut self, span: Span, message: String, expected: &Types, found: &Types) {
+        let mut diagnostic = Diagnostic::error(self.filename, span.line, &message).with_span(span);
+        if is_number(expected) && is_number(found) {
+            diagnostic = diagnostic.with_note(&format!("{found} doesn't always fit in {expected}, use `cast({expected} ...)` if that's fine"));
+        } else if let Some(difference) = generic_difference(expected, found) {
+            diagnostic = diagnostic.with_note(&difference);
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn item(&mut self, item: &ast::Item, owner: Option<&String>) {
+        match &item.kind {
+            ItemKind::Func(func) => self.func(func, owner),
+            ItemKind::Struct(structure) => {
+                for sub in &structure.body {
+                    self.item(sub, Some(&structure.name));
+                }
+            },
+            ItemKind::Enum(enumeration) => {
+                for sub in &enumeration.body {
+                    self.item(sub, Some(&enumeration.name));
+                }
+            },
+            ItemKind::Var(decl) if owner.is_none() => self.var(decl),
+            _ => (),
+        }
+    }
+
+    fn func(&mut self, func: &ast::Func, owner: Option<&String>) {
+        let name = match owner {
+            Some(owner) => format!("{owner}.{}", func.name),
+            None => func.name.clone(),
+        };
+
+        let (ret, params) = match self.weights.find_global_ident(name.clone()) {
+            Expr::Func { typ, params, .. } | Expr::MacroFunc { typ, params, .. } => (typ, params),
+            _ => return,
+        };
+
+        let mut scope = HashMap::new();
+        for param in params {
+            if let Expr::VariableName { typ, name, .. } = param {
+                scope.insert(name, typ);
+            }
+        }
+
+        self.func = name;
+        self.ret = ret;
+        self.scopes = vec![scope];
+        self.block(&func.body);
+        self.scopes.clear();
+    }
+
+    fn block(&mut self, block: &ast::Block) {
+        self.scopes.push(HashMap::new());
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &str, typ: Types) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), typ);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &ast::Stmt) {
+        match &stmt.kind {
+            StmtKind::Var(decl) => self.var(decl),
+            StmtKind::Assign { target, value } => {
+                let expected = self.infer(target);
+                if let Err(found) = self.expect(&expected, value) {
+                    self.mismatch(value.span, format!("mismatched types: expected {expected}, found {found}"), &expected, &found);
+                }
+            },
+            StmtKind::Expr(expr) => {
+                self.infer(expr);
+            },
+            StmtKind::If(branches) => {
+                for branch in branches {
+                    self.scopes.push(HashMap::new());
+                    match (&branch.cond, &branch.capture) {
+                        (Some(cond), Some(capture)) => {
+                            let captured = match self.infer(cond) {
+                                Types::TypeDef { type_name, generics: Some(generics) } if type_name == "option" && !generics.is_empty() => generics[0].clone(),
+                                _ => Types::Any,
+                            };
+                            self.declare(capture, captured);
+                        },
+                        (Some(cond), None) => self.condition(cond),
+                        _ => (),
+                    }
+                    self.block(&branch.body);
+                    self.scopes.pop();
+                }
+            },
+            StmtKind::Switch { cond, cases, .. } => {
+                let switched = self.infer(cond);
+                for case in cases {
+                    if let Some(value) = &case.cond {
+                        let found = self.infer(value);
+                        if !self.accepts(&switched, &found, value) {
+                            self.err(value.span, &format!("case is {found} but the switch is on {switched}"));
+                        }
+                    }
+                    self.block(&case.body);
+                }
+            },
+            StmtKind::Loop { cond, body, .. } => {
+                self.scopes.push(HashMap::new());
+                if let Some(cond) = cond {
+                    // only the first name of a loop can be new, it becomes the counter
+                    if let Some(counter) = first_name(cond) {
+                        if self.lookup(counter).is_none() {
+                            self.declare(counter, Types::Usize);
+                        }
+                    }
+                    self.condition(cond);
+                }
+                self.block(body);
+                self.scopes.pop();
+            },
+            StmtKind::For { iter, elem, elem_ptr, index, body } => {
+                let elem_typ = match self.infer(iter) {
+                    Types::Pointer(to) => for_elem(&to),
+                    typ => for_elem(&typ),
+                };
+
+                self.scopes.push(HashMap::new());
+                self.declare(elem, if *elem_ptr { Types::Pointer(Box::new(elem_typ)) } else { elem_typ });
+                if let Some(index) = index {
+                    self.declare(index, Types::Usize);
+                }
+                self.block(body);
+                self.scopes.pop();
+            },
+            StmtKind::Defer(inner) => self.stmt(inner),
+            StmtKind::Return(value) => self.ret(value.as_ref(), stmt.span),
+            StmtKind::Block(block) => self.block(block),
+            StmtKind::Break | StmtKind::Continue | StmtKind::Mut(_) | StmtKind::CEmbed(_) => (),
+        }
+    }
+
+    // a declaration, `let` takes the type of its value
+    fn var(&mut self, decl: &ast::VarDecl) {
+        let mut typ = self.resolve(&decl.typ);
+        match (&typ, &decl.value) {
+            (Types::Let, Some(value)) => typ = self.infer(value),
+            (Types::TypeId, Some(value)) => {
+                self.infer(value);
+            },
+            (_, Some(value)) => {
+                if let Err(found) = self.expect(&typ, value) {
+                    self.mismatch(value.span, format!("mismatched types: expected {typ}, found {found}"), &typ, &found);
+                }
+            },
+            (_, None) => (),
+        }
+
+        self.declare(&decl.name, typ);
+    }
+
+    fn ret(&mut self, value: Option<&ast::Expr>, span: Span) {
+        let ret = self.ret.clone();
+        match (&ret, value) {
+            // macro functions return whatever their typeids say
+            (Types::Any | Types::TypeId | Types::Generic(_), _) => {
+                if let Some(value) = value {
+                    self.infer(value);
+                }
+            },
+            (Types::Void, None) => (),
+            (Types::Void, Some(value)) => {
+                let found = self.infer(value);
+                self.err(value.span, &format!("function {} doesn't return anything, found {found}", self.func));
+            },
+            (ret, None) => self.err(span, &format!("function {} has to return {ret}", self.func)),
+            (ret, Some(value)) => {
+                if let Err(found) = self.expect(ret, value) {
+                    self.mismatch(value.span, format!("function {} returns {ret}, found {found}", self.func), ret, &found);
+                }
+            },
+        }
+    }
+
+    fn condition(&mut self, cond: &ast::Expr) {
+        let typ = self.infer(cond);
+        if !is_truthy(&typ) && !is_loose(&typ) {
+            self.err(cond.span, &format!("conditions have to be a bool, found {typ}"));
+        }
+    }
+
+    // a type as written, arrays don't need their length to be worked out to be compared
+    fn resolve(&self, typ: &ast::Type) -> Types {
+        match typ {
+            ast::Type::Pointer(to) => match &**to {
+                ast::Type::Void => Types::Pointer(Box::new(Types::Void)),
+                to => Types::Pointer(Box::new(self.resolve(to))),
+            },
+            ast::Type::Array { elem, .. } => Types::Arr { typ: Box::new(self.resolve(elem)), length: String::new() },
+            ast::Type::Generic(name) => Types::Generic(name.clone()),
+            ast::Type::Void => Types::Void,
+            ast::Type::Name { .. } => self.weights.resolve_type(typ),
+        }
+    }
+
+    // None when the name doesn't exist at all
+    fn lookup(&self, name: &str) -> Option<Types> {
+        let mut fields = name.split('.');
+        let root = fields.next()?;
+
+        let local = self.scopes.iter().rev().find_map(|scope| scope.get(root)).cloned();
+        let root_typ = match local {
+            Some(typ) => Some(typ),
+            None => match self.weights.find_global_ident(root.to_string()) {
+                Expr::VariableName { typ, .. } => Some(typ),
+                _ => None,
+            },
+        };
+
+        if let Some(mut typ) = root_typ {
+            for field in fields {
+                typ = self.field_type(&typ, field).unwrap_or(Types::Any);
+            }
+            return Some(typ);
+        }
+
+        match self.weights.find_global_ident(name.to_string()) {
+            // enum fields don't carry a type, they're the enum's
+            Expr::VariableName { typ: Types::None, .. } => match name.rsplit_once('.') {
+                Some((enumeration, _)) => Some(Types::TypeDef { type_name: enumeration.to_string(), generics: None }),
+                None => Some(Types::Any),
+            },
+            Expr::VariableName { typ, .. } => Some(typ),
+            Expr::StructDef { .. } | Expr::MacroStructDef { .. } => Some(Types::TypeId),
+            Expr::None if STRING_TYPES.contains_key(name) => Some(Types::TypeId),
+            Expr::None => None,
+            _ => Some(Types::Any),
+        }
+    }
+
+    fn field_type(&self, typ: &Types, field: &str) -> Option<Types> {
+        match typ {
+            Types::Pointer(to) => self.field_type(to, field),
+            Types::Arr { typ, .. } => match field {
+                "len" => Some(Types::Usize),
+                "data" => Some(Types::Pointer(typ.clone())),
+                _ => None,
+            },
+            Types::TypeDef { type_name, generics } => {
+                let (typeids, fields) = match self.weights.find_structure(&type_name.trim_start_matches("struct ").to_string()) {
+                    Expr::StructDef { struct_fields, .. } => (Vec::new(), struct_fields),
+                    Expr::MacroStructDef { struct_name, struct_fields } => match *struct_name {
+                        Expr::MacroStructName { generics, .. } => (generics, struct_fields),
+                        _ => (Vec::new(), struct_fields),
+                    },
+                    _ => return None,
+                };
+
+                let found = fields.into_iter().find_map(|field_expr| match field_expr {
+                    Expr::Variable { info, .. } => match *info {
+                        Expr::VariableName { typ, name, .. } if name == field => Some(typ),
+                        _ => None,
+                    },
+                    Expr::VariableName { typ, name, .. } if name == field => Some(typ),
+                    _ => None,
+                })?;
+
+                // a generic struct's fields use its typeids, swap in what it was made with
+                let mut given = HashMap::new();
+                for (typeid, generic) in typeids.iter().zip(generics.iter().flatten()) {
+                    if let Expr::Variable { info, .. } = typeid {
+                        if let Expr::VariableName { name, .. } = &**info {
+                            given.insert(name.clone(), generic.clone());
+                        }
+                    }
+                }
+                Some(substitute(&found, &given))
+            },
+            Types::Any | Types::Generic(_) => Some(Types::Any),
+            _ => None,
+        }
+    }
+
+    fn infer(&mut self, expr: &ast::Expr) -> Types {
+        match &expr.kind {
+            ExprKind::Number(number) => match &number.suffix {
+                Some(suffix) => string_to_type(suffix),
+                None if number.float => Types::F64,
+                None => Types::Int,
+            },
+            ExprKind::Str(_) => Types::Pointer(Box::new(Types::Char)),
+            ExprKind::Char(_) => Types::Char,
+            ExprKind::Bool(_) => Types::Bool,
+            ExprKind::Name(name) => self.lookup(name).unwrap_or(Types::Any),
+            ExprKind::Call { name, args } => self.call(name, args),
+            ExprKind::Index { base, index } => {
+                let base_typ = self.infer(base);
+                let index_typ = self.infer(index);
+                if !is_integer(&index_typ) && !is_loose(&index_typ) {
+                    self.err(index.span, &format!("indexes have to be integers, found {index_typ}"));
+                }
+
+                match base_typ {
+                    Types::Arr { typ, .. } => *typ,
+                    Types::Pointer(to) if *to != Types::Void => *to,
+                    typ if is_loose(&typ) => Types::Any,
+                    typ => {
+                        self.err(base.span, &format!("can't index {typ}, it isn't an array or pointer"));
+                        Types::Any
+                    },
+                }
+            },
+            ExprKind::Deref(inner) => match self.infer(inner) {
+                Types::Pointer(to) if *to == Types::Void => Types::Any,
+                Types::Pointer(to) => *to,
+                typ if is_loose(&typ) => Types::Any,
+                typ => {
+                    self.err(inner.span, &format!("can't dereference {typ}, it isn't a pointer"));
+                    Types::Any
+                },
+            },
+            ExprKind::Address(inner) => Types::Pointer(Box::new(self.infer(inner))),
+            ExprKind::Type(_) => Types::TypeId,
+            ExprKind::Square(inner) | ExprKind::Paren(inner) => self.infer(inner),
+            ExprKind::Binary { op, lhs, rhs } => self.binary(*op, lhs, rhs),
+            ExprKind::Unary { op, expr: inner } => self.unary(*op, inner),
+            ExprKind::ArrayLit(elems) => {
+                let mut elem_typ = Types::Any;
+                for (i, elem) in elems.iter().enumerate() {
+                    let typ = self.infer(elem);
+                    if i == 0 {
+                        elem_typ = typ;
+                    } else if !self.accepts(&elem_typ, &typ, elem) {
+                        self.err(elem.span, &format!("array elements have to be the same type, found {elem_typ} and {typ}"));
+                    }
+                }
+                Types::Arr { typ: Box::new(elem_typ), length: elems.len().to_string() }
+            },
+            ExprKind::Cast { typ, expr: inner } => self.cast(typ, inner),
+            // an embed that's only `{name}` is that name
+            ExprKind::CEmbed(code) => match embedded_name(code.trim().trim_end_matches(';').trim_end()) {
+                Some(name) => self.lookup(name).unwrap_or(Types::Any),
+                None => Types::Any,
+            },
+            ExprKind::Default | ExprKind::Garbage => Types::Any,
+        }
+    }
+
+    fn call(&mut self, name: &String, args: &[ast::Expr]) -> Types {
+        let (ret, params) = match self.weights.find_global_ident(name.clone()) {
+            Expr::Func { typ, params, .. } | Expr::MacroFunc { typ, params, .. } => (typ, params),
+            _ => (Types::Any, Vec::new()),
+        };
+
+        let mut typeids = HashMap::new();
+        for (i, arg) in args.iter().enumerate() {
+            // print, println and string.format take anything after their params
+            let Some(Expr::VariableName { typ, name: param, .. }) = params.get(i) else {
+                self.infer(arg);
+                continue;
+            };
+
+            if let Types::TypeId = typ {
+                self.infer(arg);
+                typeids.insert(param.clone(), self.typeid_arg(arg));
+                continue;
+            }
+
+            let expected = substitute(typ, &typeids);
+            if let Err(found) = self.expect(&expected, arg) {
+                self.mismatch(arg.span, format!("function {name} expected argument {} to be {expected}, found {found}", i + 1), &expected, &found);
+            }
+        }
+
+        substitute(&ret, &typeids)
+    }
+
+    // the type a typeid argument stands for
+    fn typeid_arg(&self, arg: &ast::Expr) -> Types {
+        match &arg.kind {
+            ExprKind::Type(typ) => self.resolve(typ),
+            ExprKind::Name(name) => self.resolve(&ast::Type::Name { name: name.clone(), generics: None }),
+            _ => Types::Any,
+        }
+    }
+
+    fn binary(&mut self, op: BinOp, lhs: &ast::Expr, rhs: &ast::Expr) -> Types {
+        let l = self.infer(lhs);
+        let r = self.infer(rhs);
+        let gives_bool = matches!(op, BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::And | BinOp::Or);
+        if is_loose(&l) || is_loose(&r) {
+            return if gives_bool { Types::Bool } else { Types::Any };
+        }
+
+        let text = op_text(op);
+        let span = lhs.span.to(rhs.span);
+        match op {
+            BinOp::Add | BinOp::Sub if matches!(l, Types::Pointer(_)) && is_integer(&r) => l,
+            BinOp::Add if is_integer(&l) && matches!(r, Types::Pointer(_)) => r,
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
+                if !is_number(&l) || !is_number(&r) {
+                    self.err(span, &format!("can't use `{text}` on {l} and {r}"));
+                    return Types::Any;
+                }
+                self.arith(text, span, l, r, lhs, rhs)
+            },
+            BinOp::Mod | BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr => {
+                if !is_integer(&l) || !is_integer(&r) {
+                    self.err(span, &format!("`{text}` only works on integers, found {l} and {r}"));
+                    return Types::Any;
+                }
+                self.arith(text, span, l, r, lhs, rhs)
+            },
+            BinOp::Eq | BinOp::Ne => {
+                if !self.accepts(&l, &r, rhs) && !self.accepts(&r, &l, lhs) {
+                    self.err(span, &format!("can't compare {l} and {r}"));
+                }
+                Types::Bool
+            },
+            BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge => {
+                let both_pointers = matches!((&l, &r), (Types::Pointer(_), Types::Pointer(_)));
+                if !both_pointers && (!is_number(&l) || !is_number(&r)) {
+                    self.err(span, &format!("can't compare {l} and {r} with `{text}`"));
+                } else if !both_pointers {
+                    self.arith(text, span, l, r, lhs, rhs);
+                }
+                Types::Bool
+            },
+            BinOp::And | BinOp::Or => {
+                for (typ, side) in [(&l, lhs), (&r, rhs)] {
+                    if !is_truthy(typ) {
+                        self.err(side.span, &format!("`{text}` needs bools, found {typ}"));
+                    }
+                }
+                Types::Bool
+            },
+        }
+    }
+
+    // the type two numbers are worked out in, the one the other widens to
+    fn arith(&mut self, text: &str, span: Span, l: Types, r: Types, lhs: &ast::Expr, rhs: &ast::Expr) -> Types {
+        match (is_untyped_number(lhs), is_untyped_number(rhs)) {
+            (true, _) if self.accepts(&r, &l, lhs) => return r,
+            (_, true) if self.accepts(&l, &r, rhs) => return l,
+            _ => (),
+        }
+
+        if widens(&r, &l) {
+            l
+        } else if widens(&l, &r) {
+            r
+        } else {
+            self.err(span, &format!("can't mix {l} and {r} in `{text}`, cast one of them to the other"));
+            Types::Any
+        }
+    }
+
+    fn cast(&mut self, typ: &ast::Type, inner: &ast::Expr) -> Types {
+        let to = self.cast_type(typ);
+        let from = self.infer(inner);
+        let castable = |t: &Types| is_number(t) || matches!(t, Types::Bool | Types::Pointer(_)) || self.is_enum(t);
+        let allowed = match (&from, &to) {
+            _ if is_loose(&from) || is_loose(&to) => true,
+            (Types::Pointer(_), to) | (to, Types::Pointer(_)) => matches!(to, Types::Pointer(_)) || is_integer(to),
+            _ => castable(&from) && castable(&to),
+        };
+
+        if !allowed {
+            self.err(inner.span, &format!("can't cast {from} to {to}"));
+        }
+        to
+    }
+
+    // like resolve, but a typeid of the function is a generic
+    fn cast_type(&self, typ: &ast::Type) -> Types {
+        if let ast::Type::Name { name, generics: None } = typ {
+            if let Some(Types::TypeId) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+                return Types::Generic(name.clone());
+            }
+        }
+        self.resolve(typ)
+    }
+
+    fn is_enum(&self, typ: &Types) -> bool {
+        match typ {
+            Types::TypeDef { type_name, generics: None } => matches!(self.weights.find_global_ident(type_name.clone()), Expr::EnumDef { .. }),
+            _ => false,
+        }
+    }
+
+    fn unary(&mut self, op: UnOp, inner: &ast::Expr) -> Types {
+        let typ = self.infer(inner);
+        if is_loose(&typ) {
+            return if op == UnOp::Not { Types::Bool } else { Types::Any };
+        }
+
+        match op {
+            UnOp::Not => {
+                if !is_truthy(&typ) {
+                    self.err(inner.span, &format!("`!` needs a bool, found {typ}"));
+                }
+                Types::Bool
+            },
+            UnOp::Neg if !is_number(&typ) => {
+                self.err(inner.span, &format!("can't negate {typ}, it isn't a number"));
+                Types::Any
+            },
+            UnOp::BitNot if !is_integer(&typ) => {
+                self.err(inner.span, &format!("`!` inside [] only works on integers, found {typ}"));
+                Types::Any
+            },
+            UnOp::Neg | UnOp::BitNot => typ,
+        }
+    }
+
+    // the type of `value`, or Err with it when it can't go where `expected` is wanted. the
+    // elements of array literals are checked one by one so each gets its own error
+    fn expect(&mut self, expected: &Types, value: &ast::Expr) -> Result<Types, Types> {
+        match (expected, &value.kind) {
+            (Types::Arr { typ: elem_typ, .. }, ExprKind::ArrayLit(elems)) => {
+                for elem in elems {
+                    if let Err(found) = self.expect(elem_typ, elem) {
+                        self.mismatch(elem.span, format!("mismatched types: expected {elem_typ}, found {found}"), elem_typ, &found);
+                    }
+                }
+                Ok(expected.clone())
+            },
+            // struct literals are written like arrays
+            (Types::TypeDef { .. }, ExprKind::ArrayLit(elems)) => {
+                for elem in elems {
+                    self.infer(elem);
+                }
+                Ok(expected.clone())
+            },
+            _ => {
+                let found = self.infer(value);
+                if self.accepts(expected, &found, value) { Ok(found) } else { Err(found) }
+            },
+        }
+    }
+
+    // if a value of type `found` can go where `expected` is wanted
+    fn accepts(&self, expected: &Types, found: &Types, value: &ast::Expr) -> bool {
+        match (expected, found) {
+            _ if is_loose(expected) || is_loose(found) => true,
+            (Types::Let | Types::TypeId, _) => true,
+            // integers without a type, like the 0 in `^int p: 0`, fit any number they're in range of or pointer
+            _ if is_untyped_number(value) && (is_number(expected) || matches!(expected, Types::Pointer(_))) => {
+                match literal_value(value) {
+                    Some(n) if is_integer(expected) => in_range(n, expected),
+                    _ => true,
+                }
+            },
+            _ if is_untyped_float(value) && matches!(expected, Types::F32 | Types::F64) => true,
+            _ if is_number(expected) && is_number(found) => widens(found, expected),
+            (Types::Pointer(expected_to), Types::Pointer(found_to)) if is_loose(expected_to) || is_loose(found_to) => true,
+            (Types::Arr { typ: expected_elem, length: expected_len }, Types::Arr { typ: found_elem, length: found_len }) => {
+                let lengths_match = expected_len.is_empty() || found_len.is_empty() || expected_len == found_len;
+                lengths_match && (is_loose(found_elem) || compare_type_and_type(expected_elem, found_elem))
+            },
+            (Types::Pointer(expected_to), Types::Pointer(found_to)) => {
+                **expected_to == Types::Void || **found_to == Types::Void || same_type(expected_to, found_to)
+            },
+            (Types::TypeDef { .. }, Types::TypeDef { .. }) => same_type(expected, found),
+            _ => compare_type_and_type(expected, found),
+        }
+    }
+}
+
+// the first generic parameter that differs between two of the same generic struct, looking inside
+// nested ones like the dyn[int] in option[dyn[int]]. None when there's nothing to tell apart
+fn generic_difference(expected: &Types, found: &Types) -> Option<String> {
+    match (expected, found) {
+        (Types::Pointer(expected_to), Types::Pointer(found_to)) => generic_difference(expected_to, found_to),
+        (Types::TypeDef { type_name: expected_name, generics: Some(expected_generics) }, Types::TypeDef { type_name: found_name, generics: Some(found_generics) }) => {
+            let name = expected_name.trim_start_matches("struct ");
+            // a struct naming itself inside its own definition doesn't know its generics yet
+            if name != found_name.trim_start_matches("struct ") || expected_generics.is_empty() || found_generics.is_empty() {
+                return None;
+            }
+
+            if expected_generics.len() != found_generics.len() {
+                return Some(format!("{name} takes {} generic parameters, found {}", expected_generics.len(), found_generics.len()));
+            }
+
+            for (i, (expected_generic, found_generic)) in expected_generics.iter().zip(found_generics).enumerate() {
+                if same_type(expected_generic, found_generic) {
+                    continue;
+                }
+
+                return generic_difference(expected_generic, found_generic)
+                    .or_else(|| Some(format!("generic parameter {} of {name} is {found_generic}, expected {expected_generic}", i + 1)));
+            }
+            None
+        },
+        _ => None,
+    }
+}
+
+// exactly the same type, for what's behind pointers and inside generics where numbers can't convert
+fn same_type(a: &Types, b: &Types) -> bool {
+    match (a, b) {
+        _ if is_loose(a) || is_loose(b) => true,
+        (Types::Pointer(a_to), Types::Pointer(b_to)) => same_type(a_to, b_to),
+        (Types::Arr { typ: a_elem, length: a_len }, Types::Arr { typ: b_elem, length: b_len }) => {
+            (a_len.is_empty() || b_len.is_empty() || a_len == b_len) && same_type(a_elem, b_elem)
+        },
+        (Types::TypeDef { type_name: a_name, generics: a_generics }, Types::TypeDef { type_name: b_name, generics: b_generics }) => {
+            a_name.trim_start_matches("struct ") == b_name.trim_start_matches("struct ")
+                && a_generics.is_some() == b_generics.is_some()
+                && generic_difference(a, b).is_none()
+        },
+        _ => a == b,
+    }
+}
+
+fn op_text(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::BitAnd | BinOp::And => "and",
+        BinOp::BitOr | BinOp::Or => "or",
+        BinOp::BitXor => "xor",
+        BinOp::Shl => "lshift",
+        BinOp::Shr => "rshift",
+        BinOp::Eq => "=",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Gt => ">",
+        BinOp::Le => "<=",
+        BinOp::Ge => ">=",
+    }
+}
+
+// types that the checker can't say anything about
+fn is_loose(t: &Types) -> bool {
+    matches!(t, Types::Any | Types::Generic(_) | Types::None)
+}
+
+fn is_integer(t: &Types) -> bool {
+    matches!(t,
+        Types::U8 | Types::I8 | Types::Char | Types::U16 | Types::I16 | Types::U32 | Types::I32 |
+        Types::U64 | Types::I64 | Types::UInt | Types::Int | Types::Usize
+    )
+}
+
+fn is_number(t: &Types) -> bool {
+    is_integer(t) || matches!(t, Types::F32 | Types::F64)
+}
+
+// how many bits an integer has and if it's signed. char is a byte that goes with either sign
+fn int_layout(t: &Types) -> Option<(u32, Option<bool>)> {
+    let layout = match t {
+        Types::Char => (8, None),
+        Types::U8 => (8, Some(false)),
+        Types::I8 => (8, Some(true)),
+        Types::U16 => (16, Some(false)),
+        Types::I16 => (16, Some(true)),
+        Types::U32 | Types::UInt => (32, Some(false)),
+        Types::I32 | Types::Int => (32, Some(true)),
+        Types::U64 | Types::Usize => (64, Some(false)),
+        Types::I64 => (64, Some(true)),
+        _ => return None,
+    };
+
+    Some(layout)
+}
+
+// if every value of `from` fits in `to` without a cast. integers go into floats whose mantissa holds
+// all of them, but never the other way, and a smaller unsigned integer fits in a bigger signed one
+fn widens(from: &Types, to: &Types) -> bool {
+    match (int_layout(from), int_layout(to)) {
+        (Some((from_bits, from_signed)), Some((to_bits, to_signed))) => match (from_signed, to_signed) {
+            (None, _) | (_, None) => from_bits <= to_bits,
+            (Some(false), Some(true)) => from_bits < to_bits,
+            (Some(true), Some(false)) => false,
+            _ => from_bits <= to_bits,
+        },
+        (Some((from_bits, _)), None) => match to {
+            Types::F32 => from_bits <= 16,
+            Types::F64 => from_bits <= 32,
+            _ => false,
+        },
+        (None, None) => from == to || (from == &Types::F32 && to == &Types::F64),
+        (None, Some(_)) => false,
+    }
+}
+
+// the value of an integer literal, with its `-`
+fn literal_value(expr: &ast::Expr) -> Option<i128> {
+    match &expr.kind {
+        ExprKind::Number(number) if !number.float => {
+            let digits = number.digits.as_str();
+            if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+                i128::from_str_radix(hex, 16).ok()
+            } else if digits.len() > 1 && digits.starts_with('0') {
+                i128::from_str_radix(&digits[1..], 8).ok()
+            } else {
+                digits.parse().ok()
+            }
+        },
+        ExprKind::Unary { op: UnOp::Neg, expr: inner } => literal_value(inner).map(|n| -n),
+        ExprKind::Square(inner) | ExprKind::Paren(inner) => literal_value(inner),
+        _ => None,
+    }
+}
+
+fn in_range(n: i128, t: &Types) -> bool {
+    let Some((bits, signed)) = int_layout(t) else {
+        return true
+    };
+
+    match signed {
+        Some(true) => n >= -(1 << (bits - 1)) && n < 1 << (bits - 1),
+        Some(false) => n >= 0 && n < 1 << bits,
+        None => n >= -(1 << (bits - 1)) && n < 1 << bits,
+    }
+}
+
+// what c lets go in an if
+fn is_truthy(t: &Types) -> bool {
+    is_number(t) || matches!(t, Types::Bool | Types::Pointer(_))
+}
+
+fn is_untyped_float(expr: &ast::Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Number(number) => number.suffix.is_none() && number.float,
+        ExprKind::Square(inner) | ExprKind::Paren(inner) | ExprKind::Unary { op: UnOp::Neg, expr: inner } => is_untyped_float(inner),
+        _ => false,
+    }
+}
+
+// an integer literal without a suffix, maybe negated, in brackets or in maths with others like it
+fn is_untyped_number(expr: &ast::Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Number(number) => number.suffix.is_none() && !number.float,
+        ExprKind::Square(inner) | ExprKind::Paren(inner) | ExprKind::Unary { op: UnOp::Neg | UnOp::BitNot, expr: inner } => is_untyped_number(inner),
+        ExprKind::Binary { op, lhs, rhs } if !matches!(op, BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::And | BinOp::Or) => {
+            is_untyped_number(lhs) && is_untyped_number(rhs)
+        },
+        _ => false,
+    }
+}
+
+// $T swapped for the type it was given, anything not given stays generic
+fn substitute(typ: &Types, given: &HashMap<String, Types>) -> Types {
+    match typ {
+        Types::Generic(name) => given.get(name).cloned().unwrap_or(Types::Any),
+        // the T in dyn[T] is kept as a type name
+        Types::TypeDef { type_name, generics: None } if given.contains_key(type_name) => given[type_name].clone(),
+        Types::Pointer(to) => Types::Pointer(Box::new(substitute(to, given))),
+        Types::Arr { typ, length } => Types::Arr { typ: Box::new(substitute(typ, given)), length: length.clone() },
+        Types::TypeDef { type_name, generics } => Types::TypeDef {
+            type_name: type_name.clone(),
+            generics: generics.as_ref().map(|generics| generics.iter().map(|generic| substitute(generic, given)).collect()),
+        },
+        typ => typ.clone(),
+    }
+}
+
+// what a for loop gives each time round
+fn for_elem(typ: &Types) -> Types {
+    match typ {
+        Types::Arr { typ, .. } => *typ.clone(),
+        Types::TypeDef { type_name, generics } if type_name == "dyn" || type_name == "array" => {
+            generics.as_ref().and_then(|generics| generics.first()).cloned().unwrap_or(Types::Any)
+        },
+        Types::TypeDef { type_name, .. } if type_name == "string" || type_name == "str" => Types::Char,
+        _ => Types::Any,
+    }
+}
+
+// the leftmost name in a loop condition
+fn first_name(cond: &ast::Expr) -> Option<&str> {
+    match &cond.kind {
+        ExprKind::Name(name) => Some(name),
+        ExprKind::Binary { lhs, .. } => first_name(lhs),
+        _ => None,
+    }
+}
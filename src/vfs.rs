@@ -0,0 +1,81 @@
+use std::{collections::HashMap, fmt, fs, io, path::{Component, Path, PathBuf}};
+
+// everything the front end reads goes through this, so it can run on files that aren't on disk
+pub trait FileSystem: fmt::Debug + Send + Sync {
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+    fn exists(&self, path: &Path) -> bool;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+}
+
+// the standard library, so a MemoryFs can compile without an install
+const BASE: &[(&str, &str)] = &[
+    ("base/builtin.imp", include_str!("../base/builtin.imp")),
+    ("base/dynamic.imp", include_str!("../base/dynamic.imp")),
+    ("base/env.imp", include_str!("../base/env.imp")),
+    ("base/fs.imp", include_str!("../base/fs.imp")),
+    ("base/io.imp", include_str!("../base/io.imp")),
+    ("base/math.imp", include_str!("../base/math.imp")),
+    ("base/memory.imp", include_str!("../base/memory.imp")),
+    ("base/random.imp", include_str!("../base/random.imp")),
+    ("base/string.imp", include_str!("../base/string.imp")),
+    ("base/time.imp", include_str!("../base/time.imp")),
+];
+
+// files held in memory, paths are compared with any `./` taken out
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFs {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryFs {
+    pub fn new() -> MemoryFs {
+        MemoryFs { files: HashMap::new() }
+    }
+
+    // base/ at the root, a Session looks there by default
+    pub fn with_base() -> MemoryFs {
+        let mut memory = MemoryFs::new();
+        for (path, content) in BASE {
+            memory.insert(path, *content);
+        }
+
+        memory
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<Path>, content: impl Into<String>) {
+        self.files.insert(normalise(path.as_ref()), content.into());
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.files.get(&normalise(path)) {
+            Some(content) => Ok(content.clone()),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, format!("{} isn't in the memory fs", path.display()))),
+        }
+    }
+
+    // directories exist as long as there's a file somewhere in them
+    fn exists(&self, path: &Path) -> bool {
+        let path = normalise(path);
+        self.files.keys().any(|file| file.starts_with(&path))
+    }
+}
+
+fn normalise(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
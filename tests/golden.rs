@@ -0,0 +1,294 @@
+use std::{env, fs, path::{Path, PathBuf}, process::Command};
+
+use impulse::compiler::{Cfg, Compiler};
+use impulse::declare_types::Lang;
+use impulse::generator::Gen;
+use impulse::{Diagnostics, Session};
+
+// every directory in tests/golden is a case, main.imp is the entry and the rest of its files can be imported,
+// a file in a subdirectory of the case imports its neighbours without the directory
+// expected.c: the generated c, for cases that transpile
+// expected.stdout and expected.exit: what the program prints and exits with, it's only compiled and run
+//   with gcc when expected.stdout is there, a missing expected.exit means 0
+// expected.err: the diagnostics, for cases that shouldn't transpile
+// X.imp next to an X.h: the bindings `impulse bindgen X.h` writes for it
+// `UPDATE_GOLDEN=1 cargo test` rewrites the expected files from what impulse does now
+// tests/units isn't a case, it's built a unit per module like `impulse build` does
+
+const EXPECTED: [&str; 4] = ["expected.c", "expected.stdout", "expected.exit", "expected.err"];
+
+fn cases() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let mut cases: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("unable to read {} with error: {e}", dir.display()))
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+
+    cases.sort();
+    cases
+}
+
+// the case's files by name, they're compiled from memory so paths in the output don't depend on the machine
+fn sources(case: &Path) -> Vec<(String, String)> {
+    let mut sources = Vec::new();
+    add_sources(case, "", &mut sources);
+    sources
+}
+
+// files in a subdirectory of the case are named `dir/file`
+fn add_sources(dir: &Path, prefix: &str, sources: &mut Vec<(String, String)>) {
+    for entry in fs::read_dir(dir).unwrap().filter_map(|entry| entry.ok()) {
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            add_sources(&entry.path(), &format!("{name}/"), sources);
+        } else if !EXPECTED.contains(&name.as_str()) {
+            sources.push((name, fs::read_to_string(entry.path()).unwrap()));
+        }
+    }
+}
+
+// one line per diagnostic and its notes, without colours or source snippets
+fn diagnostics_text(diagnostics: &Diagnostics) -> String {
+    let mut text = String::new();
+    for diagnostic in diagnostics.iter() {
+        if diagnostic.file.is_empty() {
+            text.push_str(&format!("{}\n", diagnostic.message));
+        } else {
+            text.push_str(&format!("{}:{}:{}: {}\n", diagnostic.file, diagnostic.line, diagnostic.column, diagnostic.message));
+        }
+
+        for note in &diagnostic.notes {
+            text.push_str(&format!("    note: {note}\n"));
+        }
+    }
+
+    text
+}
+
+fn compare(path: &Path, actual: &str, update: bool) -> Result<(), String> {
+    if update {
+        fs::write(path, actual).unwrap();
+        return Ok(());
+    }
+
+    let expected = fs::read_to_string(path)
+        .map_err(|_| format!("{} is missing, run with UPDATE_GOLDEN=1 to create it", path.display()))?;
+    if expected == actual {
+        return Ok(());
+    }
+
+    // the first line that differs is usually enough to see what happened
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let line = (0..expected_lines.len().max(actual_lines.len()))
+        .find(|&i| expected_lines.get(i) != actual_lines.get(i))
+        .unwrap_or(0);
+
+    Err(format!(
+        "{} doesn't match at line {}\nexpected: {}\n  actual: {}",
+        path.display(),
+        line + 1,
+        expected_lines.get(line).unwrap_or(&"<end of file>"),
+        actual_lines.get(line).unwrap_or(&"<end of file>"),
+    ))
+}
+
+fn has_gcc() -> bool {
+    Command::new("gcc").arg("--version").output().is_ok_and(|output| output.status.success())
+}
+
+fn run(case: &Path, c: &str, c_sources: &[String], c_flags: &[String], update: bool) -> Result<(), String> {
+    let name = case.file_name().unwrap().to_string_lossy().to_string();
+    let dir = env::temp_dir().join(format!("impulse-golden-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // local .c imports and their headers are next to main.imp in the case
+    fs::write(dir.join("output.c"), c).unwrap();
+    let headers: Vec<String> = fs::read_dir(case)
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".h"))
+        .collect();
+    for source in c_sources.iter().chain(&headers) {
+        fs::copy(case.join(source), dir.join(source)).map_err(|e| format!("unable to copy {source} with error: {e}"))?;
+    }
+
+    let result = compile_and_run(&dir, c_sources, c_flags);
+    let _ = fs::remove_dir_all(&dir);
+    let (stdout, code) = result?;
+
+    compare(&case.join("expected.stdout"), &stdout, update)?;
+
+    let exit_path = case.join("expected.exit");
+    if update {
+        if code == 0 {
+            let _ = fs::remove_file(&exit_path);
+        } else {
+            fs::write(&exit_path, format!("{code}\n")).unwrap();
+        }
+        return Ok(());
+    }
+
+    let expected_code = fs::read_to_string(&exit_path).map_or(0, |code| code.trim().parse().unwrap());
+    if code != expected_code {
+        return Err(format!("{name} exited with {code}, expected {expected_code}"));
+    }
+
+    Ok(())
+}
+
+fn compile_and_run(dir: &Path, c_sources: &[String], c_flags: &[String]) -> Result<(String, i32), String> {
+    let compiled = Command::new("gcc")
+        .arg("output.c")
+        .args(c_sources)
+        .args(["-o", "program", "-I."])
+        .args(c_flags)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("unable to run gcc with error: {e}"))?;
+
+    if !compiled.status.success() {
+        return Err(format!("gcc failed to compile the generated c\n{}", String::from_utf8_lossy(&compiled.stderr)));
+    }
+
+    let ran = Command::new(dir.join("program"))
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("unable to run the program with error: {e}"))?;
+
+    Ok((String::from_utf8_lossy(&ran.stdout).to_string(), ran.status.code().unwrap_or(-1)))
+}
+
+fn check(case: &Path, update: bool, gcc: bool) -> Result<(), String> {
+    // the expected files are for linux wherever the tests run
+    let session = Session::from_sources(sources(case)).with_cfg(Cfg::new(Some("x86_64-unknown-linux-gnu"), &[]));
+    let output = session.transpile("main.imp");
+    let err_path = case.join("expected.err");
+
+    let Some(c) = output.c else {
+        if !err_path.exists() && !update {
+            return Err(format!("failed to transpile\n{}", diagnostics_text(&output.diagnostics)));
+        }
+        return compare(&err_path, &diagnostics_text(&output.diagnostics), update);
+    };
+
+    if err_path.exists() {
+        if !update {
+            return Err(String::from("expected errors but it transpiled"));
+        }
+        fs::remove_file(&err_path).unwrap();
+    }
+
+    compare(&case.join("expected.c"), &c, update)?;
+
+    if gcc && case.join("expected.stdout").exists() {
+        run(case, &c, &output.c_sources, &output.c_flags, update)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn golden() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let gcc = has_gcc();
+    if !gcc {
+        eprintln!("gcc wasn't found, only checking the generated c");
+    }
+
+    let mut failures = Vec::new();
+    let cases = cases();
+    for case in &cases {
+        if let Err(failure) = check(case, update, gcc) {
+            failures.push(format!("{}: {failure}", case.file_name().unwrap().to_string_lossy()));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} golden cases failed\n\n{}", failures.len(), cases.len(), failures.join("\n\n"));
+}
+
+#[test]
+fn bindings() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+
+    let mut failures = Vec::new();
+    for case in &cases() {
+        for (name, source) in sources(case) {
+            let Some(stem) = name.strip_suffix(".h") else { continue };
+            let path = case.join(format!("{stem}.imp"));
+            if !path.exists() {
+                continue;
+            }
+
+            let result = impulse::bindgen::generate(&name, &source, &impulse::bindgen::default_prefix(&name))
+                .map_err(|diagnostic| diagnostic.message)
+                .and_then(|imp| compare(&path, &imp, update));
+            if let Err(failure) = result {
+                failures.push(format!("{}/{name}: {failure}", case.file_name().unwrap().to_string_lossy()));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+fn modified(units: &Path, stem: &str) -> std::time::SystemTime {
+    let object = fs::read_dir(units).unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "o") && path.file_name().unwrap().to_string_lossy().starts_with(&format!("{stem}-")))
+        .unwrap_or_else(|| panic!("no object for {stem} in {}", units.display()));
+    fs::metadata(object).unwrap().modified().unwrap()
+}
+
+// parsed from memory like the golden cases, the units and the program are written to `dir`
+fn build_units(dir: &Path, sources: Vec<(String, String)>) -> String {
+    let out = dir.join("program").to_string_lossy().to_string();
+    let expressions = Session::from_sources(sources)
+        .parse("main.imp")
+        .unwrap_or_else(|diagnostics| panic!("failed to parse\n{}", diagnostics_text(&diagnostics)));
+
+    let mut gen = Gen::new("main.imp", &out, true, Lang::C, Compiler::default(), false);
+    gen.generate(expressions).unwrap_or_else(|e| panic!("failed to build\n{}", e.message));
+
+    let ran = Command::new(&out).output().unwrap();
+    String::from_utf8_lossy(&ran.stdout).to_string()
+}
+
+// tests/units is built a unit per module, like `impulse build` does, then again after a function
+// is added to one module, which only recompiles that module's unit
+#[test]
+fn units() {
+    if !has_gcc() {
+        eprintln!("gcc wasn't found, skipping the unit build");
+        return;
+    }
+
+    let dir = env::temp_dir().join(format!("impulse-units-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let units = dir.join("program.units");
+    let result = std::panic::catch_unwind(|| {
+        let mut sources = sources(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("units"));
+        assert_eq!(build_units(&dir, sources.clone()), "42 2\n");
+        let main = modified(&units, "main");
+        let counter = modified(&units, "counter");
+
+        // mtimes can be too coarse to see a rebuild straight away
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        let module = sources.iter_mut().find(|(name, _)| name == "counter.imp").unwrap();
+        module.1.push_str("\nint reset :: () {\n    return 0;\n}\n");
+
+        assert_eq!(build_units(&dir, sources.clone()), "42 2\n");
+        assert_eq!(modified(&units, "main"), main, "main's unit was recompiled for a function it doesn't use");
+        assert_ne!(modified(&units, "counter"), counter, "counter's unit wasn't recompiled");
+    });
+
+    let _ = fs::remove_dir_all(&dir);
+    if let Err(panic) = result {
+        std::panic::resume_unwind(panic);
+    }
+}
@@ -0,0 +1,246 @@
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+#include <ctype.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdarg.h>
+#include "shapes.h"
+typedef uint8_t u8;
+typedef int8_t i8;
+typedef uint16_t u16;
+typedef int16_t i16;
+typedef uint32_t u32;
+typedef int32_t i32;
+typedef uint64_t u64;
+typedef int64_t i64;
+typedef size_t usize;
+typedef float f32;
+typedef double f64;
+typedef unsigned int uint;
+#define let __auto_type /* currently only for enums */
+#define $inline static inline __attribute__((always_inline))
+#ifdef __linux__
+    #include <locale.h>
+#endif
+$inline void exitcode(const int code) {
+    exit(code);
+}
+#define option(T, imp_struct_type_name)\
+typedef struct option_##imp_struct_type_name {\
+    T value;\
+    bool none;\
+} option_##imp_struct_type_name;
+#define array(T, imp_struct_type_name)\
+typedef struct array_##imp_struct_type_name {\
+    T* data;\
+    usize len;\
+} array_##imp_struct_type_name;
+$inline bool c__strcmp(const char* str1, const char* str2) {
+    return !strcmp(str1, str2);
+}
+typedef struct str {
+    char* data;
+    usize len;
+}str;
+str str__from(char* word) {
+    str value = {0};
+    value.data = word;
+    value.len = strlen(word);
+    return value;
+}
+str str__with_len(const usize len) {
+    char tmp[len];
+    str value = {0};
+    value.data = tmp;
+    value.len = len;
+    return value;
+}
+bool str__compare(const str first, const str second) {
+    if (first.len!=second.len) {
+        return false;
+    }
+    for (size_t i = 0; i < first.len; i++) {
+        char ch = first.data[i];
+        if (ch==second.data[i]) {
+            return false;
+        }
+    }
+    return true;
+}
+option(char, char);
+option_char str__at(const str word, const usize index) {
+    option_char ret = {0};
+    if (index>=word.len) {
+        ret.none = true;
+        return ret;
+    }
+    ret.value = word.data[index];
+    return ret;
+}
+bool str__starts_with(const str word, const char ch) {
+    option_char first_letter_op = str__at(word, (0));
+    if (first_letter_op.none) {
+        return false;
+    }
+    if (first_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+bool str__ends_with(const str word, const char ch) {
+    option_char last_letter_op = str__at(word, (word.len-1));
+    if (last_letter_op.none) {
+        return false;
+    }
+    if (last_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+void str__to_lowercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = tolower(ch);
+    }
+}
+void str__to_uppercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = toupper(ch);
+    }
+}
+void str__replace_char(str word, const char from, const char to) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        if (ch==from) {
+            word.data[i] = to;
+        }
+    }
+}
+bool str__replace_index(str word, const usize index, const char to) {
+    option_char at_index = str__at(word, index);
+    if (at_index.none==true) {
+        return false;
+    }
+    word.data[index] = to;
+    return true;
+}
+bool str__contains(const str word, const char* pattern) {
+    usize pattern_len = strlen(pattern);
+    usize head = (0);
+    if (word.len<pattern_len) {
+        return false;
+    }
+    for (size_t str__contains_it0 = 0; str__contains_it0 < word.len; str__contains_it0++) {
+        char ch = word.data[str__contains_it0];
+        if (head==pattern_len) {
+            return true;
+        }
+        if (ch==pattern[head]) {
+            head = (head+1);
+        }
+        else {
+            head = (0);
+        }
+    }
+    if (head==pattern_len) {
+        return true;
+    }
+    return false;
+}
+void str__clear(str* word) {
+    word->data[0] = '\0';
+    word->len = (0);
+}
+void print() {
+}
+void println() {
+}
+void panic(const char* msg) {
+    fprintf(stderr, "panic at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+void runtime__assert(const bool expression, const char* msg) {
+    if (expression) {
+        return ;
+    }
+    fprintf(stderr, "assertion failed at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+#include "shapes.h"
+const char* shapes_SHAPES_VERSION = "1.2";
+const int shapes_MAX_CORNERS = (4);
+const f32 shapes_HALF = (0.5);
+typedef struct shapes__Point {
+    int x;
+    int y;
+}shapes__Point;
+const shapes__Point shapes_ORIGIN = {(3), (4)};
+typedef shapes__Point shapes__Offset;
+typedef enum shapes__ShapeKind {
+    shapes_ShapeKind_SQUARE = (1),
+    shapes_ShapeKind_TRIANGLE,
+    shapes_ShapeKind_HEXAGON = (8),
+}shapes__ShapeKind;
+const usize shapes__ShapeKind_field_count = (3);
+const int shapes_SHAPES_RED = (0);
+const int shapes_SHAPES_BLUE = (1);
+typedef struct shapes__Shape {
+    shapes__ShapeKind kind;
+    shapes__Point first;
+    u8 sides;
+    f32 scale;
+}shapes__Shape;
+typedef union shapes__Number {
+    int whole;
+    f32 part;
+}shapes__Number;
+typedef struct shapes__Canvas {
+}shapes__Canvas;
+$inline shapes__Shape shapes__shape_new(const shapes__ShapeKind kind, const shapes__Point at) {
+    Shape ret__ = shape_new(kind, *(Point*)&at); return *(shapes__Shape*)&ret__;
+}
+$inline shapes__Point shapes__shape_move(const shapes__Point p, const shapes__Point by) {
+    Point ret__ = shape_move(*(Point*)&p, *(Offset*)&by); return *(shapes__Point*)&ret__;
+}
+$inline int shapes__shape_area(const shapes__Shape* shape) {
+    return shape_area((const Shape*)shape);
+}
+$inline char* shapes__shape_name(const shapes__ShapeKind kind) {
+    return (char*)shape_name(kind);
+}
+$inline shapes__Canvas* shapes__canvas_new(const usize width) {
+    return (shapes__Canvas*)canvas_new(width);
+}
+$inline usize shapes__canvas_width(const shapes__Canvas* canvas) {
+    return canvas_width((const Canvas*)canvas);
+}
+$inline void shapes__canvas_free(const shapes__Canvas* canvas) {
+    canvas_free((Canvas*)canvas);
+}
+$inline void shapes__shapes_each(const shapes__Shape* shapes, const int count, const void* callback) {
+    shapes_each((Shape*)shapes, count, (ShapeCallback)callback);
+}
+int main() {
+#ifdef _WIN32
+    system("chcp 65001 >nul");
+#elif __linux__
+    setlocale(LC_ALL, "");
+#endif
+    shapes__Shape square = shapes__shape_new(shapes_ShapeKind_SQUARE, shapes_ORIGIN);
+    printf("%s %d %d\n", shapes__shape_name(square.kind), square.sides, shapes__shape_area(&square));
+    shapes__Point by = {(1), (1)};
+    shapes__Point moved = shapes__shape_move(square.first, by);
+    printf("%d %d\n", moved.x, moved.y);
+    shapes__Shape hexagon = shapes__shape_new(shapes_ShapeKind_HEXAGON, moved);
+    printf("%s %d\n", shapes__shape_name(hexagon.kind), shapes_ShapeKind_HEXAGON);
+    shapes__Canvas* canvas = shapes__canvas_new((640));
+    printf("%zu %d\n", shapes__canvas_width(canvas), shapes_double(shapes_MAX_CORNERS));
+    shapes__canvas_free(canvas);
+    shapes__Number number = {0};
+    number.whole = shapes_SHAPES_BLUE;
+    printf("%s %d %.1f\n", shapes_SHAPES_VERSION, number.whole, shapes_HALF);
+    shapes_log("%s %d\n", shapes_format("%d-%d", (2), (3)), (7));
+}
@@ -0,0 +1,71 @@
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include "shapes.h"
+
+struct Canvas {
+    size_t width;
+};
+
+Shape shape_new(ShapeKind kind, Point at) {
+    Shape shape = {0};
+    shape.kind = kind;
+    shape.first = at;
+    shape.sides = kind == SHAPE_TRIANGLE ? 3 : 4;
+    shape.scale = 1.0f;
+    return shape;
+}
+
+Point shape_move(Point p, Offset by) {
+    return (Point){ p.x + by.x, p.y + by.y };
+}
+
+int shape_area(const Shape *shape) {
+    return shape->sides * shape->first.x * shape->scale;
+}
+
+Outline shape_outline(const Shape *shape) {
+    Outline outline = {0};
+    for (int i = 0; i < MAX_CORNERS; i++) {
+        outline.corners[i] = shape->first;
+    }
+    return outline;
+}
+
+const char *shape_name(ShapeKind kind) {
+    switch (kind) {
+        case SHAPE_SQUARE: return "square";
+        case SHAPE_TRIANGLE: return "triangle";
+        default: return "hexagon";
+    }
+}
+
+Canvas *canvas_new(size_t width) {
+    Canvas *canvas = malloc(sizeof(Canvas));
+    canvas->width = width;
+    return canvas;
+}
+
+size_t canvas_width(const Canvas *canvas) {
+    return canvas->width;
+}
+
+void canvas_free(Canvas *canvas) {
+    free(canvas);
+}
+
+void shapes_log(const char *format, ...) {
+    va_list args;
+    va_start(args, format);
+    vprintf(format, args);
+    va_end(args);
+}
+
+const char *shapes_format(const char *format, ...) {
+    static char text[64];
+    va_list args;
+    va_start(args, format);
+    vsnprintf(text, sizeof(text), format, args);
+    va_end(args);
+    return text;
+}
@@ -0,0 +1,77 @@
+#ifndef SHAPES_H
+#define SHAPES_H
+
+#include <stddef.h>
+
+#if defined(__cplusplus)
+extern "C" {
+#endif
+
+#define SHAPES_API
+#define SHAPES_VERSION "1.2"
+#define MAX_CORNERS 4
+#define HALF (1.0f/2)
+#define ORIGIN (Point){ 3, 4 }
+
+// a point on the grid
+typedef struct Point {
+    int x; // across
+    int y; // down
+} Point;
+
+typedef Point Offset;
+
+typedef enum {
+    SHAPE_SQUARE = 1,
+    SHAPE_TRIANGLE,
+    SHAPE_HEXAGON = SHAPE_TRIANGLE << 2,
+} ShapeKind;
+
+enum { SHAPES_RED, SHAPES_BLUE };
+
+typedef struct Shape {
+    ShapeKind kind;
+    Point first;
+    unsigned char sides;
+    float scale;
+} Shape;
+
+typedef struct Outline {
+    Point corners[MAX_CORNERS];
+} Outline;
+
+typedef union Number {
+    int whole;
+    float part;
+} Number;
+
+typedef struct Canvas Canvas;
+
+typedef struct Flags {
+    unsigned int visible : 1;
+} Flags;
+
+typedef void (*ShapeCallback)(const Shape *shape);
+
+SHAPES_API Shape shape_new(ShapeKind kind, Point at); // a shape with its first corner at `at`
+SHAPES_API Point shape_move(Point p, Offset by);
+SHAPES_API int shape_area(const Shape *shape);
+SHAPES_API Outline shape_outline(const Shape *shape);
+SHAPES_API const char *shape_name(ShapeKind kind);
+SHAPES_API Canvas *canvas_new(size_t width);
+SHAPES_API size_t canvas_width(const Canvas *canvas);
+SHAPES_API void canvas_free(Canvas *canvas);
+SHAPES_API void shapes_each(Shape *shapes, int count, ShapeCallback callback);
+SHAPES_API void shapes_log(const char *format, ...);
+SHAPES_API const char *shapes_format(const char *format, ...);
+SHAPES_API Flags shapes_flags(void);
+
+static inline int shapes_double(int loop) {
+    return loop * 2;
+}
+
+#if defined(__cplusplus)
+}
+#endif
+
+#endif
@@ -0,0 +1,199 @@
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+#include <ctype.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdarg.h>
+typedef uint8_t u8;
+typedef int8_t i8;
+typedef uint16_t u16;
+typedef int16_t i16;
+typedef uint32_t u32;
+typedef int32_t i32;
+typedef uint64_t u64;
+typedef int64_t i64;
+typedef size_t usize;
+typedef float f32;
+typedef double f64;
+typedef unsigned int uint;
+#define let __auto_type /* currently only for enums */
+#define $inline static inline __attribute__((always_inline))
+#ifdef __linux__
+    #include <locale.h>
+#endif
+$inline void exitcode(const int code) {
+    exit(code);
+}
+#define option(T, imp_struct_type_name)\
+typedef struct option_##imp_struct_type_name {\
+    T value;\
+    bool none;\
+} option_##imp_struct_type_name;
+#define array(T, imp_struct_type_name)\
+typedef struct array_##imp_struct_type_name {\
+    T* data;\
+    usize len;\
+} array_##imp_struct_type_name;
+$inline bool c__strcmp(const char* str1, const char* str2) {
+    return !strcmp(str1, str2);
+}
+typedef struct str {
+    char* data;
+    usize len;
+}str;
+str str__from(char* word) {
+    str value = {0};
+    value.data = word;
+    value.len = strlen(word);
+    return value;
+}
+str str__with_len(const usize len) {
+    char tmp[len];
+    str value = {0};
+    value.data = tmp;
+    value.len = len;
+    return value;
+}
+bool str__compare(const str first, const str second) {
+    if (first.len!=second.len) {
+        return false;
+    }
+    for (size_t i = 0; i < first.len; i++) {
+        char ch = first.data[i];
+        if (ch==second.data[i]) {
+            return false;
+        }
+    }
+    return true;
+}
+option(char, char);
+option_char str__at(const str word, const usize index) {
+    option_char ret = {0};
+    if (index>=word.len) {
+        ret.none = true;
+        return ret;
+    }
+    ret.value = word.data[index];
+    return ret;
+}
+bool str__starts_with(const str word, const char ch) {
+    option_char first_letter_op = str__at(word, (0));
+    if (first_letter_op.none) {
+        return false;
+    }
+    if (first_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+bool str__ends_with(const str word, const char ch) {
+    option_char last_letter_op = str__at(word, (word.len-1));
+    if (last_letter_op.none) {
+        return false;
+    }
+    if (last_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+void str__to_lowercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = tolower(ch);
+    }
+}
+void str__to_uppercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = toupper(ch);
+    }
+}
+void str__replace_char(str word, const char from, const char to) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        if (ch==from) {
+            word.data[i] = to;
+        }
+    }
+}
+bool str__replace_index(str word, const usize index, const char to) {
+    option_char at_index = str__at(word, index);
+    if (at_index.none==true) {
+        return false;
+    }
+    word.data[index] = to;
+    return true;
+}
+bool str__contains(const str word, const char* pattern) {
+    usize pattern_len = strlen(pattern);
+    usize head = (0);
+    if (word.len<pattern_len) {
+        return false;
+    }
+    for (size_t str__contains_it0 = 0; str__contains_it0 < word.len; str__contains_it0++) {
+        char ch = word.data[str__contains_it0];
+        if (head==pattern_len) {
+            return true;
+        }
+        if (ch==pattern[head]) {
+            head = (head+1);
+        }
+        else {
+            head = (0);
+        }
+    }
+    if (head==pattern_len) {
+        return true;
+    }
+    return false;
+}
+void str__clear(str* word) {
+    word->data[0] = '\0';
+    word->len = (0);
+}
+void print() {
+}
+void println() {
+}
+void panic(const char* msg) {
+    fprintf(stderr, "panic at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+void runtime__assert(const bool expression, const char* msg) {
+    if (expression) {
+        return ;
+    }
+    fprintf(stderr, "assertion failed at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+typedef enum Colour {
+    Colour_Red,
+    Colour_Green,
+    Colour_Blue,
+}Colour;
+const usize Colour_field_count = (3);
+int main() {
+#ifdef _WIN32
+    system("chcp 65001 >nul");
+#elif __linux__
+    setlocale(LC_ALL, "");
+#endif
+    u8 small = (200);
+    u16 medium = small;
+    i32 count = medium;
+    i64 wide = count;
+    f64 real = count;
+    f32 half = (1.5);
+    f64 more = half;
+    int truncated = ((int)(7.9));
+    u8 wrapped = ((u8)(300+1));
+    usize len = ((usize)truncated);
+    int back = (((int)len)*2);
+    Colour colour = ((Colour)(2));
+    if (((bool)wrapped)) {
+        printf("%d %d %zu %d %d\n", truncated, wrapped, len, back, ((int)colour));
+    }
+    printf("%.1f %.1f\n", real, more);
+}
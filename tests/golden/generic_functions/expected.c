@@ -0,0 +1,322 @@
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+#include <ctype.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdarg.h>
+typedef uint8_t u8;
+typedef int8_t i8;
+typedef uint16_t u16;
+typedef int16_t i16;
+typedef uint32_t u32;
+typedef int32_t i32;
+typedef uint64_t u64;
+typedef int64_t i64;
+typedef size_t usize;
+typedef float f32;
+typedef double f64;
+typedef unsigned int uint;
+#define let __auto_type /* currently only for enums */
+#define $inline static inline __attribute__((always_inline))
+#ifdef __linux__
+    #include <locale.h>
+#endif
+$inline void exitcode(const int code) {
+    exit(code);
+}
+#define option(T, imp_struct_type_name)\
+typedef struct option_##imp_struct_type_name {\
+    T value;\
+    bool none;\
+} option_##imp_struct_type_name;
+#define array(T, imp_struct_type_name)\
+typedef struct array_##imp_struct_type_name {\
+    T* data;\
+    usize len;\
+} array_##imp_struct_type_name;
+$inline bool c__strcmp(const char* str1, const char* str2) {
+    return !strcmp(str1, str2);
+}
+typedef struct str {
+    char* data;
+    usize len;
+}str;
+str str__from(char* word) {
+    str value = {0};
+    value.data = word;
+    value.len = strlen(word);
+    return value;
+}
+str str__with_len(const usize len) {
+    char tmp[len];
+    str value = {0};
+    value.data = tmp;
+    value.len = len;
+    return value;
+}
+bool str__compare(const str first, const str second) {
+    if (first.len!=second.len) {
+        return false;
+    }
+    for (size_t i = 0; i < first.len; i++) {
+        char ch = first.data[i];
+        if (ch==second.data[i]) {
+            return false;
+        }
+    }
+    return true;
+}
+option(char, char);
+option_char str__at(const str word, const usize index) {
+    option_char ret = {0};
+    if (index>=word.len) {
+        ret.none = true;
+        return ret;
+    }
+    ret.value = word.data[index];
+    return ret;
+}
+bool str__starts_with(const str word, const char ch) {
+    option_char first_letter_op = str__at(word, (0));
+    if (first_letter_op.none) {
+        return false;
+    }
+    if (first_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+bool str__ends_with(const str word, const char ch) {
+    option_char last_letter_op = str__at(word, (word.len-1));
+    if (last_letter_op.none) {
+        return false;
+    }
+    if (last_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+void str__to_lowercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = tolower(ch);
+    }
+}
+void str__to_uppercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = toupper(ch);
+    }
+}
+void str__replace_char(str word, const char from, const char to) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        if (ch==from) {
+            word.data[i] = to;
+        }
+    }
+}
+bool str__replace_index(str word, const usize index, const char to) {
+    option_char at_index = str__at(word, index);
+    if (at_index.none==true) {
+        return false;
+    }
+    word.data[index] = to;
+    return true;
+}
+bool str__contains(const str word, const char* pattern) {
+    usize pattern_len = strlen(pattern);
+    usize head = (0);
+    if (word.len<pattern_len) {
+        return false;
+    }
+    for (size_t str__contains_it0 = 0; str__contains_it0 < word.len; str__contains_it0++) {
+        char ch = word.data[str__contains_it0];
+        if (head==pattern_len) {
+            return true;
+        }
+        if (ch==pattern[head]) {
+            head = (head+1);
+        }
+        else {
+            head = (0);
+        }
+    }
+    if (head==pattern_len) {
+        return true;
+    }
+    return false;
+}
+void str__clear(str* word) {
+    word->data[0] = '\0';
+    word->len = (0);
+}
+void print() {
+}
+void println() {
+}
+void panic(const char* msg) {
+    fprintf(stderr, "panic at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+void runtime__assert(const bool expression, const char* msg) {
+    if (expression) {
+        return ;
+    }
+    fprintf(stderr, "assertion failed at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+$inline void* mem__realloc(void* address, const usize new_size) {
+    return realloc(address, new_size);
+}
+$inline void* mem__copy(void* dest, const void* src, const usize n) {
+    return memcpy(dest, src, n);
+}
+$inline void* mem__set(void* s, const int c, const usize n) {
+    return memset(s, c, n);
+}
+$inline void* mem__move(void* dest, const void* src, const usize n) {
+    return memmove(dest, src, n);
+}
+$inline int mem__compare(const void* ptr1, const void* ptr2, const usize n) {
+    return memcmp(ptr1, ptr2, n);
+}
+$inline void mem__dealloc(void* block) {
+    free(block);
+}
+#define dyn(T, imp_struct_type_name)\
+typedef struct dyn_##imp_struct_type_name {\
+    T* data;\
+    usize len;\
+    usize cap;\
+} dyn_##imp_struct_type_name;
+dyn(char, char);
+dyn(int, int);
+int* mem__alloc_int(const usize len) {
+    typedef int T;
+    return (T*)calloc(len, sizeof(T));
+}
+dyn_int dyn__new_int() {
+    typedef int T;
+    dyn_int new = {0};
+    new.len = (0);
+    new.cap = (32);
+    new.data = mem__alloc_int(new.cap);
+    if (new.data == NULL) {
+                panic("failed to allocate memory");
+                exit(1);
+            }
+    return new;
+}
+usize size_of_int() {
+    typedef int T;
+    return sizeof(T);
+}
+void dyn__push_int(dyn_int* arr__ref, const int elem) {
+    typedef int T;
+    dyn_int arr = *arr__ref;
+    if ((arr.len+1)>=arr.cap) {
+        arr.cap = ((arr.cap+arr.len+1)*2);
+        usize T_size = size_of_int();
+        arr.data = mem__realloc(arr.data, (arr.cap*T_size));
+    }
+    arr.data[arr.len] = elem;
+    arr.len = (arr.len+1);
+    *arr__ref = arr;
+}
+void fill_int(dyn_int* arr__ref, const int data, const usize len) {
+    typedef int T;
+    dyn_int arr = *arr__ref;
+    for (usize i = 0; i<len; i++) {
+        dyn__push_int(&arr, data);
+    }
+    *arr__ref = arr;
+}
+int index_of_int(const dyn_int arr, const int value) {
+    typedef int T;
+    for (size_t i = 0; i < arr.len; i++) {
+        int elem = arr.data[i];
+        if (elem==value) {
+            return ((int)i);
+        }
+    }
+    return (-1);
+}
+char* mem__alloc_char(const usize len) {
+    typedef char T;
+    return (T*)calloc(len, sizeof(T));
+}
+dyn_char dyn__new_char() {
+    typedef char T;
+    dyn_char new = {0};
+    new.len = (0);
+    new.cap = (32);
+    new.data = mem__alloc_char(new.cap);
+    if (new.data == NULL) {
+                panic("failed to allocate memory");
+                exit(1);
+            }
+    return new;
+}
+usize size_of_char() {
+    typedef char T;
+    return sizeof(T);
+}
+void dyn__push_char(dyn_char* arr__ref, const char elem) {
+    typedef char T;
+    dyn_char arr = *arr__ref;
+    if ((arr.len+1)>=arr.cap) {
+        arr.cap = ((arr.cap+arr.len+1)*2);
+        usize T_size = size_of_char();
+        arr.data = mem__realloc(arr.data, (arr.cap*T_size));
+    }
+    arr.data[arr.len] = elem;
+    arr.len = (arr.len+1);
+    *arr__ref = arr;
+}
+option_char dyn__at_char(const dyn_char arr, const usize index) {
+    typedef char T;
+    option_char ret = {0};
+    ret.none = false;
+    if (index>=arr.len) {
+        ret.none = true;
+        return ret;
+    }
+    ret.value = arr.data[index];
+    return ret;
+}
+option_char dyn__pop_char(dyn_char* arr__ref) {
+    typedef char T;
+    dyn_char arr = *arr__ref;
+    option_char ret = dyn__at_char(arr, (arr.len-1));
+    if (!ret.none) {
+                arr.len = (arr.len-1);
+    }
+    *arr__ref = arr;
+    return ret;
+}
+char* type__to_cstr_char() {
+    typedef char T;
+    return "char";
+}
+usize size_of_i64() {
+    typedef i64 T;
+    return sizeof(T);
+}
+int main() {
+#ifdef _WIN32
+    system("chcp 65001 >nul");
+#elif __linux__
+    setlocale(LC_ALL, "");
+#endif
+    dyn_int nums = dyn__new_int();
+    fill_int(&nums, (7), (3));
+    dyn__push_int(&nums, (9));
+    printf("%d %d %d\n", nums.len, index_of_int(nums, (9)), index_of_int(nums, (4)));
+    dyn_char letters = dyn__new_char();
+    dyn__push_char(&letters, 'a');
+    option_char last = dyn__pop_char(&letters);
+    printf("%c %d %s %d\n", last.value, letters.len, type__to_cstr_char(), ((int)size_of_i64()));
+}
@@ -0,0 +1,572 @@
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+#include <ctype.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdarg.h>
+#include <math.h>
+typedef uint8_t u8;
+typedef int8_t i8;
+typedef uint16_t u16;
+typedef int16_t i16;
+typedef uint32_t u32;
+typedef int32_t i32;
+typedef uint64_t u64;
+typedef int64_t i64;
+typedef size_t usize;
+typedef float f32;
+typedef double f64;
+typedef unsigned int uint;
+#define let __auto_type /* currently only for enums */
+#define $inline static inline __attribute__((always_inline))
+#ifdef __linux__
+    #include <locale.h>
+#endif
+$inline void exitcode(const int code) {
+    exit(code);
+}
+#define option(T, imp_struct_type_name)\
+typedef struct option_##imp_struct_type_name {\
+    T value;\
+    bool none;\
+} option_##imp_struct_type_name;
+#define array(T, imp_struct_type_name)\
+typedef struct array_##imp_struct_type_name {\
+    T* data;\
+    usize len;\
+} array_##imp_struct_type_name;
+$inline bool c__strcmp(const char* str1, const char* str2) {
+    return !strcmp(str1, str2);
+}
+typedef struct str {
+    char* data;
+    usize len;
+}str;
+str str__from(char* word) {
+    str value = {0};
+    value.data = word;
+    value.len = strlen(word);
+    return value;
+}
+str str__with_len(const usize len) {
+    char tmp[len];
+    str value = {0};
+    value.data = tmp;
+    value.len = len;
+    return value;
+}
+bool str__compare(const str first, const str second) {
+    if (first.len!=second.len) {
+        return false;
+    }
+    for (size_t i = 0; i < first.len; i++) {
+        char ch = first.data[i];
+        if (ch==second.data[i]) {
+            return false;
+        }
+    }
+    return true;
+}
+option(char, char);
+option_char str__at(const str word, const usize index) {
+    option_char ret = {0};
+    if (index>=word.len) {
+        ret.none = true;
+        return ret;
+    }
+    ret.value = word.data[index];
+    return ret;
+}
+bool str__starts_with(const str word, const char ch) {
+    option_char first_letter_op = str__at(word, (0));
+    if (first_letter_op.none) {
+        return false;
+    }
+    if (first_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+bool str__ends_with(const str word, const char ch) {
+    option_char last_letter_op = str__at(word, (word.len-1));
+    if (last_letter_op.none) {
+        return false;
+    }
+    if (last_letter_op.value!=ch) {
+        return false;
+    }
+    return true;
+}
+void str__to_lowercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = tolower(ch);
+    }
+}
+void str__to_uppercase(str word) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        word.data[i] = toupper(ch);
+    }
+}
+void str__replace_char(str word, const char from, const char to) {
+    for (size_t i = 0; i < word.len; i++) {
+        char ch = word.data[i];
+        if (ch==from) {
+            word.data[i] = to;
+        }
+    }
+}
+bool str__replace_index(str word, const usize index, const char to) {
+    option_char at_index = str__at(word, index);
+    if (at_index.none==true) {
+        return false;
+    }
+    word.data[index] = to;
+    return true;
+}
+bool str__contains(const str word, const char* pattern) {
+    usize pattern_len = strlen(pattern);
+    usize head = (0);
+    if (word.len<pattern_len) {
+        return false;
+    }
+    for (size_t str__contains_it0 = 0; str__contains_it0 < word.len; str__contains_it0++) {
+        char ch = word.data[str__contains_it0];
+        if (head==pattern_len) {
+            return true;
+        }
+        if (ch==pattern[head]) {
+            head = (head+1);
+        }
+        else {
+            head = (0);
+        }
+    }
+    if (head==pattern_len) {
+        return true;
+    }
+    return false;
+}
+void str__clear(str* word) {
+    word->data[0] = '\0';
+    word->len = (0);
+}
+void print() {
+}
+void println() {
+}
+void panic(const char* msg) {
+    fprintf(stderr, "panic at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+void runtime__assert(const bool expression, const char* msg) {
+    if (expression) {
+        return ;
+    }
+    fprintf(stderr, "assertion failed at %s:%d: %s\n", __FILE__, __LINE__, msg);
+    exitcode((1));
+}
+const f64 math_PI = (3.14159265358979323846);
+typedef struct Vector2f32 {
+    f32 x;
+    f32 y;
+}Vector2f32;
+$inline Vector2f32 Vector2f32__new(const f32 x, const f32 y) {
+    Vector2f32 new = {0};
+    new.x = x;
+    new.y = y;
+    return new;
+}
+$inline Vector2f32 Vector2f32__add(const Vector2f32 a, const Vector2f32 b) {
+    Vector2f32 c = {0};
+    c.x = (a.x+b.x);
+    c.y = (a.y+b.y);
+    return c;
+}
+$inline Vector2f32 Vector2f32__sub(const Vector2f32 a, const Vector2f32 b) {
+    Vector2f32 c = {0};
+    c.x = (a.x-b.x);
+    c.y = (a.y-b.y);
+    return c;
+}
+$inline Vector2f32 Vector2f32__mul(const Vector2f32 a, const Vector2f32 b) {
+    Vector2f32 c = {0};
+    c.x = (a.x*b.x);
+    c.y = (a.y*b.y);
+    return c;
+}
+$inline Vector2f32 Vector2f32__div(const Vector2f32 a, const Vector2f32 b) {
+    Vector2f32 c = {0};
+    c.x = (a.x/b.x);
+    c.y = (a.y/b.y);
+    return c;
+}
+char* type__to_cstr_f32() {
+    typedef f32 T;
+    return "f32";
+}
+bool type__compare_f32_f32() {
+    typedef f32 T;
+    typedef f32 K;
+    char* str_T = type__to_cstr_f32();
+    char* str_K = type__to_cstr_f32();
+    bool ret = false;
+    if (c__strcmp(str_T, str_K)) {
+        ret = true;
+    }
+    return ret;
+}
+char* type__to_cstr_f64() {
+    typedef f64 T;
+    return "f64";
+}
+bool type__compare_f32_f64() {
+    typedef f32 T;
+    typedef f64 K;
+    char* str_T = type__to_cstr_f32();
+    char* str_K = type__to_cstr_f64();
+    bool ret = false;
+    if (c__strcmp(str_T, str_K)) {
+        ret = true;
+    }
+    return ret;
+}
+f32 math__sqrt__f32(const f32 x) {
+    typedef f32 T;
+    f32 result = {0};
+    if (type__compare_f32_f32()) {
+        result = sqrtf(x);
+    }
+    else if (type__compare_f32_f64()) {
+        result = sqrt(x);
+    }
+    else {
+        printf("type %s can't be used in sqrt function as it's not a decimal\n", type__to_cstr_f32());
+        exitcode((1));
+    }
+    return result;
+}
+$inline Vector2f32 Vector2f32__normalise(const Vector2f32 vec) {
+    f32 mag = math__sqrt__f32((vec.x*vec.x+vec.y*vec.y));
+    if (mag>(0)) {
+        Vector2f32 normalised = {0};
+        normalised.x = (vec.x/mag);
+        normalised.y = (vec.y/mag);
+        return normalised;
+    }
+    return vec;
+}
+typedef struct Vector3f32 {
+    f32 x;
+    f32 y;
+    f32 z;
+}Vector3f32;
+$inline Vector3f32 Vector3f32__add(const Vector3f32 a, const Vector3f32 b) {
+    Vector3f32 c = {0};
+    c.x = (a.x+b.x);
+    c.y = (a.y+b.y);
+    c.z = (a.z+b.z);
+    return c;
+}
+$inline Vector3f32 Vector3f32__sub(const Vector3f32 a, const Vector3f32 b) {
+    Vector3f32 c = {0};
+    c.x = (a.x-b.x);
+    c.y = (a.y-b.y);
+    c.z = (a.z-b.z);
+    return c;
+}
+$inline Vector3f32 Vector3f32__mul(const Vector3f32 a, const Vector3f32 b) {
+    Vector3f32 c = {0};
+    c.x = (a.x*b.x);
+    c.y = (a.y*b.y);
+    c.z = (a.z*b.z);
+    return c;
+}
+$inline Vector3f32 Vector3f32__div(const Vector3f32 a, const Vector3f32 b) {
+    Vector3f32 c = {0};
+    c.x = (a.x/b.x);
+    c.y = (a.y/b.y);
+    c.z = (a.z/b.z);
+    return c;
+}
+$inline Vector3f32 Vector3f32__normalise(const Vector3f32 vec) {
+    f32 mag = math__sqrt__f32((vec.x*vec.x+vec.y*vec.y+vec.z*vec.z));
+    if (mag>(0)) {
+        Vector3f32 normalised = {0};
+        normalised.x = (vec.x/mag);
+        normalised.y = (vec.y/mag);
+        normalised.z = (vec.z/mag);
+        return normalised;
+    }
+    return vec;
+}
+typedef struct Vector4f32 {
+    f32 x;
+    f32 y;
+    f32 z;
+    f32 w;
+}Vector4f32;
+$inline Vector4f32 Vector4f32__add(const Vector4f32 a, const Vector4f32 b) {
+    Vector4f32 c = {0};
+    c.x = (a.x+b.x);
+    c.y = (a.y+b.y);
+    c.z = (a.z+b.z);
+    c.w = (a.w+b.w);
+    return c;
+}
+$inline Vector4f32 Vector4f32__sub(const Vector4f32 a, const Vector4f32 b) {
+    Vector4f32 c = {0};
+    c.x = (a.x-b.x);
+    c.y = (a.y-b.y);
+    c.z = (a.z-b.z);
+    c.w = (a.w-b.w);
+    return c;
+}
+$inline Vector4f32 Vector4f32__mul(const Vector4f32 a, const Vector4f32 b) {
+    Vector4f32 c = {0};
+    c.x = (a.x*b.x);
+    c.y = (a.y*b.y);
+    c.z = (a.z*b.z);
+    c.w = (a.w*b.w);
+    return c;
+}
+$inline Vector4f32 Vector4f32__div(const Vector4f32 a, const Vector4f32 b) {
+    Vector4f32 c = {0};
+    c.x = (a.x/b.x);
+    c.y = (a.y/b.y);
+    c.z = (a.z/b.z);
+    c.w = (a.w/b.w);
+    return c;
+}
+$inline Vector4f32 Vector4f32__normalise(const Vector4f32 vec) {
+    f32 mag = math__sqrt__f32((vec.x*vec.x+vec.y*vec.y+vec.z*vec.z+vec.w*vec.w));
+    if (mag>(0)) {
+        Vector4f32 normalised = {0};
+        normalised.x = (vec.x/mag);
+        normalised.y = (vec.y/mag);
+        normalised.z = (vec.z/mag);
+        normalised.w = (vec.w/mag);
+        return normalised;
+    }
+    return vec;
+}
+typedef Vector4f32 Quaternionf32;
+typedef struct Matrix4x4f32 {
+    f32 m0;
+    f32 m4;
+    f32 m8;
+    f32 m12;
+    f32 m1;
+    f32 m5;
+    f32 m9;
+    f32 m13;
+    f32 m2;
+    f32 m6;
+    f32 m10;
+    f32 m14;
+    f32 m3;
+    f32 m7;
+    f32 m11;
+    f32 m15;
+}Matrix4x4f32;
+typedef struct Vector2f64 {
+    f64 x;
+    f64 y;
+}Vector2f64;
+$inline Vector2f64 Vector2f64__add(const Vector2f64 a, const Vector2f64 b) {
+    Vector2f64 c = {0};
+    c.x = (a.x+b.x);
+    c.y = (a.y+b.y);
+    return c;
+}
+$inline Vector2f64 Vector2f64__sub(const Vector2f64 a, const Vector2f64 b) {
+    Vector2f64 c = {0};
+    c.x = (a.x-b.x);
+    c.y = (a.y-b.y);
+    return c;
+}
+$inline Vector2f64 Vector2f64__mul(const Vector2f64 a, const Vector2f64 b) {
+    Vector2f64 c = {0};
+    c.x = (a.x*b.x);
+    c.y = (a.y*b.y);
+    return c;
+}
+$inline Vector2f64 Vector2f64__div(const Vector2f64 a, const Vector2f64 b) {
+    Vector2f64 c = {0};
+    c.x = (a.x/b.x);
+    c.y = (a.y/b.y);
+    return c;
+}
+bool type__compare_f64_f32() {
+    typedef f64 T;
+    typedef f32 K;
+    char* str_T = type__to_cstr_f64();
+    char* str_K = type__to_cstr_f32();
+    bool ret = false;
+    if (c__strcmp(str_T, str_K)) {
+        ret = true;
+    }
+    return ret;
+}
+bool type__compare_f64_f64() {
+    typedef f64 T;
+    typedef f64 K;
+    char* str_T = type__to_cstr_f64();
+    char* str_K = type__to_cstr_f64();
+    bool ret = false;
+    if (c__strcmp(str_T, str_K)) {
+        ret = true;
+    }
+    return ret;
+}
+f64 math__sqrt__f64(const f64 x) {
+    typedef f64 T;
+    f64 result = {0};
+    if (type__compare_f64_f32()) {
+        result = sqrtf(x);
+    }
+    else if (type__compare_f64_f64()) {
+        result = sqrt(x);
+    }
+    else {
+        printf("type %s can't be used in sqrt function as it's not a decimal\n", type__to_cstr_f64());
+        exitcode((1));
+    }
+    return result;
+}
+$inline Vector2f64 Vector2f64__normalise(const Vector2f64 vec) {
+    f64 mag = math__sqrt__f64((vec.x*vec.x+vec.y*vec.y));
+    if (mag>(0)) {
+        Vector2f64 normalised = {0};
+        normalised.x = (vec.x/mag);
+        normalised.y = (vec.y/mag);
+        return normalised;
+    }
+    return vec;
+}
+typedef struct Vector3f64 {
+    f64 x;
+    f64 y;
+    f64 z;
+}Vector3f64;
+$inline Vector3f64 Vector3f64__add(const Vector3f64 a, const Vector3f64 b) {
+    Vector3f64 c = {0};
+    c.x = (a.x+b.x);
+    c.y = (a.y+b.y);
+    c.z = (a.z+b.z);
+    return c;
+}
+$inline Vector3f64 Vector3f64__sub(const Vector3f64 a, const Vector3f64 b) {
+    Vector3f64 c = {0};
+    c.x = (a.x-b.x);
+    c.y = (a.y-b.y);
+    c.z = (a.z-b.z);
+    return c;
+}
+$inline Vector3f64 Vector3f64__mul(const Vector3f64 a, const Vector3f64 b) {
+    Vector3f64 c = {0};
+    c.x = (a.x*b.x);
+    c.y = (a.y*b.y);
+    c.z = (a.z*b.z);
+    return c;
+}
+$inline Vector3f64 Vector3f64__div(const Vector3f64 a, const Vector3f64 b) {
+    Vector3f64 c = {0};
+    c.x = (a.x/b.x);
+    c.y = (a.y/b.y);
+    c.z = (a.z/b.z);
+    return c;
+}
+$inline Vector3f64 Vector3f64__normalise(const Vector3f64 vec) {
+    f64 mag = math__sqrt__f64((vec.x*vec.x+vec.y*vec.y+vec.z*vec.z));
+    if (mag>(0)) {
+        Vector3f64 normalised = {0};
+        normalised.x = (vec.x/mag);
+        normalised.y = (vec.y/mag);
+        normalised.z = (vec.z/mag);
+        return normalised;
+    }
+    return vec;
+}
+typedef struct Vector4f64 {
+    f64 x;
+    f64 y;
+    f64 z;
+    f64 w;
+}Vector4f64;
+$inline Vector4f64 Vector4f64__add(const Vector4f64 a, const Vector4f64 b) {
+    Vector4f64 c = {0};
+    c.x = (a.x+b.x);
+    c.y = (a.y+b.y);
+    c.z = (a.z+b.z);
+    c.w = (a.w+b.w);
+    return c;
+}
+$inline Vector4f64 Vector4f64__sub(const Vector4f64 a, const Vector4f64 b) {
+    Vector4f64 c = {0};
+    c.x = (a.x-b.x);
+    c.y = (a.y-b.y);
+    c.z = (a.z-b.z);
+    c.w = (a.w-b.w);
+    return c;
+}
+$inline Vector4f64 Vector4f64__mul(const Vector4f64 a, const Vector4f64 b) {
+    Vector4f64 c = {0};
+    c.x = (a.x*b.x);
+    c.y = (a.y*b.y);
+    c.z = (a.z*b.z);
+    c.w = (a.w*b.w);
+    return c;
+}
+$inline Vector4f64 Vector4f64__div(const Vector4f64 a, const Vector4f64 b) {
+    Vector4f64 c = {0};
+    c.x = (a.x/b.x);
+    c.y = (a.y/b.y);
+    c.z = (a.z/b.z);
+    c.w = (a.w/b.w);
+    return c;
+}
+$inline Vector4f64 Vector4f64__normalise(const Vector4f64 vec) {
+    f64 mag = math__sqrt__f64((vec.x*vec.x+vec.y*vec.y+vec.z*vec.z+vec.w*vec.w));
+    if (mag>(0)) {
+        Vector4f64 normalised = {0};
+        normalised.x = (vec.x/mag);
+        normalised.y = (vec.y/mag);
+        normalised.z = (vec.z/mag);
+        normalised.w = (vec.w/mag);
+        return normalised;
+    }
+    return vec;
+}
+typedef Vector4f64 Quaternionf64;
+typedef struct Matrix4x4f64 {
+    f64 m0;
+    f64 m4;
+    f64 m8;
+    f64 m12;
+    f64 m1;
+    f64 m5;
+    f64 m9;
+    f64 m13;
+    f64 m2;
+    f64 m6;
+    f64 m10;
+    f64 m14;
+    f64 m3;
+    f64 m7;
+    f64 m11;
+    f64 m15;
+}Matrix4x4f64;
+int main() {
+#ifdef _WIN32
+    system("chcp 65001 >nul");
+#elif __linux__
+    setlocale(LC_ALL, "");
+#endif
+    f64 area = (2.0);
+    f32 side = (9.0);
+    printf("%.2f %.1f\n", math__sqrt__f64(area), math__sqrt__f32(side));
+}